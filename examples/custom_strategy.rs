@@ -0,0 +1,75 @@
+//! An exploration strategy of its own, plugged into the depth-first exploration of the solver
+//! through the `Strategy` trait.
+//!
+//! `FactualSolver::auto_explore` branches on the position with the fewest activations left. This
+//! strategy branches on the activation with the fewest positions left instead. Every state the
+//! exploration visits stays in the assumption tree, like with `auto_explore`.
+//!
+//! Run with `cargo run --example custom_strategy`.
+
+use rune_lock_solver::{
+    activation::Activation,
+    fact_solver::{auto::Strategy, fact_db::FactDb},
+    rule::ActivationRuleKindHelpers,
+    FactualSolver, Rune, RuneLock, RunePosition,
+};
+
+///Branches on the activation with the fewest positions left, and counts the states it was asked
+///about.
+#[derive(Default)]
+struct FewestPositions {
+    branched: usize,
+}
+
+impl Strategy for FewestPositions {
+    fn branch(&mut self, facts: &FactDb) -> Vec<(RunePosition, Activation)> {
+        self.branched += 1;
+        facts
+            .size()
+            .all_activations()
+            .map(|activation| {
+                facts
+                    .possibilities_for(activation)
+                    .map(|position| (position, activation))
+                    .collect::<Vec<_>>()
+            })
+            //Activations with a single position left are placed already.
+            .filter(|candidates| candidates.len() > 1)
+            .min_by_key(|candidates| candidates.len())
+            .unwrap_or_default()
+    }
+}
+
+fn main() {
+    //Z = 0, V = 1, S = 2, C = 3
+    let lock = RuneLock::with_rules(
+        [0, 2, 1, 3, 2, 1, 3, 2, 1, 0, 2, 1].map(Rune::new),
+        vec![
+            (1, 2).alwanese(),
+            (2, 3).antakian_conjugate(),
+            (3, 4).alwanese(),
+            (4, 5).antakian_twins(),
+            (5, 6).increase_santor(),
+            (6, 7).alwanese(),
+            (7, 8).antakian_conjugate(),
+            (8, 9).alwanese(),
+            (9, 10).antakian_twins(),
+            (10, 11).increase_santor(),
+        ],
+    );
+    let mut solver = FactualSolver::new(lock);
+
+    let mut strategy = FewestPositions::default();
+    let outcome = solver.auto_explore_with(&mut strategy, solver.lock().size().activations());
+    match outcome.solution {
+        Some(solution) => println!(
+            "{} after branching in {} states.",
+            solver.outcome(solution),
+            strategy.branched
+        ),
+        None => println!(
+            "No solution after branching in {} states.",
+            strategy.branched
+        ),
+    }
+}
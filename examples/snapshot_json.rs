@@ -0,0 +1,52 @@
+//! Renders snapshots of an investigation as JSON: what every state of the assumption tree came
+//! to, as an `Outcome`, and the whole session, as `FactualSolver::save` writes it.
+//!
+//! Run with `cargo run --example snapshot_json`.
+
+use std::env;
+
+use rune_lock_solver::{
+    activation::{Activation, HumanActivation},
+    rule::ActivationRuleKindHelpers,
+    FactualSolver, Rune, RuneLock, RunePosition,
+};
+
+fn main() {
+    //Z = 0, V = 1, S = 2, C = 3
+    let lock = RuneLock::with_rules(
+        [0, 2, 1, 3, 2, 1, 3, 2, 1, 0, 2, 1].map(Rune::new),
+        vec![(1, 2).alwanese(), (2, 3).increase_santor()],
+    );
+    let mut solver = FactualSolver::new(lock);
+    let activation = |it| Activation::from(HumanActivation::new(it).unwrap());
+    let root = solver.root();
+    let first = solver.assume(activation(1), RunePosition::new(0));
+    let second = solver.assume(activation(2), RunePosition::new(1));
+
+    //Outcomes are plain data, and serialize the same for every solver.
+    let snapshot: Vec<_> = [root, first, second]
+        .into_iter()
+        .map(|node| {
+            serde_json::json!({
+                "state": node.to_string(),
+                "outcome": solver.outcome(node),
+            })
+        })
+        .collect();
+    println!("{}", serde_json::to_string_pretty(&snapshot).unwrap());
+
+    //The whole session, with the facts of every state, is what `save` writes and `load` reads.
+    let path = env::temp_dir().join(format!("rune-lock-snapshot-{}.json", std::process::id()));
+    match solver.save(&path) {
+        Ok(()) => {
+            let restored = FactualSolver::load(&path).unwrap();
+            println!(
+                "Saved the session to {} and restored it in state {}.",
+                path.display(),
+                restored.current()
+            );
+        }
+        Err(err) => println!("{}", err),
+    }
+    let _ = std::fs::remove_file(&path);
+}
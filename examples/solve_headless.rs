@@ -0,0 +1,71 @@
+//! Solves a lock without the command line, and prints the proof of every placement of the
+//! solution: the facts it rests on, checked again from scratch, and the assumptions it only holds
+//! under.
+//!
+//! Run with `cargo run --example solve_headless`.
+
+use rune_lock_solver::{
+    outcome::Outcome, rule::ActivationRuleKindHelpers, FactualSolver, Rune, RuneLock, RunePosition,
+};
+
+fn main() {
+    //Z = 0, V = 1, S = 2, C = 3
+    let lock = RuneLock::with_rules(
+        [0, 2, 1, 3, 2, 1, 3, 2, 1, 0, 2, 1].map(Rune::new),
+        vec![
+            (1, 2).alwanese(),
+            (2, 3).antakian_conjugate(),
+            (3, 4).alwanese(),
+            (4, 5).antakian_twins(),
+            (5, 6).increase_santor(),
+            (6, 7).alwanese(),
+            (7, 8).antakian_conjugate(),
+            (8, 9).alwanese(),
+            (9, 10).antakian_twins(),
+            (10, 11).increase_santor(),
+        ],
+    );
+    let mut solver = FactualSolver::new(lock);
+
+    let Some(solution) = solver
+        .auto_explore(solver.lock().size().activations())
+        .solution
+    else {
+        println!("The lock has no solution.");
+        return;
+    };
+    let Outcome::Solved { assignment } = solver.outcome(solution) else {
+        unreachable!("auto_explore only returns solved states");
+    };
+    println!("Solved in state {}.", solution);
+
+    for (position, activation) in assignment.into_iter().enumerate() {
+        let position = RunePosition::new(position);
+        //Positions stay empty on locks with fewer activations than positions.
+        let Some(activation) = activation else {
+            continue;
+        };
+        let Some(handle) = solver.facts().handle_at(position, activation) else {
+            continue;
+        };
+        let Some(certificate) = solver.certificate(solution, handle) else {
+            continue;
+        };
+        match certificate.verify() {
+            Ok(verified) => println!(
+                "{} ({} {}), under {}",
+                verified.claim,
+                certificate.steps.len(),
+                match certificate.steps.len() {
+                    1 => "step",
+                    _ => "steps",
+                },
+                match verified.premises.is_empty() {
+                    true => "no assumptions".to_string(),
+                    false => verified.premises.join(", "),
+                }
+            ),
+            Err(err) => println!("The proof of {} is invalid: {}", handle, err),
+        }
+    }
+}
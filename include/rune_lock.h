@@ -0,0 +1,28 @@
+/* The C ABI of rune-lock-solver, built with `cargo build --release --features ffi`. See src/ffi.rs
+ * for what each function does. Activations are 1 based, positions 0 based. A panic inside the
+ * library is returned as null or -1, after which the solver should be freed. */
+
+#ifndef RUNE_LOCK_H
+#define RUNE_LOCK_H
+
+#include <stdint.h>
+
+#define RUNE_UNKNOWN 255
+
+typedef struct RuneLock RuneLock;
+typedef struct FactualSolver FactualSolver;
+
+RuneLock *rune_lock_new(const uint8_t runes[12]);
+RuneLock *rune_lock_new_sized(const uint8_t *runes, uint8_t positions, uint8_t activations);
+int32_t rune_lock_add_rule(RuneLock *lock, const char *rule);
+void rune_lock_free(RuneLock *lock);
+
+FactualSolver *rune_solver_new(const RuneLock *lock);
+char *rune_solver_assume(FactualSolver *solver, uint8_t activation, uint8_t position);
+int32_t rune_solver_candidates(const FactualSolver *solver, uint8_t position);
+char *rune_solver_explain(const FactualSolver *solver, uint8_t activation, uint8_t position);
+void rune_solver_free(FactualSolver *solver);
+
+void rune_lock_string_free(char *string);
+
+#endif
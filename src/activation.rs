@@ -1,13 +1,29 @@
-use std::fmt::Display;
+use std::{fmt::Display, str::FromStr};
 
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+use crate::size::{LockSize, MAX_POSITIONS};
+
+///An activation as the solver sees it, 0 based. Whether a lock has it depends on its size, see
+///`LockSize::activation`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
 pub struct Activation(u8);
 
+///An activation as the user sees it, 1 based. Used for parsing and display only, the solver
+///works with `Activation`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct HumanActivation(u8);
+
 impl Display for Activation {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "#{}", self.0 + 1)
+        HumanActivation::from(*self).fmt(f)
+    }
+}
+
+impl Display for HumanActivation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "#{}", self.0)
     }
 }
 
@@ -15,19 +31,13 @@ impl Display for Activation {
 pub enum ActivationError {
     #[error("Activation is out of bounds")]
     OutOfBounds,
+    #[error("'{0}' is not an activation number")]
+    Malformed(String),
 }
 
 impl Activation {
-    ///1 based
-    pub fn from_human(one_based: u8) -> Result<Self, ActivationError> {
-        if one_based < 1 {
-            return Err(ActivationError::OutOfBounds);
-        }
-        Activation::new(one_based - 1)
-    }
-
     pub fn new(zero_based: u8) -> Result<Self, ActivationError> {
-        if zero_based >= 12 {
+        if zero_based as usize >= MAX_POSITIONS {
             Err(ActivationError::OutOfBounds)
         } else {
             Ok(Activation(zero_based))
@@ -38,14 +48,61 @@ impl Activation {
         self.0 as usize
     }
 
-    pub fn next(&self) -> Result<Self, ActivationError> {
-        Activation::new(self.0 + 1)
+    ///The activation after this one, if the lock has it.
+    pub fn next(&self, size: LockSize) -> Result<Self, ActivationError> {
+        size.activation(self.index() + 1)
     }
 
-    pub(crate) fn prev(&self) -> Result<Activation, ActivationError> {
+    ///The activations of the lock after this one, at most `count` of them.
+    pub fn following(&self, count: u8, size: LockSize) -> impl Iterator<Item = Activation> {
+        (self.0 + 1..size.activations() as u8)
+            .take(count as usize)
+            .map(Activation)
+    }
+
+    pub fn prev(&self) -> Result<Activation, ActivationError> {
         if self.0 == 0 {
             return Err(ActivationError::OutOfBounds);
         }
         Activation::new(self.0 - 1)
     }
 }
+
+impl HumanActivation {
+    pub fn new(one_based: u8) -> Result<Self, ActivationError> {
+        if one_based < 1 {
+            return Err(ActivationError::OutOfBounds);
+        }
+        Activation::new(one_based - 1).map(Self::from)
+    }
+
+    pub fn number(&self) -> u8 {
+        self.0
+    }
+}
+
+impl From<Activation> for HumanActivation {
+    fn from(value: Activation) -> Self {
+        Self(value.0 + 1)
+    }
+}
+
+impl From<HumanActivation> for Activation {
+    fn from(value: HumanActivation) -> Self {
+        Self(value.0 - 1)
+    }
+}
+
+///Accepts `7` as well as `#7`.
+impl FromStr for HumanActivation {
+    type Err = ActivationError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let number = s
+            .strip_prefix('#')
+            .unwrap_or(s)
+            .parse::<u8>()
+            .map_err(|_| ActivationError::Malformed(s.into()))?;
+        HumanActivation::new(number)
+    }
+}
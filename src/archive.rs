@@ -0,0 +1,369 @@
+//! A local archive of lock files. Every entry is a lock file whose leading comment lines carry its
+//! metadata, like `# solved: yes`, so entries can still be opened with `repl` directly.
+
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use itertools::Itertools;
+use thiserror::Error;
+
+use crate::{
+    fact_solver::FactualSolver,
+    lock_file::{self, LockFileError},
+    rune::{Rune, RuneError},
+    RuneLock,
+};
+
+#[derive(Debug, Error)]
+pub enum ArchiveError {
+    #[error("Could not access {path}: {source}")]
+    Io { path: String, source: io::Error },
+    #[error("{0}")]
+    Lock(#[from] LockFileError),
+    #[error("There is no lock {0} in the archive")]
+    UnknownEntry(usize),
+    #[error("Invalid rune pattern: {0}")]
+    Pattern(#[from] RuneError),
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Metadata {
+    pub date: String,
+    pub source: Option<String>,
+    ///How many (position, activation) pairs the rules alone leave open, out of 144.
+    pub difficulty: usize,
+    pub solved: bool,
+    ///Session scripts that were recorded while solving the lock.
+    pub sessions: Vec<String>,
+}
+
+pub struct Entry {
+    pub id: usize,
+    pub metadata: Metadata,
+    pub lock: RuneLock,
+    ///The lock file without the metadata.
+    body: String,
+}
+
+///What `search` looks for. Empty criteria match every entry.
+#[derive(Default)]
+pub struct Query {
+    ///Rule kinds, like `santor-chain`. A kind given twice only matches locks with two such rules.
+    pub kinds: Vec<String>,
+    ///Runes that have to appear in this order on one of the circles, `*` matching any rune.
+    pub runes: Option<String>,
+    pub solved: Option<bool>,
+}
+
+pub struct Archive {
+    directory: PathBuf,
+}
+
+impl Archive {
+    pub fn open(directory: &Path) -> Result<Self, ArchiveError> {
+        fs::create_dir_all(directory).map_err(|source| io_error(directory, source))?;
+        Ok(Self {
+            directory: directory.to_path_buf(),
+        })
+    }
+
+    ///Copies the lock file into the archive and returns its entry.
+    pub fn add(
+        &self,
+        lockfile: &Path,
+        source: Option<String>,
+        date: Option<String>,
+        solved: bool,
+    ) -> Result<Entry, ArchiveError> {
+        let lock = RuneLock::from_path(lockfile)?;
+        //Entries are kept in the text format, so TOML locks are converted.
+        let body = match lock_file::is_toml(lockfile) {
+            true => lock_file::write_lock(&lock),
+            false => fs::read_to_string(lockfile).map_err(|source| io_error(lockfile, source))?,
+        };
+        let metadata = Metadata {
+            date: date.unwrap_or_else(today),
+            source,
+            difficulty: FactualSolver::new(lock.clone()).open_candidates(),
+            solved,
+            sessions: Vec::new(),
+        };
+        let id = self
+            .entries()?
+            .iter()
+            .map(|it| it.id + 1)
+            .max()
+            .unwrap_or(1);
+        let entry = Entry {
+            id,
+            metadata,
+            lock,
+            body,
+        };
+        self.save(&entry)?;
+        Ok(entry)
+    }
+
+    pub fn get(&self, id: usize) -> Result<Entry, ArchiveError> {
+        let path = self.path_of(id);
+        if !path.exists() {
+            return Err(ArchiveError::UnknownEntry(id));
+        }
+        let text = fs::read_to_string(&path).map_err(|source| io_error(&path, source))?;
+        let (metadata, body) = parse_metadata(&text);
+        Ok(Entry {
+            id,
+            metadata,
+            lock: RuneLock::from_path(&path)?,
+            body,
+        })
+    }
+
+    ///All entries, ordered by id.
+    pub fn entries(&self) -> Result<Vec<Entry>, ArchiveError> {
+        let listing =
+            fs::read_dir(&self.directory).map_err(|source| io_error(&self.directory, source))?;
+        let mut ids = Vec::new();
+        for file in listing {
+            let path = file
+                .map_err(|source| io_error(&self.directory, source))?
+                .path();
+            if path.extension().is_some_and(|it| it == "lock") {
+                if let Some(id) = path
+                    .file_stem()
+                    .and_then(|it| it.to_str())
+                    .and_then(|it| it.parse().ok())
+                {
+                    ids.push(id);
+                }
+            }
+        }
+        ids.into_iter().sorted().map(|it| self.get(it)).collect()
+    }
+
+    pub fn search(&self, query: &Query) -> Result<Vec<Entry>, ArchiveError> {
+        let pattern = match &query.runes {
+            Some(runes) => Some(parse_pattern(runes)?),
+            None => None,
+        };
+        Ok(self
+            .entries()?
+            .into_iter()
+            .filter(|entry| query.solved.is_none_or(|it| it == entry.metadata.solved))
+            .filter(|entry| {
+                let kinds = rule_kinds(&entry.lock);
+                query
+                    .kinds
+                    .iter()
+                    .counts()
+                    .into_iter()
+                    .all(|(kind, wanted)| kinds.iter().filter(|it| *it == kind).count() >= wanted)
+            })
+            .filter(|entry| {
+                pattern
+                    .as_ref()
+                    .is_none_or(|it| has_pattern(&entry.lock, it))
+            })
+            .collect())
+    }
+
+    ///Changes the metadata of an entry.
+    pub fn update(
+        &self,
+        id: usize,
+        change: impl FnOnce(&mut Metadata),
+    ) -> Result<Entry, ArchiveError> {
+        let mut entry = self.get(id)?;
+        change(&mut entry.metadata);
+        self.save(&entry)?;
+        Ok(entry)
+    }
+
+    fn save(&self, entry: &Entry) -> Result<(), ArchiveError> {
+        let metadata = &entry.metadata;
+        let mut text = format!("# date: {}\n", metadata.date);
+        if let Some(source) = &metadata.source {
+            text.push_str(&format!("# source: {}\n", source));
+        }
+        text.push_str(&format!("# difficulty: {}\n", metadata.difficulty));
+        text.push_str(&format!(
+            "# solved: {}\n",
+            if metadata.solved { "yes" } else { "no" }
+        ));
+        for session in metadata.sessions.iter() {
+            text.push_str(&format!("# session: {}\n", session));
+        }
+        text.push_str(&entry.body);
+        let path = self.path_of(entry.id);
+        fs::write(&path, text).map_err(|source| io_error(&path, source))
+    }
+
+    fn path_of(&self, id: usize) -> PathBuf {
+        self.directory.join(format!("{}.lock", id))
+    }
+}
+
+impl Entry {
+    ///A one line summary, like `3: 2026-10-16 unsolved, difficulty 96 (daily): Z S V C S V / ...`.
+    pub fn summary(&self) -> String {
+        let metadata = &self.metadata;
+        let kinds = rule_kinds(&self.lock)
+            .into_iter()
+            .counts()
+            .into_iter()
+            .sorted()
+            .map(|(kind, count)| format!("{} {}", count, kind))
+            .join(", ");
+        let (outer, inner) = self.lock.circles();
+        format!(
+            "{}: {} {}, difficulty {}{}: {} / {}, {}",
+            self.id,
+            metadata.date,
+            if metadata.solved {
+                "solved"
+            } else {
+                "unsolved"
+            },
+            metadata.difficulty,
+            metadata
+                .source
+                .as_ref()
+                .map_or(String::new(), |it| format!(" ({})", it)),
+            outer.iter().join(" "),
+            inner.iter().join(" "),
+            kinds
+        )
+    }
+}
+
+///Splits the metadata comments at the start of an archived lock file from the lock itself.
+fn parse_metadata(text: &str) -> (Metadata, String) {
+    let mut metadata = Metadata::default();
+    let mut body = text;
+    while let Some((line, rest)) = body.split_once('\n') {
+        let Some((key, value)) = line.strip_prefix("# ").and_then(|it| it.split_once(": ")) else {
+            break;
+        };
+        match key {
+            "date" => metadata.date = value.to_string(),
+            "source" => metadata.source = Some(value.to_string()),
+            "difficulty" => metadata.difficulty = value.parse().unwrap_or_default(),
+            "solved" => metadata.solved = value == "yes",
+            "session" => metadata.sessions.push(value.to_string()),
+            _ => break,
+        }
+        body = rest;
+    }
+    (metadata, body.to_string())
+}
+
+///The kinds of the rules of the lock as they were entered: rules expanded from a template count
+///once, as the template.
+fn rule_kinds(lock: &RuneLock) -> Vec<String> {
+    let kind = |syntax: String| match syntax.split_whitespace().nth(1) {
+        Some(kind @ ("follows" | "before")) => kind.to_string(),
+        _ => syntax
+            .split_whitespace()
+            .last()
+            .unwrap_or_default()
+            .to_string(),
+    };
+    (0..lock.rules.len())
+        .filter(|it| lock.template_of(*it).is_none())
+        .map(|it| kind(lock.rules[it].syntax()))
+        .chain(
+            lock.templates
+                .iter()
+                .map(|(template, _)| kind(template.syntax())),
+        )
+        .collect()
+}
+
+///`None` stands for `*`, which matches any rune.
+fn parse_pattern(pattern: &str) -> Result<Vec<Option<Rune>>, RuneError> {
+    pattern
+        .split_whitespace()
+        .map(|it| match it {
+            "*" => Ok(None),
+            rune => rune.parse().map(Some),
+        })
+        .collect()
+}
+
+///Whether the runes appear in order on one of the circles, going around it.
+fn has_pattern(lock: &RuneLock, pattern: &[Option<Rune>]) -> bool {
+    let sectors = lock.size().sectors();
+    if pattern.len() > sectors {
+        return false;
+    }
+    let (outer, inner) = lock.circles();
+    [outer, inner].iter().any(|circle| {
+        (0..sectors).any(|start| {
+            pattern.iter().enumerate().all(|(offset, it)| {
+                it.is_none_or(|rune| circle[(start + offset) % sectors] == rune)
+            })
+        })
+    })
+}
+
+fn io_error(path: &Path, source: io::Error) -> ArchiveError {
+    ArchiveError::Io {
+        path: path.display().to_string(),
+        source,
+    }
+}
+
+///The current date as `YYYY-MM-DD` in UTC.
+fn today() -> String {
+    let days = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |it| it.as_secs() / 86_400) as i64;
+    //Converts days since 1970-01-01 to a civil date, counting in 400 year eras from 0000-03-01.
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month + 2) / 5 + 1;
+    let month = if month < 10 { month + 3 } else { month - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{lock_file::parse_lock, rune::Rune};
+
+    use super::{has_pattern, parse_metadata, parse_pattern, rule_kinds};
+
+    #[test]
+    fn test_metadata_is_split_from_the_lock() {
+        let text =
+            "# date: 2026-10-16\n# solved: yes\n# session: a.txt\n# A comment\nZ S V C S V\n";
+        let (metadata, body) = parse_metadata(text);
+        assert_eq!(metadata.date, "2026-10-16");
+        assert!(metadata.solved);
+        assert_eq!(metadata.sessions, vec!["a.txt".to_string()]);
+        assert_eq!(body, "# A comment\nZ S V C S V\n");
+    }
+
+    #[test]
+    fn test_search_by_runes_and_kinds() {
+        let lock =
+            parse_lock("Z S V C S V\nC S V Z ? V\n1 2 alwanese\nV follows Z\n3..5 santor-chain\n")
+                .unwrap_or_else(|(span, error)| panic!("{}: {}", span, error));
+        assert!(has_pattern(&lock, &parse_pattern("V Z S").unwrap()));
+        assert!(has_pattern(&lock, &parse_pattern("Z * V C").unwrap()));
+        assert!(has_pattern(&lock, &[Some(Rune::Unknown)]));
+        assert!(!has_pattern(&lock, &parse_pattern("Z Z").unwrap()));
+
+        let mut kinds = rule_kinds(&lock);
+        kinds.sort();
+        assert_eq!(kinds, vec!["alwanese", "follows", "santor-chain"]);
+    }
+}
@@ -1,14 +1,19 @@
 use std::ops::{Index, IndexMut};
 
-use crossterm::style::{Color, ResetColor, SetForegroundColor};
 use thiserror::Error;
 
-use crate::{activation::Activation, index::RunePosition};
+use crate::{
+    activation::Activation,
+    index::RunePosition,
+    size::LockSize,
+    terminal::{self, dimmed},
+};
 
 #[derive(Clone)]
 pub struct Assignment {
-    activation_of_position: [Option<Activation>; 12],
-    position_of_activation: [Option<RunePosition>; 12],
+    size: LockSize,
+    activation_of_position: Vec<Option<Activation>>,
+    position_of_activation: Vec<Option<RunePosition>>,
 }
 
 #[derive(Debug, Error)]
@@ -25,30 +30,47 @@ pub enum AssignmentError {
         activation_a: Activation,
         activation_b: Activation,
     },
+    #[error("{activation} on {position} is not a cell of a lock of {size}")]
+    OutOfRange {
+        position: RunePosition,
+        activation: Activation,
+        size: LockSize,
+    },
 }
 
 impl Assignment {
     pub fn from_tuple_iter(
+        size: LockSize,
         assignment: impl Iterator<Item = (RunePosition, Activation)>,
     ) -> Result<Self, AssignmentError> {
-        let mut target = [None; 12];
+        let mut target = vec![None; size.positions()];
         for (pos, activation) in assignment {
-            if let Some(existing) = target[pos.index()] {
-                return Err(AssignmentError::PositionDoubleAssigned {
-                    position: pos,
-                    activation_a: activation,
-                    activation_b: existing,
-                });
-            } else {
-                target[pos.index()] = Some(activation);
+            match target.get(pos.index()) {
+                None => {
+                    return Err(AssignmentError::OutOfRange {
+                        position: pos,
+                        activation,
+                        size,
+                    })
+                }
+                Some(Some(existing)) => {
+                    return Err(AssignmentError::PositionDoubleAssigned {
+                        position: pos,
+                        activation_a: activation,
+                        activation_b: *existing,
+                    })
+                }
+                Some(None) => target[pos] = Some(activation),
             }
         }
-        Self::new(target)
+        Self::new(size, target)
     }
+    #[allow(clippy::should_implement_trait)]
     pub fn from_iter(
+        size: LockSize,
         mut assignment: impl Iterator<Item = Option<Activation>>,
     ) -> Result<Self, AssignmentError> {
-        let mut target = [None; 12];
+        let mut target = vec![None; size.positions()];
         for i in target.iter_mut() {
             *i = assignment.next().expect("iterator should be long enough");
         }
@@ -56,32 +78,71 @@ impl Assignment {
             assignment.next().is_none(),
             "iterator should not be too long"
         );
-        Self::new(target)
+        Self::new(size, target)
+    }
+    ///Nothing placed yet.
+    pub fn empty(size: LockSize) -> Self {
+        Self {
+            size,
+            activation_of_position: vec![None; size.positions()],
+            position_of_activation: vec![None; size.activations()],
+        }
     }
-    pub fn new(assignment: [Option<Activation>; 12]) -> Result<Self, AssignmentError> {
-        let mut position_of = [None; 12];
+    ///The activation of every position of a lock of `size`.
+    pub fn new(
+        size: LockSize,
+        assignment: Vec<Option<Activation>>,
+    ) -> Result<Self, AssignmentError> {
+        assert_eq!(
+            assignment.len(),
+            size.positions(),
+            "there should be an entry per position"
+        );
+        let mut position_of = vec![None; size.activations()];
         for (position, a) in assignment.iter().enumerate() {
             let position = RunePosition::new(position);
             if let Some(a) = a {
-                if let Some(old) = position_of[a.index()] {
-                    return Err(AssignmentError::ActivationDoubleAssigned {
-                        activation: *a,
-                        position_a: position,
-                        position_b: old,
-                    });
+                match position_of.get(a.index()) {
+                    None => {
+                        return Err(AssignmentError::OutOfRange {
+                            position,
+                            activation: *a,
+                            size,
+                        })
+                    }
+                    Some(Some(old)) => {
+                        return Err(AssignmentError::ActivationDoubleAssigned {
+                            activation: *a,
+                            position_a: position,
+                            position_b: *old,
+                        })
+                    }
+                    Some(None) => position_of[a.index()] = Some(position),
                 }
-                position_of[a.index()] = Some(position);
             }
         }
 
         Ok(Self {
+            size,
             activation_of_position: assignment,
             position_of_activation: position_of,
         })
     }
 
+    pub fn size(&self) -> LockSize {
+        self.size
+    }
+
+    ///Whether every activation is placed.
+    pub fn is_complete(&self) -> bool {
+        self.position_of_activation.iter().all(|it| it.is_some())
+    }
+
     pub fn position_of(&self, number: Activation) -> Option<RunePosition> {
-        self.position_of_activation[number.index()]
+        self.position_of_activation
+            .get(number.index())
+            .copied()
+            .flatten()
     }
 
     pub fn assign(&mut self, position: RunePosition, activation: Activation) {
@@ -96,42 +157,85 @@ impl Assignment {
     }
 
     pub fn contains(&self, a: Activation) -> bool {
-        self.activation_of_position.iter().any(|it| *it == Some(a))
+        self.activation_of_position.contains(&Some(a))
     }
 
-    pub fn print(&self) {
+    ///The placements drawn on the circles, or a line per position in accessible mode.
+    pub fn rendered(&self) -> String {
+        if terminal::accessible() {
+            return self
+                .activation_of_position
+                .iter()
+                .enumerate()
+                .map(|(position, activation)| match activation {
+                    Some(it) => format!("Position {}: {}\n", position, it),
+                    None => format!("Position {}: open\n", position),
+                })
+                .collect();
+        }
         let assignment: Vec<_> = self
             .activation_of_position
             .iter()
             .enumerate()
             .map(|(index, it)| match it {
                 Some(it) => format!("{:3}", format!("{}", it)),
-                None => format!(
-                    "{}{:3}{}",
-                    SetForegroundColor(Color::DarkGrey),
-                    index,
-                    ResetColor,
-                ),
+                None => dimmed(format!("{:3}", index)),
             })
             .collect();
-        println!(
-            include_str!("hexagon.txt"),
-            assignment[0],
-            assignment[1],
-            assignment[2],
-            assignment[3],
-            assignment[4],
-            assignment[5],
-            assignment[6],
-            assignment[7],
-            assignment[8],
-            assignment[9],
-            assignment[10],
-            assignment[11],
-        )
+        format!("{}\n", render(2, &assignment))
     }
 }
 
+///Drawings of the circles by their geometry, the number of circles and the positions on each. A
+///`{n}` in a drawing is replaced by the label of position n.
+const DRAWINGS: [((usize, usize), &str); 1] = [((2, 6), include_str!("hexagon.txt"))];
+
+///Fills the labels of the positions, circle by circle, into the drawing of their geometry. If there
+///is no drawing for it, or its slots don't match the positions, every circle is listed on a line
+///instead.
+pub fn render(circles: usize, labels: &[String]) -> String {
+    let geometry = (circles, labels.len() / circles.max(1));
+    let drawing = DRAWINGS
+        .iter()
+        .find(|(it, _)| *it == geometry && geometry.0 * geometry.1 == labels.len())
+        .and_then(|(_, drawing)| fill(drawing, labels));
+    if let Some(drawing) = drawing {
+        return drawing;
+    }
+    labels
+        .chunks(geometry.1.max(1))
+        .enumerate()
+        .map(|(index, labels)| {
+            let name = match (circles, index) {
+                (2, 0) => "Outer circle".to_string(),
+                (2, 1) => "Inner circle".to_string(),
+                (_, index) => format!("Circle {}", index),
+            };
+            format!("{}: {}", name, labels.join(" "))
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+///The drawing with its slots filled, if it has exactly one slot per label.
+fn fill(drawing: &str, labels: &[String]) -> Option<String> {
+    let mut filled = String::with_capacity(drawing.len());
+    let mut used = vec![false; labels.len()];
+    let mut rest = drawing;
+    while let Some(start) = rest.find('{') {
+        filled.push_str(&rest[..start]);
+        let end = start + rest[start..].find('}')?;
+        let index: usize = rest[start + 1..end].parse().ok()?;
+        if std::mem::replace(used.get_mut(index)?, true) {
+            return None;
+        }
+        filled.push_str(&labels[index]);
+        rest = &rest[end + 1..];
+    }
+    filled.push_str(rest);
+    used.into_iter().all(|it| it).then_some(filled)
+}
+
 impl Index<RunePosition> for Assignment {
     type Output = Option<Activation>;
 
@@ -144,3 +248,30 @@ impl IndexMut<RunePosition> for Assignment {
         &mut self.activation_of_position[index]
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{fill, render};
+
+    #[test]
+    fn test_geometries_without_a_drawing_are_listed() {
+        let labels: Vec<_> = (0..12).map(|it| format!("#{}", it)).collect();
+        let hexagon = render(2, &labels);
+        assert!(labels.iter().all(|it| hexagon.contains(it.as_str())));
+        assert!(hexagon.contains('X'));
+
+        assert_eq!(
+            render(2, &labels[..10]),
+            "Outer circle: #0 #1 #2 #3 #4\nInner circle: #5 #6 #7 #8 #9"
+        );
+        assert_eq!(
+            render(3, &labels[..6]),
+            "Circle 0: #0 #1\nCircle 1: #2 #3\nCircle 2: #4 #5"
+        );
+
+        assert_eq!(fill("{1} {0}", &labels[..2]), Some("#1 #0".into()));
+        assert_eq!(fill("{0} {2}", &labels[..2]), None);
+        assert_eq!(fill("{0} {0}", &labels[..2]), None);
+        assert_eq!(fill("{0}", &labels[..2]), None);
+    }
+}
@@ -0,0 +1,35 @@
+//! Checks certificates written by `export-proof`, step by step against the rules of the lock they
+//! contain, without running the solver.
+
+use std::{env, path::Path, process};
+
+use rune_lock_solver::fact_solver::certificate::Certificate;
+
+fn main() {
+    let paths: Vec<_> = env::args().skip(1).collect();
+    if paths.is_empty() {
+        eprintln!("Usage: rune-lock-verify-proof <certificate>...");
+        process::exit(2);
+    }
+    let mut all_valid = true;
+    for path in paths.iter() {
+        let verified = Certificate::load(Path::new(path))
+            .map_err(|err| err.to_string())
+            .and_then(|it| it.verify().map_err(|err| err.to_string()));
+        match verified {
+            Ok(verified) => {
+                println!("{}: valid, {}", path, verified.claim);
+                for premise in verified.premises.iter() {
+                    println!("  assuming {}", premise);
+                }
+            }
+            Err(err) => {
+                println!("{}: invalid, {}", path, err);
+                all_valid = false;
+            }
+        }
+    }
+    if !all_valid {
+        process::exit(1);
+    }
+}
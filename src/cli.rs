@@ -0,0 +1,805 @@
+//! Command line interface: an interactive session by default, and subcommands for scripted use.
+
+use std::{
+    env,
+    ffi::OsString,
+    fs::{self, File, OpenOptions},
+    io::{self, BufRead, BufReader, IsTerminal, Write},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    time::Instant,
+};
+
+use clap::{CommandFactory, Parser, Subcommand, ValueEnum};
+use itertools::Itertools;
+use rustyline::{
+    completion::Completer, error::ReadlineError, highlight::Highlighter, hint::Hinter,
+    history::FileHistory, validate::Validator, Context, Editor, Helper,
+};
+use thiserror::Error;
+
+use rune_lock_solver::{
+    activation::{Activation, HumanActivation},
+    archive::{Archive, ArchiveError, Query},
+    assignment::Assignment,
+    diagnosis,
+    fact_solver::{
+        pattern::{FactPattern, FactPatternError},
+        search::SolutionCount,
+        session::SessionError,
+        verify, FactualSolver,
+    },
+    inference,
+    jobs::CancelToken,
+    lock_file, LockSize, RuneLock,
+};
+
+use crate::command::{COMMANDS, NODE_COMMANDS};
+
+///How many partial assignments `solve` visits before it gives up counting solutions.
+pub const SOLVE_LIMIT: usize = 50_000_000;
+
+#[derive(Parser)]
+#[command(
+    about = "Solve and explore Rune Circle Locks",
+    args_override_self = true
+)]
+pub struct Cli {
+    ///Take the global flags from a TOML file first, with their long names as keys, like
+    ///`accessible = true` or `messages = "de.toml"`. Flags on the command line win.
+    #[arg(long, global = true)]
+    pub config: Option<PathBuf>,
+    ///The solver exploring the locks.
+    #[arg(long, global = true, value_enum, default_value_t = Engine::Fact)]
+    pub engine: Engine,
+    ///Print the results of `solve`, `check` and `verify` as JSON, short for `--format json`.
+    #[arg(long, global = true)]
+    pub json: bool,
+    ///How `solve`, `check` and `verify` print their results.
+    #[arg(long, global = true, value_enum, default_value_t = OutputFormat::Text)]
+    pub format: OutputFormat,
+    ///What to log, like `debug` or `rune_lock_solver::fact_solver=trace`, instead of what
+    ///`RUST_LOG` says. Requires the `logging` feature.
+    #[arg(long, global = true)]
+    pub log_level: Option<String>,
+    ///Read the session input from this file instead of stdin. Scripts and piped input only print
+    ///the output of the commands, and exit with 0 if the last current state is solved, 3 if it is
+    ///still open, 4 if it contradicts and 1 if an assertion failed.
+    #[arg(long, global = true)]
+    pub script: Option<PathBuf>,
+    ///Phrase rules and facts with the messages of this catalog, a TOML table of message keys to
+    ///templates. Messages it leaves out stay in English.
+    #[arg(long, global = true)]
+    pub messages: Option<PathBuf>,
+    ///Where sessions keep what each rule kind did, to consolidate the kinds that paid off most
+    ///first in later sessions. By default `rune-lock/kind-stats.json` in `$XDG_DATA_HOME` or
+    ///`~/.local/share`, which scripts and piped input leave alone.
+    #[arg(long, global = true)]
+    pub kind_stats: Option<PathBuf>,
+    ///Where the lines typed in interactive sessions are kept, to be recalled in later ones. By
+    ///default `rune-lock/history` in `$XDG_DATA_HOME` or `~/.local/share`.
+    #[arg(long, global = true)]
+    pub history: Option<PathBuf>,
+    ///Don't keep the lines typed in interactive sessions.
+    #[arg(long, global = true)]
+    pub no_history: bool,
+    ///Neither read nor update the rule kind statistics.
+    #[arg(long, global = true)]
+    pub no_kind_stats: bool,
+    ///Where the macros defined with `macro define` are kept. By default `rune-lock/macros.toml`
+    ///in `$XDG_DATA_HOME` or `~/.local/share`.
+    #[arg(long, global = true)]
+    pub macros: Option<PathBuf>,
+    ///Neither read nor update the macros.
+    #[arg(long, global = true)]
+    pub no_macros: bool,
+    ///Print the tree, grids and hexagon as labelled lines of plain text, for screen readers.
+    #[arg(long, global = true, env = "RUNE_LOCK_ACCESSIBLE")]
+    pub accessible: bool,
+    #[command(subcommand)]
+    pub command: Option<CliCommand>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    Text,
+    Json,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Engine {
+    ///Deduces facts with the reasons they rest on, and explores assumptions in a tree of states.
+    ///The only engine so far.
+    Fact,
+}
+
+#[derive(Debug, Error)]
+pub enum ConfigError {
+    #[error("Could not access {path}: {source}")]
+    Io { path: String, source: io::Error },
+    #[error("{path} is not a config file: {source}")]
+    Format {
+        path: String,
+        source: toml::de::Error,
+    },
+    #[error("'{0}' is not a flag that can be configured")]
+    UnknownFlag(String),
+    #[error("'{0}' has to be a string, a number or a boolean")]
+    Value(String),
+}
+
+impl Cli {
+    pub fn json(&self) -> bool {
+        self.json || self.format == OutputFormat::Json
+    }
+
+    ///Where the typed lines are kept, if they are.
+    pub fn history(&self) -> Option<PathBuf> {
+        match (self.no_history, &self.history) {
+            (true, _) => None,
+            (false, Some(path)) => Some(path.clone()),
+            (false, None) => data_dir().map(|it| it.join("history")),
+        }
+    }
+
+    ///Where the rule kind statistics are kept, if they are. Without `--kind-stats`, `batch`
+    ///sessions don't use them, so scripts run the same everywhere and leave no files behind.
+    pub fn kind_stats(&self, batch: bool) -> Option<PathBuf> {
+        match (self.no_kind_stats, &self.kind_stats) {
+            (true, _) => None,
+            (false, Some(path)) => Some(path.clone()),
+            (false, None) if batch => None,
+            (false, None) => data_dir().map(|it| it.join("kind-stats.json")),
+        }
+    }
+
+    ///Where the macros are kept, if they are.
+    pub fn macros(&self) -> Option<PathBuf> {
+        match (self.no_macros, &self.macros) {
+            (true, _) => None,
+            (false, Some(path)) => Some(path.clone()),
+            (false, None) => data_dir().map(|it| it.join("macros.toml")),
+        }
+    }
+
+    ///Parses the command line, after the flags of the `--config` file if one is given.
+    pub fn parse_with_config() -> Result<Self, ConfigError> {
+        let cli = Self::parse();
+        let Some(path) = cli.config.as_deref() else {
+            return Ok(cli);
+        };
+        let config = config_args(path)?;
+        let mut args = env::args_os();
+        Ok(Self::parse_from(
+            args.next().into_iter().chain(config).chain(args),
+        ))
+    }
+}
+
+///The flags a config file sets, as command line arguments. Flags set to `false` are left out.
+fn config_args(path: &Path) -> Result<Vec<OsString>, ConfigError> {
+    let text = fs::read_to_string(path).map_err(|source| ConfigError::Io {
+        path: path.display().to_string(),
+        source,
+    })?;
+    let table: toml::Table = toml::from_str(&text).map_err(|source| ConfigError::Format {
+        path: path.display().to_string(),
+        source,
+    })?;
+    let command = Cli::command();
+    let mut args = Vec::new();
+    for (key, value) in table {
+        let known = command
+            .get_arguments()
+            .any(|it| it.is_global_set() && it.get_long() == Some(key.as_str()));
+        if !known || key == "config" {
+            return Err(ConfigError::UnknownFlag(key));
+        }
+        let flag = OsString::from(format!("--{}", key));
+        match value {
+            toml::Value::Boolean(true) => args.push(flag),
+            toml::Value::Boolean(false) => {}
+            toml::Value::String(it) => args.extend([flag, it.into()]),
+            toml::Value::Integer(it) => args.extend([flag, it.to_string().into()]),
+            toml::Value::Float(it) => args.extend([flag, it.to_string().into()]),
+            _ => return Err(ConfigError::Value(key)),
+        }
+    }
+    Ok(args)
+}
+
+#[derive(Subcommand)]
+pub enum CliCommand {
+    ///Explore a lock interactively. Without a lock file, the lock hardcoded in `main.rs` is used.
+    #[command(alias = "explore")]
+    Repl { lockfile: Option<PathBuf> },
+    ///Enter the runes and rules of a new lock, then explore it.
+    NewLock,
+    ///Derive what follows from the lock alone, and count its solutions.
+    Solve { lockfile: PathBuf },
+    ///Check that every lock file has exactly one solution, and print a summary line per lock.
+    ///Exits with 0 if all of them do, and otherwise with the code of the worst verdict: 3 for
+    ///multiple solutions, 4 for none, 2 if a lock couldn't be checked.
+    Check {
+        #[arg(required = true)]
+        lockfiles: Vec<PathBuf>,
+    },
+    ///Check a solution, given as the activations at the positions from 0 up, `-` where a lock with
+    ///fewer activations than positions leaves one empty (e.g. "3 12 1 ...").
+    Verify { lockfile: PathBuf, solution: String },
+    ///Print the hardcoded lock in the lock file format, as a starting point for new lock files.
+    Generate,
+    ///List the rules that hold in all of the given solutions, as hypotheses for hints that weren't
+    ///found in the game yet.
+    Infer {
+        lockfile: PathBuf,
+        ///Solutions in the format `verify` takes.
+        #[arg(required = true)]
+        solutions: Vec<String>,
+        ///Only list rules of this kind, like alwanese. Can be given several times.
+        #[arg(long = "kind")]
+        kinds: Vec<String>,
+        ///Append the listed rules to the lock file, marked as unverified.
+        #[arg(long)]
+        into: bool,
+    },
+    ///List the facts of saved sessions matching a pattern like "#3 cannot 7" or "* * 7"
+    ///(activation, must/cannot/contradiction, position; `*` matches anything), with the state
+    ///each was deduced in.
+    GrepFacts {
+        pattern: String,
+        ///Saved sessions, or directories whose `.json` files are searched if they are sessions.
+        #[arg(default_value = ".")]
+        paths: Vec<PathBuf>,
+    },
+    ///Keep past locks in an archive, and find them again.
+    Locks {
+        ///The directory of the archive.
+        #[arg(long, default_value = ".rune-locks")]
+        archive: PathBuf,
+        #[command(subcommand)]
+        command: LocksCommand,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum LocksCommand {
+    ///Copy a lock file into the archive.
+    Add {
+        lockfile: PathBuf,
+        ///Where the lock was found, e.g. "daily".
+        #[arg(long)]
+        source: Option<String>,
+        ///Defaults to today.
+        #[arg(long)]
+        date: Option<String>,
+        #[arg(long)]
+        solved: bool,
+    },
+    List,
+    ///List the locks with all of the given rule kinds and runes.
+    Search {
+        ///A rule kind like santor-chain. Give it three times to find locks with three of them.
+        #[arg(long = "rule")]
+        rules: Vec<String>,
+        ///Runes in the order they appear on a circle, `*` matching any rune (e.g. "Z * V").
+        #[arg(long)]
+        runes: Option<String>,
+        #[arg(long, conflicts_with = "unsolved")]
+        solved: bool,
+        #[arg(long)]
+        unsolved: bool,
+    },
+    ///Mark a lock as solved.
+    Solved {
+        id: usize,
+    },
+    ///Link a recorded session script to a lock.
+    Link {
+        id: usize,
+        session: PathBuf,
+    },
+    ///Print an archived lock file.
+    Show {
+        id: usize,
+    },
+}
+
+///Where the files kept across sessions go unless a flag says otherwise: `rune-lock` in
+///`$XDG_DATA_HOME`, or in `~/.local/share`. There is none without a home directory.
+pub fn data_dir() -> Option<PathBuf> {
+    env::var_os("XDG_DATA_HOME")
+        .filter(|it| !it.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| Path::new(&home).join(".local/share")))
+        .map(|it| it.join("rune-lock"))
+}
+
+///The ids of the states as of the last command, kept up to date by the session.
+pub type NodeNames = Arc<Mutex<Vec<String>>>;
+
+///Completes the command names at the start of a line, and the ids of the states after the
+///commands taking them.
+struct LineHelper {
+    nodes: NodeNames,
+}
+
+impl Completer for LineHelper {
+    type Candidate = String;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _: &Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<String>)> {
+        let start = line[..pos].rfind(' ').map_or(0, |it| it + 1);
+        let word = &line[start..pos];
+        let command = line.split(' ').next().unwrap_or_default();
+        let candidates = match start {
+            0 => COMMANDS
+                .iter()
+                .filter(|it| it.starts_with(word))
+                .map(|it| it.to_string())
+                .collect(),
+            _ if NODE_COMMANDS.contains(&command) => {
+                let nodes = self.nodes.lock().unwrap_or_else(|it| it.into_inner());
+                //The `n` can be left out while completing.
+                nodes
+                    .iter()
+                    .filter(|it| it.starts_with(word) || it[1..].starts_with(word))
+                    .cloned()
+                    .collect()
+            }
+            _ => Vec::new(),
+        };
+        Ok((start, candidates))
+    }
+}
+
+impl Hinter for LineHelper {
+    type Hint = String;
+}
+
+impl Highlighter for LineHelper {}
+
+impl Validator for LineHelper {}
+
+impl Helper for LineHelper {}
+
+pub type InputLines = Box<dyn Iterator<Item = io::Result<String>> + Send>;
+
+///The lines of the script, or of stdin if there is none. Lines typed on a terminal can be edited
+///and recalled, from this session or the earlier ones kept in `history`, and completed with the
+///`nodes` of the session.
+pub fn input_lines(
+    script: Option<&Path>,
+    history: Option<&Path>,
+    nodes: NodeNames,
+) -> io::Result<InputLines> {
+    match script {
+        Some(path) => Ok(Box::new(BufReader::new(File::open(path)?).lines())),
+        None if io::stdin().is_terminal() => edited_lines(history, nodes),
+        None => Ok(Box::new(std::iter::from_fn(|| {
+            let mut line = String::new();
+            match io::stdin().read_line(&mut line) {
+                Ok(0) => None,
+                Ok(_) => Some(Ok(line.trim_end_matches(['\n', '\r']).to_string())),
+                Err(err) => Some(Err(err)),
+            }
+        }))),
+    }
+}
+
+///Lines read with line editing, completion, history recall and search.
+fn edited_lines(history: Option<&Path>, nodes: NodeNames) -> io::Result<InputLines> {
+    let mut editor = Editor::<LineHelper, FileHistory>::new().map_err(io::Error::other)?;
+    editor.set_helper(Some(LineHelper { nodes }));
+    let history = history.map(Path::to_path_buf);
+    if let Some(path) = history.as_ref() {
+        //There is no history before the first session. Failing to create its directory shows when
+        //the history is updated.
+        if let Some(parent) = path.parent() {
+            let _ = fs::create_dir_all(parent);
+        }
+        let _ = editor.load_history(path);
+    }
+    Ok(Box::new(std::iter::from_fn(move || loop {
+        match editor.readline("> ") {
+            Ok(line) => {
+                if !line.trim().is_empty() && editor.add_history_entry(line.as_str()).is_ok() {
+                    if let Some(path) = history.as_ref() {
+                        if let Err(err) = editor.append_history(path) {
+                            println!("Could not update the history: {}", err);
+                        }
+                    }
+                }
+                return Some(Ok(line));
+            }
+            //Ctrl-C discards the line, like in a shell.
+            Err(ReadlineError::Interrupted) => continue,
+            Err(ReadlineError::Eof) => return None,
+            Err(err) => return Some(Err(io::Error::other(err))),
+        }
+    })))
+}
+
+pub fn solve(lock: RuneLock, json: bool, interrupt: &CancelToken) {
+    let solver = FactualSolver::new(lock);
+    let completions = verify::enumerate(
+        solver.lock(),
+        &[],
+        SOLVE_LIMIT,
+        interrupt,
+        usize::MAX,
+        |_, _| {},
+    );
+    let fixed = solver.fixed_assignment();
+    if json {
+        let fixed = solver
+            .lock()
+            .size()
+            .all_positions()
+            .map(|it| match fixed.as_ref().ok().and_then(|fixed| fixed[it]) {
+                Some(activation) => HumanActivation::from(activation).number().to_string(),
+                None => "null".to_string(),
+            })
+            .join(", ");
+        let examples = completions
+            .examples
+            .iter()
+            .map(|it| format!("[{}]", activations_of(it).join(", ")))
+            .join(", ");
+        println!(
+            "{{\"fixed\": [{}], \"solutions\": {}, \"complete\": {}, \"examples\": [{}]}}",
+            fixed, completions.count, completions.complete, examples
+        );
+        return;
+    }
+
+    print!("{}", solver.render_ui().0);
+    match completions.complete {
+        true => println!("{} solutions.", completions.count),
+        false if completions.interrupted => println!(
+            "At least {} solutions, interrupted while counting.",
+            completions.count
+        ),
+        false => println!(
+            "At least {} solutions, gave up counting after {} partial assignments.",
+            completions.count, SOLVE_LIMIT
+        ),
+    }
+    for example in completions.examples.iter() {
+        println!("  {}", activations_of(example).join(" "));
+    }
+}
+
+///How many solutions a lock has, as far as `check` is concerned. Ordered from best to worst.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Verdict {
+    Unique,
+    Multiple,
+    Unsolvable,
+    Error,
+}
+
+impl Verdict {
+    pub fn name(self) -> &'static str {
+        match self {
+            Verdict::Unique => "unique",
+            Verdict::Multiple => "multiple",
+            Verdict::Unsolvable => "unsolvable",
+            Verdict::Error => "error",
+        }
+    }
+
+    pub fn exit_code(self) -> i32 {
+        match self {
+            Verdict::Unique => 0,
+            Verdict::Error => 2,
+            Verdict::Multiple => 3,
+            Verdict::Unsolvable => 4,
+        }
+    }
+}
+
+///Checks whether each lock has a unique solution, printing a line per lock and a total. Returns
+///the worst verdict.
+pub fn check(lockfiles: &[PathBuf], json: bool, interrupt: &CancelToken) -> Verdict {
+    let mut verdicts = Vec::new();
+    for path in lockfiles {
+        let started = Instant::now();
+        let (verdict, count, nodes, error) = match RuneLock::from_path(path) {
+            Ok(mut lock) => {
+                lock.canonicalize();
+                let mut solver = FactualSolver::new(lock);
+                solver.set_interrupt(interrupt.clone());
+                let counted = solver.count_solutions(2);
+                let verdict = match counted {
+                    SolutionCount {
+                        count: 0,
+                        complete: true,
+                        ..
+                    } => Verdict::Unsolvable,
+                    SolutionCount {
+                        count: 1,
+                        complete: true,
+                        ..
+                    } => Verdict::Unique,
+                    SolutionCount { count: 2.., .. } => Verdict::Multiple,
+                    SolutionCount { .. } => Verdict::Error,
+                };
+                let error = (verdict == Verdict::Error).then(|| "interrupted".to_string());
+                (verdict, counted.count, counted.nodes, error)
+            }
+            Err(err) => (Verdict::Error, 0, 0, Some(err.to_string())),
+        };
+        let millis = started.elapsed().as_millis();
+        if json {
+            let error = match &error {
+                Some(error) => json_string(error),
+                None => "null".to_string(),
+            };
+            println!(
+                "{{\"lock\": {}, \"verdict\": \"{}\", \"solutions\": {}, \"millis\": {}, \"nodes\": {}, \"error\": {}}}",
+                json_string(&path.display().to_string()),
+                verdict.name(),
+                count,
+                millis,
+                nodes,
+                error
+            );
+        } else {
+            match error {
+                Some(error) => println!("{}: {} ({})", path.display(), verdict.name(), error),
+                None => println!(
+                    "{}: {}, {} ms, {} nodes",
+                    path.display(),
+                    verdict.name(),
+                    millis,
+                    nodes
+                ),
+            }
+        }
+        verdicts.push(verdict);
+        if interrupt.is_cancelled() {
+            break;
+        }
+    }
+    if !json {
+        let counts = [
+            Verdict::Unique,
+            Verdict::Multiple,
+            Verdict::Unsolvable,
+            Verdict::Error,
+        ]
+        .map(|verdict| {
+            let count = verdicts.iter().filter(|it| **it == verdict).count();
+            format!("{} {}", count, verdict.name())
+        });
+        println!("{} locks: {}", verdicts.len(), counts.join(", "));
+    }
+    verdicts.into_iter().max().unwrap_or(Verdict::Unique)
+}
+
+///Checks a solution against the rules and domains of the lock. Returns whether it is valid.
+pub fn verify(lock: &RuneLock, solution: &str, json: bool) -> bool {
+    let result = parse_solution(solution, lock.size()).and_then(|assignment| {
+        lock.validate(&assignment).map_err(|it| it.to_string())?;
+        match diagnosis::violated_givens(lock, &assignment).first() {
+            Some(index) => Err(format!(
+                "Given {} is violated: {}",
+                index,
+                lock.domains()[*index]
+            )),
+            None => Ok(()),
+        }
+    });
+    match (&result, json) {
+        (Ok(()), true) => println!("{{\"valid\": true}}"),
+        (Err(err), true) => println!("{{\"valid\": false, \"error\": {}}}", json_string(err)),
+        (Ok(()), false) => println!("The solution is valid."),
+        (Err(err), false) => println!("The solution is invalid: {}", err),
+    }
+    result.is_ok()
+}
+
+///Prints the rules consistent with all solutions, and appends them to the lock file `into`.
+pub fn infer(
+    lock: &RuneLock,
+    solutions: &[String],
+    kinds: &[String],
+    into: Option<&Path>,
+) -> Result<(), String> {
+    let solutions: Vec<_> = solutions
+        .iter()
+        .map(|it| parse_solution(it, lock.size()))
+        .collect::<Result<_, _>>()?;
+    for (index, solution) in solutions.iter().enumerate() {
+        lock.validate(solution)
+            .map_err(|it| format!("Solution {} does not solve the lock: {}", index + 1, it))?;
+    }
+    let rules: Vec<_> = inference::consistent_rules(lock, &solutions)
+        .into_iter()
+        .map(|it| it.syntax())
+        .filter(|rule| {
+            kinds.is_empty()
+                || kinds
+                    .iter()
+                    .any(|kind| rule.split_whitespace().last() == Some(kind))
+        })
+        .collect();
+    println!(
+        "{} rules hold in all {} solutions. They are unverified, and might only hold by chance:",
+        rules.len(),
+        solutions.len()
+    );
+    for rule in rules.iter() {
+        println!("unverified {}", rule);
+    }
+    if let Some(path) = into {
+        if lock_file::is_toml(path) {
+            return Err("Rules can only be appended to lock files in the text format".into());
+        }
+        let mut file = OpenOptions::new()
+            .append(true)
+            .open(path)
+            .map_err(|it| format!("Could not open {}: {}", path.display(), it))?;
+        let mut lines = format!("# Inferred from {} solutions\n", solutions.len());
+        //Appended lines would belong to the last stage of the file otherwise.
+        if (0..lock.rules().len()).any(|it| lock.stage_of(it) > 0) {
+            lines.push_str("stage 0\n");
+        }
+        for rule in rules.iter() {
+            lines.push_str(&format!("unverified {}\n", rule));
+        }
+        file.write_all(lines.as_bytes())
+            .map_err(|it| format!("Could not write {}: {}", path.display(), it))?;
+        println!("Appended them to {}.", path.display());
+    }
+    Ok(())
+}
+
+///Searches the sessions for facts matching the pattern, printing a line per fact and a total.
+pub fn grep_facts(pattern: &str, paths: &[PathBuf]) -> Result<(), String> {
+    let pattern: FactPattern = pattern
+        .parse()
+        .map_err(|it: FactPatternError| it.to_string())?;
+    let mut sessions = Vec::new();
+    for path in paths {
+        if !path.is_dir() {
+            sessions.push((path.clone(), true));
+            continue;
+        }
+        let entries = fs::read_dir(path)
+            .map_err(|it| format!("Could not read {}: {}", path.display(), it))?;
+        sessions.extend(
+            entries
+                .filter_map(|it| Some(it.ok()?.path()))
+                .filter(|it| it.extension().is_some_and(|it| it == "json"))
+                .sorted()
+                .map(|it| (it, false)),
+        );
+    }
+
+    let (mut searched, mut matching, mut total) = (0, 0, 0);
+    for (path, named) in sessions {
+        let solver = match FactualSolver::load(&path) {
+            Ok(solver) => solver,
+            //Other JSON files in a directory, like the rule kind statistics, are no sessions.
+            Err(SessionError::Format { .. }) if !named => continue,
+            Err(err) => {
+                println!("{}", err);
+                continue;
+            }
+        };
+        searched += 1;
+        let found = solver.find_facts(&pattern);
+        for (node, handle, fact) in found.iter() {
+            println!("{} state {}: {}: {}", path.display(), node, handle, fact);
+        }
+        matching += usize::from(!found.is_empty());
+        total += found.len();
+    }
+    println!(
+        "{} facts found in {} of {} sessions.",
+        total, matching, searched
+    );
+    Ok(())
+}
+
+pub fn locks(directory: &Path, command: LocksCommand) -> Result<(), ArchiveError> {
+    let archive = Archive::open(directory)?;
+    match command {
+        LocksCommand::Add {
+            lockfile,
+            source,
+            date,
+            solved,
+        } => {
+            let entry = archive.add(&lockfile, source, date, solved)?;
+            println!("Added {}", entry.summary());
+        }
+        LocksCommand::List => {
+            for entry in archive.entries()? {
+                println!("{}", entry.summary());
+            }
+        }
+        LocksCommand::Search {
+            rules,
+            runes,
+            solved,
+            unsolved,
+        } => {
+            let query = Query {
+                kinds: rules,
+                runes,
+                solved: match (solved, unsolved) {
+                    (true, _) => Some(true),
+                    (_, true) => Some(false),
+                    _ => None,
+                },
+            };
+            let found = archive.search(&query)?;
+            for entry in found.iter() {
+                println!("{}", entry.summary());
+            }
+            println!("{} locks found.", found.len());
+        }
+        LocksCommand::Solved { id } => {
+            let entry = archive.update(id, |it| it.solved = true)?;
+            println!("{}", entry.summary());
+        }
+        LocksCommand::Link { id, session } => {
+            archive.update(id, |it| it.sessions.push(session.display().to_string()))?;
+            println!("Linked {} to lock {}.", session.display(), id);
+        }
+        LocksCommand::Show { id } => {
+            let entry = archive.get(id)?;
+            println!("{}", entry.summary());
+            for session in entry.metadata.sessions.iter() {
+                println!("Session: {}", session);
+            }
+            print!("{}", lock_file::write_lock(&entry.lock));
+        }
+    }
+    Ok(())
+}
+
+///The activation of every position of a lock of `size`, 1 based, `-` for a position a lock of
+///fewer activations than positions leaves empty.
+pub fn parse_solution(solution: &str, size: LockSize) -> Result<Assignment, String> {
+    let activations: Vec<Option<Activation>> = solution
+        .split(|it: char| it.is_whitespace() || it == ',')
+        .filter(|it| !it.is_empty())
+        .map(|it| match it {
+            "-" => Ok(None),
+            it => it.parse::<HumanActivation>().map(|it| Some(it.into())),
+        })
+        .collect::<Result<_, _>>()
+        .map_err(|it| it.to_string())?;
+    if activations.len() != size.positions() {
+        return Err(format!(
+            "Expected {} activations, got {}",
+            size.positions(),
+            activations.len()
+        ));
+    }
+    Assignment::new(size, activations).map_err(|it| it.to_string())
+}
+
+///The 1 based activations of every position, `-` where there is none.
+pub fn activations_of(assignment: &Assignment) -> Vec<String> {
+    assignment
+        .size()
+        .all_positions()
+        .map(|it| match assignment[it] {
+            Some(activation) => HumanActivation::from(activation).number().to_string(),
+            None => "-".to_string(),
+        })
+        .collect()
+}
+
+fn json_string(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
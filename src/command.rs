@@ -1,11 +1,21 @@
-use std::num::ParseIntError;
+use std::{num::ParseIntError, path::PathBuf};
 
 use thiserror::Error;
 
-use crate::{
-    activation::{Activation, ActivationError},
-    fact_solver::fact_db::FactHandle,
+use rune_lock_solver::{
+    activation::{ActivationError, HumanActivation},
+    coordinate::{self, CoordinateError},
+    fact_solver::{
+        assertion::Assertion,
+        assumption_tree::{NodeId, NodeIdError},
+        edit::LockEdit,
+        fact_db::{FactHandle, FactHandleError},
+        merge::Side,
+    },
     index::RunePosition,
+    jobs::JobId,
+    rune::{Rune, RuneError},
+    size::LockSize,
 };
 
 #[derive(Debug, Error)]
@@ -16,72 +26,662 @@ pub enum SolverCommandError {
     NotEnoughArguments { expected: usize },
     #[error("Argument could not be parsed as a number: {0}")]
     NumberFormat(#[from] ParseIntError),
+    #[error("Unknown argument: {0}")]
+    UnknownArgument(String),
     #[error("Activation is invalid: {0}")]
     ActivationInvalid(#[from] ActivationError),
+    #[error("{0}")]
+    RuneInvalid(#[from] RuneError),
+    #[error("{0}")]
+    NodeIdInvalid(#[from] NodeIdError),
+    #[error("{0}")]
+    FactHandleInvalid(#[from] FactHandleError),
+    #[error("{0}")]
+    CoordinateInvalid(#[from] CoordinateError),
 }
 pub enum SolverCommand {
     View {
-        node: usize,
+        node: NodeId,
     },
     Assume {
         position: RunePosition,
-        activation: Activation,
+        activation: HumanActivation,
+        ///Assume it even if it is not a bifurcation in guaranteed progress mode.
+        force: bool,
+    },
+    ///Go back to the state the current one was assumed in.
+    Up,
+    ///Go back to the root state.
+    Root,
+    ///Remove a state and everything assumed in it.
+    Prune {
+        node: NodeId,
     },
     TryInPosition {
         position: RunePosition,
+        ///Also explore candidates another state already covers.
+        force: bool,
     },
     TryActivation {
-        activation: Activation,
+        activation: HumanActivation,
+        force: bool,
     },
+    ///Explain why a fact holds. Without `max_depth`, the explanation is fit into a few lines
+    ///instead. With `dot`, the proof is written to that file as a Graphviz graph.
     Explain {
         fact_handle: FactHandle,
-        // max_depth: usize,
+        max_depth: Option<usize>,
+        minimal: bool,
+        dot: Option<PathBuf>,
+    },
+    Advise {
+        activation: Option<HumanActivation>,
+    },
+    ///Explain why a candidate is eliminated in one state but not in the other.
+    ExplainDiff {
+        node_a: NodeId,
+        node_b: NodeId,
+        position: RunePosition,
+        activation: HumanActivation,
+    },
+    ///List the rules with their indices, or only those about the activation.
+    Rules {
+        activation: Option<HumanActivation>,
+    },
+    ///Show where the two activations of a rule can be placed relative to each other.
+    RuleMatrix {
+        rule: usize,
+    },
+    ///Check the session for internal inconsistencies, and repair what can be repaired.
+    Fsck {
+        repair: bool,
+    },
+    ///Write the whole session to a file.
+    Save {
+        path: PathBuf,
+    },
+    ///Replace the session with one written by `save`.
+    Load {
+        path: PathBuf,
+    },
+    ///Add the eliminations and placements of a CSV file to the root state.
+    ImportCsv {
+        path: PathBuf,
+    },
+    ///List the conflicts between the session and one written by `save`, or without a path, how
+    ///the conflicts merged so far were resolved.
+    Merge {
+        path: Option<PathBuf>,
+    },
+    ///Resolve a conflict of the last merge by trusting one side.
+    Resolve {
+        conflict: usize,
+        trusted: Side,
+    },
+    ///Write a certificate of a fact of the current state, to be checked by
+    ///`rune-lock-verify-proof`.
+    ExportProof {
+        fact_handle: FactHandle,
+        path: PathBuf,
+    },
+    ///Take back a given of the root state and replay every assumption without it.
+    RetractGiven {
+        position: RunePosition,
+        activation: HumanActivation,
+    },
+    ///Assume candidates depth first until a solution is found, at most `depth` assumptions deep.
+    Auto {
+        depth: usize,
+    },
+    ///Preview what a change of the lock would do to the session, see `ApplyEdit`.
+    EditLock {
+        edit: LockEdit,
+    },
+    ///Apply the change of the lock previewed last.
+    ApplyEdit,
+    ///Walk through every rule and given a full assignment violates.
+    WhyInvalid {
+        solution: String,
+    },
+    ///Check whether the current state has no, exactly one or multiple solutions.
+    Unique,
+    ///Search all completions of the current state for a second solution, for at most `seconds`,
+    ///keeping the progress in a checkpoint file to continue from next time.
+    ProveUnique {
+        path: PathBuf,
+        seconds: Option<u64>,
+    },
+    ///Rank the rules by the facts of the session their proofs credit them with.
+    Audit,
+    ///Look for rules of the lock that can't hold together, before any assumption.
+    CheckRules,
+    ///Search for every solution that extends the current state.
+    Solve,
+    ///Estimate how likely each undecided candidate is from `runs` random completions.
+    Sample {
+        runs: usize,
+    },
+    ///Check every fact of the state against all completions of its assumptions.
+    VerifyNode {
+        node: NodeId,
+    },
+    ///List the assumptions the fact survives retracting.
+    Independent {
+        fact_handle: FactHandle,
+    },
+    ///Assume the advice with the given (1 based) rank from the last `advise`.
+    QuickAssume {
+        rank: usize,
+    },
+    ///Fill in a rune that was unknown when the lock was entered.
+    SetRune {
+        position: RunePosition,
+        rune: Rune,
+    },
+    ///Explain a rule kind, or all of them.
+    Glossary {
+        kind: Option<String>,
+    },
+    Stats {
+        detailed: bool,
+    },
+    ///List background jobs.
+    Jobs,
+    ///Block until a background job is done and show its result.
+    Wait {
+        job: JobId,
+    },
+    Cancel {
+        job: JobId,
+    },
+    ///How long heavy commands may block the prompt before continuing in the background.
+    Budget {
+        millis: u64,
+    },
+    ///How many MiB the states may take up before a warning suggests cutting the tree down.
+    MemoryBudget {
+        mebibytes: usize,
+    },
+    ///Name a few commands separated by `;`, to run them by typing the name.
+    DefineMacro {
+        name: String,
+        commands: String,
+    },
+    RemoveMacro {
+        name: String,
+    },
+    ListMacros,
+    ///Start staging the following commands, so they can be applied or discarded together.
+    Begin,
+    ///Apply the staged commands.
+    Commit,
+    ///Discard the staged commands.
+    Abort,
+    ///Toggle refusing assumptions that aren't bifurcations.
+    Progress {
+        enabled: bool,
+    },
+    ///Toggle printing everything as labelled plain text, for screen readers.
+    Accessible {
+        enabled: bool,
+    },
+    ///Toggle disabling the commands that assume or search, leaving deduction and explanations.
+    DeduceOnly {
+        enabled: bool,
+    },
+    ///Toggle deepening open leaves while waiting for input.
+    Idle {
+        enabled: bool,
+    },
+    ///Print every fact of the current state and the grid of their handles, or with `why`, the
+    ///grid with a code of why each cell is known.
+    Dump {
+        why: bool,
+    },
+    ///Apply the rules of every stage of the game up to this one.
+    Stage {
+        stage: usize,
+    },
+    ///Print the whole tree and assignment again, instead of only what changed.
+    Refresh,
+    ///Check the current state, failing the script if it doesn't match.
+    Assert {
+        assertion: Assertion,
     },
-    Dump,
+}
+
+///The names of the commands, offered when completing the first word of a line.
+pub const COMMANDS: &[&str] = &[
+    "abort",
+    "accessible",
+    "advise",
+    "assert-contradiction",
+    "assert-eliminated",
+    "assert-solved",
+    "assume",
+    "audit",
+    "auto",
+    "begin",
+    "budget",
+    "cancel",
+    "check",
+    "commit",
+    "dump",
+    "editlock",
+    "explain",
+    "explain-diff",
+    "export-proof",
+    "fsck",
+    "glossary",
+    "idle",
+    "import-csv",
+    "independent",
+    "jobs",
+    "load",
+    "macro",
+    "matrix",
+    "memory-budget",
+    "merge",
+    "progress",
+    "prove-unique",
+    "prune",
+    "refresh",
+    "resolve",
+    "retract-given",
+    "root",
+    "rules",
+    "sample",
+    "save",
+    "set",
+    "setrune",
+    "solve",
+    "stage",
+    "stats",
+    "tryactivation",
+    "tryposition",
+    "undo",
+    "unique",
+    "up",
+    "verify-node",
+    "view",
+    "wait",
+    "why-invalid",
+];
+
+///The commands taking the ids of states, whose arguments complete to them.
+pub const NODE_COMMANDS: &[&str] = &["view", "v", "prune", "verify-node", "explain-diff", "ed"];
+
+///The argument without a trailing `--force` (or `-f`), and whether it had one.
+fn force_flag(args: &str) -> Result<(&str, bool), SolverCommandError> {
+    match args.split_once(' ') {
+        Some((args, "--force" | "-f")) => Ok((args, true)),
+        Some((_, flag)) => Err(SolverCommandError::UnknownArgument(flag.into())),
+        None => Ok((args, false)),
+    }
 }
 
 impl SolverCommand {
-    pub fn parse(text: &str) -> Result<Self, SolverCommandError> {
+    ///Reads a command of a session on a lock of `size`, which the cells it names have to be on.
+    pub fn parse(text: &str, size: LockSize) -> Result<Self, SolverCommandError> {
         let (command, args) = text.split_once(' ').unwrap_or((text, ""));
 
         match command {
             "assume" | "a" => {
-                let (position, activation) = args
-                    .split_once(' ')
-                    .ok_or(SolverCommandError::NotEnoughArguments { expected: 2 })?;
-                let position = position.parse::<usize>()?;
-                let activation = activation.parse::<u8>()?;
-                let position = RunePosition::new(position);
-                let activation = Activation::from_human(activation)?;
+                let (cell, force) = match args.trim().rsplit_once(' ') {
+                    Some((cell, "--force" | "-f")) => (cell, true),
+                    _ if args.trim().is_empty() => {
+                        return Err(SolverCommandError::NotEnoughArguments { expected: 2 })
+                    }
+                    _ => (args, false),
+                };
+                let (position, activation) = coordinate::parse_cell(cell, size)?;
 
                 Ok(SolverCommand::Assume {
                     position,
                     activation,
+                    force,
                 })
             }
+            "up" | "undo" => Ok(Self::Up),
+            "root" => Ok(Self::Root),
+            "prune" => Ok(Self::Prune {
+                node: args.parse()?,
+            }),
             "view" | "v" => {
-                let node = args.parse::<usize>()?;
+                let node = args.parse::<NodeId>()?;
                 Ok(SolverCommand::View { node })
             }
             "explain" | "e" => {
-                let fact = args.parse::<usize>()?;
+                let mut words = args.split_whitespace();
+                let fact = words
+                    .next()
+                    .ok_or(SolverCommandError::NotEnoughArguments { expected: 1 })?;
+                let (mut max_depth, mut minimal, mut dot) = (None, false, None);
+                while let Some(word) = words.next() {
+                    match word {
+                        "--minimal" | "-m" => minimal = true,
+                        "--dot" => match words.next() {
+                            Some(path) => dot = Some(path.into()),
+                            None => {
+                                return Err(SolverCommandError::NotEnoughArguments { expected: 3 })
+                            }
+                        },
+                        "full" => max_depth = Some(usize::MAX),
+                        depth => match depth.parse() {
+                            Ok(depth) => max_depth = Some(depth),
+                            Err(_) => {
+                                return Err(SolverCommandError::UnknownArgument(depth.into()))
+                            }
+                        },
+                    }
+                }
                 Ok(SolverCommand::Explain {
-                    fact_handle: FactHandle::from_raw(fact),
-                    // max_depth: depth,
+                    fact_handle: fact.parse()?,
+                    max_depth,
+                    minimal,
+                    dot,
                 })
             }
+            "explain-diff" | "ed" => {
+                let mut words = args.splitn(3, ' ');
+                let (Some(node_a), Some(node_b), Some(cell)) =
+                    (words.next(), words.next(), words.next())
+                else {
+                    return Err(SolverCommandError::NotEnoughArguments { expected: 4 });
+                };
+                let (position, activation) = coordinate::parse_cell(cell, size)?;
+                Ok(Self::ExplainDiff {
+                    node_a: node_a.parse()?,
+                    node_b: node_b.parse()?,
+                    position,
+                    activation,
+                })
+            }
+            "solve" => Ok(Self::Solve),
+            "why-invalid" => match args {
+                "" => Err(SolverCommandError::NotEnoughArguments {
+                    expected: size.positions(),
+                }),
+                solution => Ok(Self::WhyInvalid {
+                    solution: solution.to_string(),
+                }),
+            },
+            "auto" => Ok(Self::Auto {
+                depth: match args {
+                    "" => size.activations(),
+                    depth => depth.parse()?,
+                },
+            }),
+            "unique" => Ok(Self::Unique),
+            "prove-unique" => match args.rsplit_once(' ') {
+                _ if args.is_empty() => Err(SolverCommandError::NotEnoughArguments { expected: 1 }),
+                Some((path, seconds)) if seconds.parse::<u64>().is_ok() => Ok(Self::ProveUnique {
+                    path: path.trim().into(),
+                    seconds: Some(seconds.parse()?),
+                }),
+                _ => Ok(Self::ProveUnique {
+                    path: args.into(),
+                    seconds: None,
+                }),
+            },
+            "audit" => Ok(Self::Audit),
+            "check" | "check-rules" => Ok(Self::CheckRules),
+            "sample" => match args {
+                "" => Err(SolverCommandError::NotEnoughArguments { expected: 1 }),
+                runs => Ok(Self::Sample {
+                    runs: runs.parse()?,
+                }),
+            },
+            "fsck" => match args {
+                "" => Ok(Self::Fsck { repair: false }),
+                "--repair" => Ok(Self::Fsck { repair: true }),
+                _ => Err(SolverCommandError::UnknownArgument(args.to_string())),
+            },
+            "save" => match args {
+                "" => Err(SolverCommandError::NotEnoughArguments { expected: 1 }),
+                path => Ok(Self::Save { path: path.into() }),
+            },
+            "load" => match args {
+                "" => Err(SolverCommandError::NotEnoughArguments { expected: 1 }),
+                path => Ok(Self::Load { path: path.into() }),
+            },
+            "import-csv" => match args {
+                "" => Err(SolverCommandError::NotEnoughArguments { expected: 1 }),
+                path => Ok(Self::ImportCsv { path: path.into() }),
+            },
+            "merge" => Ok(Self::Merge {
+                path: Some(args).filter(|it| !it.is_empty()).map(PathBuf::from),
+            }),
+            "resolve" => match args.split_whitespace().collect::<Vec<_>>()[..] {
+                [conflict, "ours"] => Ok(Self::Resolve {
+                    conflict: conflict.parse()?,
+                    trusted: Side::Ours,
+                }),
+                [conflict, "theirs"] => Ok(Self::Resolve {
+                    conflict: conflict.parse()?,
+                    trusted: Side::Theirs,
+                }),
+                [_, other] => Err(SolverCommandError::UnknownArgument(other.to_string())),
+                _ => Err(SolverCommandError::NotEnoughArguments { expected: 2 }),
+            },
+            "export-proof" => {
+                let (fact, path) = args
+                    .split_once(' ')
+                    .ok_or(SolverCommandError::NotEnoughArguments { expected: 2 })?;
+                Ok(Self::ExportProof {
+                    fact_handle: fact.parse()?,
+                    path: path.trim().into(),
+                })
+            }
+            "retract-given" => {
+                let (position, activation) = coordinate::parse_cell(args, size)?;
+                Ok(Self::RetractGiven {
+                    position,
+                    activation,
+                })
+            }
+            "editlock" => match args.split_whitespace().collect::<Vec<_>>()[..] {
+                ["apply"] => Ok(Self::ApplyEdit),
+                ["rune", position, rune] => Ok(Self::EditLock {
+                    edit: LockEdit::SetRune {
+                        position: coordinate::parse_position(position, size)?,
+                        rune: rune.parse()?,
+                    },
+                }),
+                ["remove-rule", rule] => Ok(Self::EditLock {
+                    edit: LockEdit::RemoveRule(rule.parse()?),
+                }),
+                ["rune", ..] => Err(SolverCommandError::NotEnoughArguments { expected: 3 }),
+                ["remove-rule"] | [] => Err(SolverCommandError::NotEnoughArguments { expected: 2 }),
+                _ => Err(SolverCommandError::UnknownArgument(args.to_string())),
+            },
+            "rules" => Ok(Self::Rules {
+                activation: match args {
+                    "" => None,
+                    activation => Some(coordinate::parse_activation(activation, size)?),
+                },
+            }),
+            "matrix" => match args.split_once(' ') {
+                Some(("rule", rule)) => Ok(Self::RuleMatrix {
+                    rule: rule.parse()?,
+                }),
+                None if args == "rule" => {
+                    Err(SolverCommandError::NotEnoughArguments { expected: 2 })
+                }
+                _ => Err(SolverCommandError::UnknownArgument(args.to_string())),
+            },
+            "verify-node" => Ok(Self::VerifyNode {
+                node: args.parse()?,
+            }),
+            "independent" | "i" => Ok(Self::Independent {
+                fact_handle: args.parse()?,
+            }),
             "tryposition" | "tp" => {
-                let position = args.parse::<usize>()?;
-                let position = RunePosition::new(position);
-                Ok(Self::TryInPosition { position })
+                let (args, force) = force_flag(args)?;
+                let position = coordinate::parse_position(args, size)?;
+                Ok(Self::TryInPosition { position, force })
             }
             "tryactivation" | "ta" => {
-                let act = args.parse::<u8>()?;
-                let act = Activation::from_human(act)?;
-                Ok(Self::TryActivation { activation: act })
+                let (args, force) = force_flag(args)?;
+                let act = coordinate::parse_activation(args, size)?;
+                Ok(Self::TryActivation {
+                    activation: act,
+                    force,
+                })
+            }
+            "advise" | "ad" => match args.split_once(' ') {
+                Some(("act" | "a", act)) => {
+                    let act = coordinate::parse_activation(act, size)?;
+                    Ok(Self::Advise {
+                        activation: Some(act),
+                    })
+                }
+                Some((target, _)) => Err(SolverCommandError::UnknownCommand(target.into())),
+                None if args.is_empty() => Ok(Self::Advise { activation: None }),
+                None => Err(SolverCommandError::NotEnoughArguments { expected: 2 }),
+            },
+            "setrune" | "sr" => {
+                let (position, rune) = args
+                    .split_once(' ')
+                    .ok_or(SolverCommandError::NotEnoughArguments { expected: 2 })?;
+                let position = coordinate::parse_position(position, size)?;
+                let rune = rune.parse::<Rune>()?;
+                Ok(Self::SetRune { position, rune })
+            }
+            "glossary" | "g" => Ok(Self::Glossary {
+                kind: Some(args.to_string()).filter(|it| !it.is_empty()),
+            }),
+            "stats" => match args {
+                "" => Ok(Self::Stats { detailed: false }),
+                "--detailed" => Ok(Self::Stats { detailed: true }),
+                _ => Err(SolverCommandError::UnknownArgument(args.into())),
+            },
+            "jobs" => Ok(Self::Jobs),
+            "wait" => Ok(Self::Wait {
+                job: JobId::from_raw(args.trim_start_matches('J').parse::<usize>()?),
+            }),
+            "cancel" => Ok(Self::Cancel {
+                job: JobId::from_raw(args.trim_start_matches('J').parse::<usize>()?),
+            }),
+            "budget" => Ok(Self::Budget {
+                millis: args.parse::<u64>()?,
+            }),
+            "memory-budget" => Ok(Self::MemoryBudget {
+                mebibytes: args.parse()?,
+            }),
+            "macro" => match args.split_once(' ') {
+                Some(("define", definition)) => {
+                    let (name, commands) = definition
+                        .trim()
+                        .split_once(' ')
+                        .ok_or(SolverCommandError::NotEnoughArguments { expected: 3 })?;
+                    Ok(Self::DefineMacro {
+                        name: name.into(),
+                        commands: commands.into(),
+                    })
+                }
+                Some(("remove", name)) => Ok(Self::RemoveMacro {
+                    name: name.trim().into(),
+                }),
+                None if args.is_empty() || args == "list" => Ok(Self::ListMacros),
+                None if args == "define" || args == "remove" => {
+                    Err(SolverCommandError::NotEnoughArguments { expected: 2 })
+                }
+                _ => Err(SolverCommandError::UnknownArgument(args.into())),
+            },
+            "begin" => Ok(Self::Begin),
+            "commit" => Ok(Self::Commit),
+            "abort" => Ok(Self::Abort),
+            "accessible" => match args {
+                "on" => Ok(Self::Accessible { enabled: true }),
+                "off" => Ok(Self::Accessible { enabled: false }),
+                "" => Err(SolverCommandError::NotEnoughArguments { expected: 1 }),
+                _ => Err(SolverCommandError::UnknownArgument(args.into())),
+            },
+            "set" => match args.split_whitespace().collect::<Vec<_>>()[..] {
+                ["mode", "deduce-only"] => Ok(Self::DeduceOnly { enabled: true }),
+                ["mode", "explore"] => Ok(Self::DeduceOnly { enabled: false }),
+                ["mode", mode] => Err(SolverCommandError::UnknownArgument(mode.into())),
+                ["mode"] | [] => Err(SolverCommandError::NotEnoughArguments { expected: 2 }),
+                _ => Err(SolverCommandError::UnknownArgument(args.into())),
+            },
+            "idle" => match args {
+                "on" => Ok(Self::Idle { enabled: true }),
+                "off" => Ok(Self::Idle { enabled: false }),
+                "" => Err(SolverCommandError::NotEnoughArguments { expected: 1 }),
+                _ => Err(SolverCommandError::UnknownArgument(args.into())),
+            },
+            "progress" => match args {
+                "on" => Ok(Self::Progress { enabled: true }),
+                "off" => Ok(Self::Progress { enabled: false }),
+                "" => Err(SolverCommandError::NotEnoughArguments { expected: 1 }),
+                _ => Err(SolverCommandError::UnknownArgument(args.into())),
+            },
+            "dump" | "d" => match args {
+                "" => Ok(Self::Dump { why: false }),
+                "--why" => Ok(Self::Dump { why: true }),
+                _ => Err(SolverCommandError::UnknownArgument(args.to_string())),
+            },
+            "refresh" => Ok(Self::Refresh),
+            "assert-eliminated" => {
+                let (position, activation) = coordinate::parse_cell(args, size)?;
+                Ok(Self::Assert {
+                    assertion: Assertion::Eliminated {
+                        position,
+                        activation: activation.into(),
+                    },
+                })
+            }
+            "assert-solved" => Ok(Self::Assert {
+                assertion: Assertion::Solved,
+            }),
+            "assert-contradiction" => Ok(Self::Assert {
+                assertion: Assertion::Contradiction,
+            }),
+            "stage" => Ok(Self::Stage {
+                stage: args.parse()?,
+            }),
+            "1" | "2" | "3" | "4" | "5" | "6" | "7" | "8" | "9" if args.is_empty() => {
+                Ok(Self::QuickAssume {
+                    rank: command.parse::<usize>()?,
+                })
             }
-            "dump" | "d" => Ok(Self::Dump),
             _ => Err(SolverCommandError::UnknownCommand(command.into())),
         }
     }
+
+    ///Whether the command guesses: assumes candidates, or searches for solutions instead of
+    ///deducing. These are disabled in deduce-only mode.
+    pub fn guesses(&self) -> bool {
+        matches!(
+            self,
+            Self::Assume { .. }
+                | Self::QuickAssume { .. }
+                | Self::TryInPosition { .. }
+                | Self::TryActivation { .. }
+                | Self::Advise { .. }
+                | Self::Auto { .. }
+                | Self::Solve
+                | Self::Unique
+                | Self::ProveUnique { .. }
+                | Self::Sample { .. }
+                | Self::VerifyNode { .. }
+        )
+    }
+
+    ///Whether the command only manages jobs, macros or budgets, so it doesn't have to wait for a
+    ///job exploring the states.
+    pub fn runs_beside_jobs(&self) -> bool {
+        matches!(
+            self,
+            Self::Jobs
+                | Self::Wait { .. }
+                | Self::Cancel { .. }
+                | Self::Budget { .. }
+                | Self::MemoryBudget { .. }
+                | Self::DefineMacro { .. }
+                | Self::RemoveMacro { .. }
+                | Self::ListMacros
+        )
+    }
 }
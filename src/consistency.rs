@@ -0,0 +1,202 @@
+//! Finding rules that can't hold together before any assumption is made: rules no placement of
+//! their activations satisfies, alone or as a pair, and the rules the consolidation of the empty
+//! state runs into a contradiction with. Finding nothing doesn't mean the lock has a solution,
+//! only that it takes assumptions to tell.
+
+use std::fmt::{Display, Formatter};
+
+use itertools::Itertools;
+
+use crate::{
+    activation::Activation,
+    fact_solver::fact_db::{FactDb, FactError},
+    RuleKind, RuneLock,
+};
+
+///How many activations the rules checked together may be about, as every placement of them is
+///tried.
+const MAX_ACTIVATIONS: usize = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConflictSource {
+    ///No placement of the activations of the rules satisfies all of them.
+    Placements,
+    ///Consolidating the empty state with just these rules and the domains contradicts.
+    Consolidation,
+}
+
+#[derive(Debug, Clone)]
+pub struct RuleConflict {
+    pub source: ConflictSource,
+    ///The rules with their indices, in ascending order. Without a rule, the domains contradict
+    ///each other.
+    pub rules: Vec<(usize, RuleKind)>,
+}
+
+impl RuneLock {
+    ///Looks for active rules that can't hold together, and reports the smallest sets of them it
+    ///finds.
+    pub fn check_rules(&self) -> Vec<RuleConflict> {
+        let rules: Vec<_> = self
+            .active_rules()
+            .filter(|(_, rule)| {
+                let activations = rule.activations().len();
+                activations > 0 && activations <= MAX_ACTIVATIONS
+            })
+            .collect();
+        let mut conflicts = Vec::new();
+        let conflict = |rules: &[(usize, &RuleKind)], source| RuleConflict {
+            source,
+            rules: rules
+                .iter()
+                .map(|(index, rule)| (*index, (*rule).clone()))
+                .collect(),
+        };
+
+        let (satisfiable, unsatisfiable): (Vec<_>, Vec<_>) = rules
+            .iter()
+            .copied()
+            .partition(|rule| self.satisfiable(&[rule.1]));
+        for rule in unsatisfiable {
+            conflicts.push(conflict(&[rule], ConflictSource::Placements));
+        }
+        for (a, b) in satisfiable.iter().copied().tuple_combinations() {
+            let shared =
+                a.1.activations()
+                    .iter()
+                    .any(|it| b.1.activations().contains(it));
+            if shared && !self.satisfiable(&[a.1, b.1]) {
+                conflicts.push(conflict(&[a, b], ConflictSource::Placements));
+            }
+        }
+
+        if let Some(mut core) = self.contradicting_rules() {
+            //Dropping every rule the contradiction still arises without leaves a set that is
+            //only contradictory as a whole.
+            for rule in core.clone() {
+                let without: Vec<_> = core.iter().copied().filter(|it| *it != rule).collect();
+                if self.contradicts_with(&without) {
+                    core = without;
+                }
+            }
+            let known = conflicts.iter().any(|it| {
+                it.rules.len() == core.len() && it.rules.iter().all(|(it, _)| core.contains(it))
+            });
+            if !known {
+                let core: Vec<_> = core.iter().map(|it| (*it, &self.rules[*it])).collect();
+                conflicts.push(conflict(&core, ConflictSource::Consolidation));
+            }
+        }
+        conflicts
+    }
+
+    ///Whether some placement of the activations of the rules satisfies all of them.
+    fn satisfiable(&self, rules: &[&RuleKind]) -> bool {
+        let activations: Vec<Activation> = rules
+            .iter()
+            .flat_map(|it| it.activations())
+            .unique()
+            .collect();
+        if activations.len() > MAX_ACTIVATIONS {
+            return true;
+        }
+        self.size
+            .all_positions()
+            .permutations(activations.len())
+            .any(|positions| {
+                let placements = positions.into_iter().zip(activations.iter().copied());
+                rules
+                    .iter()
+                    .all(|rule| rule.validate_subset(self, placements.clone()).is_ok())
+            })
+    }
+
+    ///The rules the contradiction of the empty state rests on, if it has one.
+    fn contradicting_rules(&self) -> Option<Vec<usize>> {
+        let mut facts = FactDb::new(self.size);
+        match facts.integrate_givens(self) {
+            Ok(()) => None,
+            Err(FactError::Contradiction(fact)) => Some(facts.rules_of(fact).into_iter().collect()),
+        }
+    }
+
+    ///Whether the empty state contradicts with only the given rules and the domains.
+    fn contradicts_with(&self, rules: &[usize]) -> bool {
+        let mut lock = self.without_rules();
+        for rule in rules {
+            lock.add_rule(self.rules[*rule].clone(), 0);
+        }
+        for domain in self.domains.iter() {
+            lock.add_domain(domain.clone());
+        }
+        FactDb::new(self.size).integrate_givens(&lock).is_err()
+    }
+}
+
+impl Display for RuleConflict {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match (self.source, self.rules.len()) {
+            (ConflictSource::Placements, 1) => {
+                write!(f, "No placement of its activations satisfies this rule:")?
+            }
+            (ConflictSource::Placements, _) => write!(
+                f,
+                "No placement of their activations satisfies these rules together:"
+            )?,
+            (ConflictSource::Consolidation, 0) => {
+                return write!(f, "The domains contradict each other without any rule.")
+            }
+            (ConflictSource::Consolidation, _) => write!(
+                f,
+                "The empty state runs into a contradiction with these rules and the domains:"
+            )?,
+        }
+        for (index, rule) in self.rules.iter() {
+            write!(f, "\n  Rule {}: '{}'", index, rule)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{rule::ActivationRuleKindHelpers, test_support::sample_runes, RuneLock};
+
+    use super::ConflictSource;
+
+    #[test]
+    fn test_conflicting_rules_are_found() {
+        let runes = sample_runes();
+        let lock = RuneLock::with_rules(runes, vec![(1, 2).alwanese(), (3, 4).antakian_twins()]);
+        assert!(lock.check_rules().is_empty());
+
+        let lock = RuneLock::with_rules(
+            runes,
+            vec![
+                (1, 2).alwanese(),
+                (5, 6).increase_santor(),
+                (1, 2).not_alwanese(),
+            ],
+        );
+        let conflicts = lock.check_rules();
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].source, ConflictSource::Placements);
+        let rules: Vec<_> = conflicts[0].rules.iter().map(|(it, _)| *it).collect();
+        assert_eq!(rules, vec![0, 2]);
+
+        //Rules about different activations only conflict through the grid.
+        let lock = RuneLock::with_rules(
+            runes,
+            vec![
+                "1 at 0".parse().unwrap(),
+                (3, 4).antakian_twins(),
+                "2 at 0".parse().unwrap(),
+            ],
+        );
+        let conflicts = lock.check_rules();
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].source, ConflictSource::Consolidation);
+        let rules: Vec<_> = conflicts[0].rules.iter().map(|(it, _)| *it).collect();
+        assert_eq!(rules, vec![0, 2]);
+    }
+}
@@ -0,0 +1,151 @@
+//! Reading cells, a position and an activation, however they are written, so snippets of commands
+//! work no matter whose habits they follow: `3 5`, `3=5`, `pos3 act5` and `outer-s #5` are all the
+//! activation #5 on position 3. A position is its index, from 0 to 11 on the game's lock,
+//! optionally after `pos` or `p`, or on a lock of six sectors per circle its ring and direction on
+//! the hexagon, from `outer-n` clockwise to `outer-nw` (0 to 5) and `inner-n` to `inner-nw` (6 to
+//! 11). Cells are read for a lock of a given size, and have to be on it.
+
+use thiserror::Error;
+
+use crate::{
+    activation::{Activation, ActivationError, HumanActivation},
+    index::RunePosition,
+    size::LockSize,
+};
+
+const DIRECTIONS: [&str; 6] = ["n", "ne", "se", "s", "sw", "nw"];
+
+#[derive(Debug, Error)]
+pub enum CoordinateError {
+    #[error("'{0}' is not a position, expected 0 to {}, pos<n> or a ring and direction like outer-n or inner-sw", .1.positions() - 1)]
+    PositionInvalid(String, LockSize),
+    #[error("{0}")]
+    Activation(#[from] ActivationError),
+    #[error("'{0}' is not a cell, expected a position and an activation, like '3 5', '3=5', 'pos3 act5' or 'outer-s #5'")]
+    Malformed(String),
+}
+
+pub fn parse_position(text: &str, size: LockSize) -> Result<RunePosition, CoordinateError> {
+    let invalid = || CoordinateError::PositionInvalid(text.into(), size);
+    let lowercase = text.to_lowercase();
+    if let Some((ring, direction)) = lowercase.split_once('-') {
+        if size.sectors() != DIRECTIONS.len() {
+            return Err(invalid());
+        }
+        let offset = match ring {
+            "outer" | "o" => 0,
+            "inner" | "i" => size.sectors(),
+            _ => return Err(invalid()),
+        };
+        let direction = DIRECTIONS
+            .iter()
+            .position(|it| *it == direction)
+            .ok_or_else(invalid)?;
+        return Ok(RunePosition::new(offset + direction));
+    }
+    let number = ["pos", "p"]
+        .iter()
+        .find_map(|it| lowercase.strip_prefix(it))
+        .unwrap_or(&lowercase);
+    number
+        .parse::<usize>()
+        .ok()
+        .and_then(|it| size.position(it))
+        .ok_or_else(invalid)
+}
+
+///An activation like `5`, `#5` or `act5`.
+pub fn parse_activation(text: &str, size: LockSize) -> Result<HumanActivation, CoordinateError> {
+    let number = text
+        .strip_prefix("act")
+        .or_else(|| text.strip_prefix("Act"))
+        .unwrap_or(text);
+    let activation = number.parse::<HumanActivation>().map_err(|err| match err {
+        ActivationError::Malformed(_) => ActivationError::Malformed(text.into()),
+        err => err,
+    })?;
+    match size.contains(Activation::from(activation)) {
+        true => Ok(activation),
+        false => Err(ActivationError::OutOfBounds.into()),
+    }
+}
+
+///A position and an activation, written apart or joined by `=`.
+pub fn parse_cell(
+    text: &str,
+    size: LockSize,
+) -> Result<(RunePosition, HumanActivation), CoordinateError> {
+    let parts: Vec<_> = match text.split_once('=') {
+        Some((position, activation)) => vec![position.trim(), activation.trim()],
+        None => text.split_whitespace().collect(),
+    };
+    let [position, activation] = parts[..] else {
+        return Err(CoordinateError::Malformed(text.into()));
+    };
+    Ok((
+        parse_position(position, size)?,
+        parse_activation(activation, size)?,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{activation::HumanActivation, index::RunePosition, size::LockSize};
+
+    use super::{parse_cell, parse_position, CoordinateError};
+
+    #[test]
+    fn test_cells_are_read_in_every_syntax() {
+        let size = LockSize::STANDARD;
+        let expected = (RunePosition::new(3), HumanActivation::new(5).unwrap());
+        for text in [
+            "3 5",
+            "3=5",
+            "3 = #5",
+            "pos3 act5",
+            "p3 #5",
+            "outer-s #5",
+            "o-s 5",
+        ] {
+            assert_eq!(parse_cell(text, size).unwrap(), expected, "{}", text);
+        }
+        assert_eq!(
+            parse_position("inner-nw", size).unwrap(),
+            RunePosition::new(11)
+        );
+        assert_eq!(
+            parse_position("Inner-N", size).unwrap(),
+            RunePosition::new(6)
+        );
+
+        assert!(matches!(
+            parse_position("12", size),
+            Err(CoordinateError::PositionInvalid(..))
+        ));
+        assert!(matches!(
+            parse_position("middle-n", size),
+            Err(CoordinateError::PositionInvalid(..))
+        ));
+        assert!(matches!(
+            parse_cell("3 13", size),
+            Err(CoordinateError::Activation(_))
+        ));
+        assert!(matches!(
+            parse_cell("3", size),
+            Err(CoordinateError::Malformed(_))
+        ));
+        assert!(parse_cell("act5 3", size)
+            .unwrap_err()
+            .to_string()
+            .contains("'act5' is not a position"));
+
+        let small = LockSize::new(8, 6).unwrap();
+        assert_eq!(parse_cell("7 6", small).unwrap().0, RunePosition::new(7));
+        assert!(parse_position("8", small).is_err());
+        assert!(parse_position("outer-n", small).is_err());
+        assert!(matches!(
+            parse_cell("3 7", small),
+            Err(CoordinateError::Activation(_))
+        ));
+    }
+}
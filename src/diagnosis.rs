@@ -0,0 +1,316 @@
+//! Why an assignment fails validation: every violated rule with the placements it is about, and
+//! the predicate of its kind evaluated on their positions.
+
+use itertools::Itertools;
+
+use crate::{
+    activation::Activation,
+    assignment::Assignment,
+    glossary,
+    index::RunePosition,
+    rule::{RuleError, RuleKind},
+    rune::Rune,
+    terminal::title,
+    RuneLock,
+};
+
+pub struct Violation {
+    pub rule: usize,
+    pub kind: RuleKind,
+    pub error: RuleError,
+    ///The placements the rule is about, the first activation of the rule first.
+    pub involved: Vec<(RunePosition, Activation)>,
+}
+
+///Every active rule the assignment violates, in the order of the rules.
+pub fn violations(lock: &RuneLock, assignment: &Assignment) -> Vec<Violation> {
+    lock.active_rules()
+        .filter_map(|(rule, kind)| {
+            let error = kind.validate(lock, assignment).err()?;
+            Some(Violation {
+                rule,
+                kind: kind.clone(),
+                error,
+                involved: involved(lock, kind, assignment),
+            })
+        })
+        .collect()
+}
+
+///The domains of the lock the assignment places an activation outside of.
+pub fn violated_givens(lock: &RuneLock, assignment: &Assignment) -> Vec<usize> {
+    lock.domains()
+        .iter()
+        .enumerate()
+        .filter(|(_, domain)| {
+            assignment
+                .position_of(domain.activation)
+                .is_some_and(|it| !domain.contains(it, lock.size()))
+        })
+        .map(|(index, _)| index)
+        .collect()
+}
+
+fn involved(
+    lock: &RuneLock,
+    kind: &RuleKind,
+    assignment: &Assignment,
+) -> Vec<(RunePosition, Activation)> {
+    //Of the activations that have to be on different runes, the first two that aren't.
+    if let RuleKind::AllDifferentRunes { activations } = kind {
+        let placed: Vec<_> = activations
+            .iter()
+            .filter_map(|it| Some((assignment.position_of(*it)?, *it)))
+            .filter(|(position, _)| lock.runes[*position].is_known())
+            .collect();
+        return placed
+            .iter()
+            .tuple_combinations()
+            .find(|((p, _), (q, _))| lock.runes[*p] == lock.runes[*q])
+            .map(|(p, q)| vec![*p, *q])
+            .unwrap_or(placed);
+    }
+    let activations = kind.activations();
+    if !activations.is_empty() {
+        return activations
+            .into_iter()
+            .filter_map(|activation| Some((assignment.position_of(activation)?, activation)))
+            .collect();
+    }
+    //An ordering of runes is about the first activation on an X rune that isn't below one on a Y
+    //rune.
+    if let RuleKind::RuneClassBefore { earlier, later } = kind {
+        let on = |rune: Rune| {
+            lock.size()
+                .all_positions()
+                .filter(move |it| lock.runes[*it].is_known() && lock.runes[*it] == rune)
+                .filter_map(|it| Some((it, assignment[it]?)))
+        };
+        return on(*earlier)
+            .cartesian_product(on(*later).collect::<Vec<_>>())
+            .find(|((p, a), (q, b))| p != q && b <= a)
+            .map(|(p, q)| vec![p, q])
+            .unwrap_or_default();
+    }
+    //A follows rule with a gap is about the first X rune whose successors within the gap are all
+    //placed, but none on a Y rune.
+    if let RuleKind::RuneFollowsWithin {
+        first,
+        second,
+        within,
+    } = kind
+    {
+        return lock
+            .size()
+            .all_positions()
+            .filter(|it| lock.runes[*it] == *first)
+            .filter_map(|it| Some((it, assignment[it]?)))
+            .find(|(_, activation)| {
+                activation.following(*within, lock.size()).all(|it| {
+                    assignment.position_of(it).is_some_and(|position| {
+                        lock.runes[position].is_known() && lock.runes[position] != *second
+                    })
+                })
+            })
+            .map(|it| vec![it])
+            .unwrap_or_default();
+    }
+    //A follows rule is about the first X rune whose successor isn't on a Y rune.
+    let RuleKind::RuneFollowsImmediately { first, second } = kind else {
+        return Vec::new();
+    };
+    lock.size()
+        .all_positions()
+        .filter(|it| lock.runes[*it] == *first)
+        .filter_map(|position| {
+            let activation = assignment[position]?;
+            let next = activation.next(lock.size()).ok()?;
+            let next_position = assignment.position_of(next)?;
+            let rune = lock.runes[next_position];
+            (rune.is_known() && rune != *second)
+                .then_some(vec![(position, activation), (next_position, next)])
+        })
+        .next()
+        .unwrap_or_default()
+}
+
+///The circle, sector, santor value and rune of a position.
+fn describe(lock: &RuneLock, position: RunePosition) -> String {
+    let sectors = lock.size().sectors();
+    let circle = match position.index() < sectors {
+        true => "outer",
+        false => "inner",
+    };
+    format!(
+        "{} circle, sector {}, santor {}, rune {}",
+        circle,
+        position.index() % sectors,
+        lock.santor(position),
+        lock.runes[position]
+    )
+}
+
+///The predicate of the rule's kind, evaluated on the positions `p` and `q` of its activations. Rules
+///about a single activation only use `p`.
+fn evaluate(lock: &RuneLock, kind: &RuleKind, p: RunePosition, q: RunePosition) -> String {
+    let (santor_p, santor_q) = (lock.santor(p), lock.santor(q));
+    let (p, q) = (p.index(), q.index());
+    let (sectors, half) = (lock.size().sectors(), lock.size().sectors() / 2);
+    let circle = |it: usize| match it < sectors {
+        true => "outer",
+        false => "inner",
+    };
+    match kind {
+        RuleKind::Alwanese { .. } | RuleKind::NotAlwanese { .. } => format!(
+            "(q - p) mod {3} = ({0} - {1}) mod {3} = {2}",
+            q,
+            p,
+            (lock.size().positions() + q - p) % sectors,
+            sectors
+        ),
+        RuleKind::AntakianConjugates { .. } => format!(
+            "p is on the {} circle, q on the {} circle; (p + {4}) mod {5} = {}, q mod {5} = {}",
+            circle(p),
+            circle(q),
+            (p + half) % sectors,
+            q % sectors,
+            half,
+            sectors
+        ),
+        RuleKind::AlwaneseConjugates { .. } => format!(
+            "(p + {2}) mod {3} = {0}, q mod {3} = {1}",
+            (p + half) % sectors,
+            q % sectors,
+            half,
+            sectors
+        ),
+        RuleKind::DifferentRunes { .. } | RuleKind::AllDifferentRunes { .. } => {
+            format!("rune(p) = {}, rune(q) = {}", lock.runes[p], lock.runes[q])
+        }
+        RuleKind::AntakianTwins { .. } | RuleKind::NotAntakianTwins { .. } => format!(
+            "p is on the {} circle, q on the {} circle",
+            circle(p),
+            circle(q)
+        ),
+        RuleKind::IncreaseSantor { .. } | RuleKind::DecreaseSantor { .. } => {
+            format!("santor(p) = {}, santor(q) = {}", santor_p, santor_q)
+        }
+        RuleKind::Max0Conductive { .. } => format!(
+            "p is in sector {} of the {} circle, q in sector {} of the {} circle",
+            p % sectors,
+            circle(p),
+            q % sectors,
+            circle(q)
+        ),
+        RuleKind::RuneFollowsWithin { second, within, .. } => format!(
+            "none of the {} activations after the one on p is on a {} rune",
+            within, second
+        ),
+        RuleKind::RuneClassBefore { .. } => format!(
+            "rune(p) = {}, rune(q) = {}, and the activation on q is not the higher one",
+            lock.runes[p], lock.runes[q]
+        ),
+        RuleKind::RuneFollowsImmediately { second, .. } => format!(
+            "the next activation is on a {} rune instead of a {} rune",
+            lock.runes[q], second
+        ),
+        RuleKind::ActivationAtPosition { position, .. } => {
+            format!("p = {}, but has to be {}", p, position)
+        }
+        RuleKind::ActivationNotAtPosition { position, .. } => {
+            format!("p = {}, the position {} is ruled out", p, position)
+        }
+        RuleKind::Not(rule) => format!(
+            "{}, which fulfills the negated rule",
+            evaluate(lock, rule, RunePosition::new(p), RunePosition::new(q))
+        ),
+        RuleKind::AnyOf(alternatives) => alternatives
+            .iter()
+            .map(|it| evaluate(lock, it, RunePosition::new(p), RunePosition::new(q)))
+            .join(", and "),
+        RuleKind::Implies { consequence, .. } => format!(
+            "the condition holds, but {}",
+            evaluate(
+                lock,
+                consequence,
+                RunePosition::new(p),
+                RunePosition::new(q)
+            )
+        ),
+    }
+}
+
+///The violation in words: the rule, the predicate it failed, its placements with their
+///positions' properties, and the placements on the hexagon.
+pub fn render_violation(lock: &RuneLock, violation: &Violation) -> String {
+    let mut text = format!(
+        "{}\n",
+        title(format!("Rule {}: '{}'", violation.rule, violation.kind))
+    );
+    if let Some(predicate) = glossary::predicate(violation.kind.name()) {
+        text.push_str(&format!("Requires: {}\n", predicate));
+    }
+    for (name, (position, activation)) in ["p", "q"].iter().zip(violation.involved.iter()) {
+        text.push_str(&format!(
+            "  {} = {}, where {} is: {}\n",
+            name,
+            position,
+            activation,
+            describe(lock, *position)
+        ));
+    }
+    let failed = match violation.involved[..] {
+        [(p, _), (q, _)] if matches!(violation.error, RuleError::Violated) => {
+            evaluate(lock, &violation.kind, p, q)
+        }
+        [(p, _)] if matches!(violation.error, RuleError::Violated) => {
+            evaluate(lock, &violation.kind, p, p)
+        }
+        _ => violation.error.to_string(),
+    };
+    text.push_str(&format!("Failed: {}\n", failed));
+    if let Ok(placements) =
+        Assignment::from_tuple_iter(lock.size(), violation.involved.iter().copied())
+    {
+        text.push_str(&placements.rendered());
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        assignment::Assignment,
+        rule::ActivationRuleKindHelpers,
+        test_support::{activation, sample_runes},
+        RuneLock,
+    };
+
+    use super::violations;
+
+    #[test]
+    fn test_every_violated_rule_is_listed() {
+        let lock = RuneLock::with_rules(
+            sample_runes(),
+            vec![
+                (1, 2).alwanese(),
+                (2, 3).antakian_twins(),
+                (3, 4).increase_santor(),
+            ],
+        );
+        let assignment = Assignment::from_iter(
+            lock.size(),
+            [1, 8, 7, 4, 9, 10, 2, 3, 11, 12, 5, 6]
+                .into_iter()
+                .map(|it| Some(activation(it))),
+        )
+        .unwrap();
+
+        let violated = violations(&lock, &assignment);
+        let rules: Vec<_> = violated.iter().map(|it| it.rule).collect();
+        assert_eq!(rules, vec![0, 2]);
+        assert_eq!(violated[0].involved.len(), 2);
+        assert_eq!(violated[0].involved[0].0.index(), 0);
+        assert_eq!(violated[0].involved[1].0.index(), 6);
+    }
+}
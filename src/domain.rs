@@ -0,0 +1,124 @@
+//! Restrictions of an activation to a part of the lock that are known before solving starts, like
+//! "activation 1 is somewhere on the outer ring". Unlike rules, they are given facts of the root
+//! state, and never something an assumption could be blamed for.
+
+use std::{fmt::Display, str::FromStr};
+
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::{
+    activation::{Activation, ActivationError, HumanActivation},
+    index::RunePosition,
+    size::{LockSize, MAX_POSITIONS},
+};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Region {
+    OuterRing,
+    InnerRing,
+    Positions(Vec<RunePosition>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ActivationDomain {
+    pub activation: Activation,
+    pub region: Region,
+}
+
+impl ActivationDomain {
+    pub fn contains(&self, position: RunePosition, size: LockSize) -> bool {
+        match &self.region {
+            Region::OuterRing => position.index() < size.sectors(),
+            Region::InnerRing => position.index() >= size.sectors(),
+            Region::Positions(positions) => positions.contains(&position),
+        }
+    }
+
+    ///Whether the activation and the positions of the domain are on a lock of `size`. Parsing
+    ///only knows the largest lock.
+    pub fn check_size(&self, size: LockSize) -> Result<(), DomainParseError> {
+        if !size.contains(self.activation) {
+            return Err(ActivationError::OutOfBounds.into());
+        }
+        match &self.region {
+            Region::Positions(positions) => {
+                match positions.iter().find(|it| it.index() >= size.positions()) {
+                    Some(position) => Err(DomainParseError::PositionInvalid(position.to_string())),
+                    None => Ok(()),
+                }
+            }
+            Region::OuterRing | Region::InnerRing => Ok(()),
+        }
+    }
+}
+
+impl ActivationDomain {
+    ///The domain in the syntax `FromStr` accepts.
+    pub fn syntax(&self) -> String {
+        let activation = HumanActivation::from(self.activation).number();
+        match &self.region {
+            Region::OuterRing => format!("{} outer", activation),
+            Region::InnerRing => format!("{} inner", activation),
+            Region::Positions(positions) => {
+                format!("{} in {}", activation, positions.iter().join(" "))
+            }
+        }
+    }
+}
+
+impl Display for ActivationDomain {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.region {
+            Region::OuterRing => write!(f, "{} is on the outer ring", self.activation),
+            Region::InnerRing => write!(f, "{} is on the inner ring", self.activation),
+            Region::Positions(positions) => write!(
+                f,
+                "{} is on one of {}",
+                self.activation,
+                positions.iter().join(", ")
+            ),
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum DomainParseError {
+    #[error(
+        "Expected '<activation> outer', '<activation> inner' or '<activation> in <positions>'"
+    )]
+    Malformed,
+    #[error("Activation is invalid: {0}")]
+    ActivationInvalid(#[from] ActivationError),
+    #[error("Position is invalid: {0}")]
+    PositionInvalid(String),
+}
+
+///Parses domains written as `1 outer`, `1 inner` or `1 in 0 2 4` (activations are 1 based).
+impl FromStr for ActivationDomain {
+    type Err = DomainParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let words: Vec<_> = s.split_whitespace().collect();
+        let [activation, region, positions @ ..] = &words[..] else {
+            return Err(DomainParseError::Malformed);
+        };
+        let activation = Activation::from(activation.parse::<HumanActivation>()?);
+        let region = match (*region, positions) {
+            ("outer", []) => Region::OuterRing,
+            ("inner", []) => Region::InnerRing,
+            ("in", [_, ..]) => Region::Positions(
+                positions
+                    .iter()
+                    .map(|it| match it.parse::<usize>() {
+                        Ok(position) if position < MAX_POSITIONS => Ok(RunePosition::new(position)),
+                        _ => Err(DomainParseError::PositionInvalid(it.to_string())),
+                    })
+                    .collect::<Result<_, _>>()?,
+            ),
+            _ => return Err(DomainParseError::Malformed),
+        };
+        Ok(Self { activation, region })
+    }
+}
@@ -0,0 +1,166 @@
+use std::{collections::BTreeSet, fmt::Display};
+
+use crate::{activation::Activation, index::RunePosition, jobs::CancelToken, RuneLock};
+
+use super::{
+    fact_db::{FactDb, FactError},
+    Fact, FactKind, FactReason,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AdviceOutcome {
+    Open,
+    Contradicts,
+}
+
+///A candidate assumption, together with what a single consolidation step after assuming it
+///would teach us.
+#[derive(Debug, Clone)]
+pub struct Advice {
+    pub position: RunePosition,
+    pub activation: Activation,
+    pub outcome: AdviceOutcome,
+    ///Rules which produced at least one new fact during the lookahead.
+    pub fired_rules: BTreeSet<usize>,
+    ///Eliminations that were derived directly from the assumed fact.
+    pub direct_eliminations: usize,
+    ///Eliminations that were derived from other, newly derived facts.
+    pub second_order_eliminations: usize,
+}
+
+impl Advice {
+    fn score(&self) -> usize {
+        match self.outcome {
+            AdviceOutcome::Contradicts => usize::MAX,
+            AdviceOutcome::Open => {
+                self.second_order_eliminations * 2
+                    + self.direct_eliminations
+                    + self.fired_rules.len()
+            }
+        }
+    }
+}
+
+///Assumes every open position of each of the given activations on a copy of `facts` and ranks
+///the candidates by how much knowledge a single consolidation step after it produces. Assumptions
+///that immediately contradict are ranked first, as they are deductions in disguise.
+pub fn advise(
+    facts: &FactDb,
+    lock: &RuneLock,
+    activations: impl Iterator<Item = Activation>,
+) -> Vec<Advice> {
+    advise_cancellable(facts, lock, activations, &CancelToken::default())
+        .expect("advice without a cancel token should never be cancelled")
+}
+
+///Like `advise`, but gives up with `None` between two lookaheads once `cancel` is set.
+pub fn advise_cancellable(
+    facts: &FactDb,
+    lock: &RuneLock,
+    activations: impl Iterator<Item = Activation>,
+    cancel: &CancelToken,
+) -> Option<Vec<Advice>> {
+    let mut advice = Vec::new();
+    for activation in activations {
+        let candidates: Vec<_> = facts.possibilities_for(activation).collect();
+        //An activation with only one option left is not a real choice.
+        if candidates.len() < 2 {
+            continue;
+        }
+        for position in candidates {
+            if cancel.is_cancelled() {
+                return None;
+            }
+            advice.push(lookahead(facts, lock, position, activation));
+        }
+    }
+    advice.sort_by_key(|it| std::cmp::Reverse(it.score()));
+    Some(advice)
+}
+
+///Runs only one iteration of the consolidation, rather than up to its fixpoint, so the lookahead
+///stays cheap enough to run for every candidate.
+fn lookahead(
+    facts: &FactDb,
+    lock: &RuneLock,
+    position: RunePosition,
+    activation: Activation,
+) -> Advice {
+    let mut derived = facts.clone();
+    let known = derived.fact_count();
+    let outcome = match derived.integrate_and_consolidate_once(
+        Fact {
+            kind: FactKind::ActivationMustBeOn,
+            reasons: vec![FactReason::Assumption],
+            position,
+            activation,
+            range: Vec::new(),
+        },
+        lock,
+    ) {
+        Ok(_) => AdviceOutcome::Open,
+        Err(FactError::Contradiction(_)) => AdviceOutcome::Contradicts,
+    };
+
+    let assumed = derived.handle_at(position, activation);
+    let mut advice = Advice {
+        position,
+        activation,
+        outcome,
+        fired_rules: BTreeSet::new(),
+        direct_eliminations: 0,
+        second_order_eliminations: 0,
+    };
+
+    for (_, fact) in derived.facts_since(known) {
+        for reason in fact.reasons.iter() {
+            if let FactReason::Rule(rule) = reason {
+                advice.fired_rules.insert(*rule);
+            }
+        }
+        if fact.kind != FactKind::ActivationCannotBeOn {
+            continue;
+        }
+        let direct = fact
+            .reasons
+            .iter()
+            .any(|it| matches!(it, FactReason::Fact(handle, _) if Some(*handle) == assumed));
+        let eliminations = fact.positions().count();
+        if direct {
+            advice.direct_eliminations += eliminations;
+        } else {
+            advice.second_order_eliminations += eliminations;
+        }
+    }
+    advice
+}
+
+impl Display for Advice {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.outcome {
+            AdviceOutcome::Contradicts => write!(
+                f,
+                "Assume {} = {}: contradicts immediately",
+                self.position, self.activation
+            ),
+            AdviceOutcome::Open => {
+                write!(
+                    f,
+                    "Assume {} = {}: {} direct, {} second-order eliminations",
+                    self.position,
+                    self.activation,
+                    self.direct_eliminations,
+                    self.second_order_eliminations
+                )?;
+                if !self.fired_rules.is_empty() {
+                    write!(f, " (rules")?;
+                    for rule in self.fired_rules.iter() {
+                        write!(f, " {}", rule)?;
+                    }
+                    write!(f, ")")?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
@@ -0,0 +1,76 @@
+//! Checks of the current state for recorded sessions, so they can be replayed as regression tests.
+
+use thiserror::Error;
+
+use crate::{activation::Activation, index::RunePosition};
+
+use super::{assumption_tree::AssumptionTreeNodeHandle, FactKind, FactualSolver, SolverStateState};
+
+pub enum Assertion {
+    ///The activation is known not to be on the position.
+    Eliminated {
+        position: RunePosition,
+        activation: Activation,
+    },
+    ///Every activation is placed, and the placement satisfies all rules.
+    Solved,
+    ///The state contradicts the lock.
+    Contradiction,
+}
+
+#[derive(Debug, Error)]
+pub enum AssertionError {
+    #[error("{activation} is not eliminated from position {position} in state {node}")]
+    NotEliminated {
+        node: AssumptionTreeNodeHandle,
+        position: RunePosition,
+        activation: Activation,
+    },
+    #[error("State {0} is not solved")]
+    NotSolved(AssumptionTreeNodeHandle),
+    #[error("State {0} does not contradict the lock")]
+    NoContradiction(AssumptionTreeNodeHandle),
+}
+
+impl FactualSolver {
+    ///Checks the assertion against the current state.
+    pub fn check(&self, assertion: &Assertion) -> Result<(), AssertionError> {
+        let node = self.current;
+        let state = &self.states[node];
+        let contradicts = matches!(state.state, SolverStateState::Contradicts(_))
+            || state.facts.fixed_assignment().is_err();
+        let holds = match assertion {
+            Assertion::Eliminated {
+                position,
+                activation,
+            } => state
+                .facts
+                .handle_at(*position, *activation)
+                .and_then(|it| state.facts.get(it))
+                .is_some_and(|it| it.kind == FactKind::ActivationCannotBeOn),
+            Assertion::Solved => {
+                !contradicts
+                    && state.facts.fixed_assignment().is_ok_and(|fixed| {
+                        fixed.is_complete() && self.lock.validate(&fixed).is_ok()
+                    })
+            }
+            Assertion::Contradiction => contradicts,
+        };
+        match (holds, assertion) {
+            (true, _) => Ok(()),
+            (
+                false,
+                Assertion::Eliminated {
+                    position,
+                    activation,
+                },
+            ) => Err(AssertionError::NotEliminated {
+                node,
+                position: *position,
+                activation: *activation,
+            }),
+            (false, Assertion::Solved) => Err(AssertionError::NotSolved(node)),
+            (false, Assertion::Contradiction) => Err(AssertionError::NoContradiction(node)),
+        }
+    }
+}
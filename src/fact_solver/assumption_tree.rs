@@ -1,38 +1,81 @@
 use std::{
     fmt::{Display, Formatter},
     ops::{Index, IndexMut},
+    str::FromStr,
 };
 
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
+use crate::terminal;
+
+#[derive(Clone, Serialize, Deserialize)]
 struct AssumptionTreeNode<T> {
     pub parent: Option<AssumptionTreeNodeHandle>,
     pub data: T,
     children: Vec<AssumptionTreeNodeHandle>,
 }
 
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct AssumptionTreeNodeHandle(usize);
 
+///How nodes are referred to by the user: the node number prefixed with `n` and followed by a
+///check letter, e.g. `n17b`. The check letter catches mistyped and swapped digits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NodeId(usize);
+
+#[derive(Debug, Error)]
+pub enum NodeIdError {
+    #[error("'{0}' is not a node id. Node ids look like n17b")]
+    Malformed(String),
+    #[error("Node id is missing its check letter, did you mean {0}?")]
+    MissingCheckLetter(NodeId),
+    #[error("A bare number could be a state or a fact: write {0} for the state, or F{} for the fact", .0.index())]
+    Ambiguous(NodeId),
+    #[error("The check letter of '{0}' does not match, it is probably mistyped")]
+    WrongCheckLetter(String),
+}
+
+#[derive(Clone, Serialize, Deserialize)]
 pub struct AssumptionTree<T> {
-    nodes: Vec<AssumptionTreeNode<T>>,
+    ///Removed nodes leave a gap, so the ids of the remaining ones stay the same.
+    nodes: Vec<Option<AssumptionTreeNode<T>>>,
 }
 
 #[derive(Debug, Error)]
 pub enum AssumptionTreeError {
     #[error("Node {0} does not exist")]
-    UnknownNode(usize),
+    UnknownNode(NodeId),
+    #[error("Node {0} was pruned, it is no longer part of the tree")]
+    Pruned(NodeId),
+    #[error("The root can't be removed")]
+    RootRemoval,
+}
+
+///A broken link between the nodes of a tree.
+#[derive(Debug, Error)]
+pub enum TreeLinkViolation {
+    #[error("The root {0} has a parent")]
+    RootWithParent(NodeId),
+    #[error("Node {0} has no parent")]
+    Orphan(NodeId),
+    #[error("Node {node} has {parent} as its parent, which is not an earlier node")]
+    LaterParent { node: NodeId, parent: NodeId },
+    #[error("Node {node} is not among the children of its parent {parent}")]
+    MissingChild { node: NodeId, parent: NodeId },
+    #[error("Node {parent} lists {child} as a child, which has another parent")]
+    ForeignChild { parent: NodeId, child: NodeId },
 }
 
 impl<T> AssumptionTree<T> {
     pub fn new(initial: T) -> (Self, AssumptionTreeNodeHandle) {
         (
             Self {
-                nodes: vec![AssumptionTreeNode {
+                nodes: vec![Some(AssumptionTreeNode {
                     parent: None,
                     data: initial,
                     children: vec![],
-                }],
+                })],
             },
             AssumptionTreeNodeHandle(0),
         )
@@ -43,28 +86,154 @@ impl<T> AssumptionTree<T> {
         parent: AssumptionTreeNodeHandle,
         child: T,
     ) -> AssumptionTreeNodeHandle {
-        self.nodes.push(AssumptionTreeNode {
+        self.nodes.push(Some(AssumptionTreeNode {
             parent: Some(parent),
             data: child,
             children: vec![],
-        });
+        }));
 
         let child_handle = AssumptionTreeNodeHandle(self.nodes.len() - 1);
 
-        self.nodes[parent.0].children.push(child_handle);
+        self.node_mut(parent).children.push(child_handle);
+
+        child_handle
+    }
 
-        return child_handle;
+    ///Removes the node and everything below it, and returns the removed handles. The root can't
+    ///be removed.
+    pub fn remove_subtree(
+        &mut self,
+        node: AssumptionTreeNodeHandle,
+    ) -> Result<Vec<AssumptionTreeNodeHandle>, AssumptionTreeError> {
+        if !self.contains(node) {
+            return Err(AssumptionTreeError::UnknownNode(NodeId(node.0)));
+        }
+        let parent = self
+            .parent_of(node)
+            .ok_or(AssumptionTreeError::RootRemoval)?;
+        self.node_mut(parent).children.retain(|it| *it != node);
+        let mut removed = Vec::new();
+        let mut open = vec![node];
+        while let Some(handle) = open.pop() {
+            if let Some(it) = self.nodes[handle.0].take() {
+                open.extend(it.children);
+                removed.push(handle);
+            }
+        }
+        removed.sort_by_key(|it| it.0);
+        Ok(removed)
     }
 
+    ///The handle of the node, telling nodes that were removed apart from ones that never existed.
     pub fn get_handle(&self, node: usize) -> Result<AssumptionTreeNodeHandle, AssumptionTreeError> {
-        if node >= self.nodes.len() {
-            return Err(AssumptionTreeError::UnknownNode(node));
+        match self.nodes.get(node) {
+            Some(Some(_)) => Ok(AssumptionTreeNodeHandle(node)),
+            Some(None) => Err(AssumptionTreeError::Pruned(NodeId(node))),
+            None => Err(AssumptionTreeError::UnknownNode(NodeId(node))),
         }
-        return Ok(AssumptionTreeNodeHandle(node));
+    }
+
+    ///Every node of the tree, in insertion order.
+    pub fn handles(&self) -> impl Iterator<Item = AssumptionTreeNodeHandle> + '_ {
+        self.nodes
+            .iter()
+            .enumerate()
+            .filter(|(_, it)| it.is_some())
+            .map(|(index, _)| AssumptionTreeNodeHandle(index))
+    }
+
+    pub fn root(&self) -> AssumptionTreeNodeHandle {
+        AssumptionTreeNodeHandle(0)
+    }
+
+    pub fn contains(&self, node: AssumptionTreeNodeHandle) -> bool {
+        self.nodes.get(node.0).is_some_and(|it| it.is_some())
+    }
+
+    pub fn children_of(
+        &self,
+        node: AssumptionTreeNodeHandle,
+    ) -> impl Iterator<Item = AssumptionTreeNodeHandle> + '_ {
+        self.node(node).children.iter().copied()
+    }
+
+    pub fn is_leaf(&self, node: AssumptionTreeNodeHandle) -> bool {
+        self.node(node).children.is_empty()
     }
 
     pub fn parent_of(&self, node: AssumptionTreeNodeHandle) -> Option<AssumptionTreeNodeHandle> {
-        self.nodes[node.0].parent
+        self.node(node).parent
+    }
+
+    ///Checks that only the root has no parent, that parents are inserted before their children,
+    ///and that the children of every node are exactly the nodes that have it as their parent.
+    pub fn check_links(&self) -> Vec<TreeLinkViolation> {
+        let mut violations = Vec::new();
+        for handle in self.handles() {
+            let (index, node) = (handle.0, self.node(handle));
+            match (index, node.parent) {
+                (0, Some(_)) => violations.push(TreeLinkViolation::RootWithParent(NodeId(0))),
+                (0, None) => {}
+                (_, None) => violations.push(TreeLinkViolation::Orphan(NodeId(index))),
+                (_, Some(parent)) if parent.0 >= index || !self.contains(parent) => violations
+                    .push(TreeLinkViolation::LaterParent {
+                        node: NodeId(index),
+                        parent: NodeId(parent.0),
+                    }),
+                (_, Some(parent)) => {
+                    if !self.node(parent).children.contains(&handle) {
+                        violations.push(TreeLinkViolation::MissingChild {
+                            node: NodeId(index),
+                            parent: NodeId(parent.0),
+                        })
+                    }
+                }
+            }
+            for child in node.children.iter() {
+                let parent = match self.contains(*child) {
+                    true => self.node(*child).parent,
+                    false => None,
+                };
+                if parent != Some(handle) {
+                    violations.push(TreeLinkViolation::ForeignChild {
+                        parent: NodeId(index),
+                        child: NodeId(child.0),
+                    })
+                }
+            }
+        }
+        violations
+    }
+
+    ///Rebuilds the children of every node from the parents. Nodes without a valid parent are
+    ///attached to the root.
+    pub fn repair_links(&mut self) {
+        let handles: Vec<_> = self.handles().collect();
+        for handle in handles {
+            let parent = match (handle.0, self.node(handle).parent) {
+                (0, _) => None,
+                (index, Some(parent)) if parent.0 < index && self.contains(parent) => Some(parent),
+                _ => Some(self.root()),
+            };
+            let node = self.node_mut(handle);
+            node.children.clear();
+            node.parent = parent;
+            if let Some(parent) = parent {
+                self.node_mut(parent).children.push(handle);
+            }
+        }
+    }
+
+    fn node(&self, handle: AssumptionTreeNodeHandle) -> &AssumptionTreeNode<T> {
+        self.nodes[handle.0]
+            .as_ref()
+            .unwrap_or_else(|| panic!("Node {} was removed", handle))
+    }
+
+    fn node_mut(&mut self, handle: AssumptionTreeNodeHandle) -> &mut AssumptionTreeNode<T> {
+        self.nodes[handle.0]
+            .as_mut()
+            .unwrap_or_else(|| panic!("Node {} was removed", handle))
     }
 }
 
@@ -72,13 +241,13 @@ impl<T> Index<AssumptionTreeNodeHandle> for AssumptionTree<T> {
     type Output = T;
 
     fn index(&self, index: AssumptionTreeNodeHandle) -> &Self::Output {
-        &self.nodes.index(index.0).data
+        &self.node(index).data
     }
 }
 
 impl<T> IndexMut<AssumptionTreeNodeHandle> for AssumptionTree<T> {
     fn index_mut(&mut self, index: AssumptionTreeNodeHandle) -> &mut Self::Output {
-        &mut self.nodes.index_mut(index.0).data
+        &mut self.node_mut(index).data
     }
 }
 
@@ -93,9 +262,20 @@ where
             indent: usize,
             f: &mut Formatter<'_>,
         ) -> std::fmt::Result {
-            write!(f, "{0:1$} - ({3}) {2}\n", "", indent, nodes[handle], handle)?;
-            for child in nodes.nodes[handle.0].children.iter() {
-                do_fmt(*child, nodes, indent + 2, f)?;
+            match (terminal::accessible(), nodes.parent_of(handle)) {
+                (true, Some(parent)) => writeln!(
+                    f,
+                    "State {}, level {}, under {}: {}",
+                    handle,
+                    indent / 2,
+                    parent,
+                    nodes[handle]
+                )?,
+                (true, None) => writeln!(f, "State {}: {}", handle, nodes[handle])?,
+                (false, _) => writeln!(f, "{0:1$} - ({3}) {2}", "", indent, nodes[handle], handle)?,
+            }
+            for child in nodes.children_of(handle) {
+                do_fmt(child, nodes, indent + 2, f)?;
             }
             Ok(())
         }
@@ -106,6 +286,131 @@ where
 
 impl Display for AssumptionTreeNodeHandle {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        self.0.fmt(f)
+        NodeId(self.0).fmt(f)
+    }
+}
+
+impl NodeId {
+    pub fn from_index(index: usize) -> Self {
+        Self(index)
+    }
+
+    pub fn index(&self) -> usize {
+        self.0
+    }
+
+    fn check_letter(&self) -> char {
+        let sum: u32 = self
+            .0
+            .to_string()
+            .bytes()
+            .rev()
+            .enumerate()
+            .map(|(weight, digit)| (weight as u32 + 1) * (digit - b'0') as u32)
+            .sum();
+        (b'a' + (sum % 26) as u8) as char
+    }
+}
+
+impl Display for NodeId {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "n{}{}", self.0, self.check_letter())
+    }
+}
+
+impl FromStr for NodeId {
+    type Err = NodeIdError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let digits = s.strip_prefix('n').unwrap_or(s);
+        let (digits, check_letter) = match digits.chars().last() {
+            Some(last) if last.is_ascii_lowercase() => (&digits[..digits.len() - 1], Some(last)),
+            _ => (digits, None),
+        };
+        let id = digits
+            .parse::<usize>()
+            .map(NodeId)
+            .map_err(|_| NodeIdError::Malformed(s.into()))?;
+        match check_letter {
+            None if !s.starts_with('n') => Err(NodeIdError::Ambiguous(id)),
+            None => Err(NodeIdError::MissingCheckLetter(id)),
+            Some(letter) if letter != id.check_letter() => {
+                Err(NodeIdError::WrongCheckLetter(s.into()))
+            }
+            Some(_) => Ok(id),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::fact_solver::fact_db::{FactHandle, FactHandleError};
+
+    use super::{AssumptionTree, AssumptionTreeError, NodeId, NodeIdError};
+
+    #[test]
+    fn test_node_id_roundtrip() {
+        for id in 0..200 {
+            let text = NodeId(id).to_string();
+            assert_eq!(text.parse::<NodeId>().unwrap(), NodeId(id));
+        }
+    }
+
+    #[test]
+    fn test_node_id_catches_typos() {
+        let swapped = NodeId(12).to_string().replace("12", "21");
+        assert!(matches!(
+            swapped.parse::<NodeId>(),
+            Err(NodeIdError::WrongCheckLetter(_))
+        ));
+        assert!(matches!(
+            "n12".parse::<NodeId>(),
+            Err(NodeIdError::MissingCheckLetter(NodeId(12)))
+        ));
+    }
+
+    #[test]
+    fn test_bare_numbers_are_ambiguous() {
+        assert!(matches!(
+            "12".parse::<NodeId>(),
+            Err(NodeIdError::Ambiguous(NodeId(12)))
+        ));
+        assert!(matches!(
+            "12".parse::<FactHandle>(),
+            Err(FactHandleError::Ambiguous(12))
+        ));
+        assert_eq!(
+            "F12".parse::<FactHandle>().unwrap(),
+            FactHandle::from_raw(12)
+        );
+        assert!("n12".parse::<FactHandle>().is_err());
+        assert!("F12".parse::<NodeId>().is_err());
+    }
+
+    #[test]
+    fn test_removed_subtrees_keep_the_other_ids() {
+        let (mut tree, root) = AssumptionTree::new("root");
+        let a = tree.insert_child(root, "a");
+        let b = tree.insert_child(root, "b");
+        let a1 = tree.insert_child(a, "a1");
+        let a2 = tree.insert_child(a1, "a2");
+
+        assert!(tree.remove_subtree(root).is_err());
+        assert_eq!(tree.remove_subtree(a).unwrap(), vec![a, a1, a2]);
+        assert_eq!(tree.handles().collect::<Vec<_>>(), vec![root, b]);
+        assert_eq!(tree.children_of(root).collect::<Vec<_>>(), vec![b]);
+        assert_eq!(tree[b], "b");
+        assert!(matches!(
+            tree.get_handle(1),
+            Err(AssumptionTreeError::Pruned(NodeId(1)))
+        ));
+        assert!(matches!(
+            tree.get_handle(9),
+            Err(AssumptionTreeError::UnknownNode(NodeId(9)))
+        ));
+        assert!(tree.check_links().is_empty());
+
+        let c = tree.insert_child(b, "c");
+        assert_eq!(tree.get_handle(5).unwrap(), c);
     }
 }
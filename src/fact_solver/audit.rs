@@ -0,0 +1,146 @@
+//! Which rules carried the session: every fact a state deduced is credited to the rules its proof
+//! rests on, split evenly between them, so a rule that forced a lot on its own ranks above one that
+//! only ever helped out. Summed up by kind, it tells which kinds of hints are worth hunting for
+//! first in the next lock.
+
+use std::fmt::{Display, Formatter};
+
+use itertools::Itertools;
+
+use crate::RuleKind;
+
+use super::FactualSolver;
+
+#[derive(Debug, Clone)]
+pub struct RuleCredit {
+    pub rule: usize,
+    pub kind: RuleKind,
+    ///The facts the rule is credited with, each counting `1 / n` for a proof resting on `n` rules.
+    pub credit: f64,
+    ///The facts whose proof the rule appears in at all.
+    pub facts: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct RuleAudit {
+    ///Every rule of the lock, the most credited first.
+    pub rules: Vec<RuleCredit>,
+    ///The credit of the rules of each kind, summed up, with how many rules it is spread over.
+    pub kinds: Vec<(&'static str, f64, usize)>,
+    ///Facts deduced from assumptions and givens alone, which no rule is credited with.
+    pub uncredited: usize,
+}
+
+impl FactualSolver {
+    ///Credits every fact of every state to the rules its proof rests on. A state is only
+    ///credited with the facts it added to those of its parent.
+    pub fn audit_rules(&self) -> RuleAudit {
+        let mut rules: Vec<_> = self
+            .lock
+            .rules
+            .iter()
+            .enumerate()
+            .map(|(rule, kind)| RuleCredit {
+                rule,
+                kind: kind.clone(),
+                credit: 0.0,
+                facts: 0,
+            })
+            .collect();
+        let mut uncredited = 0;
+        for node in self.states.handles() {
+            let facts = &self.states[node].facts;
+            let known = self.inherited(node);
+            for (handle, _) in facts.facts_since(known) {
+                let blamed = facts.rules_of(handle);
+                if blamed.is_empty() {
+                    uncredited += 1;
+                }
+                for rule in blamed.iter() {
+                    rules[*rule].credit += 1.0 / blamed.len() as f64;
+                    rules[*rule].facts += 1;
+                }
+            }
+        }
+
+        let kinds = rules
+            .iter()
+            .into_group_map_by(|it| it.kind.name())
+            .into_iter()
+            .map(|(kind, rules)| {
+                (
+                    kind,
+                    rules.iter().map(|it| it.credit).sum::<f64>(),
+                    rules.len(),
+                )
+            })
+            .sorted_by(|a, b| b.1.total_cmp(&a.1).then(a.0.cmp(b.0)))
+            .collect();
+        rules.sort_by(|a, b| b.credit.total_cmp(&a.credit).then(a.rule.cmp(&b.rule)));
+        RuleAudit {
+            rules,
+            kinds,
+            uncredited,
+        }
+    }
+}
+
+impl Display for RuleAudit {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Rules by the facts they are credited with:")?;
+        for it in self.rules.iter() {
+            writeln!(
+                f,
+                "  {:>7.2} Rule {}: '{}', in {} proofs",
+                it.credit, it.rule, it.kind, it.facts
+            )?;
+        }
+        writeln!(f, "Kinds by the facts their rules are credited with:")?;
+        for (kind, credit, rules) in self.kinds.iter() {
+            writeln!(f, "  {:>7.2} {}, {} rules", credit, kind, rules)?;
+        }
+        write!(
+            f,
+            "{} facts rest on assumptions and givens alone.",
+            self.uncredited
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        fact_solver::FactualSolver,
+        index::RunePosition,
+        rule::ActivationRuleKindHelpers,
+        test_support::{activation, sample_runes},
+        RuneLock,
+    };
+
+    #[test]
+    fn test_facts_are_credited_to_the_rules_of_their_proof() {
+        let lock = RuneLock::with_rules(
+            sample_runes(),
+            vec![
+                (1, 2).alwanese(),
+                (5, 6).increase_santor(),
+                (7, 8).antakian_twins(),
+            ],
+        );
+        let mut solver = FactualSolver::new(lock);
+        solver.assume(activation(1), RunePosition::new(0));
+
+        let audit = solver.audit_rules();
+        assert_eq!(audit.rules.len(), 3);
+        assert!(audit
+            .rules
+            .windows(2)
+            .all(|it| it[0].credit >= it[1].credit));
+        //Assuming #1 only tells the alwanese rule something new.
+        assert_eq!(audit.rules[0].rule, 0);
+        let total: f64 = audit.rules.iter().map(|it| it.credit).sum();
+        let kinds: f64 = audit.kinds.iter().map(|(_, credit, _)| credit).sum();
+        assert!((total - kinds).abs() < 1e-9);
+        assert!(audit.uncredited > 0);
+    }
+}
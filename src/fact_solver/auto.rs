@@ -0,0 +1,245 @@
+//! Depth-first exploration that drives `assume` by itself, recording every state it visits in the
+//! assumption tree so the search can be inspected and continued by hand.
+
+use std::ops::ControlFlow;
+
+use crate::{activation::Activation, index::RunePosition};
+
+use super::{
+    assumption_tree::AssumptionTreeNodeHandle, fact_db::FactDb, search::narrowest_branch,
+    FactualSolver, SolverAction, SolverStateState,
+};
+
+///Picks the candidates the exploration branches on in a state.
+pub trait Strategy {
+    ///The candidates to assume in turn, in the order they are tried. Together they have to cover
+    ///every solution of the state, like all the activations left for one position do, or all the
+    ///positions left for one activation on a lock with positions to spare. Only asked
+    ///about states that are neither contradicted nor solved, so at least one activation is open.
+    fn branch(&mut self, facts: &FactDb) -> Vec<(RunePosition, Activation)>;
+}
+
+///Branches on the position with the fewest activations left, the strategy of `auto_explore`. On a
+///lock of fewer activations than positions a position may stay empty, so it branches on the
+///activation with the fewest positions left instead.
+pub struct FewestActivations;
+
+impl Strategy for FewestActivations {
+    fn branch(&mut self, facts: &FactDb) -> Vec<(RunePosition, Activation)> {
+        let Ok(fixed) = facts.fixed_assignment() else {
+            return Vec::new();
+        };
+        narrowest_branch(facts, &fixed).unwrap_or_default()
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct AutoOutcome {
+    ///The first state whose facts place every activation.
+    pub solution: Option<AssumptionTreeNodeHandle>,
+    ///How many states were added to the tree.
+    pub added: usize,
+    ///How many of the visited states were contradicted.
+    pub contradictions: usize,
+    pub interrupted: bool,
+}
+
+impl FactualSolver {
+    ///Explores the current state depth first, at most `depth` assumptions deep, until a solution is
+    ///found. Each step assumes a candidate of the position with the fewest left, and backtracks
+    ///once the state is contradicted. The current state becomes the solution, if there is one.
+    pub fn auto_explore(&mut self, depth: usize) -> AutoOutcome {
+        self.auto_explore_with(&mut FewestActivations, depth)
+    }
+
+    ///Like `auto_explore`, but branches on the candidates the strategy picks.
+    pub fn auto_explore_with(&mut self, strategy: &mut impl Strategy, depth: usize) -> AutoOutcome {
+        let mut outcome = AutoOutcome::default();
+        if let ControlFlow::Break(solution) =
+            self.explore(self.current, depth, strategy, &mut outcome)
+        {
+            outcome.solution = solution;
+        }
+        if let Some(solution) = outcome.solution {
+            self.current = solution;
+        }
+        outcome
+    }
+
+    ///Breaks with the solution, or with `None` if interrupted.
+    fn explore(
+        &mut self,
+        node: AssumptionTreeNodeHandle,
+        depth: usize,
+        strategy: &mut impl Strategy,
+        outcome: &mut AutoOutcome,
+    ) -> ControlFlow<Option<AssumptionTreeNodeHandle>> {
+        if self.interrupt.is_cancelled() {
+            outcome.interrupted = true;
+            return ControlFlow::Break(None);
+        }
+        let facts = &self.states[node].facts;
+        if let SolverStateState::Contradicts(_) = self.states[node].state {
+            outcome.contradictions += 1;
+            return ControlFlow::Continue(());
+        }
+        let Ok(fixed) = facts.fixed_assignment() else {
+            return ControlFlow::Continue(());
+        };
+        if fixed.is_complete() {
+            return match self.lock.validate(&fixed) {
+                Ok(_) => ControlFlow::Break(Some(node)),
+                Err(_) => ControlFlow::Continue(()),
+            };
+        }
+        if depth == 0 {
+            return ControlFlow::Continue(());
+        }
+        for (position, activation) in strategy.branch(facts) {
+            //Branches assumed earlier, by hand or by a previous run, are reused.
+            let existing = self.states.children_of(node).find(|it| {
+                matches!(
+                    self.states[*it].action,
+                    SolverAction::Assume { position: p, activation: a } if p == position && a == activation
+                )
+            });
+            let child = match existing {
+                Some(it) => it,
+                None => {
+                    outcome.added += 1;
+                    let derived = self.derive(node, position, activation);
+                    let child = self.states.insert_child(node, derived);
+                    self.publish_derived(child);
+                    child
+                }
+            };
+            self.explore(child, depth - 1, strategy, outcome)?;
+        }
+        ControlFlow::Continue(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        activation::Activation,
+        fact_solver::{fact_db::FactDb, view::View, FactualSolver},
+        index::RunePosition,
+        outcome::Outcome,
+        rule::ActivationRuleKindHelpers,
+        rune::Rune,
+        size::LockSize,
+        test_support::sample_runes,
+        RuneLock,
+    };
+
+    use super::Strategy;
+
+    ///Branches on the positions of the lowest activation that isn't placed yet.
+    struct LowestActivation {
+        branches: usize,
+    }
+
+    impl Strategy for LowestActivation {
+        fn branch(&mut self, facts: &FactDb) -> Vec<(RunePosition, Activation)> {
+            self.branches += 1;
+            let open = facts
+                .size()
+                .all_activations()
+                .map(|activation| (activation, facts.possibilities_for(activation).collect()))
+                .find(|(_, positions): &(_, Vec<_>)| positions.len() > 1);
+            open.map_or_else(Vec::new, |(activation, positions)| {
+                positions.into_iter().map(|it| (it, activation)).collect()
+            })
+        }
+    }
+
+    #[test]
+    fn test_auto_finds_a_solution() {
+        let lock = RuneLock::with_rules(
+            sample_runes(),
+            vec![
+                (1, 2).alwanese(),
+                (2, 3).antakian_conjugate(),
+                (3, 4).alwanese(),
+                (4, 5).antakian_twins(),
+                (5, 6).increase_santor(),
+                (6, 7).alwanese(),
+                (7, 8).antakian_conjugate(),
+                (8, 9).alwanese(),
+                (9, 10).antakian_twins(),
+                (10, 11).increase_santor(),
+            ],
+        );
+        let mut solver = FactualSolver::new(lock.clone());
+        let root = solver.root();
+
+        let shallow = solver.auto_explore(1);
+        assert!(shallow.solution.is_none());
+        assert_eq!(solver.current(), root);
+
+        let outcome = solver.auto_explore(12);
+        let solution = outcome.solution.unwrap();
+        assert_eq!(solver.current(), solution);
+        assert!(!outcome.interrupted);
+        assert!(lock.validate(&solver.fixed_assignment().unwrap()).is_ok());
+        assert_eq!(
+            solver.states.handles().count(),
+            1 + shallow.added + outcome.added
+        );
+    }
+
+    #[test]
+    fn test_auto_leaves_positions_of_small_locks_empty() {
+        let size = LockSize::new(8, 6).unwrap();
+        let mut lock =
+            RuneLock::sized(size, [0, 2, 1, 3, 2, 1, 3, 0].map(Rune::new).to_vec()).unwrap();
+        for rule in [
+            (1, 2).alwanese(),
+            (2, 3).antakian_conjugate(),
+            (4, 5).increase_santor(),
+        ] {
+            lock.add_rule(rule, 0);
+        }
+        let mut solver = FactualSolver::new(lock.clone());
+
+        let outcome = solver.auto_explore(size.activations());
+        let solution = outcome.solution.unwrap();
+        let fixed = solver.fixed_assignment().unwrap();
+        assert!(fixed.is_complete());
+        assert!(lock.validate(&fixed).is_ok());
+        let Outcome::Solved { assignment } = solver.outcome(solution) else {
+            panic!("auto_explore stops at a solution");
+        };
+        assert_eq!(assignment.len(), 8);
+        assert_eq!(assignment.iter().filter(|it| it.is_none()).count(), 2);
+
+        let solutions = solver.solve_all();
+        assert!(solutions.complete);
+        assert!(solutions
+            .assignments
+            .iter()
+            .all(|it| it.is_complete() && lock.validate(it).is_ok()));
+    }
+
+    #[test]
+    fn test_auto_follows_the_strategy() {
+        let lock = RuneLock::with_rules(
+            sample_runes(),
+            vec![(1, 2).alwanese(), (2, 3).antakian_conjugate()],
+        );
+        let mut solver = FactualSolver::new(lock.clone());
+        let mut strategy = LowestActivation { branches: 0 };
+
+        let outcome = solver.auto_explore_with(&mut strategy, 12);
+        assert!(outcome.solution.is_some());
+        assert!(strategy.branches > 0);
+        assert!(lock.validate(&solver.fixed_assignment().unwrap()).is_ok());
+        //The first branch is on the positions of #1.
+        let first = solver.states.children_of(solver.root()).next().unwrap();
+        assert!(matches!(
+            solver.states[first].action,
+            super::SolverAction::Assume { activation, .. } if activation == Activation::from_usize(0)
+        ));
+    }
+}
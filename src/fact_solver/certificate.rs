@@ -0,0 +1,593 @@
+//! Proofs of single facts that can be checked without the solver: the lock and every fact the
+//! proven one rests on, each citing only facts before it. Checking a step only evaluates the
+//! rules of the lock on the cells involved, so a certificate can be trusted without trusting the
+//! session it was exported from.
+
+use std::{collections::HashSet, fs, io, path::Path};
+
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::{
+    activation::Activation, assignment::Assignment, index::RunePosition, rule::ValidateTupleError,
+    RuleKind, RuneLock,
+};
+
+use super::{
+    assumption_tree::AssumptionTreeNodeHandle, fact_db::FactHandle, ContradictionKind, Fact,
+    FactKind, FactReason, FactualSolver,
+};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum StepReason {
+    ///An earlier step, by its index.
+    Step(usize),
+    Rule(usize),
+    ///A domain of the lock, by its index.
+    Given(usize),
+    ///Imported from outside of the solver, with the note describing where from.
+    External(String),
+    Assumption,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Step {
+    ///The handle of the fact in the state it was exported from, like `F12`, so every export of
+    ///the state refers to the fact by the same id.
+    #[serde(default)]
+    pub id: String,
+    pub kind: FactKind,
+    pub activation: Activation,
+    pub position: RunePosition,
+    #[serde(default)]
+    pub range: Vec<RunePosition>,
+    pub reasons: Vec<StepReason>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Certificate {
+    pub lock: RuneLock,
+    ///The facts the claim rests on, in an order where every step only cites steps before it. The
+    ///claim is the last step.
+    pub steps: Vec<Step>,
+}
+
+#[derive(Debug, Error)]
+pub enum CertificateError {
+    #[error("The certificate has no steps")]
+    Empty,
+    #[error("The lock has {0} runes, which don't fill its positions")]
+    RuneCount(usize),
+    #[error("Step {0} is about a position or activation that is not on the lock")]
+    OutOfRange(usize),
+    #[error("Step {step} cites step {cited}, which doesn't come before it")]
+    Forward { step: usize, cited: usize },
+    #[error("Step {step} cites rule {rule}, which the lock doesn't apply")]
+    UnknownRule { step: usize, rule: usize },
+    #[error("Step {step} cites domain {domain}, which the lock doesn't have")]
+    UnknownDomain { step: usize, domain: usize },
+    #[error("Step {0} '{1}' does not follow from its reasons")]
+    NotEntailed(usize, String),
+}
+
+#[derive(Debug, Error)]
+pub enum CertificateFileError {
+    #[error("Could not access {path}: {source}")]
+    Io { path: String, source: io::Error },
+    #[error("{path} is not a certificate: {source}")]
+    Format {
+        path: String,
+        source: serde_json::Error,
+    },
+}
+
+///What a valid certificate proves.
+pub struct Verified {
+    pub claim: String,
+    ///What the claim only holds under: the assumptions and imported facts among the steps.
+    pub premises: Vec<String>,
+}
+
+impl Step {
+    fn fact(&self) -> Fact {
+        Fact {
+            kind: self.kind,
+            activation: self.activation,
+            position: self.position,
+            reasons: Vec::new(),
+            range: self.range.clone(),
+        }
+    }
+
+    fn is_premise(&self) -> bool {
+        self.reasons
+            .iter()
+            .any(|it| matches!(it, StepReason::Assumption | StepReason::External(_)))
+    }
+}
+
+impl FactualSolver {
+    ///A certificate for the fact of the state, with every fact it rests on.
+    pub fn certificate(
+        &self,
+        node: AssumptionTreeNodeHandle,
+        handle: FactHandle,
+    ) -> Option<Certificate> {
+        let facts = &self.states[node].facts;
+        facts.get(handle)?;
+        //Reasons always come before the facts citing them, so ordering by handle is enough.
+        let mut cited = HashSet::new();
+        let mut open = vec![handle];
+        while let Some(handle) = open.pop() {
+            if !cited.insert(handle) {
+                continue;
+            }
+            for reason in facts.get(handle)?.reasons.iter() {
+                if let FactReason::Fact(reason, _) = reason {
+                    open.push(*reason);
+                }
+            }
+        }
+        let order: Vec<_> = cited.into_iter().sorted().collect();
+        let steps = order
+            .iter()
+            .map(|handle| {
+                let fact = facts.get(*handle)?;
+                let reasons = fact
+                    .reasons
+                    .iter()
+                    .map(|reason| match reason {
+                        FactReason::Fact(cited, _) => {
+                            StepReason::Step(order.binary_search(cited).unwrap())
+                        }
+                        FactReason::Rule(rule) => StepReason::Rule(*rule),
+                        FactReason::Given(domain) => StepReason::Given(*domain),
+                        FactReason::External(note) => {
+                            StepReason::External(facts.note(*note).into())
+                        }
+                        FactReason::Assumption => StepReason::Assumption,
+                    })
+                    .collect();
+                Some(Step {
+                    id: handle.to_string(),
+                    kind: fact.kind,
+                    activation: fact.activation,
+                    position: fact.position,
+                    range: fact.range.clone(),
+                    reasons,
+                })
+            })
+            .collect::<Option<_>>()?;
+        Some(Certificate {
+            lock: self.lock.clone(),
+            steps,
+        })
+    }
+}
+
+impl Certificate {
+    ///Writes the certificate as JSON, or as an HTML page if the path ends in `.html`.
+    pub fn save(&self, path: &Path) -> Result<(), CertificateFileError> {
+        let html = path
+            .extension()
+            .is_some_and(|it| it.eq_ignore_ascii_case("html"));
+        let text = match html {
+            true => self.to_html(),
+            false => serde_json::to_string_pretty(self).map_err(|source| {
+                CertificateFileError::Format {
+                    path: path.display().to_string(),
+                    source,
+                }
+            })?,
+        };
+        fs::write(path, text).map_err(|source| CertificateFileError::Io {
+            path: path.display().to_string(),
+            source,
+        })
+    }
+
+    pub fn load(path: &Path) -> Result<Self, CertificateFileError> {
+        let text = fs::read_to_string(path).map_err(|source| CertificateFileError::Io {
+            path: path.display().to_string(),
+            source,
+        })?;
+        serde_json::from_str(&text).map_err(|source| CertificateFileError::Format {
+            path: path.display().to_string(),
+            source,
+        })
+    }
+
+    ///The anchor of every step in exports that link the steps: its id, or its index for
+    ///certificates written before steps had ids. Ids that repeat get their index appended.
+    pub fn anchors(&self) -> Vec<String> {
+        let mut seen = HashSet::new();
+        self.steps
+            .iter()
+            .enumerate()
+            .map(|(index, step)| {
+                let anchor = step
+                    .id
+                    .chars()
+                    .filter(|it| it.is_ascii_alphanumeric() || *it == '-')
+                    .collect::<String>();
+                match anchor.is_empty() || !seen.insert(anchor.clone()) {
+                    true => format!("step-{}", index),
+                    false => anchor,
+                }
+            })
+            .collect()
+    }
+
+    ///The steps as an HTML page, the claim first. Every step has an anchor, and the steps it
+    ///cites link to theirs, so the reasoning can be followed by clicking through it.
+    pub fn to_html(&self) -> String {
+        let anchors = self.anchors();
+        let escape = |text: String| {
+            text.replace('&', "&amp;")
+                .replace('<', "&lt;")
+                .replace('>', "&gt;")
+        };
+        let claim = self
+            .steps
+            .last()
+            .map_or(String::new(), |it| escape(it.fact().to_string()));
+        let mut html = format!(
+            "<!DOCTYPE html>\n<html>\n<head><meta charset=\"utf-8\"><title>{}</title></head>\n<body>\n<h1>{}</h1>\n<ol>\n",
+            claim, claim
+        );
+        for (index, step) in self.steps.iter().enumerate().rev() {
+            let reasons = step
+                .reasons
+                .iter()
+                .map(|reason| match reason {
+                    StepReason::Step(cited) => match anchors.get(*cited) {
+                        Some(anchor) => format!("<a href=\"#{}\">{}</a>", anchor, anchor),
+                        None => format!("step {}", cited),
+                    },
+                    StepReason::Rule(rule) => match self.lock.rules().get(*rule) {
+                        Some(kind) => escape(format!("rule {} '{}'", rule, kind)),
+                        None => format!("rule {}", rule),
+                    },
+                    StepReason::Given(domain) => match self.lock.domains().get(*domain) {
+                        Some(it) => escape(format!("given {} '{}'", domain, it)),
+                        None => format!("given {}", domain),
+                    },
+                    StepReason::External(note) => escape(format!("imported: {}", note)),
+                    StepReason::Assumption => "assumption".to_string(),
+                })
+                .unique()
+                .join(", ");
+            html.push_str(&format!(
+                "<li id=\"{}\"><strong>{}</strong> {} <em>because</em> {}</li>\n",
+                anchors[index],
+                anchors[index],
+                escape(step.fact().to_string()),
+                reasons
+            ));
+        }
+        html.push_str("</ol>\n</body>\n</html>\n");
+        html
+    }
+
+    ///Checks every step against the rules of the lock, and returns the claim of the last one.
+    pub fn verify(&self) -> Result<Verified, CertificateError> {
+        let claim = self.steps.last().ok_or(CertificateError::Empty)?;
+        if self.lock.runes().len() != self.lock.size().positions() {
+            return Err(CertificateError::RuneCount(self.lock.runes().len()));
+        }
+        for (index, step) in self.steps.iter().enumerate() {
+            self.check_step(index, step)?;
+        }
+        Ok(Verified {
+            claim: claim.fact().to_string(),
+            premises: self
+                .steps
+                .iter()
+                .filter(|it| it.is_premise())
+                .map(|it| it.fact().to_string())
+                .collect(),
+        })
+    }
+
+    fn check_step(&self, index: usize, step: &Step) -> Result<(), CertificateError> {
+        let fact = step.fact();
+        let size = self.lock.size();
+        let in_range = |position: RunePosition, activation: Activation| {
+            position.index() < size.positions() && size.contains(activation)
+        };
+        if !fact.positions().all(|it| in_range(it, fact.activation)) {
+            return Err(CertificateError::OutOfRange(index));
+        }
+        let mut cited = Vec::new();
+        let mut rules = Vec::new();
+        for reason in step.reasons.iter() {
+            match reason {
+                StepReason::Step(it) if *it >= index => {
+                    return Err(CertificateError::Forward {
+                        step: index,
+                        cited: *it,
+                    })
+                }
+                StepReason::Step(it) => cited.push(self.steps[*it].fact()),
+                StepReason::Rule(rule) => {
+                    let kind = self
+                        .lock
+                        .rules()
+                        .get(*rule)
+                        .filter(|_| self.lock.is_active(*rule))
+                        .filter(|it| it.check_size(size).is_ok())
+                        .ok_or(CertificateError::UnknownRule {
+                            step: index,
+                            rule: *rule,
+                        })?;
+                    rules.push(kind.clone());
+                }
+                StepReason::Given(domain) if *domain >= self.lock.domains().len() => {
+                    return Err(CertificateError::UnknownDomain {
+                        step: index,
+                        domain: *domain,
+                    })
+                }
+                StepReason::Given(_) | StepReason::External(_) | StepReason::Assumption => {}
+            }
+        }
+        if step.is_premise() {
+            return Ok(());
+        }
+        let check = Check {
+            lock: &self.lock,
+            cited,
+            rules,
+            domains: step
+                .reasons
+                .iter()
+                .filter_map(|it| match it {
+                    StepReason::Given(domain) => Some(*domain),
+                    _ => None,
+                })
+                .collect(),
+        };
+        match check.entails(&fact) {
+            true => Ok(()),
+            false => Err(CertificateError::NotEntailed(index, fact.to_string())),
+        }
+    }
+}
+
+///The reasons of a single step.
+struct Check<'a> {
+    lock: &'a RuneLock,
+    cited: Vec<Fact>,
+    rules: Vec<RuleKind>,
+    domains: Vec<usize>,
+}
+
+type Cell = (RunePosition, Activation);
+///Whether a cell is in the same lane as the one a fact is about.
+type SameLane<'a> = &'a dyn Fn(Cell) -> bool;
+
+impl Check<'_> {
+    fn entails(&self, fact: &Fact) -> bool {
+        let cell = (fact.position, fact.activation);
+        let size = self.lock.size();
+        //A position only has to take an activation on locks that activate every position.
+        let lanes = |cells: &dyn Fn(SameLane<'_>) -> bool| {
+            (size.is_square() && cells(&|it| it.0 == cell.0)) || cells(&|it| it.1 == cell.1)
+        };
+        match fact.kind {
+            FactKind::ActivationCannotBeOn => fact
+                .positions()
+                .all(|position| self.excluded((position, fact.activation))),
+            FactKind::ActivationMustBeOn => lanes(&|same| {
+                size.cells()
+                    .filter(|it| *it != cell && same(*it))
+                    .all(|it| self.excluded_by_facts(it))
+            }),
+            FactKind::Contradiction(ContradictionKind::ContradictingRequirements) => {
+                let on_cell = |kind: FactKind| {
+                    self.cited
+                        .iter()
+                        .any(|it| it.kind == kind && it.covers(cell.0, cell.1))
+                };
+                on_cell(FactKind::ActivationMustBeOn) && on_cell(FactKind::ActivationCannotBeOn)
+            }
+            FactKind::Contradiction(ContradictionKind::NoOptionsLeft) => lanes(&|same| {
+                size.cells()
+                    .filter(|it| same(*it))
+                    .all(|it| self.excluded_by_facts(it))
+            }),
+        }
+    }
+
+    fn placements(&self) -> impl Iterator<Item = Cell> + '_ {
+        self.cited
+            .iter()
+            .filter(|it| it.kind == FactKind::ActivationMustBeOn)
+            .map(|it| (it.position, it.activation))
+    }
+
+    ///Whether the cited facts rule the cell out, directly or by a placement in its lanes.
+    fn excluded_by_facts(&self, cell: Cell) -> bool {
+        self.cited
+            .iter()
+            .any(|it| it.kind == FactKind::ActivationCannotBeOn && it.covers(cell.0, cell.1))
+            || self
+                .placements()
+                .any(|(position, activation)| (position == cell.0) != (activation == cell.1))
+    }
+
+    fn excluded(&self, cell: Cell) -> bool {
+        let by_domain = self.domains.iter().any(|it| {
+            let domain = &self.lock.domains()[*it];
+            domain.activation == cell.1 && !domain.contains(cell.0, self.lock.size())
+        });
+        by_domain
+            || self.excluded_by_facts(cell)
+            || self
+                .rules
+                .iter()
+                .any(|rule| self.excluded_by_rule(rule, cell))
+            || self.excluded_by_santor_chain(cell)
+    }
+
+    ///Whether the rule can't be fulfilled with the activation on the cell, given the candidates
+    ///the cited facts leave the other activation of the rule.
+    fn excluded_by_rule(&self, rule: &RuleKind, cell: Cell) -> bool {
+        let alone = Assignment::from_tuple_iter(self.lock.size(), std::iter::once(cell))
+            .is_ok_and(|it| rule.validate(self.lock, &it).is_err());
+        if alone {
+            return true;
+        }
+        let Some((first, second)) = rule.activation_pair() else {
+            //Rules about runes only exclude what a placement next to them contradicts, or what
+            //the runes can't be counted out for. Rules about more activations exclude what
+            //contradicts the placements taken together.
+            return self.placements().any(|placed| {
+                placed != cell && rule.validate_tuple(self.lock, placed, cell).is_err()
+            }) || rule.excluded_by_rune_counts(self.lock, cell, |position, activation| {
+                self.excluded_by_facts((position, activation))
+            }) || rule
+                .validate_subset(
+                    self.lock,
+                    self.placements()
+                        .filter(|it| *it != cell)
+                        .chain(std::iter::once(cell)),
+                )
+                .is_err_and(|it| matches!(it, ValidateTupleError::RuleError(_)));
+        };
+        let other = match cell.1 {
+            it if it == first => second,
+            it if it == second => first,
+            _ => return false,
+        };
+        self.lock
+            .size()
+            .all_positions()
+            .filter(|it| !self.excluded_by_facts((*it, other)))
+            .all(|position| {
+                rule.validate_tuple(self.lock, cell, (position, other))
+                    .is_err()
+            })
+    }
+
+    ///Whether the cited santor rules form a chain the activation on the cell doesn't fit into,
+    ///as the chain needs a distinct santor value per step.
+    fn excluded_by_santor_chain(&self, cell: Cell) -> bool {
+        let links: Vec<_> = self
+            .rules
+            .iter()
+            .filter_map(|it| match it {
+                RuleKind::IncreaseSantor { first, second } => Some((*first, *second)),
+                _ => None,
+            })
+            .collect();
+        if links.is_empty() || links.len() != self.rules.len() {
+            return false;
+        }
+        let Some(start) = links
+            .iter()
+            .map(|it| it.0)
+            .find(|it| links.iter().all(|link| link.1 != *it))
+        else {
+            return false;
+        };
+        let mut chain = vec![start];
+        while let Some((_, next)) = links.iter().find(|it| Some(&it.0) == chain.last()) {
+            chain.push(*next);
+        }
+        if chain.len() != links.len() + 1 {
+            return false;
+        }
+        let Some(step) = chain.iter().position(|it| *it == cell.1) else {
+            return false;
+        };
+        let lock = self.lock;
+        let santor = lock.santor(cell.0);
+        if lock.santors_between(None, Some(santor)) < step
+            || lock.santors_between(Some(santor), None) < chain.len() - 1 - step
+        {
+            return true;
+        }
+        self.placements().any(|(position, activation)| {
+            let Some(other_step) = chain.iter().position(|it| *it == activation) else {
+                return false;
+            };
+            let (low, high, from, to) = match other_step > step {
+                true => (santor, lock.santor(position), step, other_step),
+                false => (lock.santor(position), santor, other_step, step),
+            };
+            other_step != step
+                && !(low < high && lock.santors_between(Some(low), Some(high)) >= to - from - 1)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        domain::{ActivationDomain, Region},
+        fact_solver::FactualSolver,
+        index::RunePosition,
+        rule::{ActivationRuleKindHelpers, RuleTemplate},
+        test_support::{activation, sample_runes},
+        RuneLock,
+    };
+
+    use super::{CertificateError, StepReason};
+
+    #[test]
+    fn test_every_fact_has_a_valid_certificate() {
+        let mut lock = RuneLock::with_rules(
+            sample_runes(),
+            vec![
+                (1, 2).alwanese(),
+                (2, 3).antakian_conjugate(),
+                (3, 4).different_runes(),
+            ],
+        );
+        lock.add_template(RuleTemplate::santor_chain(9, 12), 0);
+        lock.add_domain(ActivationDomain {
+            activation: activation(5),
+            region: Region::InnerRing,
+        });
+        let mut solver = FactualSolver::new(lock);
+        let node = solver.assume(activation(1), RunePosition::new(0));
+        solver.deepen_idle_leaf();
+
+        let facts = &solver.states[node].facts;
+        assert!(facts.fact_count() > 15);
+        for (handle, fact) in facts.iter_facts() {
+            let certificate = solver.certificate(node, handle).unwrap();
+            let verified = certificate.verify();
+            assert!(verified.is_ok(), "{}: {}", fact, verified.err().unwrap());
+        }
+
+        let (handle, _) = facts
+            .iter_facts()
+            .find(|(_, it)| it.reasons.len() > 1)
+            .unwrap();
+        let certificate = solver.certificate(node, handle).unwrap();
+        let html = certificate.to_html();
+        let anchors = certificate.anchors();
+        assert_eq!(anchors.last(), Some(&handle.to_string()));
+        for (step, anchor) in certificate.steps.iter().zip(anchors.iter()) {
+            assert!(html.contains(&format!("<li id=\"{}\">", anchor)));
+            for reason in step.reasons.iter() {
+                if let StepReason::Step(cited) = reason {
+                    assert!(html.contains(&format!("href=\"#{}\"", anchors[*cited])));
+                }
+            }
+        }
+
+        let mut forged = solver.certificate(node, handle).unwrap();
+        let claim = forged.steps.last_mut().unwrap();
+        claim
+            .reasons
+            .retain(|it| !matches!(it, StepReason::Rule(_)));
+        assert!(matches!(
+            forged.verify(),
+            Err(CertificateError::NotEntailed(..))
+        ));
+    }
+}
@@ -0,0 +1,172 @@
+//! Which earlier assumptions the contradictions of a sweep rest on. When `try` contradicts most
+//! candidates, the explanations of the contradictions read one at a time hide that they all trace
+//! back to the same hypothesis; grouped by the assumptions they blame, a single assumption doing
+//! all the damage stands out.
+
+use std::{
+    collections::BTreeSet,
+    fmt::{Display, Formatter},
+};
+
+use itertools::Itertools;
+
+use crate::{activation::Activation, index::RunePosition};
+
+use super::{
+    assumption_tree::AssumptionTreeNodeHandle, FactualSolver, SolverAction, SolverStateState,
+};
+
+///Contradictions that rest on the same earlier assumptions.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContradictionCluster {
+    ///The assumptions made before the sweep the contradictions rest on, empty for contradictions
+    ///of the swept candidates with the rules alone.
+    pub blame: BTreeSet<(RunePosition, Activation)>,
+    pub states: Vec<AssumptionTreeNodeHandle>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContradictionClusters {
+    ///How many of the states were contradicted.
+    pub contradictions: usize,
+    ///The clusters, the largest first.
+    pub clusters: Vec<ContradictionCluster>,
+    ///Every earlier assumption any contradiction rests on, with how many contradictions do, the
+    ///most blamed first.
+    pub assumptions: Vec<((RunePosition, Activation), usize)>,
+}
+
+impl FactualSolver {
+    ///Groups the contradicted states among `states` by the assumptions their contradiction rests
+    ///on, leaving out the state's own assumption, which every contradiction of a sweep rests on.
+    pub fn cluster_contradictions(
+        &self,
+        states: &[AssumptionTreeNodeHandle],
+    ) -> ContradictionClusters {
+        let mut blamed = Vec::new();
+        for node in states.iter().copied() {
+            let state = &self.states[node];
+            let SolverStateState::Contradicts(fact) = state.state else {
+                continue;
+            };
+            let own = match state.action {
+                SolverAction::Assume {
+                    position,
+                    activation,
+                } => Some((position, activation)),
+                SolverAction::Root => None,
+            };
+            let blame: BTreeSet<_> = state
+                .facts
+                .assumptions_of(fact)
+                .into_iter()
+                .filter_map(|it| state.facts.get(it))
+                .map(|it| (it.position, it.activation))
+                .filter(|it| Some(*it) != own)
+                .collect();
+            blamed.push((blame, node));
+        }
+
+        let assumptions = blamed
+            .iter()
+            .flat_map(|(blame, _)| blame.iter().copied())
+            .counts()
+            .into_iter()
+            .sorted_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)))
+            .collect();
+        let clusters = blamed
+            .iter()
+            .cloned()
+            .into_group_map()
+            .into_iter()
+            .map(|(blame, states)| ContradictionCluster { blame, states })
+            .sorted_by(|a, b| {
+                b.states
+                    .len()
+                    .cmp(&a.states.len())
+                    .then_with(|| a.blame.cmp(&b.blame))
+            })
+            .collect();
+        ContradictionClusters {
+            contradictions: blamed.len(),
+            clusters,
+            assumptions,
+        }
+    }
+}
+
+fn cell((position, activation): &(RunePosition, Activation)) -> String {
+    format!("{} = {}", position, activation)
+}
+
+impl Display for ContradictionClusters {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let total = self.contradictions;
+        for (assumption, count) in self.assumptions.iter() {
+            writeln!(
+                f,
+                "{} of {} contradictions trace to assumption {}.",
+                count,
+                total,
+                cell(assumption)
+            )?;
+        }
+        for cluster in self.clusters.iter() {
+            let blame = match cluster.blame.is_empty() {
+                true => "the swept candidate alone".to_string(),
+                false => cluster.blame.iter().map(cell).join(" and "),
+            };
+            writeln!(
+                f,
+                "  {} rest on {}: states {}",
+                cluster.states.len(),
+                blame,
+                cluster.states.iter().join(", ")
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        fact_solver::FactualSolver,
+        index::RunePosition,
+        test_support::{activation, sample_runes},
+        RuneLock,
+    };
+
+    #[test]
+    fn test_contradictions_are_clustered_by_the_assumptions_they_rest_on() {
+        let lock = RuneLock::with_rules(
+            sample_runes(),
+            vec!["if 1 at 0 then 2 at 1".parse().unwrap()],
+        );
+        let mut solver = FactualSolver::new(lock);
+        let earlier = solver.assume(activation(1), RunePosition::new(0));
+        //With #1 on 0, #2 has to be on 1, so every other position of #2 contradicts.
+        let mut states: Vec<_> = [2, 3, 4]
+            .into_iter()
+            .map(|position| {
+                solver.set_current(earlier);
+                solver.assume(activation(2), RunePosition::new(position))
+            })
+            .collect();
+        solver.set_current(earlier);
+        let open = solver.assume(activation(3), RunePosition::new(5));
+
+        states.push(open);
+        let clusters = solver.cluster_contradictions(&states);
+        assert_eq!(clusters.contradictions, 3);
+        assert_eq!(
+            clusters.assumptions,
+            vec![((RunePosition::new(0), activation(1)), 3)]
+        );
+        assert_eq!(clusters.clusters.len(), 1);
+        assert_eq!(clusters.clusters[0].states.len(), 3);
+        assert!(clusters
+            .to_string()
+            .starts_with("3 of 3 contradictions trace to assumption 0 = #1."));
+    }
+}
@@ -0,0 +1,131 @@
+//! Explanations as Graphviz graphs, for proofs too deep to read as text: every fact of the proof
+//! is a node with an edge to each fact it helped derive, and the rules, givens, imports and
+//! assumptions a fact rests on come in as labelled edges of their own.
+
+use std::collections::HashSet;
+
+use crate::RuneLock;
+
+use super::{
+    fact_db::{FactDb, FactHandle},
+    FactKind, FactLifetime, FactReason, FactualSolver,
+};
+
+fn quoted(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+impl FactDb {
+    ///The proof of the fact as a DOT digraph, or `None` if there is no such fact. Facts that hold
+    ///in every state are drawn gray, contradictions red.
+    pub fn explanation_dot(&self, fact_handle: FactHandle, lock: &RuneLock) -> Option<String> {
+        self.get(fact_handle)?;
+        let mut dot = format!(
+            "digraph {} {{\n  rankdir=BT;\n  node [shape=box];\n",
+            quoted(&fact_handle.to_string())
+        );
+        let mut visited = HashSet::from([fact_handle]);
+        let mut unvisited = vec![fact_handle];
+        while let Some(handle) = unvisited.pop() {
+            let fact = self.get(handle)?;
+            let mut style = Vec::new();
+            if let FactKind::Contradiction(_) = fact.kind {
+                style.push("color=red");
+            }
+            if self.lifetime(handle) == FactLifetime::Universal {
+                style.push("fontcolor=gray40");
+            }
+            if handle == fact_handle {
+                style.push("penwidth=2");
+            }
+            dot.push_str(&format!(
+                "  {} [label={}{}];\n",
+                handle,
+                quoted(&format!("{}: {}", handle, fact)),
+                style
+                    .iter()
+                    .map(|it| format!(", {}", it))
+                    .collect::<String>()
+            ));
+
+            let mut cited = HashSet::new();
+            for (index, reason) in fact.reasons.iter().enumerate() {
+                let label = match reason {
+                    FactReason::Fact(reason, _) => {
+                        if cited.insert(*reason) {
+                            dot.push_str(&format!("  {} -> {};\n", reason, handle));
+                        }
+                        if visited.insert(*reason) {
+                            unvisited.push(*reason);
+                        }
+                        continue;
+                    }
+                    FactReason::Rule(rule) => format!("Rule {} '{}'", rule, lock.rules[*rule]),
+                    FactReason::Given(domain) => {
+                        format!("Given {} '{}'", domain, lock.domains[*domain])
+                    }
+                    FactReason::External(note) => {
+                        format!("External {} '{}'", note, self.note(*note))
+                    }
+                    FactReason::Assumption => "Assumed".to_string(),
+                };
+                dot.push_str(&format!(
+                    "  {0}_{1} [shape=point];\n  {0}_{1} -> {0} [label={2}];\n",
+                    handle,
+                    index,
+                    quoted(&label)
+                ));
+            }
+        }
+        dot.push_str("}\n");
+        Some(dot)
+    }
+}
+
+impl FactualSolver {
+    ///The proof of a fact of the current state as a DOT digraph, see `FactDb::explanation_dot`.
+    pub fn explanation_dot(&self, fact_handle: FactHandle) -> Option<String> {
+        self.states[self.current]
+            .facts
+            .explanation_dot(fact_handle, &self.lock)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        fact_solver::{fact_db::FactHandle, FactualSolver},
+        index::RunePosition,
+        rule::ActivationRuleKindHelpers,
+        test_support::{activation, sample_runes},
+        RuneLock,
+    };
+
+    #[test]
+    fn test_proofs_are_written_as_graphs() {
+        let lock = RuneLock::with_rules(sample_runes(), vec![(1, 2).alwanese()]);
+        let mut solver = FactualSolver::new(lock);
+        solver.assume(activation(1), RunePosition::new(0));
+        let facts = solver.facts();
+        let handle = facts
+            .handle_at(RunePosition::new(6), activation(2))
+            .unwrap();
+
+        let dot = solver.explanation_dot(handle).unwrap();
+        assert!(dot.starts_with(&format!("digraph \"{}\" {{", handle)));
+        assert!(dot.trim_end().ends_with('}'));
+        assert!(dot.contains(&format!("  {} [label=\"{}: ", handle, handle)));
+        assert!(dot.contains("[label=\"Rule 0 '"));
+        assert!(dot.contains("[label=\"Assumed\"]"));
+        //Every fact the proof cites is drawn, and only once.
+        for line in dot.lines().filter(|it| it.contains(" -> F")) {
+            let (from, _) = line.trim().split_once(" -> ").unwrap();
+            if !from.contains('_') {
+                assert_eq!(dot.matches(&format!("  {} [label=", from)).count(), 1);
+            }
+        }
+        assert!(solver
+            .explanation_dot(FactHandle::from_raw(10_000))
+            .is_none());
+    }
+}
@@ -0,0 +1,216 @@
+//! Changing the definition of the lock mid-session, e.g. a rune that was misread, with a preview of
+//! what the change would do to the session before it is applied.
+
+use std::fmt::{Display, Formatter};
+
+use itertools::Itertools;
+use thiserror::Error;
+
+use crate::{index::RunePosition, rune::Rune, RuneLock};
+
+use super::{
+    assumption_tree::AssumptionTreeNodeHandle,
+    fact_db::FactDb,
+    retract::{conclusion, externals, BranchChange},
+    subscription::Subscribers,
+    Fact, FactualSolver,
+};
+
+#[derive(Debug, Clone, Copy)]
+pub enum LockEdit {
+    SetRune {
+        position: RunePosition,
+        rune: Rune,
+    },
+    ///Removes the rule with this index, the rules after it move up by one.
+    RemoveRule(usize),
+}
+
+#[derive(Debug, Error)]
+pub enum EditError {
+    #[error("There is no rule {0}")]
+    UnknownRule(usize),
+}
+
+///What an edit changes about the session.
+pub struct EditImpact {
+    ///Facts the root state knows after the edit, but not before.
+    pub gained: Vec<Fact>,
+    ///Facts the root state knew before the edit, but not after.
+    pub lost: Vec<Fact>,
+    ///States that conclude something different after the edit.
+    pub changes: Vec<BranchChange>,
+    ///States whose placements solved the lock before the edit, but violate it after.
+    pub broken_solutions: Vec<AssumptionTreeNodeHandle>,
+}
+
+impl Display for LockEdit {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LockEdit::SetRune { position, rune } => write!(f, "set rune {} to {}", position, rune),
+            LockEdit::RemoveRule(rule) => write!(f, "remove rule {}", rule),
+        }
+    }
+}
+
+///The facts of `a` about cells `b` knows nothing or something else about.
+fn missing_from(a: &FactDb, b: &FactDb) -> Vec<Fact> {
+    let kind = |db: &FactDb, position, activation| {
+        db.handle_at(position, activation)
+            .and_then(|it| db.get(it))
+            .map(|it| it.kind)
+    };
+    a.size()
+        .cells()
+        .filter(|(position, activation)| {
+            kind(a, *position, *activation) != kind(b, *position, *activation)
+        })
+        .filter_map(|(position, activation)| a.handle_at(position, activation))
+        .unique()
+        .filter_map(|it| a.get(it).cloned())
+        .collect()
+}
+
+///Whether the facts place every activation, and the placements are valid.
+fn solves(lock: &RuneLock, facts: &FactDb) -> bool {
+    facts
+        .fixed_assignment()
+        .is_ok_and(|assignment| assignment.is_complete() && lock.validate(&assignment).is_ok())
+}
+
+impl FactualSolver {
+    ///Applies the edit to a copy of the session and reports what would change, leaving the session
+    ///as it is.
+    pub fn preview_edit(&self, edit: LockEdit) -> Result<EditImpact, EditError> {
+        let mut preview = self.clone();
+        preview.subscribers = Subscribers::default();
+        preview.apply_edit(edit)
+    }
+
+    ///Changes the lock, then rebuilds the root state from the givens and imported facts and
+    ///replays every assumption on top of it.
+    pub fn apply_edit(&mut self, edit: LockEdit) -> Result<EditImpact, EditError> {
+        let root = self.states.root();
+        let before: Vec<_> = self
+            .states
+            .handles()
+            .map(|node| {
+                let state = &self.states[node];
+                (node, conclusion(&state.facts, &state.state))
+            })
+            .collect();
+        let solutions: Vec<_> = self
+            .states
+            .handles()
+            .filter(|it| solves(&self.lock, &self.states[*it].facts))
+            .collect();
+        let root_facts = self.states[root].facts.clone();
+
+        match edit {
+            LockEdit::SetRune { position, rune } => self.lock.set_rune(position, rune),
+            LockEdit::RemoveRule(rule) => {
+                self.lock
+                    .remove_rule(rule)
+                    .ok_or(EditError::UnknownRule(rule))?;
+            }
+        }
+        let externals = externals(&root_facts, None);
+        self.rebuild_root(externals);
+
+        let facts = &self.states[root].facts;
+        let changes = before
+            .into_iter()
+            .filter_map(|(node, before)| {
+                let state = &self.states[node];
+                let after = conclusion(&state.facts, &state.state);
+                (after != before).then_some(BranchChange {
+                    node,
+                    before,
+                    after,
+                })
+            })
+            .collect();
+        let broken_solutions = solutions
+            .into_iter()
+            .filter(|it| !solves(&self.lock, &self.states[*it].facts))
+            .collect();
+        Ok(EditImpact {
+            gained: missing_from(facts, &root_facts),
+            lost: missing_from(&root_facts, facts),
+            changes,
+            broken_solutions,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        fact_solver::FactualSolver,
+        rule::ActivationRuleKindHelpers,
+        test_support::{activation, sample_runes},
+        RuleTemplate, RuneLock,
+    };
+
+    use super::LockEdit;
+
+    #[test]
+    fn test_edits_are_previewed_before_they_apply() {
+        let lock = RuneLock::with_rules(
+            sample_runes(),
+            vec![
+                (1, 2).alwanese(),
+                (2, 3).antakian_conjugate(),
+                (3, 4).alwanese(),
+                (4, 5).antakian_twins(),
+                (5, 6).increase_santor(),
+                (6, 7).alwanese(),
+                (7, 8).antakian_conjugate(),
+                (8, 9).alwanese(),
+                (9, 10).antakian_twins(),
+                (10, 11).increase_santor(),
+                (11, 12).different_runes(),
+            ],
+        );
+        let mut solver = FactualSolver::new(lock);
+        let solution = solver.auto_explore(12).solution.unwrap();
+        let assignment = solver.fixed_assignment().unwrap();
+        let eleven = assignment.position_of(activation(11)).unwrap();
+        let twelve = assignment.position_of(activation(12)).unwrap();
+        let rune = solver.lock().runes()[eleven];
+
+        let impact = solver
+            .preview_edit(LockEdit::SetRune {
+                position: twelve,
+                rune,
+            })
+            .unwrap();
+        assert_eq!(impact.broken_solutions, vec![solution]);
+        assert!(impact.changes.iter().any(|it| it.node == solution));
+        assert_ne!(solver.lock().runes()[twelve], rune);
+
+        let impact = solver.apply_edit(LockEdit::RemoveRule(10)).unwrap();
+        assert!(impact.broken_solutions.is_empty());
+        assert_eq!(solver.lock().rules().len(), 10);
+        assert_eq!(solver.current(), solution);
+        assert!(solver.apply_edit(LockEdit::RemoveRule(10)).is_err());
+    }
+
+    #[test]
+    fn test_removing_a_rule_of_a_template_drops_the_template() {
+        let mut lock = RuneLock::with_rules(
+            sample_runes(),
+            vec![(1, 2).alwanese(), (2, 3).antakian_conjugate()],
+        );
+        lock.add_template(RuleTemplate::santor_chain(9, 12), 0);
+        let mut solver = FactualSolver::new(lock);
+
+        let impact = solver.preview_edit(LockEdit::RemoveRule(3)).unwrap();
+        assert!(impact.broken_solutions.is_empty());
+        assert!(solver.lock().template_of(3).is_some());
+
+        solver.apply_edit(LockEdit::RemoveRule(3)).unwrap();
+        assert_eq!(solver.lock().rules().len(), 4);
+        assert!((0..4).all(|it| solver.lock().template_of(it).is_none()));
+    }
+}
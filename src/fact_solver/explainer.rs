@@ -3,8 +3,7 @@ use std::collections::HashMap;
 use itertools::Itertools;
 
 use crate::{
-    activation::Activation, fact_solver::ContradictionKind, index::RunePosition, rule::RuleKind,
-    RuneLock,
+    activation::Activation, fact_solver::ContradictionKind, index::RunePosition, RuneLock,
 };
 
 use super::{
@@ -120,15 +119,23 @@ impl FactExceptPosition {
         }
     }
 }
-pub fn explain_fact(fact_handle: FactHandle, facts: &FactDb, lock: &RuneLock) {
-    explain_fact_d(fact_handle, facts, lock, 0)
+pub fn explain_fact(fact_handle: FactHandle, facts: &FactDb, lock: &RuneLock) -> String {
+    let mut text = String::new();
+    explain_fact_d(&mut text, fact_handle, facts, lock, 0);
+    text
 }
 
-pub fn explain_fact_d(fact_handle: FactHandle, facts: &FactDb, lock: &RuneLock, depth: usize) {
+pub fn explain_fact_d(
+    text: &mut String,
+    fact_handle: FactHandle,
+    facts: &FactDb,
+    lock: &RuneLock,
+    depth: usize,
+) {
     let fact = match facts.get(fact_handle) {
         Some(i) => i,
         None => {
-            println!("Unknown Fact: {}", fact_handle);
+            text.push_str(&format!("Unknown Fact: {}\n", fact_handle));
             return;
         }
     };
@@ -150,20 +157,21 @@ pub fn explain_fact_d(fact_handle: FactHandle, facts: &FactDb, lock: &RuneLock,
 
     for (handle, f) in fact_reasons {
         let fep = FactExceptPosition::from(f);
-        let positions = similar_but_position
-            .entry(fep)
-            .or_insert_with(|| Vec::new());
-        positions.push((*handle, f.position));
+        let positions = similar_but_position.entry(fep).or_default();
+        positions.extend(f.positions().map(|it| (*handle, it)));
     }
 
     let inset = depth * 4;
-    println!("{0}: {1}", fact_handle, fact);
+    text.push_str(&format!("{0}: {1}\n", fact_handle, fact));
 
     for reason in fact.reasons.iter() {
         match reason {
             FactReason::Fact(_, _) => {} //Handled Later
-            FactReason::Rule(_) | FactReason::Assumption => {
-                print_fact_reason(reason, facts, lock, depth);
+            FactReason::Rule(_)
+            | FactReason::Given(_)
+            | FactReason::External(_)
+            | FactReason::Assumption => {
+                print_fact_reason(text, reason, facts, lock, depth);
             }
         }
     }
@@ -186,29 +194,51 @@ pub fn explain_fact_d(fact_handle: FactHandle, facts: &FactDb, lock: &RuneLock,
             FactKind::ActivationMustBeOn => format!("{} must be on {}", fact.activation, positions),
         };
 
-        println!("{:1$} -> {2}", "", inset, verb);
+        text.push_str(&format!("{:1$} -> {2}\n", "", inset, verb));
 
         let mut reasons = fact.reasons.clone();
         reasons.sort();
         for reason in reasons {
-            print_fact_reason(&reason, facts, lock, depth + 1);
+            print_fact_reason(text, &reason, facts, lock, depth + 1);
         }
     }
 }
 
-fn print_fact_reason(reason: &FactReason, facts: &FactDb, lock: &RuneLock, depth: usize) {
+fn print_fact_reason(
+    text: &mut String,
+    reason: &FactReason,
+    facts: &FactDb,
+    lock: &RuneLock,
+    depth: usize,
+) {
     let inset = depth * 4;
-    match reason {
+    let line = match reason {
         FactReason::Fact(handle, _) => {
-            print!("{:1$} -> ", "", inset);
-            explain_fact_d(*handle, facts, lock, depth + 1);
-        }
-        FactReason::Rule(rule) => {
-            println!(
-                "{:1$} -> Rule {2}: '{3}'",
-                "", inset, rule, lock.rules[*rule]
-            )
+            text.push_str(&format!("{:1$} -> ", "", inset));
+            explain_fact_d(text, *handle, facts, lock, depth + 1);
+            return;
         }
-        FactReason::Assumption => println!("{:1$} -> Fact Assumed.", "", inset),
-    }
+        FactReason::Rule(rule) => format!(
+            "{:1$} -> Rule {2}: '{3}'{4}",
+            "",
+            inset,
+            rule,
+            lock.rules[*rule],
+            lock.provenance(*rule)
+        ),
+        FactReason::Given(domain) => format!(
+            "{:1$} -> Given {2}: '{3}'",
+            "", inset, domain, lock.domains[*domain]
+        ),
+        FactReason::External(note) => format!(
+            "{:1$} -> External {2}: '{3}'",
+            "",
+            inset,
+            note,
+            facts.note(*note)
+        ),
+        FactReason::Assumption => format!("{:1$} -> Fact Assumed.", "", inset),
+    };
+    text.push_str(&line);
+    text.push('\n');
 }
@@ -1,23 +1,39 @@
-use std::fmt::{Debug, Display};
+use std::{
+    cmp::Reverse,
+    collections::{BTreeSet, BinaryHeap, HashMap, HashSet},
+    fmt::{Debug, Display},
+    str::FromStr,
+};
 
-use log::{debug, log_enabled};
-use ndarray::{Array2, Axis};
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
 
 use crate::{
     activation::Activation,
     assignment::{Assignment, AssignmentError},
     fact_solver::ContradictionKind,
     index::RunePosition,
-    rule::{RuleKind, ValidateTupleError},
-    RuneLock,
+    jobs::CancelToken,
+    kind_stats::KindStats,
+    logging::{debug, debug_enabled},
+    messages,
+    rule::{RuleKind, RuleTemplate, Truth, ValidateTupleError},
+    rune::Rune,
+    size::LockSize,
+    terminal, RuneLock,
 };
 
 use super::{
+    assumption_tree::NodeId,
+    grid::{FactGrid, GridAxis},
+    proof::minimal_reasons,
+    stats::FactDbStats,
     view::{ChooseView, View},
-    DebugInfo, Fact, FactKind, FactReason,
+    DebugInfo, Fact, FactKind, FactLifetime, FactReason,
 };
 
-#[derive(Clone, Debug, Copy, Hash, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Clone, Debug, Copy, Hash, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
 pub struct FactHandle(usize);
 impl FactHandle {
     pub fn from_raw(node: usize) -> FactHandle {
@@ -25,10 +41,51 @@ impl FactHandle {
     }
 }
 
-#[derive(Clone)]
+#[derive(Debug, Error)]
+pub enum FactHandleError {
+    #[error("'{0}' is not a fact handle. Fact handles look like F34")]
+    Malformed(String),
+    #[error("A bare number could be a fact or a state: write F{0} for the fact, or {} for the state", NodeId::from_index(*.0))]
+    Ambiguous(usize),
+}
+
+///Accepts handles as they are displayed, like `F34` (or `f34`).
+impl FromStr for FactHandle {
+    type Err = FactHandleError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.strip_prefix(['F', 'f']) {
+            Some(digits) => digits
+                .parse()
+                .map(FactHandle)
+                .map_err(|_| FactHandleError::Malformed(s.into())),
+            None => match s.parse() {
+                Ok(index) => Err(FactHandleError::Ambiguous(index)),
+                Err(_) => Err(FactHandleError::Malformed(s.into())),
+            },
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
 pub struct FactDb {
+    ///Sessions saved before locks had a size are of the game's size.
+    #[serde(default)]
+    size: LockSize,
     facts: Vec<Fact>,
-    fact_lookup: Array2<Option<FactHandle>>,
+    fact_lookup: FactGrid,
+    ///What `FactReason::External` refers to.
+    notes: Vec<String>,
+    ///The lifetime of every fact, labelled as it is integrated. Missing for facts of sessions
+    ///saved before lifetimes were tracked, which get theirs from their proof instead.
+    #[serde(default)]
+    lifetimes: Vec<FactLifetime>,
+    #[cfg(feature = "stats")]
+    #[serde(skip)]
+    stats: FactDbStats,
+    ///What the rules did while consolidating, until the solver takes it to learn from.
+    #[serde(skip)]
+    kind_work: KindStats,
 }
 
 #[derive(Debug)]
@@ -52,13 +109,47 @@ pub enum FactError {
     Contradiction(FactHandle),
 }
 
+///An illegal configuration of the fact grid. Consolidation should never leave one behind.
+#[derive(Debug, Error)]
+pub enum InvariantViolation {
+    #[error("{activation} must be on both {position_a} and {position_b} without a contradiction")]
+    ActivationMustBeTwice {
+        activation: Activation,
+        position_a: RunePosition,
+        position_b: RunePosition,
+    },
+    #[error("{position} must hold both {activation_a} and {activation_b} without a contradiction")]
+    PositionMustBeTwice {
+        position: RunePosition,
+        activation_a: Activation,
+        activation_b: Activation,
+    },
+    #[error("Cell {position}/{activation} refers to {fact}, which describes another cell")]
+    MisplacedFact {
+        position: RunePosition,
+        activation: Activation,
+        fact: FactHandle,
+    },
+    #[error("Cell {position}/{activation} refers to {fact}, which does not exist")]
+    DanglingHandle {
+        position: RunePosition,
+        activation: Activation,
+        fact: FactHandle,
+    },
+    #[error("{fact} is derived from {reason}, which is not an earlier fact")]
+    LaterReason {
+        fact: FactHandle,
+        reason: FactHandle,
+    },
+}
+
 impl SingleFactIntegrationResult {
     fn expect_without_contradiction(self, db: &FactDb) -> Result<Self, FactError> {
         match self {
             SingleFactIntegrationResult::Unchanged(handle)
             | SingleFactIntegrationResult::Integrated(handle) => {
                 match db.facts.get(handle.0).unwrap().kind {
-                    FactKind::Contradiction(_) => Err(FactError::Contradiction(handle.clone())),
+                    FactKind::Contradiction(_) => Err(FactError::Contradiction(handle)),
                     FactKind::ActivationCannotBeOn | FactKind::ActivationMustBeOn => Ok(self),
                 }
             }
@@ -77,13 +168,24 @@ impl<T> Possibilities<T> {
 }
 
 impl FactDb {
-    pub fn new(runes: usize, activations: usize) -> Self {
+    ///Knows nothing about a lock of `size` yet.
+    pub fn new(size: LockSize) -> Self {
         Self {
+            size,
             facts: Vec::new(),
-            fact_lookup: Array2::from_elem((runes, activations), None),
+            fact_lookup: FactGrid::new(size.positions(), size.activations()),
+            notes: Vec::new(),
+            lifetimes: Vec::new(),
+            #[cfg(feature = "stats")]
+            stats: FactDbStats::default(),
+            kind_work: KindStats::default(),
         }
     }
 
+    pub fn size(&self) -> LockSize {
+        self.size
+    }
+
     pub fn integrate_and_consolidate(
         &mut self,
         fact: Fact,
@@ -94,56 +196,248 @@ impl FactDb {
             .expect_without_contradiction(self)?
         {
             SingleFactIntegrationResult::Unchanged(_) => Ok(()),
-            SingleFactIntegrationResult::Integrated(_) => {
-                loop {
-                    let mut changed = false;
-                    debug!("==\n==\n== Unique per RunePosition");
-                    if let ConsolidationResult::Changes =
-                        self.consolidate_unique_per_view::<RunePosition>()?
-                    {
-                        changed = true
-                    }
-                    if log_enabled!(log::Level::Debug) {
-                        self.info_dump();
-                    }
-                    debug!("==\n==\n== Unique per Activation");
-                    if let ConsolidationResult::Changes =
-                        self.consolidate_unique_per_view::<Activation>()?
-                    {
-                        changed = true
-                    }
-                    if log_enabled!(log::Level::Debug) {
-                        self.info_dump();
-                    }
-                    debug!("==\n==\n== Rules");
-                    if let ConsolidationResult::Changes = self.consolidate_rules(lock)? {
-                        changed = true
-                    }
-                    if log_enabled!(log::Level::Debug) {
-                        self.info_dump();
-                    }
+            SingleFactIntegrationResult::Integrated(_) => self.consolidate(lock),
+        }
+        //be mapped to corresponding contradictions.
+    }
 
-                    debug!("Changes? {:?}", changed);
-                    if !changed {
-                        break;
-                    }
+    ///Rules out every position outside of the domains of the lock and consolidates.
+    pub fn integrate_givens(&mut self, lock: &RuneLock) -> Result<(), FactError> {
+        for (index, domain) in lock.domains.iter().enumerate() {
+            let integrations = self
+                .size
+                .all_positions()
+                .filter(|it| !domain.contains(*it, self.size))
+                .map(|position| Fact {
+                    kind: FactKind::ActivationCannotBeOn,
+                    activation: domain.activation,
+                    position,
+                    reasons: vec![FactReason::Given(index)],
+                    range: Vec::new(),
+                })
+                .collect();
+            self.integrate_consolidation(integrations)?;
+        }
+        self.consolidate(lock)
+    }
+
+    ///Derives everything that follows from the current facts, until nothing changes anymore.
+    pub fn consolidate(&mut self, lock: &RuneLock) -> Result<(), FactError> {
+        while let ConsolidationResult::Changes = self.consolidation_step(lock)? {}
+        #[cfg(debug_assertions)]
+        if let Err(violation) = self.check_invariants() {
+            panic!("Consolidation left an illegal fact grid: {}", violation);
+        }
+        Ok(())
+    }
+
+    ///Integrates the fact and runs a single iteration of the consolidation, as a cheap preview
+    ///of what a full consolidation would find.
+    pub fn integrate_and_consolidate_once(
+        &mut self,
+        fact: Fact,
+        lock: &RuneLock,
+    ) -> Result<(), FactError> {
+        match self
+            .integrate_single_fact(fact)
+            .expect_without_contradiction(self)?
+        {
+            SingleFactIntegrationResult::Unchanged(_) => Ok(()),
+            SingleFactIntegrationResult::Integrated(_) => self.consolidation_step(lock).map(|_| ()),
+        }
+    }
+
+    ///One iteration of every consolidation.
+    fn consolidation_step(&mut self, lock: &RuneLock) -> Result<ConsolidationResult, FactError> {
+        self.record(|it| it.consolidation_iterations += 1);
+        let mut changed = false;
+        debug!("==\n==\n== Unique per RunePosition");
+        if let ConsolidationResult::Changes = self.consolidate_unique_per_view::<RunePosition>()? {
+            changed = true
+        }
+        if debug_enabled() {
+            debug!("{}", self.info_dump());
+        }
+        debug!("==\n==\n== Unique per Activation");
+        if let ConsolidationResult::Changes = self.consolidate_unique_per_view::<Activation>()? {
+            changed = true
+        }
+        if debug_enabled() {
+            debug!("{}", self.info_dump());
+        }
+        debug!("==\n==\n== Rules");
+        if let ConsolidationResult::Changes = self.consolidate_rules(lock)? {
+            changed = true
+        }
+        if debug_enabled() {
+            debug!("{}", self.info_dump());
+        }
+
+        debug!("==\n==\n== Different runes");
+        if let ConsolidationResult::Changes = self.consolidate_different_runes(lock)? {
+            changed = true
+        }
+
+        debug!("==\n==\n== Rune counts");
+        if let ConsolidationResult::Changes = self.consolidate_rune_counts(lock)? {
+            changed = true
+        }
+
+        debug!("==\n==\n== Implications");
+        if let ConsolidationResult::Changes = self.consolidate_implications(lock)? {
+            changed = true
+        }
+
+        debug!("==\n==\n== Gaps of follows rules");
+        if let ConsolidationResult::Changes = self.consolidate_gaps(lock)? {
+            changed = true
+        }
+
+        debug!("==\n==\n== Rules over more activations");
+        if let ConsolidationResult::Changes = self.consolidate_subsets(lock)? {
+            changed = true
+        }
+
+        debug!("==\n==\n== Single activation rules");
+        if let ConsolidationResult::Changes = self.consolidate_single_rules(lock)? {
+            changed = true
+        }
+
+        debug!("==\n==\n== Santor chains");
+        if let ConsolidationResult::Changes = self.consolidate_santor_chains(lock)? {
+            changed = true
+        }
+
+        debug!("Changes? {:?}", changed);
+        Ok(match changed {
+            true => ConsolidationResult::Changes,
+            false => ConsolidationResult::Unchanged,
+        })
+    }
+
+    fn integrate_single_fact(&mut self, fact: Fact) -> SingleFactIntegrationResult {
+        let known = self.facts.len();
+        let result = self.integrate_single_fact_unrecorded(fact);
+        if let SingleFactIntegrationResult::Unchanged(_) = result {
+            self.record(|it| it.unchanged_integrations += 1);
+        }
+        for index in known..self.facts.len() {
+            let kind = self.facts[index].kind;
+            self.record(|it| match kind {
+                FactKind::ActivationMustBeOn => it.must_be_created += 1,
+                FactKind::ActivationCannotBeOn => it.cannot_be_created += 1,
+                FactKind::Contradiction(_) => it.contradictions_created += 1,
+            });
+        }
+        self.label_lifetimes();
+        result
+    }
+
+    ///Labels the facts integrated since the last call. A fact is branch-local if it is assumed
+    ///or follows from a branch-local fact, whose label is known already as reasons are earlier
+    ///facts.
+    fn label_lifetimes(&mut self) {
+        while self.lifetimes.len() < self.facts.len() {
+            let fact = &self.facts[self.lifetimes.len()];
+            let local = fact.reasons.iter().any(|it| match it {
+                FactReason::Assumption => true,
+                FactReason::Fact(reason, _) => self.lifetime(*reason) == FactLifetime::BranchLocal,
+                FactReason::Rule(_) | FactReason::Given(_) | FactReason::External(_) => false,
+            });
+            self.lifetimes.push(match local {
+                true => FactLifetime::BranchLocal,
+                false => FactLifetime::Universal,
+            });
+        }
+    }
+
+    pub fn lifetime(&self, fact: FactHandle) -> FactLifetime {
+        match self.lifetimes.get(fact.0) {
+            Some(it) => *it,
+            None if self.assumptions_of(fact).is_empty() => FactLifetime::Universal,
+            None => FactLifetime::BranchLocal,
+        }
+    }
+
+    ///Integrates the fact of `other` with every fact its proof rests on, as facts of this
+    ///database, and returns its handle here. Meant for universal facts, whose proofs hold in
+    ///every state of the tree.
+    pub fn adopt(&mut self, other: &FactDb, fact: FactHandle) -> FactHandle {
+        self.adopt_shared(other, fact, 0)
+    }
+
+    ///Like `adopt`, for a database whose first `shared` facts are those of `other`, under the
+    ///same handles, like a state and the one it was derived from. Those are neither walked nor
+    ///integrated again.
+    pub fn adopt_shared(&mut self, other: &FactDb, fact: FactHandle, shared: usize) -> FactHandle {
+        if fact.0 < shared {
+            return fact;
+        }
+        let mut proof = BTreeSet::new();
+        let mut open = vec![fact];
+        while let Some(handle) = open.pop() {
+            if handle.0 >= shared && proof.insert(handle) {
+                open.extend(
+                    other.facts[handle.0]
+                        .reasons
+                        .iter()
+                        .filter_map(|it| match it {
+                            FactReason::Fact(reason, _) => Some(*reason),
+                            _ => None,
+                        }),
+                );
+            }
+        }
+        let mut adopted = HashMap::new();
+        //Reasons are earlier facts, so they are adopted before the facts resting on them.
+        for handle in proof {
+            let mut fact = other.facts[handle.0].clone();
+            for reason in fact.reasons.iter_mut() {
+                if let FactReason::Fact(it, _) = reason {
+                    *it = adopted.get(it).copied().unwrap_or(*it);
                 }
-                Ok(())
             }
+            let known = self.fact_count();
+            let result = match fact.range.is_empty() {
+                true => self.integrate_single_fact(fact),
+                false => {
+                    //Cells the range covers could be known here already.
+                    let mut result = None;
+                    for position in fact.positions().collect::<Vec<_>>() {
+                        let single = Fact {
+                            position,
+                            range: Vec::new(),
+                            ..fact.clone()
+                        };
+                        result.get_or_insert(self.integrate_single_fact(single));
+                    }
+                    result.unwrap()
+                }
+            };
+            let (SingleFactIntegrationResult::Unchanged(it)
+            | SingleFactIntegrationResult::Integrated(it)) = result;
+            debug!(
+                "Adopted {} as {} ({} new facts)",
+                handle,
+                it,
+                self.fact_count() - known
+            );
+            adopted.insert(handle, it);
         }
-        //be mapped to corresponding contradictions.
+        adopted[&fact]
     }
 
     ///Only operates on the position and activation of the supplied fact.
     ///Does no global reasoning. Only updatesthe information about that singular fact that we have.
-    fn integrate_single_fact(&mut self, fact: Fact) -> SingleFactIntegrationResult {
-        let existing_fact = &mut self.fact_lookup[[fact.position.index(), fact.activation.index()]];
+    fn integrate_single_fact_unrecorded(&mut self, fact: Fact) -> SingleFactIntegrationResult {
+        let existing_fact = self
+            .fact_lookup
+            .get_mut(fact.position.index(), fact.activation.index());
 
-        let handle = if let Some(existing_handle) = existing_fact {
+        if let Some(existing_handle) = existing_fact {
             let existing = self.facts.get(existing_handle.0).unwrap();
 
             //If the new rule already exists...
-            if existing.kind == fact.kind {}
 
             //Integrate current into existing
             match (&existing.kind, &fact.kind) {
@@ -154,7 +448,7 @@ impl FactDb {
                     //It already exists. Fine. (We could see which one has the shorter reasoning,
                     //but who careessss) (If we did that shorter thingy we have to take care not to
                     //run into circular reasoning with consolidate)
-                    return SingleFactIntegrationResult::Unchanged(existing_handle.clone());
+                    SingleFactIntegrationResult::Unchanged(*existing_handle)
                 }
                 //A newcoming Contradictin overwrites All
                 (_, FactKind::Contradiction(_)) => {
@@ -163,11 +457,11 @@ impl FactDb {
                     self.facts.push(fact);
                     *existing_fact = Some(handle);
 
-                    return SingleFactIntegrationResult::Integrated(handle);
+                    SingleFactIntegrationResult::Integrated(handle)
                 }
                 //An existing Contradiction cannot be overwritten
                 (FactKind::Contradiction(_), _) => {
-                    return SingleFactIntegrationResult::Unchanged(existing_handle.clone());
+                    SingleFactIntegrationResult::Unchanged(*existing_handle)
                 }
                 //New Fact contradicts with old Fact
                 (FactKind::ActivationCannotBeOn, FactKind::ActivationMustBeOn)
@@ -179,7 +473,7 @@ impl FactDb {
                         kind: FactKind::Contradiction(ContradictionKind::ContradictingRequirements),
                         reasons: vec![
                             FactReason::Fact(
-                                existing_handle.clone(),
+                                *existing_handle,
                                 DebugInfo {
                                     origin: "integrate_single_fact",
                                 },
@@ -200,7 +494,7 @@ impl FactDb {
                     );
                     self.facts.push(contradiction);
 
-                    return SingleFactIntegrationResult::Integrated(contradicting_handle.clone());
+                    SingleFactIntegrationResult::Integrated(contradicting_handle)
                 }
             }
         } else {
@@ -209,24 +503,22 @@ impl FactDb {
             self.facts.push(fact);
             *existing_fact = Some(handle);
 
-            return SingleFactIntegrationResult::Integrated(handle);
-        };
+            SingleFactIntegrationResult::Integrated(handle)
+        }
     }
 
-    fn consolidate_unique_per_view<T: View + ChooseView>(
-        &mut self,
-    ) -> Result<ConsolidationResult, FactError>
+    fn consolidate_unique_per_view<T>(&mut self) -> Result<ConsolidationResult, FactError>
     where
         T::Complement: PartialEq + Copy + Debug,
-        T: Copy + Debug,
+        T: View + ChooseView + Copy + Debug,
     {
         let mut integrations = Vec::new();
-        for (view, complements) in self
-            .fact_lookup
-            .lanes(T::Complement::axis())
-            .into_iter()
-            .enumerate()
-        {
+        //Each view only takes one of its complements if there are no more views than
+        //complements: a lock activating fewer positions than it has leaves some of them empty.
+        let covered =
+            self.fact_lookup.len_of(T::axis()) <= self.fact_lookup.len_of(T::Complement::axis());
+        for view in 0..self.fact_lookup.len_of(T::axis()) {
+            let complements: Vec<_> = self.fact_lookup.lane(T::axis(), view).collect();
             let view = T::from_usize(view);
             debug!("Consolidating View {:?}", view);
 
@@ -260,14 +552,15 @@ impl FactDb {
                         activation: T::choose_activation(view, complement),
                         position: T::choose_position(view, complement),
                         reasons: vec![FactReason::Fact(
-                            must_be_fact.clone(),
+                            *must_be_fact,
                             DebugInfo {
                                 origin: "consolidate_views must_be_fact",
                             },
                         )],
+                        range: Vec::new(),
                     });
                 }
-            } else {
+            } else if covered {
                 //If there is only one place left -> Introduce a MustBe with all other places as
                 //Reasons
                 let mut possibility = Possibilities::None;
@@ -285,7 +578,7 @@ impl FactDb {
                             }
                             FactKind::ActivationCannotBeOn => {
                                 reasons.push(FactReason::Fact(
-                                    fact.clone(),
+                                    *fact,
                                     DebugInfo {
                                         origin: "consolidate_views only_one_place_left",
                                     },
@@ -306,6 +599,7 @@ impl FactDb {
                             activation: T::choose_activation(view, possibility),
                             position: T::choose_position(view, possibility),
                             reasons,
+                            range: Vec::new(),
                         });
                     }
                     Possibilities::None => {
@@ -316,6 +610,7 @@ impl FactDb {
                                 activation: T::choose_activation(view, complement),
                                 position: T::choose_position(view, complement),
                                 reasons: reasons.clone(),
+                                range: Vec::new(),
                             })
                         }
                     }
@@ -327,150 +622,200 @@ impl FactDb {
         self.integrate_consolidation(integrations)
     }
 
-    pub fn info_dump(&self) {
-        println!("Current knowledge:= ======= ======");
-        for (i, f) in self.facts.iter().enumerate() {
-            println!("Fact {}, {:?}", i, f);
+    ///Every fact, and the grid of what is known with the handles of the facts.
+    pub fn info_dump(&self) -> String {
+        let mut text = String::from("Current knowledge:= ======= ======\n");
+        for (handle, fact) in self.iter_facts() {
+            text.push_str(&format!("Fact {}, {:?}\n", handle, fact));
         }
-        println!("[..] means Must Be, X..X means Contradiction, others mean CannotBe");
-        print!("    {:3}", "");
-        for i in 0..self.fact_lookup.shape()[1] {
-            print!("| {:^5} ", i);
+        text.push_str("[..] means Must Be, X..X means Contradiction, others mean CannotBe\n");
+        text.push_str(&self.grid(|handle| {
+            let fact = &self.facts[handle.0];
+            match fact.kind {
+                FactKind::Contradiction(_) => format!("|X{:^5}X", handle.to_string()),
+                FactKind::ActivationCannotBeOn => format!("| {:^5} ", handle.to_string()),
+                FactKind::ActivationMustBeOn => format!("|[{:^5}]", handle.to_string()),
+            }
+        }));
+        text
+    }
+
+    ///The grid of `info_dump`, with a code of why each cell is known instead of its fact, see
+    ///`reason_code`.
+    pub fn reason_dump(&self) -> String {
+        let mut text = String::from(
+            "U: follows from the rest of its row or column, R<n>: rule n, A: assumed,\n",
+        );
+        text.push_str("G: given, E: imported, C: contradiction, [..] means Must Be\n");
+        text.push_str(&self.grid(
+            |handle| match (self.get(handle), self.reason_code(handle)) {
+                (Some(fact), Some(code)) if fact.kind == FactKind::ActivationMustBeOn => {
+                    format!("|[{:^5}]", code)
+                }
+                (Some(_), Some(code)) => format!("| {:^5} ", code),
+                _ => format!("| {:^5} ", " "),
+            },
+        ));
+        text
+    }
+
+    ///A row per position with a cell per activation, known cells rendered by `cell`.
+    fn grid(&self, cell: impl Fn(FactHandle) -> String) -> String {
+        let mut text = format!("    {:3}", "");
+        for i in 0..self.fact_lookup.len_of(GridAxis::Activation) {
+            text.push_str(&format!("| {:^5} ", i));
         }
-        println!("");
-        for (position, activations) in self
-            .fact_lookup
-            .lanes(Activation::axis())
-            .into_iter()
-            .enumerate()
-        {
-            print!("Pos {:3}", position);
-            for (_, fact) in activations.iter().enumerate() {
+        text.push('\n');
+        for (position, cells) in &self.iter_cells().group_by(|(position, _, _)| *position) {
+            text.push_str(&format!("Pos {:3}", position.index()));
+            for (_, _, fact) in cells {
                 match fact {
-                    Some(it) => {
-                        let fact = &self.facts[it.0];
-                        match fact.kind {
-                            FactKind::Contradiction(_) => {
-                                print!("|X{:^5}X", it.0);
-                            }
-                            FactKind::ActivationCannotBeOn => {
-                                print!("| {:^5} ", it.0);
-                            }
-                            FactKind::ActivationMustBeOn => {
-                                print!("|[{:^5}]", it.0);
-                            }
-                        }
-                    }
-                    None => print!("| {:^5} ", " "),
+                    Some(it) => text.push_str(&cell(it)),
+                    None => text.push_str(&format!("| {:^5} ", " ")),
                 }
             }
-            println!("");
+            text.push('\n');
         }
+        text
     }
 
+    ///A short code of why the fact holds, from its own reasons: `C` for a contradiction, `R<n>`
+    ///for the first rule it cites, `A` for an assumption, `G` for a given, `E` for an imported
+    ///fact, and `U` for a fact that only cites other facts, as it follows from them being the
+    ///only place left, or taken, in its row or column.
+    pub fn reason_code(&self, handle: FactHandle) -> Option<String> {
+        let fact = self.get(handle)?;
+        if let FactKind::Contradiction(_) = fact.kind {
+            return Some("C".into());
+        }
+        let reasons = &fact.reasons;
+        let code = match reasons.iter().find_map(|it| match it {
+            FactReason::Rule(rule) => Some(*rule),
+            _ => None,
+        }) {
+            Some(rule) => format!("R{}", rule),
+            None if reasons.contains(&FactReason::Assumption) => "A".into(),
+            None if reasons.iter().any(|it| matches!(it, FactReason::Given(_))) => "G".into(),
+            None if reasons
+                .iter()
+                .any(|it| matches!(it, FactReason::External(_))) =>
+            {
+                "E".into()
+            }
+            None => "U".into(),
+        };
+        Some(code)
+    }
+
+    ///Checks the candidates related to every given by a rule. The rules are consolidated a kind at
+    ///a time, in the order of the lock, and a kind's eliminations are integrated before the next
+    ///kind is checked, so a contradiction found early spares the checks of the later kinds.
     fn consolidate_rules(&mut self, lock: &RuneLock) -> Result<ConsolidationResult, FactError> {
-        let mut integrations = Vec::new();
+        let mut result = ConsolidationResult::Unchanged;
         //Check if the fixed_assignment is valid (We don't need to do that, as internal
         //inconsistencies will com up in the second state anyways.)
 
-        if log_enabled!(log::Level::Debug) {
-            self.info_dump();
+        if debug_enabled() {
+            debug!("{}", self.info_dump());
         }
 
-        //Second check the implications of the current assignment
-        for ((given_position, given_activation), fact) in self.givens() {
-            debug!(
-                "Given: {:?} {:?} through {:?}",
-                given_position, given_activation, fact
-            );
-            //Get all rules which affect this given
-            for (rule_index, rule) in lock.rules.iter().enumerate() {
-                match rule {
-                    RuleKind::Alwanese { first, second }
-                    | RuleKind::AntakianConjugates { first, second }
-                    | RuleKind::AlwaneseConjugates { first, second }
-                    | RuleKind::DifferentRunes { first, second }
-                    | RuleKind::AntakianTwins { first, second }
-                    | RuleKind::IncreaseSantor { first, second }
-                    | RuleKind::Max0Conductive { first, second } => {
-                        for (this, other) in [(first, second), (second, first)] {
-                            if *this == given_activation {
-                                debug!(
-                                    "Consolidating Rule: {:?} in config {:?}-{:?} for {:?}@{:?}",
-                                    rule, this, other, given_activation, given_position
-                                );
-                                for possibility in self.possibilities_for(*other) {
-                                    // possibility == position and similar shenanigans can happen,
-                                    // because it might be that the previous consolidation has
-                                    // inserted a fact, that hasn't had the chance to be
-                                    // consolidated as well yet. Therefore Invalid Assignment
-                                    // Errors can happen
-                                    match rule.validate_tuple(
-                                        lock,
-                                        (given_position, given_activation),
-                                        (possibility, *other),
-                                    ) {
-                                        Ok(_) => {}
+        for (kind, rules) in lock.rules_by_kind() {
+            let mut integrations = Vec::new();
+            let mut attempts = 0;
+            let givens: Vec<_> = self.givens().collect();
+            for ((given_position, given_activation), fact) in givens.iter().copied() {
+                debug!(
+                    "Given: {:?} {:?} through {:?}",
+                    given_position, given_activation, fact
+                );
+                //Get all rules which affect this given
+                for (rule_index, rule) in rules.iter().copied() {
+                    match rule {
+                        RuleKind::RuneFollowsImmediately { first, .. } => {
+                            let given_rune = lock.runes[given_position];
+                            for (rune, affected_activation) in [
+                                (first, given_activation.next(self.size)),
+                                // (second, given_activation.prev()),
+                            ] {
+                                if given_rune.is_known() && given_rune == *rune {
+                                    match affected_activation {
+                                        Ok(affected_activation) => {
+                                            for possibility in
+                                                self.possibilities_for(affected_activation)
+                                            {
+                                                attempts += 1;
+                                                match rule.validate_tuple(
+                                                    lock,
+                                                    (given_position, given_activation),
+                                                    (possibility, affected_activation),
+                                                ) {
+                                                    Ok(_) => {}
+                                                    Err(_) => integrations.push(Fact {
+                                                        kind: FactKind::ActivationCannotBeOn,
+                                                        activation: affected_activation,
+                                                        position: possibility,
+                                                        reasons: vec![
+                                                            FactReason::Fact(
+                                                                fact,
+                                                                DebugInfo {
+                                                                    origin:
+                                                                        "consolidate_rules runes",
+                                                                },
+                                                            ),
+                                                            FactReason::Rule(rule_index),
+                                                        ],
+                                                        range: Vec::new(),
+                                                    }),
+                                                }
+                                            }
+                                        }
                                         Err(_) => integrations.push(Fact {
                                             kind: FactKind::ActivationCannotBeOn,
-                                            activation: *other,
-                                            position: possibility,
+                                            activation: given_activation,
+                                            position: given_position,
                                             reasons: vec![
                                                 FactReason::Fact(
                                                     fact,
                                                     DebugInfo {
-                                                        origin: "consolidate_rules",
+                                                        origin: "consolidate_rules runes",
                                                     },
                                                 ),
                                                 FactReason::Rule(rule_index),
                                             ],
+                                            range: Vec::new(),
                                         }),
                                     }
                                 }
                             }
-                            //The rule has this activation as its first, so it has implications
-                            //on the second.
                         }
-                    }
-                    RuleKind::RuneFollowsImmediately { first, .. } => {
-                        let given_rune = lock.runes[given_position];
-                        for (rune, affected_activation) in [
-                            (first, given_activation.next()),
-                            // (second, given_activation.prev()),
-                        ] {
-                            if given_rune == *rune {
-                                match affected_activation {
-                                    Ok(affected_activation) => {
-                                        for possibility in
-                                            self.possibilities_for(affected_activation)
-                                        {
-                                            match rule.validate_tuple(
-                                                lock,
-                                                (given_position, given_activation),
-                                                (possibility, affected_activation),
-                                            ) {
-                                                Ok(_) => {}
-                                                Err(_) => integrations.push(Fact {
-                                                    kind: FactKind::ActivationCannotBeOn,
-                                                    activation: affected_activation,
-                                                    position: possibility,
-                                                    reasons: vec![
-                                                        FactReason::Fact(
-                                                            fact,
-                                                            DebugInfo {
-                                                                origin: "consolidate_rules runes",
-                                                            },
-                                                        ),
-                                                        FactReason::Rule(rule_index),
-                                                    ],
-                                                }),
-                                            }
-                                        }
+                        //A placement on one of the runes limits the activations on the other.
+                        RuleKind::RuneClassBefore { earlier, later } => {
+                            let given_rune = lock.runes[given_position];
+                            if !given_rune.is_known()
+                                || (given_rune != *earlier && given_rune != *later)
+                            {
+                                continue;
+                            }
+                            for other in self.size.all_activations() {
+                                if other == given_activation {
+                                    continue;
+                                }
+                                for possibility in self.possibilities_for(other) {
+                                    attempts += 1;
+                                    if rule
+                                        .validate_tuple(
+                                            lock,
+                                            (given_position, given_activation),
+                                            (possibility, other),
+                                        )
+                                        .is_ok()
+                                    {
+                                        continue;
                                     }
-                                    Err(_) => integrations.push(Fact {
+                                    integrations.push(Fact {
                                         kind: FactKind::ActivationCannotBeOn,
-                                        activation: given_activation,
-                                        position: given_position,
+                                        activation: other,
+                                        position: possibility,
                                         reasons: vec![
                                             FactReason::Fact(
                                                 fact,
@@ -480,15 +825,657 @@ impl FactDb {
                                             ),
                                             FactReason::Rule(rule_index),
                                         ],
-                                    }),
+                                        range: Vec::new(),
+                                    });
                                 }
                             }
                         }
+                        //Single activation rules don't need a given, see
+                        //`consolidate_single_rules`.
+                        rule => {
+                            let Some((first, second)) = rule.activation_pair() else {
+                                continue;
+                            };
+                            for (this, other) in [(first, second), (second, first)] {
+                                if this == given_activation {
+                                    debug!(
+                                        "Consolidating Rule: {:?} in config {:?}-{:?} for {:?}@{:?}",
+                                        rule, this, other, given_activation, given_position
+                                    );
+                                    for possibility in self.possibilities_for(other) {
+                                        // possibility == position and similar shenanigans can happen,
+                                        // because it might be that the previous consolidation has
+                                        // inserted a fact, that hasn't had the chance to be
+                                        // consolidated as well yet. Therefore Invalid Assignment
+                                        // Errors can happen
+                                        attempts += 1;
+                                        let allowed = if this == first {
+                                            lock.allows_pair(
+                                                rule_index,
+                                                given_position,
+                                                possibility,
+                                            )
+                                        } else {
+                                            lock.allows_pair(
+                                                rule_index,
+                                                possibility,
+                                                given_position,
+                                            )
+                                        };
+                                        if allowed {
+                                            continue;
+                                        }
+                                        integrations.push(Fact {
+                                            kind: FactKind::ActivationCannotBeOn,
+                                            activation: other,
+                                            position: possibility,
+                                            reasons: vec![
+                                                FactReason::Fact(
+                                                    fact,
+                                                    DebugInfo {
+                                                        origin: "consolidate_rules",
+                                                    },
+                                                ),
+                                                FactReason::Rule(rule_index),
+                                            ],
+                                            range: Vec::new(),
+                                        });
+                                    }
+                                }
+                                //The rule has this activation as its first, so it has implications
+                                //on the second.
+                            }
+                        }
+                    }
+                }
+            }
+            let successes = integrations.len();
+            self.record(|it| {
+                it.rule_prune_attempts += attempts;
+                it.rule_prune_successes += successes;
+            });
+            let known = self.fact_count();
+            let integrated = self.integrate_consolidation(integrations);
+            let eliminations = (self.fact_count() - known) as u64;
+            let contradicts = integrated.is_err();
+            self.kind_work.record(kind, |it| {
+                it.checks += attempts as u64;
+                it.eliminations += eliminations;
+                it.contradictions += contradicts as u64;
+            });
+            if let ConsolidationResult::Changes = integrated? {
+                result = ConsolidationResult::Changes;
+            }
+        }
+        Ok(result)
+    }
+
+    #[cfg(feature = "stats")]
+    fn record(&mut self, update: impl FnOnce(&mut FactDbStats)) {
+        update(&mut self.stats)
+    }
+
+    #[cfg(not(feature = "stats"))]
+    fn record(&mut self, _update: impl FnOnce(&mut FactDbStats)) {}
+
+    ///Approximately how many bytes the database takes up, including the deduction chains of its
+    ///facts.
+    pub fn memory_usage(&self) -> usize {
+        let reasons: usize = self
+            .facts
+            .iter()
+            .map(|it| {
+                it.reasons.capacity() * std::mem::size_of::<FactReason>()
+                    + it.range.capacity() * std::mem::size_of::<RunePosition>()
+            })
+            .sum();
+        std::mem::size_of::<Self>()
+            + self.facts.capacity() * std::mem::size_of::<Fact>()
+            + reasons
+            + self.fact_lookup.heap_size()
+            + self.notes.iter().map(|it| it.capacity()).sum::<usize>()
+    }
+
+    ///What the rules did since this was last called.
+    pub fn take_kind_work(&mut self) -> KindStats {
+        std::mem::take(&mut self.kind_work)
+    }
+
+    ///The collected counters, or `None` without the `stats` feature.
+    pub fn stats(&self) -> Option<&FactDbStats> {
+        #[cfg(feature = "stats")]
+        {
+            Some(&self.stats)
+        }
+        #[cfg(not(feature = "stats"))]
+        {
+            None
+        }
+    }
+
+    ///Rules about where a single activation is rule cells out on their own, without a given.
+    fn consolidate_single_rules(
+        &mut self,
+        lock: &RuneLock,
+    ) -> Result<ConsolidationResult, FactError> {
+        let mut integrations = Vec::new();
+        for (rule_index, rule) in lock.active_rules() {
+            if rule.single_activation().is_none() {
+                continue;
+            }
+            for position in self.size.all_positions() {
+                for activation in self.size.all_activations() {
+                    let eliminated = self
+                        .handle_at(position, activation)
+                        .and_then(|it| self.get(it))
+                        .is_some_and(|it| it.kind == FactKind::ActivationCannotBeOn);
+                    let allowed = Assignment::from_tuple_iter(
+                        self.size,
+                        std::iter::once((position, activation)),
+                    )
+                    .is_ok_and(|it| rule.validate(lock, &it).is_ok());
+                    if eliminated || allowed {
+                        continue;
+                    }
+                    integrations.push(Fact {
+                        kind: FactKind::ActivationCannotBeOn,
+                        activation,
+                        position,
+                        reasons: vec![FactReason::Rule(rule_index)],
+                        range: Vec::new(),
+                    });
+                }
+            }
+        }
+        self.integrate_consolidation(integrations)
+    }
+
+    ///Runes repeat on the lock, so an activation of a `DifferentRunes` rule that is left with
+    ///copies of a single rune, decided or not, rules out every copy of that rune for the other
+    ///activation. The eliminations cite the rule and the facts that confined the activation.
+    fn consolidate_different_runes(
+        &mut self,
+        lock: &RuneLock,
+    ) -> Result<ConsolidationResult, FactError> {
+        let mut integrations = Vec::new();
+        for (rule_index, rule) in lock.active_rules() {
+            let RuleKind::DifferentRunes { first, second } = *rule else {
+                continue;
+            };
+            for (this, other) in [(first, second), (second, first)] {
+                let Ok(rune) = self
+                    .possibilities_for(this)
+                    .map(|it| lock.runes[it])
+                    .unique()
+                    .exactly_one()
+                else {
+                    continue;
+                };
+                if !rune.is_known() {
+                    continue;
+                }
+                let lane: Vec<_> = self
+                    .fact_lookup
+                    .lane(GridAxis::Activation, this.index())
+                    .flatten()
+                    .collect();
+                let confining = match lane
+                    .iter()
+                    .find(|it| self.facts[it.0].kind == FactKind::ActivationMustBeOn)
+                {
+                    Some(placed) => vec![*placed],
+                    None => lane,
+                };
+                for possibility in self.possibilities_for(other) {
+                    if lock.runes[possibility] != rune {
+                        continue;
+                    }
+                    let mut reasons = vec![FactReason::Rule(rule_index)];
+                    reasons.extend(confining.iter().map(|it| {
+                        FactReason::Fact(
+                            *it,
+                            DebugInfo {
+                                origin: "consolidate_different_runes",
+                            },
+                        )
+                    }));
+                    integrations.push(Fact {
+                        kind: FactKind::ActivationCannotBeOn,
+                        activation: other,
+                        position: possibility,
+                        reasons,
+                        range: Vec::new(),
+                    });
+                }
+            }
+        }
+        self.integrate_consolidation(integrations)
+    }
+
+    ///Counting arguments for `follows` and `before` rules over the whole lock, see
+    ///`RuleKind::excluded_by_rune_counts`. The eliminations cite the rule, and for `follows` the
+    ///facts that ruled out the neighbouring activation on the runes it was counted on.
+    fn consolidate_rune_counts(
+        &mut self,
+        lock: &RuneLock,
+    ) -> Result<ConsolidationResult, FactError> {
+        let mut integrations = Vec::new();
+        for (rule_index, rule) in lock.active_rules() {
+            let (RuleKind::RuneFollowsImmediately { first, second }
+            | RuleKind::RuneClassBefore {
+                earlier: first,
+                later: second,
+            }) = *rule
+            else {
+                continue;
+            };
+            let mut open = vec![vec![false; self.size.activations()]; self.size.positions()];
+            for activation in self.size.all_activations() {
+                for position in self.possibilities_for(activation) {
+                    open[position.index()][activation.index()] = true;
+                }
+            }
+            let excluded = |position: RunePosition, activation: Activation| {
+                !open[position.index()][activation.index()]
+            };
+            for position in self.size.all_positions() {
+                for activation in self.size.all_activations() {
+                    if excluded(position, activation)
+                        || !rule.excluded_by_rune_counts(lock, (position, activation), excluded)
+                    {
+                        continue;
+                    }
+                    let (rune, neighbour) = match lock.runes[position] {
+                        rune if rune == first => (second, activation.next(self.size)),
+                        _ => (first, activation.prev()),
+                    };
+                    let mut reasons = vec![FactReason::Rule(rule_index)];
+                    //Orderings are counted out by the runes alone.
+                    if let (Ok(neighbour), RuleKind::RuneFollowsImmediately { .. }) =
+                        (neighbour, rule)
+                    {
+                        reasons.extend(
+                            self.size
+                                .all_positions()
+                                .filter(|it| lock.runes[*it] == rune)
+                                .filter_map(|it| self.handle_at(it, neighbour))
+                                .unique()
+                                .map(|it| {
+                                    FactReason::Fact(
+                                        it,
+                                        DebugInfo {
+                                            origin: "consolidate_rune_counts",
+                                        },
+                                    )
+                                }),
+                        );
+                    }
+                    integrations.push(Fact {
+                        kind: FactKind::ActivationCannotBeOn,
+                        activation,
+                        position,
+                        reasons,
+                        range: Vec::new(),
+                    });
+                }
+            }
+        }
+        self.integrate_consolidation(integrations)
+    }
+
+    ///Once the condition of an implication holds on the placed activations, its consequence rules
+    ///out the cells it would be violated with, like a rule of its own. The eliminations cite the
+    ///placements it was checked with.
+    fn consolidate_implications(
+        &mut self,
+        lock: &RuneLock,
+    ) -> Result<ConsolidationResult, FactError> {
+        let Ok(fixed) = self.fixed_assignment() else {
+            return Ok(ConsolidationResult::Unchanged);
+        };
+        let mut integrations = Vec::new();
+        for (rule_index, rule) in lock.active_rules() {
+            let RuleKind::Implies {
+                condition,
+                consequence,
+            } = rule
+            else {
+                continue;
+            };
+            if condition.truth(lock, &fixed) != Truth::Holds {
+                continue;
+            }
+            let placed: Vec<_> = consequence
+                .activations()
+                .into_iter()
+                .filter_map(|it| Some((fixed.position_of(it)?, it)))
+                .collect();
+            let mut reasons = vec![FactReason::Rule(rule_index)];
+            reasons.extend(
+                condition
+                    .activations()
+                    .into_iter()
+                    .chain(placed.iter().map(|(_, it)| *it))
+                    .unique()
+                    .filter_map(|it| self.handle_at(fixed.position_of(it)?, it))
+                    .map(|it| {
+                        FactReason::Fact(
+                            it,
+                            DebugInfo {
+                                origin: "consolidate_implications",
+                            },
+                        )
+                    }),
+            );
+            for activation in consequence.activations() {
+                if fixed.position_of(activation).is_some() {
+                    continue;
+                }
+                for position in self.possibilities_for(activation) {
+                    let violated = Assignment::from_tuple_iter(
+                        self.size,
+                        placed
+                            .iter()
+                            .copied()
+                            .chain(std::iter::once((position, activation))),
+                    )
+                    .is_ok_and(|it| consequence.validate(lock, &it).is_err());
+                    if violated {
+                        integrations.push(Fact {
+                            kind: FactKind::ActivationCannotBeOn,
+                            activation,
+                            position,
+                            reasons: reasons.clone(),
+                            range: Vec::new(),
+                        });
+                    }
+                }
+            }
+        }
+        self.integrate_consolidation(integrations)
+    }
+
+    ///Forward checking for `follows` rules with a gap: eliminates the cells that leave an
+    ///activation on an X rune without a Y rune within the gap, together with the placements on the
+    ///runes of the rule and the placements in the gaps after the X runes.
+    fn consolidate_gaps(&mut self, lock: &RuneLock) -> Result<ConsolidationResult, FactError> {
+        let Ok(fixed) = self.fixed_assignment() else {
+            return Ok(ConsolidationResult::Unchanged);
+        };
+        let mut integrations = Vec::new();
+        for (rule_index, rule) in lock.active_rules() {
+            let RuleKind::RuneFollowsWithin {
+                first,
+                second,
+                within,
+            } = *rule
+            else {
+                continue;
+            };
+            let on_runes: Vec<_> = self
+                .size
+                .all_positions()
+                .filter(|it| [first, second, Rune::Unknown].contains(&lock.runes[*it]))
+                .filter_map(|it| Some((it, fixed[it]?)))
+                .collect();
+            let placed: Vec<_> = on_runes
+                .iter()
+                .filter(|(position, _)| lock.runes[*position] == first)
+                .flat_map(|(_, activation)| activation.following(within, self.size))
+                .filter_map(|it| Some((fixed.position_of(it)?, it)))
+                .chain(on_runes.iter().copied())
+                .unique()
+                .collect();
+            let mut reasons = vec![FactReason::Rule(rule_index)];
+            reasons.extend(
+                placed
+                    .iter()
+                    .filter_map(|(position, activation)| self.handle_at(*position, *activation))
+                    .map(|it| {
+                        FactReason::Fact(
+                            it,
+                            DebugInfo {
+                                origin: "consolidate_gaps",
+                            },
+                        )
+                    }),
+            );
+            for activation in self.size.all_activations() {
+                if fixed.position_of(activation).is_some() {
+                    continue;
+                }
+                for position in self.possibilities_for(activation) {
+                    let violated = rule
+                        .validate_subset(
+                            lock,
+                            placed
+                                .iter()
+                                .copied()
+                                .chain(std::iter::once((position, activation))),
+                        )
+                        .is_err_and(|it| matches!(it, ValidateTupleError::RuleError(_)));
+                    if violated {
+                        integrations.push(Fact {
+                            kind: FactKind::ActivationCannotBeOn,
+                            activation,
+                            position,
+                            reasons: reasons.clone(),
+                            range: Vec::new(),
+                        });
+                    }
+                }
+            }
+        }
+        self.integrate_consolidation(integrations)
+    }
+
+    ///Forward checking for rules the pair analysis can't handle, like rules over more than two
+    ///activations or alternatives of different shapes: eliminates the positions of an open
+    ///activation of the rule that break it together with the activations placed so far.
+    fn consolidate_subsets(&mut self, lock: &RuneLock) -> Result<ConsolidationResult, FactError> {
+        let Ok(fixed) = self.fixed_assignment() else {
+            return Ok(ConsolidationResult::Unchanged);
+        };
+        let mut integrations = Vec::new();
+        for (rule_index, rule) in lock.active_rules() {
+            let activations = rule.activations();
+            let paired = rule.activation_pair().is_some() || rule.single_activation().is_some();
+            //Implications have a consolidation of their own, which waits for the condition.
+            if (activations.len() <= 2 && paired) || matches!(rule, RuleKind::Implies { .. }) {
+                continue;
+            }
+            let placed: Vec<_> = activations
+                .iter()
+                .filter_map(|it| Some((fixed.position_of(*it)?, *it)))
+                .collect();
+            if placed.is_empty() {
+                continue;
+            }
+            let mut reasons = vec![FactReason::Rule(rule_index)];
+            reasons.extend(
+                placed
+                    .iter()
+                    .filter_map(|(position, activation)| self.handle_at(*position, *activation))
+                    .map(|it| {
+                        FactReason::Fact(
+                            it,
+                            DebugInfo {
+                                origin: "consolidate_subsets",
+                            },
+                        )
+                    }),
+            );
+            for activation in activations {
+                if fixed.position_of(activation).is_some() {
+                    continue;
+                }
+                for position in self.possibilities_for(activation) {
+                    let violated = rule
+                        .validate_subset(
+                            lock,
+                            placed
+                                .iter()
+                                .copied()
+                                .chain(std::iter::once((position, activation))),
+                        )
+                        .is_err_and(|it| matches!(it, ValidateTupleError::RuleError(_)));
+                    if violated {
+                        integrations.push(Fact {
+                            kind: FactKind::ActivationCannotBeOn,
+                            activation,
+                            position,
+                            reasons: reasons.clone(),
+                            range: Vec::new(),
+                        });
+                    }
+                }
+            }
+        }
+        self.integrate_consolidation(integrations)
+    }
+
+    ///Pair analysis: eliminates positions of an activation for which no open position of a
+    ///related activation is left that satisfies their rule. Unlike `consolidate_rules`, this also
+    ///reasons about activations that aren't given yet, and is therefore more expensive.
+    fn consolidate_pairs(&mut self, lock: &RuneLock) -> Result<ConsolidationResult, FactError> {
+        let mut integrations = Vec::new();
+        for (rule_index, rule) in lock.active_rules() {
+            let (first, second) = match rule.activation_pair() {
+                Some(it) => it,
+                None => continue,
+            };
+            for (this, other) in [(first, second), (second, first)] {
+                let others: Vec<_> = self.possibilities_for(other).collect();
+                for possibility in self.possibilities_for(this) {
+                    let compatible = others.iter().any(|other_possibility| {
+                        rule.validate_tuple(lock, (possibility, this), (*other_possibility, other))
+                            .is_ok()
+                    });
+                    if compatible {
+                        continue;
+                    }
+                    let mut reasons = vec![FactReason::Rule(rule_index)];
+                    reasons.extend(
+                        self.fact_lookup
+                            .lane(GridAxis::Activation, other.index())
+                            .flatten()
+                            .map(|it| {
+                                FactReason::Fact(
+                                    it,
+                                    DebugInfo {
+                                        origin: "consolidate_pairs",
+                                    },
+                                )
+                            }),
+                    );
+                    integrations.push(Fact {
+                        kind: FactKind::ActivationCannotBeOn,
+                        activation: this,
+                        position: possibility,
+                        reasons,
+                        range: Vec::new(),
+                    });
+                }
+            }
+        }
+        self.integrate_consolidation(integrations)
+    }
+
+    ///Transitive reasoning along santor chains: an activation `n` steps after another needs `n - 1`
+    ///distinct santor values strictly between the two, which the pairwise rules can't see.
+    fn consolidate_santor_chains(
+        &mut self,
+        lock: &RuneLock,
+    ) -> Result<ConsolidationResult, FactError> {
+        let mut integrations = Vec::new();
+        for (template, rules) in lock.templates.iter() {
+            let RuleTemplate::SantorChain { .. } = template;
+            let chain = template.activations();
+            //Every step of the chain needs its rule, the reasons cite them by step.
+            if rules.len() != chain.len() - 1 || !rules.iter().all(|it| lock.is_active(*it)) {
+                continue;
+            }
+            let givens: Vec<_> = chain
+                .iter()
+                .map(|activation| {
+                    self.fact_lookup
+                        .lane(GridAxis::Activation, activation.index())
+                        .flatten()
+                        .find(|it| self.facts[it.0].kind == FactKind::ActivationMustBeOn)
+                        .map(|it| (self.facts[it.0].position, it))
+                })
+                .collect();
+
+            for (step, activation) in chain.iter().enumerate() {
+                for position in self.possibilities_for(*activation) {
+                    let santor = lock.santor(position);
+                    let rules_between = |from: usize, to: usize| {
+                        rules[from..to].iter().copied().map(FactReason::Rule)
+                    };
+
+                    let steps_after = chain.len() - 1 - step;
+                    if lock.santors_between(None, Some(santor)) < step
+                        || lock.santors_between(Some(santor), None) < steps_after
+                    {
+                        integrations.push(Fact {
+                            kind: FactKind::ActivationCannotBeOn,
+                            activation: *activation,
+                            position,
+                            reasons: rules_between(0, chain.len() - 1).collect(),
+                            range: Vec::new(),
+                        });
+                        continue;
+                    }
+
+                    for (other_step, given) in givens.iter().enumerate() {
+                        let (given_position, given) = match given {
+                            Some(it) if other_step != step => *it,
+                            _ => continue,
+                        };
+                        let (low, high, from, to) = if other_step > step {
+                            (santor, lock.santor(given_position), step, other_step)
+                        } else {
+                            (lock.santor(given_position), santor, other_step, step)
+                        };
+                        if low < high
+                            && lock.santors_between(Some(low), Some(high)) >= to - from - 1
+                        {
+                            continue;
+                        }
+                        let mut reasons = vec![FactReason::Fact(
+                            given,
+                            DebugInfo {
+                                origin: "consolidate_santor_chains",
+                            },
+                        )];
+                        reasons.extend(rules_between(from, to));
+                        integrations.push(Fact {
+                            kind: FactKind::ActivationCannotBeOn,
+                            activation: *activation,
+                            position,
+                            reasons,
+                            range: Vec::new(),
+                        });
+                        break;
                     }
                 }
             }
         }
-        return self.integrate_consolidation(integrations);
+        self.integrate_consolidation(integrations)
+    }
+
+    ///Runs pair analysis and regular consolidation until neither of them finds anything new, or
+    ///until `cancel` is set, which is checked between rounds. Returns whether any facts were
+    ///derived.
+    pub fn deepen(&mut self, lock: &RuneLock, cancel: &CancelToken) -> Result<bool, FactError> {
+        let known = self.fact_count();
+        while !cancel.is_cancelled() {
+            if let ConsolidationResult::Unchanged = self.consolidate_pairs(lock)? {
+                break;
+            }
+            self.consolidate(lock)?;
+        }
+        Ok(self.fact_count() > known)
     }
 
     fn integrate_consolidation(
@@ -496,10 +1483,12 @@ impl FactDb {
         integrations: Vec<Fact>,
     ) -> Result<ConsolidationResult, FactError> {
         let mut result = ConsolidationResult::Unchanged;
-        for f in integrations {
-            let new_fact = self
-                .integrate_single_fact(f)
-                .expect_without_contradiction(self)?;
+        for f in self.collapse_ranges(integrations) {
+            let new_fact = match f.range.is_empty() {
+                true => self.integrate_single_fact(f),
+                false => self.integrate_range(f),
+            }
+            .expect_without_contradiction(self)?;
             match new_fact {
                 SingleFactIntegrationResult::Unchanged(_) => {}
                 SingleFactIntegrationResult::Integrated(_) => result = ConsolidationResult::Changes,
@@ -508,6 +1497,56 @@ impl FactDb {
         Ok(result)
     }
 
+    ///Merges the eliminations of an activation that share their reasons into a single range fact.
+    ///Only cells that are empty and not claimed by any other integration are merged, everything
+    ///else is left as it is, in its original order.
+    fn collapse_ranges(&self, integrations: Vec<Fact>) -> Vec<Fact> {
+        let mut claims = HashMap::new();
+        for fact in integrations.iter() {
+            *claims.entry((fact.position, fact.activation)).or_insert(0) += 1;
+        }
+        let mut collapsed: Vec<Fact> = Vec::with_capacity(integrations.len());
+        let on_empty_cell = |fact: &Fact| {
+            fact.kind == FactKind::ActivationCannotBeOn
+                && claims[&(fact.position, fact.activation)] == 1
+                && self
+                    .fact_lookup
+                    .get(fact.position.index(), fact.activation.index())
+                    .is_none()
+        };
+        for fact in integrations {
+            if !on_empty_cell(&fact) {
+                collapsed.push(fact);
+                continue;
+            }
+            let range = collapsed.iter_mut().find(|it| {
+                on_empty_cell(it) && it.activation == fact.activation && it.reasons == fact.reasons
+            });
+            match range {
+                Some(range) => range.range.push(fact.position),
+                None => collapsed.push(fact),
+            }
+        }
+        collapsed
+    }
+
+    ///Adds a range fact, whose cells must all be empty.
+    fn integrate_range(&mut self, fact: Fact) -> SingleFactIntegrationResult {
+        let handle = FactHandle(self.facts.len());
+        debug!("Created Range {:?}: {:?}", handle, fact);
+        for position in fact.positions() {
+            let cell = self
+                .fact_lookup
+                .get_mut(position.index(), fact.activation.index());
+            debug_assert!(cell.is_none(), "Range {} covers a known cell", handle);
+            *cell = Some(handle);
+        }
+        self.facts.push(fact);
+        self.record(|it| it.cannot_be_created += 1);
+        self.label_lifetimes();
+        SingleFactIntegrationResult::Integrated(handle)
+    }
+
     fn givens<'a>(&'a self) -> impl Iterator<Item = ((RunePosition, Activation), FactHandle)> + 'a {
         self.fact_lookup
             .indexed_iter()
@@ -519,7 +1558,7 @@ impl FactDb {
                                 RunePosition::new(position),
                                 Activation::new(activation as u8).unwrap(),
                             ),
-                            fact.clone(),
+                            fact,
                         )),
                         _ => None,
                     }
@@ -529,10 +1568,97 @@ impl FactDb {
             })
     }
 
+    ///Scans the grid for configurations consolidation must never produce: two givens sharing an
+    ///activation or a position, and cells pointing at facts about other cells.
+    pub fn check_invariants(&self) -> Result<(), InvariantViolation> {
+        let mut position_of = vec![None; self.fact_lookup.len_of(GridAxis::Activation)];
+        let mut activation_of = vec![None; self.fact_lookup.len_of(GridAxis::Position)];
+        for ((position, activation), handle) in self.fact_lookup.indexed_iter() {
+            let handle = match handle {
+                Some(it) => it,
+                None => continue,
+            };
+            let position = RunePosition::new(position);
+            let activation = Activation::new(activation as u8).unwrap();
+            let Some(fact) = self.facts.get(handle.0) else {
+                return Err(InvariantViolation::DanglingHandle {
+                    position,
+                    activation,
+                    fact: handle,
+                });
+            };
+            if !fact.covers(position, activation) {
+                return Err(InvariantViolation::MisplacedFact {
+                    position,
+                    activation,
+                    fact: handle,
+                });
+            }
+            if fact.kind != FactKind::ActivationMustBeOn {
+                continue;
+            }
+            if let Some(position_b) = position_of[activation.index()].replace(position) {
+                return Err(InvariantViolation::ActivationMustBeTwice {
+                    activation,
+                    position_a: position,
+                    position_b,
+                });
+            }
+            if let Some(activation_b) = activation_of[position.index()].replace(activation) {
+                return Err(InvariantViolation::PositionMustBeTwice {
+                    position,
+                    activation_a: activation,
+                    activation_b,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    ///Checks that facts are only derived from facts that were known before them, so reasons can
+    ///never be circular.
+    pub fn check_reasons(&self) -> Result<(), InvariantViolation> {
+        for (index, fact) in self.facts.iter().enumerate() {
+            for reason in fact.reasons.iter() {
+                if let FactReason::Fact(reason, _) = reason {
+                    if reason.0 >= index {
+                        return Err(InvariantViolation::LaterReason {
+                            fact: FactHandle(index),
+                            reason: *reason,
+                        });
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
     pub fn fixed_assignment(&self) -> Result<Assignment, AssignmentError> {
-        Assignment::from_tuple_iter(self.givens().map(|it| it.0))
+        Assignment::from_tuple_iter(self.size, self.givens().map(|it| it.0))
     }
 
+    ///What is still possible for a position or an activation: the activations that may still be
+    ///on the position, or the positions the activation may still be on.
+    ///
+    ///```
+    ///use rune_lock_solver::{
+    ///    activation::{Activation, HumanActivation}, index::RunePosition,
+    ///    rule::ActivationRuleKindHelpers, FactualSolver, LockSize, Rune, RuneLock,
+    ///};
+    ///
+    ///let runes = [0, 2, 1, 3, 2, 1, 3, 2, 1, 0, 2, 1].map(Rune::new);
+    ///let mut solver = FactualSolver::new(RuneLock::with_rules(runes, vec![(1, 2).alwanese()]));
+    ///let first = Activation::from(HumanActivation::new(1).unwrap());
+    ///let second = Activation::from(HumanActivation::new(2).unwrap());
+    ///assert_eq!(solver.facts().possibilities_for(second).count(), 12);
+    ///
+    ///solver.assume(first, RunePosition::new(0));
+    ///let facts = solver.facts();
+    ///assert_eq!(facts.possibilities_for(RunePosition::new(0)).collect::<Vec<_>>(), vec![first]);
+    ///assert!(facts
+    ///    .possibilities_for(second)
+    ///    .all(|it| it.alwanese_of(RunePosition::new(0), LockSize::STANDARD)));
+    ///```
     pub fn possibilities_for<'a, T: View + Debug>(
         &'a self,
         view: T,
@@ -543,8 +1669,7 @@ impl FactDb {
         debug!("possibilities for {:?}", view);
         //TODO Test if that is the correct axis
         self.fact_lookup
-            .index_axis(T::axis(), view.index())
-            .into_iter()
+            .lane(T::axis(), view.index())
             .enumerate()
             .filter_map(|(complement, handle)| {
                 let complement = T::Complement::from_usize(complement);
@@ -565,66 +1690,303 @@ impl FactDb {
             })
     }
 
-    pub fn explain(&self, fact_handle: FactHandle, lock: &RuneLock, max_depth: usize) {
-        fn explain_fact(
-            db: &FactDb,
-            lock: &RuneLock,
+    ///The lines of the reason tree of a fact. With `minimal`, every fact only lists the reasons
+    ///that are actually needed to derive it. Sub-proofs are expanded as long as they fit into
+    ///`max_lines`, the ones that depend on assumptions first, and then the shallower ones, and
+    ///no deeper than `max_depth` steps below the fact. The others are summarized by their handle
+    ///and how many facts they take.
+    pub fn explanation(
+        &self,
+        fact_handle: FactHandle,
+        lock: &RuneLock,
+        max_lines: usize,
+        max_depth: usize,
+        minimal: bool,
+    ) -> Vec<String> {
+        struct Step {
             handle: FactHandle,
-            current_depth: usize,
-            max_depth: usize,
-        ) {
-            if current_depth > max_depth {
-                return;
-            }
-            match db.facts.get(handle.0) {
-                Some(fact) => {
-                    println!("{}: {}", handle, fact);
-                    let mut reasons = fact.reasons.clone();
-                    reasons.sort_by_key(|a| match a {
+            depth: usize,
+            expanded: bool,
+            children: Vec<usize>,
+        }
+
+        if self.get(fact_handle).is_none() {
+            return vec![format!("Unknown handle {}", fact_handle)];
+        }
+        let mut known: HashMap<FactHandle, Vec<FactReason>> = HashMap::new();
+        let mut reasons_of = |handle: FactHandle| {
+            known
+                .entry(handle)
+                .or_insert_with(|| {
+                    let mut reasons = if minimal {
+                        minimal_reasons(self, lock, handle)
+                    } else {
+                        self.get(handle).unwrap().reasons.clone()
+                    };
+                    let key = |a: &FactReason| match a {
                         FactReason::Fact(handle, _) => 10000 + handle.0,
-                        FactReason::Rule(rule) => 10 + rule,
+                        FactReason::Rule(rule) => 1000 + rule,
+                        FactReason::External(note) => 100 + note,
+                        FactReason::Given(domain) => 10 + domain,
                         FactReason::Assumption => 0,
+                    };
+                    reasons.sort_by_key(key);
+                    //A fact can be a reason several times over, from different deductions.
+                    reasons.dedup_by_key(|it| key(it));
+                    reasons
+                })
+                .clone()
+        };
+
+        //Expanding a step turns its summary into its header, so it costs a line per reason.
+        let mut steps = vec![Step {
+            handle: fact_handle,
+            depth: 0,
+            expanded: false,
+            children: Vec::new(),
+        }];
+        let mut lines = 1;
+        let mut open = BinaryHeap::from([(true, Reverse(0), 0)]);
+        while let Some((_, _, index)) = open.pop() {
+            let reasons = reasons_of(steps[index].handle);
+            if index != 0 && (lines + reasons.len() > max_lines || steps[index].depth >= max_depth)
+            {
+                continue;
+            }
+            lines += reasons.len();
+            steps[index].expanded = true;
+            let depth = steps[index].depth + 1;
+            for reason in reasons {
+                if let FactReason::Fact(handle, _) = reason {
+                    let child = steps.len();
+                    steps.push(Step {
+                        handle,
+                        depth,
+                        expanded: false,
+                        children: Vec::new(),
                     });
-                    for reason in reasons {
-                        match reason {
-                            FactReason::Fact(fact, _debug_info) => {
-                                // print!("{0:1$}  -> (from {2})", "", inset, debug_info.origin);
-                                if current_depth + 1 <= max_depth {
-                                    print!("{0:1$}  -> ", "", current_depth * 4);
-                                    explain_fact(
-                                        db,
-                                        lock,
-                                        fact.clone(),
-                                        current_depth + 1,
-                                        max_depth,
-                                    )
-                                }
-                            }
-                            FactReason::Rule(rule) => {
-                                println!(
-                                    "{0:1$}  -> Rule {2} '{3}'",
-                                    "",
-                                    current_depth * 4,
-                                    rule,
-                                    lock.rules[rule]
-                                )
-                            }
-                            FactReason::Assumption => {
-                                println!("{0:1$}  -> Fact is Assumed", "", current_depth * 4)
-                            }
-                        }
+                    steps[index].children.push(child);
+                    let branch_local = self.lifetime(handle) == FactLifetime::BranchLocal;
+                    open.push((branch_local, Reverse(depth), child));
+                }
+            }
+        }
+
+        let mut rendered = Vec::new();
+        let mut pending = vec![(0, String::new())];
+        while let Some((index, prefix)) = pending.pop() {
+            let step = &steps[index];
+            let inset = step.depth * 4;
+            if !step.expanded {
+                let mut proof = HashSet::new();
+                let mut unvisited = vec![step.handle];
+                while let Some(handle) = unvisited.pop() {
+                    if proof.insert(handle) {
+                        unvisited.extend(reasons_of(handle).into_iter().filter_map(
+                            |it| match it {
+                                FactReason::Fact(handle, _) => Some(handle),
+                                _ => None,
+                            },
+                        ));
                     }
                 }
-                None => println!("Unknown handle {}", handle),
+                let plural = if proof.len() == 1 { "" } else { "s" };
+                rendered.push(format!(
+                    "{}\u{2026}via {} (proof of {} step{})",
+                    prefix,
+                    step.handle,
+                    proof.len(),
+                    plural
+                ));
+                continue;
+            }
+            let fact = self.get(step.handle).unwrap();
+            rendered.push(match self.lifetime(step.handle) {
+                FactLifetime::Universal => format!(
+                    "{}{}: {} {}",
+                    prefix,
+                    step.handle,
+                    fact,
+                    terminal::dimmed("(universal)")
+                ),
+                FactLifetime::BranchLocal => format!("{}{}: {}", prefix, step.handle, fact),
+            });
+            let mut children = step.children.iter();
+            let mut nested = Vec::new();
+            for reason in reasons_of(step.handle) {
+                let line = match reason {
+                    FactReason::Fact(_, _) => {
+                        let child = *children.next().unwrap();
+                        nested.push((child, format!("{0:1$}  -> ", "", inset)));
+                        continue;
+                    }
+                    FactReason::Rule(rule) => format!(
+                        "{0:1$}  -> Rule {2} '{3}'{4}",
+                        "",
+                        inset,
+                        rule,
+                        lock.rules[rule],
+                        lock.provenance(rule)
+                    ),
+                    FactReason::Given(domain) => format!(
+                        "{0:1$}  -> Given {2} '{3}'",
+                        "", inset, domain, lock.domains[domain]
+                    ),
+                    FactReason::External(note) => format!(
+                        "{0:1$}  -> External {2} '{3}'",
+                        "",
+                        inset,
+                        note,
+                        self.note(note)
+                    ),
+                    FactReason::Assumption => format!("{0:1$}  -> Fact is Assumed", "", inset),
+                };
+                rendered.push(line);
             }
+            //Fact reasons are sorted last, so their sub-proofs follow the other reasons.
+            pending.extend(nested.into_iter().rev());
         }
+        rendered
+    }
 
-        explain_fact(self, lock, fact_handle, 0, max_depth);
+    ///Describes something the database was told from outside, returning its index for
+    ///`FactReason::External`.
+    pub fn add_note(&mut self, note: String) -> usize {
+        self.notes.push(note);
+        self.notes.len() - 1
+    }
+
+    pub fn note(&self, index: usize) -> &str {
+        &self.notes[index]
     }
 
     pub fn get(&self, fact: FactHandle) -> Option<&Fact> {
         self.facts.get(fact.0)
     }
+
+    ///All assumed facts.
+    pub fn assumptions(&self) -> impl Iterator<Item = FactHandle> + '_ {
+        self.iter_facts()
+            .filter(|(_, fact)| fact.reasons.contains(&FactReason::Assumption))
+            .map(|(handle, _)| handle)
+    }
+
+    ///The assumed facts the given fact transitively depends on.
+    pub fn assumptions_of(&self, fact: FactHandle) -> BTreeSet<FactHandle> {
+        let mut assumptions = BTreeSet::new();
+        let mut visited = HashSet::new();
+        let mut open = vec![fact];
+        while let Some(handle) = open.pop() {
+            if !visited.insert(handle) {
+                continue;
+            }
+            let fact = match self.get(handle) {
+                Some(it) => it,
+                None => continue,
+            };
+            for reason in fact.reasons.iter() {
+                match reason {
+                    FactReason::Fact(reason, _) => open.push(*reason),
+                    FactReason::Assumption => {
+                        assumptions.insert(handle);
+                    }
+                    FactReason::Rule(_) | FactReason::Given(_) | FactReason::External(_) => {}
+                }
+            }
+        }
+        assumptions
+    }
+
+    ///The rules the given fact transitively depends on.
+    pub fn rules_of(&self, fact: FactHandle) -> BTreeSet<usize> {
+        let mut rules = BTreeSet::new();
+        let mut visited = HashSet::new();
+        let mut open = vec![fact];
+        while let Some(handle) = open.pop() {
+            if !visited.insert(handle) {
+                continue;
+            }
+            let fact = match self.get(handle) {
+                Some(it) => it,
+                None => continue,
+            };
+            for reason in fact.reasons.iter() {
+                match reason {
+                    FactReason::Fact(reason, _) => open.push(*reason),
+                    FactReason::Rule(rule) => {
+                        rules.insert(*rule);
+                    }
+                    FactReason::Assumption | FactReason::Given(_) | FactReason::External(_) => {}
+                }
+            }
+        }
+        rules
+    }
+
+    ///A database of the same size knowing only `facts`, taken as assumed, to find out what
+    ///follows from them alone. Their reasons are left out, as they refer to this database.
+    pub(super) fn knowing_only<'a>(
+        &self,
+        facts: impl IntoIterator<Item = &'a Fact>,
+    ) -> Result<FactDb, FactError> {
+        let mut db = FactDb::new(self.size);
+        for fact in facts {
+            for position in fact.positions() {
+                db.integrate_single_fact(Fact {
+                    position,
+                    range: Vec::new(),
+                    reasons: vec![FactReason::Assumption],
+                    ..fact.clone()
+                })
+                .expect_without_contradiction(&db)?;
+            }
+        }
+        Ok(db)
+    }
+
+    pub fn len_of(&self, axis: GridAxis) -> usize {
+        self.fact_lookup.len_of(axis)
+    }
+
+    pub fn fact_count(&self) -> usize {
+        self.facts.len()
+    }
+
+    ///Every fact with its handle, in the order they were recorded. Facts are only ever added, so
+    ///a fact keeps its place in this order for the lifetime of the database.
+    pub fn iter_facts(&self) -> impl Iterator<Item = (FactHandle, &Fact)> {
+        self.facts_since(0)
+    }
+
+    ///Every cell, position by position and within a position by activation, with the fact that
+    ///currently describes it, if any.
+    pub fn iter_cells(
+        &self,
+    ) -> impl Iterator<Item = (RunePosition, Activation, Option<FactHandle>)> + '_ {
+        self.fact_lookup
+            .indexed_iter()
+            .map(|((position, activation), fact)| {
+                (
+                    RunePosition::from_usize(position),
+                    Activation::from_usize(activation),
+                    fact,
+                )
+            })
+    }
+
+    ///All facts that were recorded after the first `known` facts.
+    pub fn facts_since(&self, known: usize) -> impl Iterator<Item = (FactHandle, &Fact)> {
+        self.facts
+            .iter()
+            .enumerate()
+            .skip(known)
+            .map(|(handle, fact)| (FactHandle(handle), fact))
+    }
+
+    ///The fact currently describing the given cell, if any.
+    pub fn handle_at(&self, position: RunePosition, activation: Activation) -> Option<FactHandle> {
+        self.fact_lookup.get(position.index(), activation.index())
+    }
 }
 
 impl Display for FactHandle {
@@ -635,90 +1997,496 @@ impl Display for FactHandle {
 
 impl Display for Fact {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self.kind {
-            FactKind::Contradiction(k) => match k {
-                ContradictionKind::ContradictingRequirements => write!(
-                    f,
-                    "{} has contradicting facts regarding position {}",
-                    self.activation, self.position
-                ),
-                ContradictionKind::NoOptionsLeft => {
-                    write!(
-                        f,
-                        "{} or {} has no options left to go",
-                        self.activation, self.position
-                    )
-                }
-            },
-            FactKind::ActivationCannotBeOn => {
-                write!(f, "{} cannot be on {}", self.activation, self.position)
+        let key = match self.kind {
+            FactKind::Contradiction(ContradictionKind::ContradictingRequirements) => {
+                "contradiction.contradicting-requirements"
             }
-            FactKind::ActivationMustBeOn => {
-                write!(f, "{} must be on {}", self.activation, self.position)
+            FactKind::Contradiction(ContradictionKind::NoOptionsLeft) => {
+                "contradiction.no-options-left"
             }
-        }
+            FactKind::ActivationCannotBeOn => "fact.cannot-be-on",
+            FactKind::ActivationMustBeOn => "fact.must-be-on",
+        };
+        f.write_str(&messages::message(
+            key,
+            &[
+                ("activation", &self.activation),
+                ("position", &self.position),
+                ("positions", &self.positions().join(", ")),
+            ],
+        ))
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use ndarray::Axis;
+    use crate::{
+        fact_solver::{Fact, FactKind, FactReason},
+        index::RunePosition,
+        rule::{ActivationRuleKindHelpers, RuleKind},
+        rune::Rune,
+        size::LockSize,
+        test_support::{activation, sample_runes},
+        RuneLock,
+    };
 
-    use crate::{activation::Activation, fact_solver::view::View, index::RunePosition};
+    use super::{FactDb, FactLifetime};
 
-    use super::FactDb;
+    #[test]
+    fn test_lanes_collapse_into_ranges() {
+        let lock = RuneLock::with_rules(sample_runes(), vec![]);
+        let first = activation(1);
+        let mut db = FactDb::new(LockSize::STANDARD);
+        assert!(db
+            .integrate_and_consolidate(
+                Fact {
+                    kind: FactKind::ActivationMustBeOn,
+                    activation: first,
+                    position: RunePosition::new(0),
+                    reasons: vec![FactReason::Assumption],
+                    range: Vec::new(),
+                },
+                &lock,
+            )
+            .is_ok());
+
+        let range = db.handle_at(RunePosition::new(1), first).unwrap();
+        for position in (1..12).map(RunePosition::new) {
+            assert_eq!(db.handle_at(position, first), Some(range));
+        }
+        assert_eq!(db.get(range).unwrap().positions().count(), 11);
+        //The must-be, one range for its activation, and a fact per other activation of its position.
+        assert_eq!(db.fact_count(), 1 + 1 + 11);
+        assert!(db.check_invariants().is_ok());
+    }
 
     #[test]
-    fn test_axis_lanes() {
-        //We have 3 runes and 9 activations.
-        let db = FactDb::new(3, 9);
+    fn test_facts_and_cells_are_iterated_in_a_stable_order() {
+        let lock = RuneLock::with_rules(sample_runes(), vec![(1, 2).alwanese()]);
+        let mut db = FactDb::new(LockSize::STANDARD);
+        assert!(db
+            .integrate_and_consolidate(
+                Fact {
+                    kind: FactKind::ActivationMustBeOn,
+                    activation: activation(1),
+                    position: RunePosition::new(0),
+                    reasons: vec![FactReason::Assumption],
+                    range: Vec::new(),
+                },
+                &lock,
+            )
+            .is_ok());
+
+        let facts: Vec<_> = db.iter_facts().map(|(handle, _)| handle).collect();
+        assert_eq!(facts.len(), db.fact_count());
+        assert!(facts.windows(2).all(|it| it[0] < it[1]));
+        assert!(db
+            .iter_facts()
+            .all(|(handle, fact)| db.get(handle).is_some_and(|it| std::ptr::eq(it, fact))));
 
-        dbg!(&db.fact_lookup);
+        let cells: Vec<_> = db.iter_cells().collect();
+        assert_eq!(cells.len(), 144);
+        assert!(cells
+            .windows(2)
+            .all(|it| (it[0].0, it[0].1) < (it[1].0, it[1].1)));
+        for (position, activation, fact) in cells {
+            assert_eq!(db.handle_at(position, activation), fact);
+        }
+    }
 
-        //Lookup 2nd Rune 7th Activation
-        let rune_2_activation_7 = db.fact_lookup[[2, 7]];
-        dbg!(rune_2_activation_7);
+    #[test]
+    fn test_single_activation_rules_need_no_given() {
+        let lock = RuneLock::with_rules(
+            sample_runes(),
+            vec!["1 at 4".parse().unwrap(), "2 not-at 7".parse().unwrap()],
+        );
+        let mut db = FactDb::new(LockSize::STANDARD);
+        assert!(db.integrate_givens(&lock).is_ok());
 
-        //Lookup Activations for Rune 1 (Should be 9 long)
-        let activations_for_rune = db.fact_lookup.index_axis(RunePosition::axis(), 1);
-        dbg!(activations_for_rune);
-        assert_eq!(activations_for_rune.len(), 9);
+        let kind_at = |position, activation| {
+            db.handle_at(RunePosition::new(position), activation)
+                .and_then(|it| db.get(it))
+                .map(|it| it.kind)
+        };
+        assert_eq!(
+            kind_at(4, activation(1)),
+            Some(FactKind::ActivationMustBeOn)
+        );
+        assert_eq!(
+            kind_at(7, activation(2)),
+            Some(FactKind::ActivationCannotBeOn)
+        );
+        assert_eq!(kind_at(6, activation(2)), None);
+        assert!(db.check_invariants().is_ok());
+    }
 
-        //Lookup Runes for Activation 1 (Should be 3 long)
-        let runes_for_activation = db.fact_lookup.index_axis(Activation::axis(), 1);
-        dbg!(runes_for_activation);
-        assert_eq!(runes_for_activation.len(), 3);
+    #[test]
+    fn test_alternatives_of_different_shapes_prune() {
+        let lock = RuneLock::with_rules(
+            sample_runes(),
+            vec!["1 2 alwanese or 1 at 6".parse().unwrap()],
+        );
+        let mut db = FactDb::new(LockSize::STANDARD);
+        assert!(db.integrate_givens(&lock).is_ok());
+        assert!(db
+            .integrate_and_consolidate(
+                Fact {
+                    kind: FactKind::ActivationMustBeOn,
+                    activation: activation(1),
+                    position: RunePosition::new(0),
+                    reasons: vec![FactReason::Assumption],
+                    range: Vec::new(),
+                },
+                &lock,
+            )
+            .is_ok());
 
-        //Iterate Runes with their activations
-        //Index is the rune, collection is of activations
-        let activations_for_rune: Vec<_> = db
-            .fact_lookup
-            .lanes(Activation::axis())
-            .into_iter()
-            .enumerate()
+        let possibilities: Vec<_> = db
+            .possibilities_for(activation(2))
+            .map(|it| it.index())
             .collect();
-        assert_eq!(activations_for_rune.len(), 3);
-        assert_eq!(activations_for_rune[0].1.len(), 9);
+        assert_eq!(possibilities, vec![1, 2, 7, 8]);
+    }
 
-        //Iterate Activations with their runes
-        //Index is the activation, collection is of runes
-        let runes_for_activation: Vec<_> = db
-            .fact_lookup
-            .lanes(RunePosition::axis())
-            .into_iter()
-            .enumerate()
-            .collect();
-        assert_eq!(runes_for_activation.len(), 9);
-        assert_eq!(runes_for_activation[0].1.len(), 3);
+    #[test]
+    fn test_cells_are_coded_by_why_they_are_known() {
+        let lock = RuneLock::with_rules(sample_runes(), vec!["2 not-at 7".parse().unwrap()]);
+        let mut db = FactDb::new(LockSize::STANDARD);
+        assert!(db.integrate_givens(&lock).is_ok());
+        assert!(db
+            .integrate_and_consolidate(
+                Fact {
+                    kind: FactKind::ActivationMustBeOn,
+                    activation: activation(1),
+                    position: RunePosition::new(0),
+                    reasons: vec![FactReason::Assumption],
+                    range: Vec::new(),
+                },
+                &lock,
+            )
+            .is_ok());
+
+        let code_at = |position, activation| {
+            db.handle_at(RunePosition::new(position), activation)
+                .and_then(|it| db.reason_code(it))
+        };
+        assert_eq!(code_at(0, activation(1)).as_deref(), Some("A"));
+        assert_eq!(code_at(7, activation(2)).as_deref(), Some("R0"));
+        assert_eq!(code_at(5, activation(1)).as_deref(), Some("U"));
+        assert_eq!(code_at(0, activation(3)).as_deref(), Some("U"));
+        assert_eq!(code_at(6, activation(2)), None);
+    }
+
+    #[test]
+    fn test_universal_facts_can_be_adopted_by_the_root() {
+        let lock = RuneLock::with_rules(sample_runes(), vec![(1, 2).alwanese()]);
+        let fact = |it, position, reason| Fact {
+            kind: FactKind::ActivationCannotBeOn,
+            activation: activation(it),
+            position: RunePosition::new(position),
+            reasons: vec![reason],
+            range: Vec::new(),
+        };
+        let mut branch = FactDb::new(LockSize::STANDARD);
+        assert!(branch
+            .integrate_and_consolidate(
+                Fact {
+                    kind: FactKind::ActivationMustBeOn,
+                    ..fact(1, 0, FactReason::Assumption)
+                },
+                &lock,
+            )
+            .is_ok());
+        let derived = branch
+            .handle_at(RunePosition::new(3), activation(2))
+            .unwrap();
+        assert_eq!(branch.lifetime(derived), FactLifetime::BranchLocal);
+
+        assert!(branch
+            .integrate_and_consolidate(fact(3, 5, FactReason::Rule(0)), &lock)
+            .is_ok());
+        let universal = branch
+            .handle_at(RunePosition::new(5), activation(3))
+            .unwrap();
+        assert_eq!(branch.lifetime(universal), FactLifetime::Universal);
+
+        let mut root = FactDb::new(LockSize::STANDARD);
+        let adopted = root.adopt(&branch, universal);
+        assert_eq!(
+            root.handle_at(RunePosition::new(5), activation(3)),
+            Some(adopted)
+        );
+        assert_eq!(root.lifetime(adopted), FactLifetime::Universal);
+        assert!(root.check_invariants().is_ok());
+    }
+
+    #[test]
+    fn test_explanations_summarize_what_does_not_fit() {
+        let lock = RuneLock::with_rules(
+            sample_runes(),
+            vec![
+                (1, 2).alwanese(),
+                (2, 3).antakian_conjugate(),
+                (3, 4).alwanese(),
+                (4, 5).antakian_twins(),
+                (5, 6).increase_santor(),
+                (6, 7).alwanese(),
+                (7, 8).antakian_conjugate(),
+                (8, 9).alwanese(),
+                (9, 10).antakian_twins(),
+                (10, 11).increase_santor(),
+            ],
+        );
+        let mut db = FactDb::new(LockSize::STANDARD);
+        for (it, position) in [(1, 0), (3, 4), (6, 5)] {
+            assert!(db
+                .integrate_and_consolidate(
+                    Fact {
+                        kind: FactKind::ActivationMustBeOn,
+                        activation: activation(it),
+                        position: RunePosition::new(position),
+                        reasons: vec![FactReason::Assumption],
+                        range: Vec::new(),
+                    },
+                    &lock,
+                )
+                .is_ok());
+        }
+        let (deepest, _) = db
+            .iter_facts()
+            .max_by_key(|(handle, _)| {
+                db.explanation(*handle, &lock, usize::MAX, usize::MAX, false)
+                    .len()
+            })
+            .unwrap();
+
+        let full = db.explanation(deepest, &lock, usize::MAX, usize::MAX, false);
+        assert!(full.len() > 6);
+        assert!(!full.iter().any(|it| it.contains("\u{2026}via")));
+        let short = db.explanation(deepest, &lock, 6, usize::MAX, false);
+        assert!(short.len() <= 6);
+        assert!(short[0].starts_with(&deepest.to_string()));
+        assert!(short
+            .iter()
+            .any(|it| it.contains("\u{2026}via") && it.contains("steps)")));
+        //Only the reasons of the fact itself, however many lines there are room for.
+        let shallow = db.explanation(deepest, &lock, usize::MAX, 1, false);
+        assert!(shallow.len() < full.len());
+        assert!(shallow.iter().skip(1).all(|it| it.starts_with("  -> ")));
+    }
+
+    #[test]
+    fn test_implications_propagate_once_the_condition_holds() {
+        let lock = RuneLock::with_rules(
+            sample_runes(),
+            vec!["if 1 at 0 then 2 3 alwanese".parse().unwrap()],
+        );
+        let place = |db: &mut FactDb, position, it| {
+            db.integrate_and_consolidate(
+                Fact {
+                    kind: FactKind::ActivationMustBeOn,
+                    activation: activation(it),
+                    position: RunePosition::new(position),
+                    reasons: vec![FactReason::Assumption],
+                    range: Vec::new(),
+                },
+                &lock,
+            )
+            .is_ok()
+        };
+        let by_rule = |db: &FactDb, position| {
+            db.handle_at(RunePosition::new(position), activation(3))
+                .and_then(|it| db.get(it))
+                .is_some_and(|it| it.reasons.contains(&FactReason::Rule(0)))
+        };
+
+        let mut db = FactDb::new(LockSize::STANDARD);
+        assert!(place(&mut db, 5, 1));
+        assert!(place(&mut db, 1, 2));
+        assert!(!by_rule(&db, 4));
+
+        let mut db = FactDb::new(LockSize::STANDARD);
+        assert!(place(&mut db, 0, 1));
+        assert!(place(&mut db, 1, 2));
+        assert!(by_rule(&db, 4));
+        assert!(!by_rule(&db, 2));
+        assert!(db.check_invariants().is_ok());
+    }
+
+    #[test]
+    fn test_rules_over_more_activations_check_the_placed_ones() {
+        let lock = RuneLock::with_rules(
+            sample_runes(),
+            vec!["3 6 9 all-different-runes".parse().unwrap()],
+        );
+        let mut db = FactDb::new(LockSize::STANDARD);
+        let mut place = |position, it| {
+            db.integrate_and_consolidate(
+                Fact {
+                    kind: FactKind::ActivationMustBeOn,
+                    activation: activation(it),
+                    position: RunePosition::new(position),
+                    reasons: vec![FactReason::Assumption],
+                    range: Vec::new(),
+                },
+                &lock,
+            )
+            .is_ok()
+        };
+        assert!(place(0, 3));
+        assert!(place(1, 6));
+        let by_rule = |position| {
+            db.handle_at(RunePosition::new(position), activation(9))
+                .and_then(|it| db.get(it))
+                .is_some_and(|it| it.reasons.contains(&FactReason::Rule(0)))
+        };
+        for position in [4, 7, 9, 10] {
+            assert!(by_rule(position));
+        }
+        assert!(!by_rule(2));
+        assert!(db.check_invariants().is_ok());
+    }
+
+    #[test]
+    fn test_rune_orderings_limit_the_other_runes() {
+        //Runes Z on 0 and 9, runes C on 3 and 6.
+        let lock = RuneLock::with_rules(sample_runes(), vec!["Z before C".parse().unwrap()]);
+        let mut db = FactDb::new(LockSize::STANDARD);
+        assert!(db.integrate_givens(&lock).is_ok());
+        let by_rule = |db: &FactDb, position, it| {
+            db.handle_at(RunePosition::new(position), activation(it))
+                .and_then(|it| db.get(it))
+                .is_some_and(|it| {
+                    it.kind == FactKind::ActivationCannotBeOn
+                        && it.reasons.contains(&FactReason::Rule(0))
+                })
+        };
+        for (position, it) in [(3, 1), (6, 2), (0, 12), (9, 11)] {
+            assert!(by_rule(&db, position, it));
+        }
+        assert!(!by_rule(&db, 3, 3));
+        assert!(!by_rule(&db, 0, 10));
+
+        assert!(db
+            .integrate_and_consolidate(
+                Fact {
+                    kind: FactKind::ActivationMustBeOn,
+                    activation: activation(6),
+                    position: RunePosition::new(3),
+                    reasons: vec![FactReason::Assumption],
+                    range: Vec::new(),
+                },
+                &lock,
+            )
+            .is_ok());
+        for it in 7..=10 {
+            assert!(by_rule(&db, 0, it));
+            assert!(by_rule(&db, 9, it));
+        }
+        assert!(!by_rule(&db, 9, 5));
+        assert!(db.check_invariants().is_ok());
+    }
+
+    #[test]
+    fn test_gaps_of_follows_rules() {
+        //Runes Z on 0 and 9, runes V on 2, 5, 8 and 11.
+        let lock = RuneLock::with_rules(
+            sample_runes(),
+            vec!["V follows Z within 2".parse().unwrap()],
+        );
+        let mut db = FactDb::new(LockSize::STANDARD);
+        assert!(db.integrate_givens(&lock).is_ok());
+        let by_rule = |db: &FactDb, position, it| {
+            db.handle_at(RunePosition::new(position), activation(it))
+                .and_then(|it| db.get(it))
+                .is_some_and(|it| {
+                    it.kind == FactKind::ActivationCannotBeOn
+                        && it.reasons.contains(&FactReason::Rule(0))
+                })
+        };
+        assert!(by_rule(&db, 0, 12));
+        assert!(by_rule(&db, 9, 12));
+        assert!(!by_rule(&db, 9, 11));
+
+        for (position, it) in [(0, 1), (1, 2)] {
+            assert!(db
+                .integrate_and_consolidate(
+                    Fact {
+                        kind: FactKind::ActivationMustBeOn,
+                        activation: activation(it),
+                        position: RunePosition::new(position),
+                        reasons: vec![FactReason::Assumption],
+                        range: Vec::new(),
+                    },
+                    &lock,
+                )
+                .is_ok());
+        }
+        for position in [3, 4, 6, 7, 9, 10] {
+            assert!(by_rule(&db, position, 3));
+        }
+        for position in [2, 5, 8, 11] {
+            assert!(!by_rule(&db, position, 3));
+        }
+        assert!(db.check_invariants().is_ok());
+    }
+
+    #[test]
+    fn test_rune_counts_of_follows_rules() {
+        let runes = sample_runes();
+        //Two runes 0 on 0 and 9, two runes 3 on 3 and 6: every 3 is preceded by a 0.
+        let follows = |first, second| RuleKind::RuneFollowsImmediately {
+            first: Rune::new(first),
+            second: Rune::new(second),
+        };
+        let lock = RuneLock::with_rules(runes, vec![follows(0, 3)]);
+        let mut db = FactDb::new(LockSize::STANDARD);
+        assert!(db.integrate_givens(&lock).is_ok());
+        let eliminated = |db: &FactDb, position, activation| {
+            db.handle_at(RunePosition::new(position), activation)
+                .and_then(|it| db.get(it))
+                .is_some_and(|it| {
+                    it.kind == FactKind::ActivationCannotBeOn
+                        && it.reasons.contains(&FactReason::Rule(0))
+                })
+        };
+        for position in [0, 9] {
+            assert!(eliminated(&db, position, activation(12)));
+        }
+        for position in [3, 6] {
+            assert!(eliminated(&db, position, activation(1)));
+        }
+        assert!(!eliminated(&db, 3, activation(2)));
+        assert!(db.check_invariants().is_ok());
+
+        //Four runes 1 can't all be followed by one of the two runes 0.
+        let lock = RuneLock::with_rules(runes, vec![follows(1, 0)]);
+        assert!(FactDb::new(LockSize::STANDARD)
+            .integrate_givens(&lock)
+            .is_err());
     }
 
     #[test]
-    fn test_indexed_iter() {
-        let db = FactDb::new(3, 9);
+    fn test_different_runes_rule_out_every_copy_of_a_rune() {
+        //Rune 0 is on positions 0 and 9, which are all that is left for activation 1.
+        let mut rules = vec![(1, 2).different_runes()];
+        rules.extend(
+            [1, 2, 3, 4, 5, 6, 7, 8, 10, 11]
+                .map(|it| format!("1 not-at {}", it).parse::<RuleKind>().unwrap()),
+        );
+        let lock = RuneLock::with_rules(sample_runes(), rules);
+        let mut db = FactDb::new(LockSize::STANDARD);
+        assert!(db.integrate_givens(&lock).is_ok());
 
-        for ((a, b), _) in db.fact_lookup.indexed_iter() {
-            dbg!((a, b));
+        for position in [0, 9].map(RunePosition::new) {
+            let fact = db
+                .handle_at(position, activation(2))
+                .and_then(|it| db.get(it))
+                .unwrap();
+            assert_eq!(fact.kind, FactKind::ActivationCannotBeOn);
+            assert!(fact.reasons.contains(&FactReason::Rule(0)));
         }
+        assert_eq!(db.handle_at(RunePosition::new(2), activation(2)), None);
+        assert!(db.check_invariants().is_ok());
     }
-    //We have 3 runes and 9 activations.
 }
@@ -0,0 +1,176 @@
+//! Consistency checks of a whole session, for states that were loaded from disk or built by a
+//! buggy command: the links of the tree, the facts of every state and whether the states are
+//! marked the way their facts say.
+
+use thiserror::Error;
+
+use super::{
+    assumption_tree::{AssumptionTreeNodeHandle, TreeLinkViolation},
+    fact_db::{FactHandle, InvariantViolation},
+    FactKind, FactualSolver, SolverAction, SolverStateState,
+};
+
+#[derive(Debug, Error)]
+pub enum Inconsistency {
+    #[error("{0}")]
+    Tree(#[from] TreeLinkViolation),
+    #[error("The current state {0} does not exist")]
+    UnknownCurrent(AssumptionTreeNodeHandle),
+    #[error("State {node}: {violation}")]
+    Facts {
+        node: AssumptionTreeNodeHandle,
+        violation: InvariantViolation,
+    },
+    #[error("State {node} is marked as contradicted by {fact}, which is no contradiction")]
+    FalseContradiction {
+        node: AssumptionTreeNodeHandle,
+        fact: FactHandle,
+    },
+    #[error("State {node} contains the contradiction {fact}, but isn't marked as contradicted")]
+    MissedContradiction {
+        node: AssumptionTreeNodeHandle,
+        fact: FactHandle,
+    },
+}
+
+pub struct Finding {
+    pub inconsistency: Inconsistency,
+    pub repaired: bool,
+}
+
+impl FactualSolver {
+    ///Checks the whole session for inconsistencies. With `repair`, broken tree links are rebuilt,
+    ///states are marked the way their facts say, and states with broken facts are derived from
+    ///their parent again. Broken facts of the root can't be repaired.
+    pub fn fsck(&mut self, repair: bool) -> Vec<Finding> {
+        let mut findings = Vec::new();
+        let violations = self.states.check_links();
+        if repair && !violations.is_empty() {
+            self.states.repair_links();
+        }
+        findings.extend(violations.into_iter().map(|it| Finding {
+            inconsistency: it.into(),
+            repaired: repair,
+        }));
+        if !self.states.contains(self.current) {
+            findings.push(Finding {
+                inconsistency: Inconsistency::UnknownCurrent(self.current),
+                repaired: repair,
+            });
+            if repair {
+                self.current = self.states.root();
+            }
+        }
+
+        //Handles are handed out in insertion order, so parents are always repaired first.
+        for node in self.states.handles().collect::<Vec<_>>() {
+            let facts = &self.states[node].facts;
+            let violation = facts.check_invariants().and_then(|_| facts.check_reasons());
+            if let Err(violation) = violation {
+                let parent = self.states.parent_of(node);
+                let action = self.states[node].action;
+                let rederived = match (repair, parent, action) {
+                    (
+                        true,
+                        Some(parent),
+                        SolverAction::Assume {
+                            position,
+                            activation,
+                        },
+                    ) => {
+                        self.states[node] = self.derive(parent, position, activation);
+                        true
+                    }
+                    _ => false,
+                };
+                findings.push(Finding {
+                    inconsistency: Inconsistency::Facts { node, violation },
+                    repaired: rederived,
+                });
+                if !rederived {
+                    continue;
+                }
+            }
+
+            let state = &mut self.states[node];
+            let contradiction = state
+                .facts
+                .iter_facts()
+                .find(|(_, fact)| matches!(fact.kind, FactKind::Contradiction(_)))
+                .map(|(handle, _)| handle);
+            let inconsistency = match (state.state, contradiction) {
+                (SolverStateState::Contradicts(fact), _)
+                    if !state
+                        .facts
+                        .get(fact)
+                        .is_some_and(|it| matches!(it.kind, FactKind::Contradiction(_))) =>
+                {
+                    Inconsistency::FalseContradiction { node, fact }
+                }
+                (SolverStateState::Unexplored, Some(fact)) => {
+                    Inconsistency::MissedContradiction { node, fact }
+                }
+                _ => continue,
+            };
+            if repair {
+                state.state = match contradiction {
+                    Some(fact) => SolverStateState::Contradicts(fact),
+                    None => SolverStateState::Unexplored,
+                };
+            }
+            findings.push(Finding {
+                inconsistency,
+                repaired: repair,
+            });
+        }
+        findings
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        fact_solver::{fact_db::FactHandle, FactualSolver, SolverStateState},
+        index::RunePosition,
+        rule::ActivationRuleKindHelpers,
+        test_support::{activation, sample_runes},
+        RuneLock,
+    };
+
+    use super::Inconsistency;
+
+    #[test]
+    fn test_mismarked_states_are_found_and_repaired() {
+        let lock = RuneLock::with_rules(
+            sample_runes(),
+            vec![(1, 2).alwanese(), (2, 3).increase_santor()],
+        );
+        let mut solver = FactualSolver::new(lock);
+        let root = solver.current();
+        let first = solver.assume(activation(1), RunePosition::new(0));
+        //#2 has to be next to #1, so this contradicts.
+        let contradicted = solver.assume(activation(2), RunePosition::new(9));
+        solver.set_current(root);
+        solver.assume(activation(1), RunePosition::new(6));
+        assert!(matches!(
+            solver.states[contradicted].state,
+            SolverStateState::Contradicts(_)
+        ));
+        assert!(solver.fsck(false).is_empty());
+
+        solver.states[first].state = SolverStateState::Contradicts(FactHandle::from_raw(0));
+        solver.states[contradicted].state = SolverStateState::Unexplored;
+        let findings = solver.fsck(true);
+        assert_eq!(findings.len(), 2);
+        assert!(matches!(
+            findings[0].inconsistency,
+            Inconsistency::FalseContradiction { .. }
+        ));
+        assert!(matches!(
+            findings[1].inconsistency,
+            Inconsistency::MissedContradiction { .. }
+        ));
+        assert!(findings.iter().all(|it| it.repaired));
+        assert!(solver.fsck(false).is_empty());
+    }
+}
@@ -0,0 +1,250 @@
+#[cfg(feature = "ndarray")]
+use ndarray::Array2;
+use serde::{Deserialize, Serialize};
+
+use super::fact_db::FactHandle;
+
+///The two dimensions of the fact grid. A lane along one axis fixes an index on that axis and
+///walks over every index of the other one.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GridAxis {
+    Position,
+    Activation,
+}
+
+///Lookup of the fact currently describing each (position, activation) cell.
+///
+///Backed by an `ndarray` when the feature of the same name is enabled, and by a flat,
+///row-major `Vec` otherwise. Either way it is stored as [`GridData`], so a session saved with one
+///backend loads with the other.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(into = "GridData", try_from = "StoredGrid")]
+pub struct FactGrid {
+    #[cfg(feature = "ndarray")]
+    cells: Array2<Option<FactHandle>>,
+    #[cfg(not(feature = "ndarray"))]
+    cells: Vec<Option<FactHandle>>,
+    positions: usize,
+    activations: usize,
+}
+
+///How a [`FactGrid`] is stored: its cells row by row, a row for each position.
+#[derive(Serialize, Deserialize)]
+struct GridData {
+    rows: usize,
+    cols: usize,
+    data: Vec<Option<FactHandle>>,
+}
+
+///The cells of a grid saved before it was stored as [`GridData`], as the backend wrote them.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum LegacyCells {
+    Array {
+        dim: (usize, usize),
+        data: Vec<Option<FactHandle>>,
+    },
+    Flat(Vec<Option<FactHandle>>),
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum StoredGrid {
+    Grid(GridData),
+    Legacy {
+        cells: LegacyCells,
+        positions: usize,
+        activations: usize,
+    },
+}
+
+impl From<FactGrid> for GridData {
+    fn from(grid: FactGrid) -> Self {
+        Self {
+            rows: grid.positions,
+            cols: grid.activations,
+            data: grid.indexed_iter().map(|(_, it)| it).collect(),
+        }
+    }
+}
+
+impl TryFrom<StoredGrid> for FactGrid {
+    type Error = String;
+
+    fn try_from(stored: StoredGrid) -> Result<Self, Self::Error> {
+        let (rows, cols, data) = match stored {
+            StoredGrid::Grid(GridData { rows, cols, data }) => (rows, cols, data),
+            StoredGrid::Legacy {
+                cells: LegacyCells::Array { dim, data },
+                positions,
+                activations,
+            } if dim == (positions, activations) => (positions, activations, data),
+            StoredGrid::Legacy {
+                cells: LegacyCells::Array { dim, .. },
+                positions,
+                activations,
+            } => {
+                return Err(format!(
+                    "The cells are {}x{}, but the grid is {}x{}",
+                    dim.0, dim.1, positions, activations
+                ))
+            }
+            StoredGrid::Legacy {
+                cells: LegacyCells::Flat(data),
+                positions,
+                activations,
+            } => (positions, activations, data),
+        };
+        if data.len() != rows * cols {
+            return Err(format!(
+                "A {}x{} grid needs {} cells, but {} are stored",
+                rows,
+                cols,
+                rows * cols,
+                data.len()
+            ));
+        }
+        let mut grid = FactGrid::new(rows, cols);
+        for (index, fact) in data.into_iter().enumerate() {
+            *grid.get_mut(index / cols, index % cols) = fact;
+        }
+        Ok(grid)
+    }
+}
+
+impl FactGrid {
+    pub fn new(positions: usize, activations: usize) -> Self {
+        Self {
+            #[cfg(feature = "ndarray")]
+            cells: Array2::from_elem((positions, activations), None),
+            #[cfg(not(feature = "ndarray"))]
+            cells: vec![None; positions * activations],
+            positions,
+            activations,
+        }
+    }
+
+    ///The bytes the cells take up on the heap.
+    pub fn heap_size(&self) -> usize {
+        self.positions * self.activations * std::mem::size_of::<Option<FactHandle>>()
+    }
+
+    pub fn len_of(&self, axis: GridAxis) -> usize {
+        match axis {
+            GridAxis::Position => self.positions,
+            GridAxis::Activation => self.activations,
+        }
+    }
+
+    #[cfg(feature = "ndarray")]
+    pub fn get(&self, position: usize, activation: usize) -> Option<FactHandle> {
+        self.cells[[position, activation]]
+    }
+
+    #[cfg(not(feature = "ndarray"))]
+    pub fn get(&self, position: usize, activation: usize) -> Option<FactHandle> {
+        self.cells[position * self.activations + activation]
+    }
+
+    #[cfg(feature = "ndarray")]
+    pub fn get_mut(&mut self, position: usize, activation: usize) -> &mut Option<FactHandle> {
+        &mut self.cells[[position, activation]]
+    }
+
+    #[cfg(not(feature = "ndarray"))]
+    pub fn get_mut(&mut self, position: usize, activation: usize) -> &mut Option<FactHandle> {
+        &mut self.cells[position * self.activations + activation]
+    }
+
+    ///Fixes `index` on `axis` and yields the cells along the other axis.
+    pub fn lane(
+        &self,
+        axis: GridAxis,
+        index: usize,
+    ) -> impl Iterator<Item = Option<FactHandle>> + '_ {
+        let count = match axis {
+            GridAxis::Position => self.activations,
+            GridAxis::Activation => self.positions,
+        };
+        (0..count).map(move |it| match axis {
+            GridAxis::Position => self.get(index, it),
+            GridAxis::Activation => self.get(it, index),
+        })
+    }
+
+    ///Yields every cell as `((position, activation), fact)`.
+    pub fn indexed_iter(&self) -> impl Iterator<Item = ((usize, usize), Option<FactHandle>)> + '_ {
+        (0..self.positions).flat_map(move |position| {
+            (0..self.activations)
+                .map(move |activation| ((position, activation), self.get(position, activation)))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{activation::Activation, fact_solver::view::View, index::RunePosition};
+
+    use crate::fact_solver::fact_db::FactHandle;
+
+    use super::{FactGrid, GridAxis};
+
+    #[test]
+    fn test_lanes() {
+        //We have 3 runes and 9 activations.
+        let grid = FactGrid::new(3, 9);
+
+        //Lookup Activations for Rune 1 (Should be 9 long)
+        assert_eq!(grid.lane(RunePosition::axis(), 1).count(), 9);
+
+        //Lookup Runes for Activation 1 (Should be 3 long)
+        assert_eq!(grid.lane(Activation::axis(), 1).count(), 3);
+
+        assert_eq!(grid.len_of(GridAxis::Position), 3);
+        assert_eq!(grid.len_of(GridAxis::Activation), 9);
+    }
+
+    #[test]
+    fn test_indexed_iter() {
+        let mut grid = FactGrid::new(3, 9);
+        *grid.get_mut(2, 7) = Some(FactHandle::from_raw(4));
+
+        let cells: Vec<_> = grid.indexed_iter().collect();
+        assert_eq!(cells.len(), 27);
+        assert_eq!(cells[2 * 9 + 7].0, (2, 7));
+        assert!(cells[2 * 9 + 7].1.is_some());
+        assert_eq!(
+            grid.lane(GridAxis::Activation, 7)
+                .filter(|it| it.is_some())
+                .count(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_serialization_is_the_same_for_every_backend() {
+        let mut grid = FactGrid::new(2, 3);
+        *grid.get_mut(1, 2) = Some(FactHandle::from_raw(4));
+        *grid.get_mut(0, 1) = Some(FactHandle::from_raw(7));
+
+        let text = serde_json::to_string(&grid).unwrap();
+        assert_eq!(
+            text,
+            r#"{"rows":2,"cols":3,"data":[null,7,null,null,null,4]}"#
+        );
+        let loaded: FactGrid = serde_json::from_str(&text).unwrap();
+        assert_eq!(loaded.len_of(GridAxis::Position), 2);
+        assert_eq!(loaded.len_of(GridAxis::Activation), 3);
+        assert!(loaded.indexed_iter().eq(grid.indexed_iter()));
+
+        //Grids saved by either backend before keep loading.
+        for legacy in [
+            r#"{"cells":{"v":1,"dim":[2,3],"data":[null,7,null,null,null,4]},"positions":2,"activations":3}"#,
+            r#"{"cells":[null,7,null,null,null,4],"positions":2,"activations":3}"#,
+        ] {
+            let loaded: FactGrid = serde_json::from_str(legacy).unwrap();
+            assert!(loaded.indexed_iter().eq(grid.indexed_iter()));
+        }
+        assert!(serde_json::from_str::<FactGrid>(r#"{"rows":2,"cols":3,"data":[null]}"#).is_err());
+    }
+}
@@ -0,0 +1,233 @@
+//! Eliminations tracked outside of the solver, like in a shared spreadsheet. Every row of a CSV
+//! file `position,activation,eliminated|assigned,note` becomes a fact of the root state, with the
+//! row as its reason.
+
+use std::fmt::{Display, Formatter};
+
+use thiserror::Error;
+
+use crate::{
+    activation::{Activation, HumanActivation},
+    index::RunePosition,
+    size::LockSize,
+};
+
+use super::{
+    fact_db::FactError::Contradiction, Fact, FactKind, FactReason, FactualSolver, SolverStateState,
+};
+
+#[derive(Debug, Error)]
+pub enum CsvError {
+    #[error("Row {row}: expected position, activation, eliminated or assigned, and a note")]
+    MissingColumns { row: usize },
+    #[error("Row {row}: '{text}' is not a position from 0 to {}", size.positions() - 1)]
+    Position {
+        row: usize,
+        text: String,
+        size: LockSize,
+    },
+    #[error("Row {row}: '{text}' is not an activation from 1 to {}", size.activations())]
+    Activation {
+        row: usize,
+        text: String,
+        size: LockSize,
+    },
+    #[error("Row {row}: '{text}' is neither 'eliminated' nor 'assigned'")]
+    Kind { row: usize, text: String },
+}
+
+pub struct ExternalFact {
+    ///The line of the file, counting from 1.
+    pub row: usize,
+    pub position: RunePosition,
+    pub activation: Activation,
+    ///Whether the activation is placed on the position, instead of eliminated from it.
+    pub assigned: bool,
+    pub note: String,
+}
+
+pub enum ImportOutcome {
+    Imported,
+    AlreadyKnown,
+    ///The row contradicts what the root state knows, and was left out.
+    Conflict(String),
+}
+
+impl Display for ExternalFact {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let verb = match self.assigned {
+            true => "must be on",
+            false => "cannot be on",
+        };
+        write!(f, "{} {} {}", self.activation, verb, self.position)?;
+        if !self.note.is_empty() {
+            write!(f, " ({})", self.note)?;
+        }
+        Ok(())
+    }
+}
+
+///Reads the rows of a CSV file. A first row that doesn't start with a number is taken as a
+///header and skipped, like empty rows. Positions and activations have to be on a lock of `size`.
+pub fn parse_csv(text: &str, size: LockSize) -> Result<Vec<ExternalFact>, CsvError> {
+    let mut facts = Vec::new();
+    for (index, line) in text.lines().enumerate() {
+        let row = index + 1;
+        let mut columns = line.splitn(4, ',').map(|it| it.trim().trim_matches('"'));
+        let (Some(position), Some(activation), Some(kind)) =
+            (columns.next(), columns.next(), columns.next())
+        else {
+            if line.trim().is_empty() {
+                continue;
+            }
+            return Err(CsvError::MissingColumns { row });
+        };
+        let number = position.parse::<usize>();
+        if number.is_err() && row == 1 {
+            continue;
+        }
+        let Some(parsed_position) = number.ok().and_then(|it| size.position(it)) else {
+            return Err(CsvError::Position {
+                row,
+                text: position.to_string(),
+                size,
+            });
+        };
+        let activation = activation
+            .parse::<HumanActivation>()
+            .ok()
+            .map(Activation::from)
+            .filter(|it| size.contains(*it))
+            .ok_or_else(|| CsvError::Activation {
+                row,
+                text: activation.to_string(),
+                size,
+            })?;
+        let assigned = match kind.to_lowercase().as_str() {
+            "eliminated" => false,
+            "assigned" => true,
+            _ => {
+                return Err(CsvError::Kind {
+                    row,
+                    text: kind.to_string(),
+                })
+            }
+        };
+        facts.push(ExternalFact {
+            row,
+            position: parsed_position,
+            activation,
+            assigned,
+            note: columns.next().unwrap_or_default().to_string(),
+        });
+    }
+    Ok(facts)
+}
+
+impl FactualSolver {
+    ///Adds the facts to the root state, so they hold in every state like the domains of the lock,
+    ///and rebuilds the tree. Rows that contradict the root state are left out instead of
+    ///overwriting what it knows.
+    pub fn import(&mut self, facts: &[ExternalFact]) -> Vec<ImportOutcome> {
+        let root = self.states.root();
+        let known = self.states[root].facts.fact_count();
+        let outcomes = facts
+            .iter()
+            .map(|external| {
+                let state = &mut self.states[root];
+                if let SolverStateState::Contradicts(reason) = state.state {
+                    return ImportOutcome::Conflict(format!(
+                        "the root state is already contradicted by {}",
+                        reason
+                    ));
+                }
+                let kind = match external.assigned {
+                    true => FactKind::ActivationMustBeOn,
+                    false => FactKind::ActivationCannotBeOn,
+                };
+                let known = state
+                    .facts
+                    .handle_at(external.position, external.activation)
+                    .and_then(|it| Some((it, state.facts.get(it)?.kind)));
+                match known {
+                    Some((_, known)) if known == kind => return ImportOutcome::AlreadyKnown,
+                    Some((handle, _)) => {
+                        return ImportOutcome::Conflict(format!("{} says otherwise", handle))
+                    }
+                    None => {}
+                }
+                let mut facts = state.facts.clone();
+                let note = facts.add_note(format!("row {}: {}", external.row, external));
+                let integrated = facts.integrate_and_consolidate(
+                    Fact {
+                        kind,
+                        activation: external.activation,
+                        position: external.position,
+                        reasons: vec![FactReason::External(note)],
+                        range: Vec::new(),
+                    },
+                    &self.lock,
+                );
+                match integrated {
+                    Ok(()) => {
+                        state.facts = facts;
+                        ImportOutcome::Imported
+                    }
+                    Err(Contradiction(reason)) => {
+                        ImportOutcome::Conflict(format!("it leads to the contradiction {}", reason))
+                    }
+                }
+            })
+            .collect();
+        self.publish(root, known);
+        self.reroot();
+        outcomes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        fact_solver::FactualSolver,
+        index::RunePosition,
+        rule::ActivationRuleKindHelpers,
+        size::LockSize,
+        test_support::{activation, sample_runes},
+        RuneLock,
+    };
+
+    use super::{parse_csv, CsvError, ImportOutcome};
+
+    #[test]
+    fn test_conflicting_rows_are_reported() {
+        let lock = RuneLock::with_rules(sample_runes(), vec![(1, 2).alwanese()]);
+        let mut solver = FactualSolver::new(lock);
+        let rows = parse_csv(
+            "position,activation,kind,note\n0,1,assigned,from the session\n0,1,assigned,again\n0,2,assigned,typo\n5,3,eliminated,\n",
+            LockSize::STANDARD,
+        )
+        .unwrap();
+        assert_eq!(rows.len(), 4);
+
+        let outcomes = solver.import(&rows);
+        assert!(matches!(outcomes[0], ImportOutcome::Imported));
+        assert!(matches!(outcomes[1], ImportOutcome::AlreadyKnown));
+        assert!(matches!(outcomes[2], ImportOutcome::Conflict(_)));
+        assert!(matches!(outcomes[3], ImportOutcome::Imported));
+        let fixed = solver.fixed_assignment().unwrap();
+        assert_eq!(fixed[RunePosition::new(0)], Some(activation(1)));
+
+        assert!(parse_csv("0,13,eliminated,\n", LockSize::STANDARD).is_err());
+        assert!(parse_csv("0,1\n", LockSize::STANDARD).is_err());
+        let small = LockSize::new(8, 6).unwrap();
+        assert!(parse_csv("7,6,eliminated,\n", small).is_ok());
+        assert!(matches!(
+            parse_csv("8,1,eliminated,\n", small),
+            Err(CsvError::Position { .. })
+        ));
+        assert!(matches!(
+            parse_csv("0,7,eliminated,\n", small),
+            Err(CsvError::Activation { .. })
+        ));
+    }
+}
@@ -0,0 +1,215 @@
+//! The knowledge about a single rule relating two activations, as the grid of where the first and
+//! the second activation can be placed relative to each other.
+
+use std::fmt::{Display, Formatter};
+
+use itertools::Itertools;
+use thiserror::Error;
+
+use crate::{activation::Activation, index::RunePosition, rule::RuleKind, terminal};
+
+use super::{FactKind, FactualSolver};
+
+#[derive(Debug, Error)]
+pub enum RuleMatrixError {
+    #[error("There is no rule {0}")]
+    UnknownRule(usize),
+    #[error("Rule {0} ('{1}') does not relate two activations")]
+    NotBinary(usize, RuleKind),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PairState {
+    ///The rule allows the pair, and the facts don't rule it out either.
+    Allowed,
+    ///The rule itself rules the pair out, regardless of any facts.
+    Forbidden,
+    ///The rule allows the pair, but the facts of the state rule out one of its placements.
+    Eliminated,
+}
+
+pub struct RuleMatrix {
+    pub rule: usize,
+    pub kind: RuleKind,
+    pub first: Activation,
+    pub second: Activation,
+    ///Indexed by the position of the first activation, then the position of the second one.
+    pub pairs: Vec<Vec<PairState>>,
+}
+
+impl RuleMatrix {
+    ///How many pairs the matrix has, allowed or not.
+    pub fn size(&self) -> usize {
+        self.pairs.len() * self.pairs.len()
+    }
+
+    pub fn allowed(&self) -> usize {
+        self.pairs
+            .iter()
+            .flatten()
+            .filter(|it| **it == PairState::Allowed)
+            .count()
+    }
+}
+
+impl FactualSolver {
+    ///Where the rule lets its two activations be, given the current state.
+    pub fn rule_matrix(&self, rule: usize) -> Result<RuleMatrix, RuleMatrixError> {
+        let kind = self
+            .lock
+            .rules
+            .get(rule)
+            .ok_or(RuleMatrixError::UnknownRule(rule))?
+            .clone();
+        let (first, second) = kind
+            .activation_pair()
+            .ok_or_else(|| RuleMatrixError::NotBinary(rule, kind.clone()))?;
+        let facts = &self.states[self.current].facts;
+        let excluded = |position: RunePosition, activation: Activation| {
+            facts
+                .handle_at(position, activation)
+                .and_then(|it| facts.get(it))
+                .is_some_and(|it| it.kind != FactKind::ActivationMustBeOn)
+        };
+        let positions = self.lock.size().positions();
+        let mut pairs = vec![vec![PairState::Allowed; positions]; positions];
+        for (first_position, row) in pairs.iter_mut().enumerate() {
+            let first_position = RunePosition::new(first_position);
+            for (second_position, pair) in row.iter_mut().enumerate() {
+                let second_position = RunePosition::new(second_position);
+                *pair = if !self.lock.allows_pair(rule, first_position, second_position) {
+                    PairState::Forbidden
+                } else if excluded(first_position, first) || excluded(second_position, second) {
+                    PairState::Eliminated
+                } else {
+                    PairState::Allowed
+                };
+            }
+        }
+        Ok(RuleMatrix {
+            rule,
+            kind,
+            first,
+            second,
+            pairs,
+        })
+    }
+}
+
+impl Display for RuleMatrix {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Rule {}: '{}'", self.rule, self.kind)?;
+        if terminal::accessible() {
+            for (position, row) in self.pairs.iter().enumerate() {
+                let on = |state| {
+                    let positions = (0..row.len()).filter(|it| row[*it] == state).join(", ");
+                    match positions.is_empty() {
+                        true => "none".to_string(),
+                        false => positions,
+                    }
+                };
+                writeln!(
+                    f,
+                    "{} on position {}: {} allowed on {}; eliminated by facts on {}; forbidden by the rule on {}.",
+                    self.first,
+                    position,
+                    self.second,
+                    on(PairState::Allowed),
+                    on(PairState::Eliminated),
+                    on(PairState::Forbidden)
+                )?;
+            }
+            return write!(f, "{} of {} pairs allowed.", self.allowed(), self.size());
+        }
+        writeln!(
+            f,
+            "Rows: position of {}, columns: position of {}. o allowed, - eliminated by facts, x forbidden by the rule.",
+            self.first, self.second
+        )?;
+        write!(f, "   ")?;
+        for position in 0..self.pairs.len() {
+            write!(f, "{:>3}", position)?;
+        }
+        writeln!(f)?;
+        for (position, row) in self.pairs.iter().enumerate() {
+            write!(f, "{:>3}", position)?;
+            for pair in row {
+                match pair {
+                    PairState::Allowed => write!(f, "{:>3}", "o")?,
+                    PairState::Eliminated => write!(f, "  {}", terminal::dimmed("-"))?,
+                    PairState::Forbidden => write!(f, "  {}", terminal::dimmed("x"))?,
+                }
+            }
+            writeln!(f)?;
+        }
+        write!(f, "{} of {} pairs allowed.", self.allowed(), self.size())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        fact_solver::FactualSolver,
+        index::RunePosition,
+        rule::ActivationRuleKindHelpers,
+        rune::Rune,
+        test_support::{activation, sample_runes},
+        RuneLock,
+    };
+
+    use super::PairState;
+
+    #[test]
+    fn test_matrix_follows_the_facts() {
+        let lock = RuneLock::with_rules(sample_runes(), vec![(1, 2).alwanese()]);
+        let mut solver = FactualSolver::new(lock);
+        let before = solver.rule_matrix(0).unwrap();
+        for position in 0..12 {
+            assert_eq!(before.pairs[position][position], PairState::Forbidden);
+        }
+        assert!(before.allowed() > 0);
+
+        solver.assume(activation(1), RunePosition::new(0));
+        let after = solver.rule_matrix(0).unwrap();
+        assert!(after.allowed() > 0);
+        assert!(after.allowed() < before.allowed());
+        assert!(after.pairs[1..]
+            .iter()
+            .flatten()
+            .all(|it| *it != PairState::Allowed));
+        assert!(solver.rule_matrix(1).is_err());
+    }
+
+    #[test]
+    fn test_pair_table_agrees_with_the_rules() {
+        let mut lock = RuneLock::with_rules(
+            sample_runes(),
+            vec![
+                (1, 2).alwanese(),
+                (2, 3).different_runes(),
+                (3, 4).antakian_twins(),
+                (4, 5).increase_santor(),
+            ],
+        );
+        let agrees = |lock: &RuneLock| {
+            for (rule, kind) in lock.rules().iter().enumerate() {
+                let (first, second) = kind.activation_pair().unwrap();
+                for a in (0..12).map(RunePosition::new) {
+                    for b in (0..12).map(RunePosition::new) {
+                        assert_eq!(
+                            lock.allows_pair(rule, a, b),
+                            kind.validate_tuple(lock, (a, first), (b, second)).is_ok()
+                        );
+                    }
+                }
+            }
+        };
+        agrees(&lock);
+        assert!(lock.allows_pair(1, RunePosition::new(0), RunePosition::new(1)));
+
+        //The table is built anew once a rune changes.
+        lock.set_rune(RunePosition::new(1), Rune::new(0));
+        agrees(&lock);
+        assert!(!lock.allows_pair(1, RunePosition::new(0), RunePosition::new(1)));
+    }
+}
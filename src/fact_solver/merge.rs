@@ -0,0 +1,344 @@
+//! Merging a collaborator's session into this one. Where the same candidate is ruled out in one
+//! session and assumed without a contradiction in the other, usually because the sessions don't
+//! apply the same rules, the conflict is listed with the proofs of both sides, and resolved by
+//! picking the side to trust. Every decision is kept in the session, with the session it was
+//! merged from.
+//!
+//! Only the root states and the assumptions made in them are compared: deeper states rest on
+//! assumptions the other session didn't necessarily make.
+
+use std::fmt::{Display, Formatter};
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::{activation::Activation, index::RunePosition, RuneLock};
+
+use super::{
+    fact_db::{FactDb, FactError::Contradiction},
+    Fact, FactKind, FactReason, FactualSolver, SolverAction, SolverStateState,
+};
+
+///How many lines of a proof are shown for a conflict.
+const PROOF_LINES: usize = 12;
+///How many steps below the ruled out candidate a proof is expanded.
+const PROOF_DEPTH: usize = 3;
+
+#[derive(Debug, Error)]
+pub enum MergeError {
+    #[error("The sessions are about different runes or lock sizes, there is nothing to merge")]
+    DifferentRunes,
+    #[error("{0} is not a conflict of the last merge")]
+    UnknownConflict(usize),
+    #[error("Ruling out {activation} on {position} contradicts this session by {reason}")]
+    Contradiction {
+        position: RunePosition,
+        activation: Activation,
+        reason: String,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Side {
+    Ours,
+    Theirs,
+}
+
+impl Display for Side {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Side::Ours => write!(f, "this session"),
+            Side::Theirs => write!(f, "the merged session"),
+        }
+    }
+}
+
+///What a session knows about a candidate from its root state.
+enum Verdict {
+    ///The root state rules the candidate out, or assuming it contradicts.
+    RuledOut {
+        proof: Vec<String>,
+        rules: Vec<usize>,
+    },
+    ///Assuming the candidate led to a state that doesn't contradict.
+    Open(String),
+}
+
+pub struct Conflict {
+    pub position: RunePosition,
+    pub activation: Activation,
+    ///The side ruling the candidate out, the other one assumed it without a contradiction.
+    pub ruled_out_by: Side,
+    ///The rules the side ruling the candidate out rests on, by their index in its lock.
+    pub rules: Vec<usize>,
+    pub ours: Vec<String>,
+    pub theirs: Vec<String>,
+}
+
+pub struct Merge {
+    ///Where the merged session came from.
+    pub source: String,
+    ///The rules only this session applies, and only the merged one, in the syntax of the command
+    ///line.
+    pub only_ours: Vec<String>,
+    pub only_theirs: Vec<String>,
+    pub conflicts: Vec<Conflict>,
+}
+
+///How a conflict was resolved, kept with the session.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MergeDecision {
+    pub position: RunePosition,
+    pub activation: Activation,
+    pub trusted: Side,
+    pub ruled_out_by: Side,
+    ///The session merged.
+    pub source: String,
+    ///The first line of the proof ruling the candidate out.
+    pub claim: String,
+}
+
+impl Display for Conflict {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let open = match self.ruled_out_by {
+            Side::Ours => Side::Theirs,
+            Side::Theirs => Side::Ours,
+        };
+        writeln!(
+            f,
+            "{} on {} is ruled out by {}, but {} assumed it without a contradiction.",
+            self.activation, self.position, self.ruled_out_by, open
+        )?;
+        for (side, proof) in [(Side::Ours, &self.ours), (Side::Theirs, &self.theirs)] {
+            writeln!(f, "  In {}:", side)?;
+            for line in proof {
+                writeln!(f, "    {}", line)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Display for MergeDecision {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let verdict = match self.trusted == self.ruled_out_by {
+            true => "ruled out",
+            false => "open",
+        };
+        write!(
+            f,
+            "Merged {}: {} on {} is {}, trusting {}. Ruled out by {} with {}",
+            self.source,
+            self.activation,
+            self.position,
+            verdict,
+            self.trusted,
+            self.ruled_out_by,
+            self.claim
+        )
+    }
+}
+
+fn rule_syntax(lock: &RuneLock) -> Vec<String> {
+    lock.active_rules().map(|(_, rule)| rule.syntax()).collect()
+}
+
+impl FactualSolver {
+    ///The conflicts between this session and the other one, about the same runes.
+    pub fn merge(&self, other: &FactualSolver, source: &str) -> Result<Merge, MergeError> {
+        if self.lock.runes() != other.lock.runes() || self.lock.size() != other.lock.size() {
+            return Err(MergeError::DifferentRunes);
+        }
+        let conflicts = self
+            .lock
+            .size()
+            .cells()
+            .filter_map(|(position, activation)| {
+                let (ruled_out_by, rules, ours, theirs) = match (
+                    self.verdict(position, activation)?,
+                    other.verdict(position, activation)?,
+                ) {
+                    (Verdict::RuledOut { proof, rules }, Verdict::Open(open)) => {
+                        (Side::Ours, rules, proof, vec![open])
+                    }
+                    (Verdict::Open(open), Verdict::RuledOut { proof, rules }) => {
+                        (Side::Theirs, rules, vec![open], proof)
+                    }
+                    _ => return None,
+                };
+                Some(Conflict {
+                    position,
+                    activation,
+                    ruled_out_by,
+                    rules,
+                    ours,
+                    theirs,
+                })
+            })
+            .collect();
+        let (ours, theirs) = (rule_syntax(&self.lock), rule_syntax(&other.lock));
+        Ok(Merge {
+            source: source.to_string(),
+            only_ours: ours
+                .iter()
+                .filter(|it| !theirs.contains(it))
+                .cloned()
+                .collect(),
+            only_theirs: theirs
+                .iter()
+                .filter(|it| !ours.contains(it))
+                .cloned()
+                .collect(),
+            conflicts,
+        })
+    }
+
+    fn verdict(&self, position: RunePosition, activation: Activation) -> Option<Verdict> {
+        let root = self.states.root();
+        let facts = &self.states[root].facts;
+        let ruled_out = |facts: &FactDb, handle| Verdict::RuledOut {
+            proof: facts.explanation(handle, &self.lock, PROOF_LINES, PROOF_DEPTH, false),
+            rules: facts.rules_of(handle).into_iter().collect(),
+        };
+        if let Some(handle) = facts.handle_at(position, activation) {
+            if facts.get(handle)?.kind == FactKind::ActivationCannotBeOn {
+                return Some(ruled_out(facts, handle));
+            }
+        }
+        let child = self.states.children_of(root).find(|it| {
+            matches!(
+                self.states[*it].action,
+                SolverAction::Assume { position: p, activation: a } if p == position && a == activation
+            )
+        })?;
+        match self.states[child].state {
+            SolverStateState::Contradicts(fact) => Some(ruled_out(&self.states[child].facts, fact)),
+            _ => Some(Verdict::Open(format!(
+                "Assumed in state {}, which came to: {}",
+                child,
+                self.outcome(child)
+            ))),
+        }
+    }
+
+    ///Resolves a conflict of the merge by trusting one side. Trusting the merged session where it
+    ///ruled the candidate out adds that as a fact of the root state, citing the merge. Every other
+    ///decision leaves the states as they are, as this session can't unlearn what its own rules
+    ///prove; it is only recorded.
+    pub fn resolve(
+        &mut self,
+        merge: &Merge,
+        conflict: usize,
+        trusted: Side,
+    ) -> Result<&MergeDecision, MergeError> {
+        let conflict = conflict
+            .checked_sub(1)
+            .and_then(|it| merge.conflicts.get(it))
+            .ok_or(MergeError::UnknownConflict(conflict))?;
+        let proof = match conflict.ruled_out_by {
+            Side::Ours => &conflict.ours,
+            Side::Theirs => &conflict.theirs,
+        };
+        let decision = MergeDecision {
+            position: conflict.position,
+            activation: conflict.activation,
+            trusted,
+            ruled_out_by: conflict.ruled_out_by,
+            source: merge.source.clone(),
+            claim: proof.first().cloned().unwrap_or_default(),
+        };
+        let root = self.states.root();
+        let known = self.states[root].facts.fact_count();
+        let adopt = trusted == Side::Theirs
+            && conflict.ruled_out_by == Side::Theirs
+            && self.states[root]
+                .facts
+                .handle_at(conflict.position, conflict.activation)
+                .is_none();
+        if adopt {
+            let mut facts = self.states[root].facts.clone();
+            let note = facts.add_note(format!("merged from {}: {}", merge.source, decision.claim));
+            facts
+                .integrate_and_consolidate(
+                    Fact {
+                        kind: FactKind::ActivationCannotBeOn,
+                        activation: conflict.activation,
+                        position: conflict.position,
+                        reasons: vec![FactReason::External(note)],
+                        range: Vec::new(),
+                    },
+                    &self.lock,
+                )
+                .map_err(|Contradiction(reason)| MergeError::Contradiction {
+                    position: conflict.position,
+                    activation: conflict.activation,
+                    reason: reason.to_string(),
+                })?;
+            self.states[root].facts = facts;
+            self.publish(root, known);
+            self.reroot();
+        }
+        self.merges.push(decision);
+        Ok(self.merges.last().unwrap())
+    }
+
+    ///The conflicts resolved so far, in the order they were.
+    pub fn merge_decisions(&self) -> &[MergeDecision] {
+        &self.merges
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        fact_solver::FactualSolver,
+        index::RunePosition,
+        rule::ActivationRuleKindHelpers,
+        test_support::{activation, sample_runes},
+        RuneLock,
+    };
+
+    use super::{MergeError, Side};
+
+    #[test]
+    fn test_conflicts_are_resolved_with_provenance() {
+        let runes = sample_runes();
+        let mut ours = FactualSolver::new(RuneLock::with_rules(runes, vec![]));
+        let mut theirs =
+            FactualSolver::new(RuneLock::with_rules(runes, vec![(5, 6).increase_santor()]));
+        //Only their rule rules out #5 on position 0.
+        ours.assume(activation(5), RunePosition::new(0));
+        theirs.assume(activation(5), RunePosition::new(0));
+        ours.set_current(ours.root());
+
+        let merge = ours.merge(&theirs, "theirs.json").unwrap();
+        assert_eq!(merge.only_theirs, vec![(5, 6).increase_santor().syntax()]);
+        assert!(merge.only_ours.is_empty());
+        let conflict = merge
+            .conflicts
+            .iter()
+            .position(|it| it.position == RunePosition::new(0) && it.activation == activation(5))
+            .unwrap();
+        assert_eq!(merge.conflicts[conflict].ruled_out_by, Side::Theirs);
+
+        assert!(matches!(
+            ours.resolve(&merge, 0, Side::Ours),
+            Err(MergeError::UnknownConflict(0))
+        ));
+        let decision = ours.resolve(&merge, conflict + 1, Side::Theirs).unwrap();
+        assert_eq!(decision.source, "theirs.json");
+        let facts = &ours.states[ours.root()].facts;
+        let handle = facts
+            .handle_at(RunePosition::new(0), activation(5))
+            .unwrap();
+        assert!(facts.note(0).starts_with("merged from theirs.json"));
+        assert!(ours.explain(handle, 40, 40, false).contains("merged from"));
+        assert_eq!(ours.merge_decisions().len(), 1);
+        assert!(ours
+            .merge(&theirs, "theirs.json")
+            .unwrap()
+            .conflicts
+            .iter()
+            .all(|it| it.position != RunePosition::new(0) || it.activation != activation(5)));
+    }
+}
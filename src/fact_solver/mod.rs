@@ -1,48 +1,104 @@
+pub mod advisor;
+pub mod assertion;
 pub mod assumption_tree;
+pub mod audit;
+pub mod auto;
+pub mod certificate;
+pub mod clusters;
+pub mod dot;
+pub mod edit;
 mod explainer;
 pub mod fact_db;
+pub mod fsck;
+pub mod grid;
+pub mod import;
+pub mod matrix;
+pub mod merge;
+pub mod pattern;
+pub mod proof;
+pub mod retract;
+pub mod sampling;
+pub mod search;
+pub mod session;
+pub mod stats;
+pub mod subscription;
+pub mod uniqueness;
+pub mod verify;
 pub mod view;
 
 use std::{
     cmp::Ordering,
+    collections::HashMap,
     fmt::{Debug, Display, Formatter},
 };
 
-use log::debug;
-use slotmap::{new_key_type, SlotMap};
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
 
 use crate::{
-    activation::Activation, fact_solver::explainer::explain_fact, index::RunePosition, RuneLock,
+    activation::Activation,
+    assignment::{Assignment, AssignmentError},
+    fact_solver::explainer::explain_fact,
+    index::RunePosition,
+    jobs::CancelToken,
+    kind_stats::KindStats,
+    logging::debug,
+    outcome::Outcome,
+    rune::Rune,
+    size::LockSize,
+    terminal, RuleKind, RuneLock,
 };
 
 use self::{
-    assumption_tree::{AssumptionTree, AssumptionTreeError, AssumptionTreeNodeHandle},
+    advisor::{advise, advise_cancellable, Advice},
+    assumption_tree::{AssumptionTree, AssumptionTreeError, AssumptionTreeNodeHandle, NodeId},
+    edit::LockEdit,
     fact_db::{FactDb, FactError::Contradiction, FactHandle},
+    merge::MergeDecision,
+    subscription::Subscribers,
     view::{ChooseView, View},
 };
 
+#[derive(Debug, Error)]
+pub enum StageError {
+    #[error("Stage {0} was already reached, stages can only advance")]
+    AlreadyReached(usize),
+}
+
 #[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
 pub struct DebugInfo {
     pub origin: &'static str,
 }
 
-#[derive(PartialEq, Eq, Debug, Copy, Clone, Hash)]
+///Debug info isn't saved with a session, facts of a loaded session get this one.
+impl Default for DebugInfo {
+    fn default() -> Self {
+        Self { origin: "loaded" }
+    }
+}
+
+#[derive(PartialEq, Eq, Debug, Copy, Clone, Hash, Serialize, Deserialize)]
 pub enum ContradictionKind {
     ContradictingRequirements,
     NoOptionsLeft,
 }
 
-#[derive(PartialEq, Eq, Debug, Copy, Clone, Hash)]
+#[derive(PartialEq, Eq, Debug, Copy, Clone, Hash, Serialize, Deserialize)]
 pub enum FactKind {
     Contradiction(ContradictionKind),
     ActivationCannotBeOn,
     ActivationMustBeOn,
 }
 
-#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq, Serialize, Deserialize)]
 pub enum FactReason {
-    Fact(FactHandle, DebugInfo),
+    Fact(FactHandle, #[serde(skip)] DebugInfo),
     Rule(usize),
+    ///One of the domains of the lock, which hold in every state.
+    Given(usize),
+    ///Imported from outside of the solver, described by the note of the database with this index.
+    External(usize),
     Assumption,
 }
 
@@ -56,23 +112,98 @@ impl Ord for FactReason {
         match (other, self) {
             (FactReason::Fact(h, _), FactReason::Fact(o, _)) => h.cmp(o),
             (FactReason::Rule(r), FactReason::Rule(o)) => r.cmp(o),
+            (FactReason::Given(g), FactReason::Given(o)) => g.cmp(o),
+            (FactReason::External(e), FactReason::External(o)) => e.cmp(o),
             (FactReason::Assumption, _) => Ordering::Greater,
             (_, FactReason::Assumption) => Ordering::Less,
+            (FactReason::External(_), _) => Ordering::Greater,
+            (_, FactReason::External(_)) => Ordering::Less,
+            (FactReason::Given(_), _) => Ordering::Greater,
+            (_, FactReason::Given(_)) => Ordering::Less,
             (FactReason::Rule(_), _) => Ordering::Greater,
             (_, FactReason::Rule(_)) => Ordering::Less,
         }
     }
 }
 
-#[derive(Clone, Debug)]
+///Whether a fact holds in every state, or only under the assumptions of its branch.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FactLifetime {
+    ///Derived from the rules, domains and imported facts alone.
+    Universal,
+    ///Derived from an assumption.
+    BranchLocal,
+}
+
+///Something known about an activation on a position, and what it follows from.
+///
+///```
+///use rune_lock_solver::{
+///    activation::{Activation, HumanActivation},
+///    fact_solver::{FactKind, FactReason},
+///    index::RunePosition,
+///    rule::ActivationRuleKindHelpers,
+///    FactualSolver, Rune, RuneLock,
+///};
+///
+///let runes = [0, 2, 1, 3, 2, 1, 3, 2, 1, 0, 2, 1].map(Rune::new);
+///let mut solver = FactualSolver::new(RuneLock::with_rules(runes, vec![(1, 2).alwanese()]));
+///let first = Activation::from(HumanActivation::new(1).unwrap());
+///solver.assume(first, RunePosition::new(0));
+///
+///let facts = solver.facts();
+///let (_, given) = facts
+///    .iter_facts()
+///    .find(|(_, it)| it.kind() == FactKind::ActivationMustBeOn)
+///    .unwrap();
+///assert_eq!((given.position(), given.activation()), (RunePosition::new(0), first));
+///assert!(matches!(given.reasons(), [FactReason::Assumption]));
+///assert!(facts
+///    .iter_facts()
+///    .any(|(_, it)| it.reasons().iter().any(|reason| matches!(reason, FactReason::Rule(_)))));
+///```
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Fact {
     kind: FactKind,
     activation: Activation,
     position: RunePosition,
     reasons: Vec<FactReason>,
+    ///Further positions the activation is ruled out of for the same reasons, so a whole lane
+    ///doesn't take a fact per cell. Only `ActivationCannotBeOn` facts cover ranges.
+    #[serde(default)]
+    range: Vec<RunePosition>,
+}
+
+impl Fact {
+    pub fn kind(&self) -> FactKind {
+        self.kind
+    }
+
+    pub fn activation(&self) -> Activation {
+        self.activation
+    }
+
+    ///The first position the fact is about, see `positions` for all of them.
+    pub fn position(&self) -> RunePosition {
+        self.position
+    }
+
+    ///What the fact follows from, all of them together.
+    pub fn reasons(&self) -> &[FactReason] {
+        &self.reasons
+    }
+
+    ///The positions the fact is about, the range included.
+    pub fn positions(&self) -> impl Iterator<Item = RunePosition> + '_ {
+        std::iter::once(self.position).chain(self.range.iter().copied())
+    }
+
+    pub fn covers(&self, position: RunePosition, activation: Activation) -> bool {
+        self.activation == activation && self.positions().any(|it| it == position)
+    }
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 pub enum SolverAction {
     Assume {
         position: RunePosition,
@@ -81,7 +212,7 @@ pub enum SolverAction {
     Root,
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 pub enum SolverStateState {
     Unexplored,
     Contradicts(FactHandle),
@@ -102,134 +233,1654 @@ impl Display for SolverAction {
 
 impl Display for SolverStateState {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        match self {
-            SolverStateState::Contradicts(fact) => write!(f, "✘ ({})", fact),
-            SolverStateState::Unexplored => write!(f, " "),
+        match (self, terminal::accessible()) {
+            (SolverStateState::Contradicts(fact), true) => write!(f, "contradicted by {}", fact),
+            (SolverStateState::Unexplored, true) => write!(f, "open"),
+            (SolverStateState::Contradicts(fact), false) => write!(f, "✘ ({})", fact),
+            (SolverStateState::Unexplored, false) => write!(f, " "),
             // SolverStateState::Solved => write!(f, "✔"),
         }
     }
 }
 
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 struct FactSolverState {
     facts: FactDb,
     action: SolverAction,
     state: SolverStateState,
+    ///Whether idle deepening already ran on this state.
+    deepened: bool,
+    ///How many facts the parent had when this state was derived from it. Facts are only ever
+    ///added, so the state shares those with its parent under the same handles. `None` in
+    ///sessions saved before facts were passed down the tree, where it shares all of them.
+    #[serde(default)]
+    inherited: Option<usize>,
+}
+
+///What idle deepening learned about an open leaf.
+pub struct DeepeningReport {
+    pub node: AssumptionTreeNodeHandle,
+    pub forced: Vec<(FactHandle, Fact)>,
+    pub contradiction: Option<FactHandle>,
+    ///How many of the facts it derived hold in every state, and were copied to the root.
+    pub lifted: usize,
+}
+
+impl DeepeningReport {
+    pub fn is_empty(&self) -> bool {
+        self.forced.is_empty() && self.contradiction.is_none() && self.lifted == 0
+    }
+}
+
+impl Display for DeepeningReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if let Some(fact) = self.contradiction {
+            write!(f, "State {} is contradicted by {}.", self.node, fact)?;
+        } else {
+            write!(f, "State {} forces:", self.node)?;
+            for (handle, fact) in self.forced.iter() {
+                write!(f, " {}: {};", handle, fact)?;
+            }
+        }
+        if self.lifted > 0 {
+            write!(
+                f,
+                " {} of its facts hold in every state and were copied to the root.",
+                self.lifted
+            )?;
+        }
+        Ok(())
+    }
+}
+
+///What the rules of a stage derived once they were applied, see `FactualSolver::advance_stage`.
+pub struct StageReport {
+    pub stage: usize,
+    ///The state the new facts are counted in, the current one.
+    pub node: AssumptionTreeNodeHandle,
+    ///Every rule the stage applied, with how many of the new facts cite it.
+    pub rules: Vec<(usize, RuleKind, usize)>,
+    pub new_facts: usize,
+}
+
+impl Display for StageReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "Stage {} applies {} more rules.",
+            self.stage,
+            self.rules.len()
+        )?;
+        for (index, rule, derived) in self.rules.iter() {
+            writeln!(
+                f,
+                "  Rule {} '{}': {} new facts in state {}",
+                index, rule, derived, self.node
+            )?;
+        }
+        write!(
+            f,
+            "{} facts of state {} are new in total.",
+            self.new_facts, self.node
+        )
+    }
+}
+
+///What a single consolidation iteration after an assumption found, as an estimate of the outcome
+///of exploring it fully.
+#[derive(Clone)]
+pub struct Prediction {
+    pub position: RunePosition,
+    pub activation: Activation,
+    pub contradiction: Option<FactHandle>,
+    ///Activations placed by the iteration, besides the assumed one.
+    pub forced: usize,
+}
+
+impl Display for Prediction {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} = {}: ", self.position, self.activation)?;
+        match self.contradiction {
+            Some(fact) => write!(f, "contradicted by {}", fact),
+            None => write!(f, "{} placements forced", self.forced),
+        }
+    }
+}
+
+///Something the solver noticed while carrying out a command, which frontends may want to pass
+///on, see `FactualSolver::take_notices`.
+#[derive(Clone)]
+pub enum Notice {
+    ///The assumption was made in a child of the state already, which became current instead.
+    AlreadyAssumed {
+        position: RunePosition,
+        activation: Activation,
+        node: AssumptionTreeNodeHandle,
+    },
+    ///Facts that hold in every state, copied to the root, with their handles there.
+    Lifted(Vec<FactHandle>),
+    ///A sweep left out the candidate, as another child of the state already covers it.
+    Covered {
+        position: RunePosition,
+        activation: Activation,
+        child: AssumptionTreeNodeHandle,
+        action: SolverAction,
+    },
+    ///A sweep was interrupted after exploring some of its candidates.
+    Interrupted { explored: usize, candidates: usize },
+    ///What a sweep predicted for a candidate, before exploring it.
+    Predicted(Prediction),
+    ///The state that became current is contradicted.
+    Contradicted {
+        node: AssumptionTreeNodeHandle,
+        fact: FactHandle,
+    },
+}
+
+impl Display for Notice {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Notice::AlreadyAssumed {
+                position,
+                activation,
+                node,
+            } => write!(
+                f,
+                "{} = {} is already assumed in state {}.",
+                position, activation, node
+            ),
+            Notice::Lifted(facts) => write!(
+                f,
+                "{} hold in every state and were copied to the root.",
+                facts.iter().join(", ")
+            ),
+            Notice::Covered {
+                position,
+                activation,
+                child,
+                action,
+            } => write!(
+                f,
+                "Skipping {} = {}, state {} ({}) already covers it. Add --force to explore it anyway.",
+                position, activation, child, action
+            ),
+            Notice::Interrupted {
+                explored,
+                candidates,
+            } => write!(
+                f,
+                "Interrupted after exploring {} of {} candidates.",
+                explored, candidates
+            ),
+            Notice::Predicted(prediction) => write!(f, "Predicted {}", prediction),
+            Notice::Contradicted { node, fact } => {
+                write!(f, "Warning: state {} is contradicted by {}.", node, fact)
+            }
+        }
+    }
 }
 
 impl Display for FactSolverState {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        write!(f, "[{}], {}", self.state, self.action)
+        match terminal::accessible() {
+            true => write!(f, "{}, {}", self.action, self.state),
+            false => write!(f, "[{}], {}", self.state, self.action),
+        }
     }
 }
 
-pub struct FactualSolver<'a> {
-    lock: &'a RuneLock,
+///What was last shown to the user, so that only the differences have to be printed.
+#[derive(Clone, PartialEq)]
+pub struct Displayed {
+    nodes: Vec<(AssumptionTreeNodeHandle, String)>,
+    current: AssumptionTreeNodeHandle,
+    placed: Vec<Option<Activation>>,
+    verdict: String,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct FactualSolver {
+    lock: RuneLock,
     states: AssumptionTree<FactSolverState>,
     current: AssumptionTreeNodeHandle,
+    ///Checked by long-running operations, which stop early at a consistent point once it is set.
+    #[serde(skip)]
+    interrupt: CancelToken,
+    #[serde(skip)]
+    subscribers: Subscribers,
+    ///What the rule kinds did in earlier sessions and this one.
+    #[serde(skip)]
+    kind_stats: KindStats,
+    ///What the solver noticed since they were last taken.
+    #[serde(skip)]
+    notices: Vec<Notice>,
+    ///How the conflicts of merged sessions were resolved.
+    #[serde(default)]
+    merges: Vec<MergeDecision>,
 }
 
-impl<'a> FactualSolver<'a> {
-    pub fn new(lock: &'a RuneLock) -> Self {
+impl FactualSolver {
+    pub fn new(lock: RuneLock) -> Self {
+        //Domains and some rules, like santor chains, already restrict the grid without any
+        //assumptions.
+        let mut facts = FactDb::new(lock.size());
+        let state = match facts.integrate_givens(&lock) {
+            Ok(()) => SolverStateState::Unexplored,
+            Err(Contradiction(reason)) => SolverStateState::Contradicts(reason),
+        };
         let (tree, root) = AssumptionTree::new(FactSolverState {
-            facts: FactDb::new(12, 12),
+            facts,
             action: SolverAction::Root,
-            state: SolverStateState::Unexplored,
+            state,
+            deepened: false,
+            inherited: None,
         });
-        Self {
+        let mut solver = Self {
             lock,
             states: tree,
             current: root,
+            interrupt: CancelToken::default(),
+            subscribers: Subscribers::default(),
+            kind_stats: KindStats::default(),
+            notices: Vec::new(),
+            merges: Vec::new(),
+        };
+        solver.learn(root);
+        solver
+    }
+
+    pub fn lock(&self) -> &RuneLock {
+        &self.lock
+    }
+
+    ///The facts of the current state.
+    pub fn facts(&self) -> &FactDb {
+        &self.states[self.current].facts
+    }
+
+    ///Starts from what the rule kinds did in earlier sessions, consolidating the kinds that paid
+    ///off most first.
+    pub fn tune(&mut self, stats: KindStats) {
+        self.kind_stats = stats;
+        self.lock.set_kind_order(self.kind_stats.order());
+    }
+
+    ///What the rule kinds did in earlier sessions, as given to `tune`, and in this one.
+    pub fn kind_stats(&self) -> &KindStats {
+        &self.kind_stats
+    }
+
+    ///Adds what the rules did while consolidating the state to the statistics, and orders the
+    ///kinds by them from now on.
+    pub(super) fn learn(&mut self, node: AssumptionTreeNodeHandle) {
+        let work = self.states[node].facts.take_kind_work();
+        if work.is_empty() {
+            return;
         }
+        self.kind_stats.merge(&work);
+        self.lock.set_kind_order(self.kind_stats.order());
+    }
+
+    ///What the solver noticed while carrying out the commands since the last call, in order.
+    ///The library doesn't print, frontends show these as they see fit.
+    pub fn take_notices(&mut self) -> Vec<Notice> {
+        std::mem::take(&mut self.notices)
+    }
+
+    ///Lets long-running operations be interrupted through `interrupt`, e.g. by Ctrl-C.
+    pub fn set_interrupt(&mut self, interrupt: CancelToken) {
+        self.interrupt = interrupt;
+    }
+
+    ///How many activations are left for the position, and how many positions for the activation,
+    ///in the current state. If either is at most two, assuming the candidate is a bifurcation:
+    ///it and its alternative split the remaining space in two.
+    pub fn options(&self, position: RunePosition, activation: Activation) -> (usize, usize) {
+        let facts = &self.states[self.current].facts;
+        (
+            facts.possibilities_for(position).count(),
+            facts.possibilities_for(activation).count(),
+        )
+    }
+
+    ///How many (position, activation) pairs are neither ruled out nor known in the current state.
+    pub fn open_candidates(&self) -> usize {
+        let facts = &self.states[self.current].facts;
+        self.lock
+            .size()
+            .cells()
+            .filter(|(position, activation)| facts.handle_at(*position, *activation).is_none())
+            .count()
     }
 
+    ///Assumes the activation on the position in the current state, and continues in the state
+    ///that follows, which is returned. Assuming what a child of the current state assumed
+    ///already continues in that child.
+    ///
+    ///```
+    ///use rune_lock_solver::{
+    ///    activation::{Activation, HumanActivation}, index::RunePosition, outcome::Outcome,
+    ///    rule::ActivationRuleKindHelpers, FactualSolver, Rune, RuneLock,
+    ///};
+    ///
+    ///let runes = [0, 2, 1, 3, 2, 1, 3, 2, 1, 0, 2, 1].map(Rune::new);
+    ///let mut solver = FactualSolver::new(RuneLock::with_rules(runes, vec![(1, 2).alwanese()]));
+    ///let first = Activation::from(HumanActivation::new(1).unwrap());
+    ///
+    ///let node = solver.assume(first, RunePosition::new(0));
+    ///assert_eq!(solver.current(), node);
+    ///assert!(!matches!(solver.outcome(node), Outcome::Contradiction { .. }));
+    ///solver.set_current(solver.root());
+    ///assert_eq!(solver.assume(first, RunePosition::new(0)), node);
+    ///```
     pub fn assume(
         &mut self,
         activation: Activation,
         position: RunePosition,
     ) -> AssumptionTreeNodeHandle {
-        let mut derived_facts = self.states[self.current].facts.clone();
-        let state = match derived_facts.integrate_and_consolidate(
-            Fact {
-                kind: FactKind::ActivationMustBeOn {},
-                reasons: vec![FactReason::Assumption],
-                position,
-                activation,
-            },
-            self.lock,
-        ) {
-            Ok(_) => SolverStateState::Unexplored,
-            Err(err) => match err {
-                Contradiction(reason) => SolverStateState::Contradicts(reason),
-            },
-        };
+        //Assuming the same thing twice would only clone the existing node.
+        if let Some(existing) = self.assumed_child(position, activation) {
+            self.current = existing;
+            return existing;
+        }
+        let derived = self.derive(self.current, position, activation);
+        self.insert_derived(derived)
+    }
+
+    ///The child of the current state assuming exactly the activation on the position, if any.
+    fn assumed_child(
+        &mut self,
+        position: RunePosition,
+        activation: Activation,
+    ) -> Option<AssumptionTreeNodeHandle> {
+        let existing = self.states.children_of(self.current).find(|it| {
+            matches!(
+                self.states[*it].action,
+                SolverAction::Assume { position: p, activation: a } if p == position && a == activation
+            )
+        })?;
+        self.notices.push(Notice::AlreadyAssumed {
+            position,
+            activation,
+            node: existing,
+        });
+        Some(existing)
+    }
+
+    ///Adds the derived state as a child of the current one and makes it current.
+    fn insert_derived(&mut self, derived: FactSolverState) -> AssumptionTreeNodeHandle {
         debug!(
             "================================================================ {:?}!",
-            state
+            derived.state
         );
 
-        self.current = self.states.insert_child(
-            self.current,
-            FactSolverState {
-                facts: derived_facts,
-                action: SolverAction::Assume {
+        self.current = self.states.insert_child(self.current, derived);
+        self.publish_derived(self.current);
+        let lifted = self.lift_universal(self.current);
+        if !lifted.is_empty() {
+            self.notices.push(Notice::Lifted(lifted));
+        }
+        self.current
+    }
+
+    ///Another child of `node` that explores the same states as assuming the activation on the
+    ///position in `node` would: its state places the activation there, and placing it forces the
+    ///child's assumption in turn. Assuming exactly what a child assumed is caught by `assume`.
+    pub fn covering_child(
+        &self,
+        node: AssumptionTreeNodeHandle,
+        position: RunePosition,
+        activation: Activation,
+    ) -> Option<AssumptionTreeNodeHandle> {
+        let places = |facts: &FactDb, position: RunePosition, activation: Activation| {
+            facts
+                .handle_at(position, activation)
+                .and_then(|it| facts.get(it))
+                .is_some_and(|it| it.kind == FactKind::ActivationMustBeOn)
+        };
+        //Only derived once a child places the candidate.
+        let mut assumed: Option<FactDb> = None;
+        self.states.children_of(node).find(|child| {
+            let SolverAction::Assume {
+                position: p,
+                activation: a,
+            } = self.states[*child].action
+            else {
+                return false;
+            };
+            if (p, a) == (position, activation)
+                || !places(&self.states[*child].facts, position, activation)
+            {
+                return false;
+            }
+            let facts = assumed.get_or_insert_with(|| {
+                let mut facts = self.states[node].facts.clone();
+                //A contradiction is fine, the facts integrated up to it still show what is forced.
+                let _ = facts.integrate_and_consolidate(
+                    Fact {
+                        kind: FactKind::ActivationMustBeOn,
+                        reasons: vec![FactReason::Assumption],
+                        position,
+                        activation,
+                        range: Vec::new(),
+                    },
+                    &self.lock,
+                );
+                facts
+            });
+            places(facts, p, a)
+        })
+    }
+
+    ///Copies the universal facts `node` derived on top of its parent which the root doesn't know
+    ///yet to the root, and passes them on down the tree. Returns their handles in the root.
+    pub(super) fn lift_universal(&mut self, node: AssumptionTreeNodeHandle) -> Vec<FactHandle> {
+        let root = self.states.root();
+        if node == root {
+            return Vec::new();
+        }
+        if let SolverStateState::Contradicts(_) = self.states[root].state {
+            return Vec::new();
+        }
+        let facts = &self.states[node].facts;
+        let root_facts = &self.states[root].facts;
+        let known_at_root = |fact: &Fact| {
+            fact.positions().all(|position| {
+                root_facts
+                    .handle_at(position, fact.activation)
+                    .and_then(|it| root_facts.get(it))
+                    .is_some_and(|it| it.kind == fact.kind)
+            })
+        };
+        let lifted: Vec<_> = facts
+            .facts_since(self.inherited(node))
+            .filter(|(handle, fact)| {
+                facts.lifetime(*handle) == FactLifetime::Universal && !known_at_root(fact)
+            })
+            .map(|(handle, _)| handle)
+            .collect();
+        if lifted.is_empty() {
+            return lifted;
+        }
+        let mut root_facts = root_facts.clone();
+        let known = root_facts.fact_count();
+        let adopted: Vec<_> = lifted
+            .into_iter()
+            .map(|it| root_facts.adopt(facts, it))
+            .collect();
+        let contradiction =
+            first_contradiction(&root_facts, adopted.iter().copied()).or_else(|| {
+                root_facts
+                    .consolidate(&self.lock)
+                    .err()
+                    .map(|Contradiction(reason)| reason)
+            });
+        self.pin_inherited();
+        self.states[root].facts = root_facts;
+        if let Some(reason) = contradiction {
+            self.states[root].state = SolverStateState::Contradicts(reason);
+        }
+        self.publish(root, known);
+        self.push_down(known);
+        adopted
+    }
+
+    ///How many facts `node` shares with its parent, see `FactSolverState::inherited`.
+    pub(super) fn inherited(&self, node: AssumptionTreeNodeHandle) -> usize {
+        match (self.states.parent_of(node), self.states[node].inherited) {
+            (None, _) => 0,
+            (Some(_), Some(inherited)) => inherited,
+            (Some(parent), None) => self.states[parent].facts.fact_count(),
+        }
+    }
+
+    ///Records how many facts each state shares with its parent, before the parents learn more.
+    fn pin_inherited(&mut self) {
+        let nodes: Vec<_> = self.states.handles().collect();
+        for node in nodes {
+            self.states[node].inherited = Some(self.inherited(node));
+        }
+    }
+
+    ///Passes what the root learned from its fact `known` on down the tree, parents first: every
+    ///state adopts what its parent just learned, and consolidates. The facts the states had keep
+    ///their handles, unlike with `reroot`.
+    fn push_down(&mut self, known: usize) {
+        let mut learned = HashMap::from([(self.states.root(), known)]);
+        let nodes: Vec<_> = self.states.handles().skip(1).collect();
+        for node in nodes {
+            let Some(parent) = self.states.parent_of(node) else {
+                continue;
+            };
+            let shared = self.inherited(node);
+            let state = &self.states[node];
+            let known = state.facts.fact_count();
+            learned.insert(node, known);
+            //Contradicted states stay as they are, and so do the ones below them.
+            if let SolverStateState::Contradicts(_) = state.state {
+                continue;
+            }
+            let parent_facts = &self.states[parent].facts;
+            let mut facts = state.facts.clone();
+            let adopted: Vec<_> = parent_facts
+                .facts_since(learned[&parent])
+                .map(|(handle, _)| facts.adopt_shared(parent_facts, handle, shared))
+                .collect();
+            if facts.fact_count() == known {
+                continue;
+            }
+            let contradiction = first_contradiction(&facts, adopted).or_else(|| {
+                facts
+                    .consolidate(&self.lock)
+                    .err()
+                    .map(|Contradiction(reason)| reason)
+            });
+            let state = &mut self.states[node];
+            state.facts = facts;
+            state.deepened = false;
+            if let Some(reason) = contradiction {
+                state.state = SolverStateState::Contradicts(reason);
+            }
+            self.publish(node, known);
+        }
+    }
+
+    ///The state reached by assuming `activation` on `position` in the state `parent`.
+    fn derive(
+        &self,
+        parent: AssumptionTreeNodeHandle,
+        position: RunePosition,
+        activation: Activation,
+    ) -> FactSolverState {
+        let parent = &self.states[parent];
+        let mut facts = parent.facts.clone();
+        let state = match parent.state {
+            //Everything derived from a contradiction is contradictory as well.
+            SolverStateState::Contradicts(reason) => SolverStateState::Contradicts(reason),
+            SolverStateState::Unexplored => match facts.integrate_and_consolidate(
+                Fact {
+                    kind: FactKind::ActivationMustBeOn {},
+                    reasons: vec![FactReason::Assumption],
                     position,
                     activation,
+                    range: Vec::new(),
+                },
+                &self.lock,
+            ) {
+                Ok(_) => SolverStateState::Unexplored,
+                Err(err) => match err {
+                    Contradiction(reason) => SolverStateState::Contradicts(reason),
                 },
-                state,
             },
-        );
-        self.current
+        };
+        FactSolverState {
+            facts,
+            action: SolverAction::Assume {
+                position,
+                activation,
+            },
+            state,
+            deepened: false,
+            inherited: Some(parent.facts.fact_count()),
+        }
+    }
+
+    ///Brings the whole tree up to date after the root's knowledge changed: the root is
+    ///consolidated again, and every other state is rebuilt by replaying its assumption on top of
+    ///its (already rebuilt) parent. States that now contradict are marked as such.
+    pub fn reroot(&mut self) {
+        let handles: Vec<_> = self.states.handles().collect();
+        let mut handles = handles.into_iter();
+        if let Some(node) = handles.next() {
+            let root = &mut self.states[node];
+            root.deepened = false;
+            let known = root.facts.fact_count();
+            if let SolverStateState::Unexplored = root.state {
+                if let Err(Contradiction(reason)) = root.facts.consolidate(&self.lock) {
+                    root.state = SolverStateState::Contradicts(reason);
+                }
+            }
+            self.publish(node, known);
+        }
+        //Handles are handed out in insertion order, so parents are always rebuilt first.
+        for node in handles {
+            let parent = match self.states.parent_of(node) {
+                Some(it) => it,
+                None => continue,
+            };
+            if let SolverAction::Assume {
+                position,
+                activation,
+            } = self.states[node].action
+            {
+                self.states[node] = self.derive(parent, position, activation);
+                self.publish_derived(node);
+            }
+        }
     }
 
-    pub fn try_possibilities<T: View + Debug + ChooseView + Clone>(&mut self, it: T)
+    ///Assumes every candidate of the position or activation in a state of its own, and returns
+    ///those states with their outcomes. Candidates another child of the current state already
+    ///covers (see `covering_child`) are reported and left out, unless `force` is set.
+    pub fn try_possibilities<T: View + Debug + ChooseView + Clone>(
+        &mut self,
+        it: T,
+        force: bool,
+    ) -> Vec<(AssumptionTreeNodeHandle, Outcome)>
     where
         T::Complement: Debug + Clone,
     {
         let current = self.current;
         let current_facts = &self.states[current].facts;
-        let possibilities: Vec<_> = current_facts.possibilities_for(it.clone()).collect();
+        //Exploring a candidate can lift facts to the root, which reach the current state only
+        //after the predictions were made.
+        let predicted_on = current_facts.fact_count();
+        let mut predictions = Vec::new();
+        for possibility in current_facts.possibilities_for(it.clone()) {
+            let position = T::choose_position(it.clone(), possibility.clone());
+            let activation = T::choose_activation(it.clone(), possibility);
+            match self.covering_child(current, position, activation) {
+                Some(child) if !force => self.notices.push(Notice::Covered {
+                    position,
+                    activation,
+                    child,
+                    action: self.states[child].action,
+                }),
+                _ => predictions.push(self.predict_facts(position, activation)),
+            }
+        }
+        //Contradictions are settled right away, and many forced placements tend to settle the
+        //rest of the lock quickly, so those are explored first.
+        predictions
+            .sort_by_key(|(it, _)| (it.contradiction.is_none(), std::cmp::Reverse(it.forced)));
 
-        for possibility in possibilities {
-            self.assume(
-                T::choose_activation(it.clone(), possibility.clone()),
-                T::choose_position(it.clone(), possibility.clone()),
-            );
+        let candidates = predictions.len();
+        let mut outcomes = Vec::new();
+        for (explored, (prediction, mut facts)) in predictions.into_iter().enumerate() {
+            //Every candidate is explored completely, so stopping between two leaves a consistent
+            //tree.
+            if self.interrupt.is_cancelled() {
+                self.notices.push(Notice::Interrupted {
+                    explored,
+                    candidates,
+                });
+                break;
+            }
+            self.notices.push(Notice::Predicted(prediction.clone()));
+            let (position, activation) = (prediction.position, prediction.activation);
+            let node = match self.assumed_child(position, activation) {
+                Some(existing) => existing,
+                None => {
+                    let current_facts = &self.states[current].facts;
+                    let adopted: Vec<_> = current_facts
+                        .facts_since(predicted_on)
+                        .map(|(handle, _)| facts.adopt_shared(current_facts, handle, predicted_on))
+                        .collect();
+                    //A contradiction the prediction found is final, otherwise the consolidation
+                    //picks up where the prediction stopped.
+                    let contradiction = prediction
+                        .contradiction
+                        .or_else(|| first_contradiction(&facts, adopted));
+                    let state = match contradiction {
+                        Some(reason) => SolverStateState::Contradicts(reason),
+                        None => match facts.consolidate(&self.lock) {
+                            Ok(()) => SolverStateState::Unexplored,
+                            Err(Contradiction(reason)) => SolverStateState::Contradicts(reason),
+                        },
+                    };
+                    self.insert_derived(FactSolverState {
+                        facts,
+                        action: SolverAction::Assume {
+                            position,
+                            activation,
+                        },
+                        state,
+                        deepened: false,
+                        inherited: Some(predicted_on),
+                    })
+                }
+            };
+            outcomes.push((node, self.outcome(node)));
             self.current = current;
         }
+        outcomes
+    }
+
+    ///What the state came to: its contradiction with the rules behind it, the solution it
+    ///reached, the placements it forced on top of its parent, or how many facts it added.
+    pub fn outcome(&self, node: AssumptionTreeNodeHandle) -> Outcome {
+        let state = &self.states[node];
+        let facts = &state.facts;
+        if let SolverStateState::Contradicts(fact) = state.state {
+            return Outcome::Contradiction {
+                fact: Some(fact),
+                blame: facts.rules_of(fact).into_iter().collect(),
+                reason: facts
+                    .get(fact)
+                    .map_or_else(|| fact.to_string(), |it| it.to_string()),
+            };
+        }
+        if let Ok(fixed) = facts.fixed_assignment() {
+            if fixed.is_complete() && self.lock.validate(&fixed).is_ok() {
+                return Outcome::Solved {
+                    assignment: self
+                        .lock
+                        .size()
+                        .all_positions()
+                        .map(|it| fixed[it])
+                        .collect(),
+                };
+            }
+        }
+        let known = self.inherited(node);
+        let placements: Vec<_> = facts
+            .facts_since(known)
+            .filter(|(_, fact)| {
+                fact.kind == FactKind::ActivationMustBeOn
+                    && !fact.reasons.contains(&FactReason::Assumption)
+            })
+            .map(|(_, fact)| (fact.position, fact.activation))
+            .collect();
+        if placements.is_empty() {
+            Outcome::Progress {
+                facts_added: facts.fact_count() - known,
+            }
+        } else {
+            Outcome::Forced { placements }
+        }
+    }
+
+    ///Estimates the outcome of assuming the activation on the position in the current state, by
+    ///running only a single consolidation iteration.
+    pub fn predict(&self, position: RunePosition, activation: Activation) -> Prediction {
+        self.predict_facts(position, activation).0
+    }
+
+    ///The prediction along with the facts its consolidation iteration left behind.
+    fn predict_facts(
+        &self,
+        position: RunePosition,
+        activation: Activation,
+    ) -> (Prediction, FactDb) {
+        let state = &self.states[self.current];
+        let mut facts = state.facts.clone();
+        let placed = |facts: &FactDb| {
+            facts.fixed_assignment().map_or(0, |it| {
+                it.size()
+                    .all_positions()
+                    .filter(|position| it[*position].is_some())
+                    .count()
+            })
+        };
+        let before = placed(&facts);
+        let contradiction = match state.state {
+            SolverStateState::Contradicts(reason) => Some(reason),
+            SolverStateState::Unexplored => facts
+                .integrate_and_consolidate_once(
+                    Fact {
+                        kind: FactKind::ActivationMustBeOn,
+                        reasons: vec![FactReason::Assumption],
+                        position,
+                        activation,
+                        range: Vec::new(),
+                    },
+                    &self.lock,
+                )
+                .err()
+                .map(|Contradiction(reason)| reason),
+        };
+        let forced = placed(&facts).saturating_sub(before + 1);
+        (
+            Prediction {
+                position,
+                activation,
+                contradiction,
+                forced,
+            },
+            facts,
+        )
     }
 
     pub fn get_tree_handle(
         &self,
-        node_id: usize,
+        node_id: NodeId,
+    ) -> Result<AssumptionTreeNodeHandle, AssumptionTreeError> {
+        self.states.get_handle(node_id.index())
+    }
+
+    ///The state of the node id, like `get_tree_handle`, with a notice if it is contradicted, for
+    ///commands that would rather look at a state that isn't.
+    pub fn target(
+        &mut self,
+        node_id: NodeId,
     ) -> Result<AssumptionTreeNodeHandle, AssumptionTreeError> {
-        self.states.get_handle(node_id)
+        let handle = self.get_tree_handle(node_id)?;
+        self.notice_contradiction(handle);
+        Ok(handle)
+    }
+
+    fn notice_contradiction(&mut self, node: AssumptionTreeNodeHandle) {
+        if let SolverStateState::Contradicts(fact) = self.states[node].state {
+            self.notices.push(Notice::Contradicted { node, fact });
+        }
+    }
+
+    pub fn root(&self) -> AssumptionTreeNodeHandle {
+        self.states.root()
+    }
+
+    ///Every state of the tree, in the order they were made.
+    pub fn nodes(&self) -> impl Iterator<Item = AssumptionTreeNodeHandle> + '_ {
+        self.states.handles()
+    }
+
+    ///The state the given one was assumed in, `None` for the root.
+    pub fn parent_of(&self, node: AssumptionTreeNodeHandle) -> Option<AssumptionTreeNodeHandle> {
+        self.states.parent_of(node)
+    }
+
+    ///Removes the state and everything assumed in it. If the current state is among them, its
+    ///parent becomes the current state. Returns the removed states.
+    pub fn prune(
+        &mut self,
+        node: AssumptionTreeNodeHandle,
+    ) -> Result<Vec<AssumptionTreeNodeHandle>, AssumptionTreeError> {
+        let parent = self.states.parent_of(node);
+        let removed = self.states.remove_subtree(node)?;
+        if let Some(parent) = parent.filter(|_| removed.contains(&self.current)) {
+            self.current = parent;
+        }
+        Ok(removed)
     }
 
     pub fn set_current(&mut self, new: AssumptionTreeNodeHandle) {
+        self.notice_contradiction(new);
         self.current = new;
     }
 
-    pub fn display_ui(&self) {
-        println!("{}", self.states);
-        println!("Current State: {}", self.current);
-        let fixed = self.states[self.current].facts.fixed_assignment().unwrap();
-        fixed.print();
-        match self.lock.validate(&fixed) {
-            Err(err) => println!("Invalid Assignment: {}", err),
-            Ok(_) => println!("Valid State."),
+    ///The activations known to be on a position in the current state.
+    pub fn fixed_assignment(&self) -> Result<Assignment, AssignmentError> {
+        self.states[self.current].facts.fixed_assignment()
+    }
+
+    ///The whole tree and the current assignment, as text to be shown, along with what it shows.
+    pub fn render_ui(&self) -> (String, Displayed) {
+        let mut text = format!("{}Current State: {}\n", self.states, self.current);
+        let displayed = self.displayed();
+        if terminal::accessible() {
+            text.push_str(&self.rendered_candidates());
+        } else if let Ok(fixed) = self.states[self.current].facts.fixed_assignment() {
+            text.push_str(&fixed.rendered());
+        }
+        text.push_str(&format!("{}\n", displayed.verdict));
+        (text, displayed)
+    }
+
+    ///A line per position of the current state with its activation, or the ones left for it.
+    fn rendered_candidates(&self) -> String {
+        let facts = &self.states[self.current].facts;
+        let fixed = facts.fixed_assignment().ok();
+        let mut text = String::new();
+        for position in self.lock.size().all_positions() {
+            if let Some(activation) = fixed.as_ref().and_then(|it| it[position]) {
+                text.push_str(&format!("Position {}: {}\n", position, activation));
+                continue;
+            }
+            let candidates: Vec<_> = facts.possibilities_for(position).collect();
+            match candidates.is_empty() {
+                true => text.push_str(&format!("Position {}: no candidates left\n", position)),
+                false => text.push_str(&format!(
+                    "Position {}: candidates {}\n",
+                    position,
+                    candidates.iter().join(", ")
+                )),
+            }
+        }
+        text
+    }
+
+    ///Only what changed since `shown`: new or changed tree nodes, a different current state, and
+    ///activations that were placed or removed, as text to be shown, along with what is shown now.
+    pub fn render_changes(&self, shown: &Displayed) -> (String, Displayed) {
+        let displayed = self.displayed();
+        let (changed, added, removed) = match terminal::accessible() {
+            true => ("Changed state", "New state", "Removed state"),
+            false => ("~", "+", "-"),
+        };
+        let mut text = String::new();
+        for (node, line) in displayed.nodes.iter() {
+            match shown.nodes.iter().find(|(it, _)| it == node) {
+                Some((_, old)) if old == line => {}
+                Some(_) => text.push_str(&format!("{} ({}) {}\n", changed, node, line)),
+                None => match self.states.parent_of(*node) {
+                    Some(parent) => text.push_str(&format!(
+                        "{} ({}) {} (under {})\n",
+                        added, node, line, parent
+                    )),
+                    None => text.push_str(&format!("{} ({}) {}\n", added, node, line)),
+                },
+            }
+        }
+        for (node, line) in shown.nodes.iter() {
+            if !displayed.nodes.iter().any(|(it, _)| it == node) {
+                text.push_str(&format!("{} ({}) {}\n", removed, node, line));
+            }
+        }
+        if displayed.current != shown.current {
+            text.push_str(&format!("Current State: {}\n", displayed.current));
+        }
+        for (position, (old, new)) in shown.placed.iter().zip(displayed.placed.iter()).enumerate() {
+            match (old, new) {
+                (old, new) if old == new => {}
+                (_, Some(new)) => text.push_str(&format!("{} is on {}\n", new, position)),
+                (Some(old), None) => {
+                    text.push_str(&format!("{} is no longer on {}\n", old, position))
+                }
+                (None, None) => {}
+            }
+        }
+        if displayed.verdict != shown.verdict {
+            text.push_str(&format!("{}\n", displayed.verdict));
+        }
+        (text, displayed)
+    }
+
+    ///What `render_ui` shows, without the text.
+    pub fn displayed(&self) -> Displayed {
+        let size = self.lock.size();
+        let (placed, verdict) = match self.states[self.current].facts.fixed_assignment() {
+            Ok(fixed) => (
+                size.all_positions().map(|it| fixed[it]).collect(),
+                match self.lock.validate(&fixed) {
+                    Err(err) => format!("Invalid Assignment: {}", err),
+                    Ok(_) => "Valid State.".to_string(),
+                },
+            ),
+            Err(err) => (
+                vec![None; size.positions()],
+                format!("Inconsistent knowledge: {}", err),
+            ),
+        };
+        Displayed {
+            nodes: self
+                .states
+                .handles()
+                .map(|it| (it, self.states[it].to_string()))
+                .collect(),
+            current: self.current,
+            placed,
+            verdict,
+        }
+    }
+
+    ///Why a fact holds, in about `max_lines` lines and at most `max_depth` steps deep. A
+    ///`minimal` explanation drops every reason that is not needed to derive its fact.
+    pub fn explain(
+        &self,
+        fact_handle: FactHandle,
+        max_lines: usize,
+        max_depth: usize,
+        minimal: bool,
+    ) -> String {
+        let mut text = format!(
+            "Explaining Fact: {} in state {}\n",
+            fact_handle, self.current
+        );
+        let db = &self.states[self.current].facts;
+        for line in db.explanation(fact_handle, &self.lock, max_lines, max_depth, minimal) {
+            text.push_str(&format!("{}\n", line));
+        }
+        if !minimal {
+            text.push_str("============\n");
+            text.push_str(&explain_fact(fact_handle, db, &self.lock));
+        }
+        text
+    }
+
+    ///Ranks candidate assumptions in the current state. If `activation` is given, only that
+    ///activation's positions are considered.
+    pub fn advise(&self, activation: Option<Activation>) -> Vec<Advice> {
+        let facts = &self.states[self.current].facts;
+        advise(
+            facts,
+            &self.lock,
+            Self::advised_activations(self.lock.size(), activation),
+        )
+    }
+
+    ///Like `advise`, but detached from the solver so it can run as a background job.
+    pub fn advise_job(
+        &self,
+        activation: Option<Activation>,
+    ) -> impl FnOnce(&CancelToken) -> Option<Vec<Advice>> + Send + 'static {
+        let facts = self.states[self.current].facts.clone();
+        let lock = self.lock.clone();
+        move |cancel| {
+            let activations = Self::advised_activations(lock.size(), activation);
+            advise_cancellable(&facts, &lock, activations, cancel)
+        }
+    }
+
+    fn advised_activations(
+        size: LockSize,
+        activation: Option<Activation>,
+    ) -> impl Iterator<Item = Activation> {
+        let all = activation.is_none();
+        size.all_activations()
+            .filter(move |it| all || Some(*it) == activation)
+    }
+
+    pub fn current(&self) -> AssumptionTreeNodeHandle {
+        self.current
+    }
+
+    ///Explains why `activation` is eliminated from `position` in one of the two states but not in
+    ///the other, and which assumptions of that state's branch the elimination depends on.
+    pub fn explain_diff(
+        &self,
+        a: AssumptionTreeNodeHandle,
+        b: AssumptionTreeNodeHandle,
+        position: RunePosition,
+        activation: Activation,
+    ) -> String {
+        let eliminating = |node: AssumptionTreeNodeHandle| {
+            let db = &self.states[node].facts;
+            db.handle_at(position, activation)
+                .filter(|it| db.get(*it).unwrap().kind != FactKind::ActivationMustBeOn)
+        };
+        let (node, other, fact_handle) = match (eliminating(a), eliminating(b)) {
+            (Some(fact), None) => (a, b, fact),
+            (None, Some(fact)) => (b, a, fact),
+            (Some(_), Some(_)) => {
+                return format!(
+                    "{} is eliminated from {} in both states.\n",
+                    activation, position
+                );
+            }
+            (None, None) => {
+                return format!(
+                    "{} is eliminated from {} in neither state.\n",
+                    activation, position
+                );
+            }
+        };
+
+        let mut text = format!(
+            "{} is eliminated from {} in state {}, but not in state {}:\n",
+            activation, position, node, other
+        );
+        let db = &self.states[node].facts;
+        for line in db.explanation(fact_handle, &self.lock, 10, usize::MAX, true) {
+            text.push_str(&format!("{}\n", line));
+        }
+
+        let other_db = &self.states[other].facts;
+        let assumed_in_other: Vec<_> = other_db
+            .assumptions()
+            .map(|it| {
+                let fact = other_db.get(it).unwrap();
+                (fact.position, fact.activation)
+            })
+            .collect();
+        let unique: Vec<_> = db
+            .assumptions_of(fact_handle)
+            .into_iter()
+            .filter(|it| {
+                let fact = db.get(*it).unwrap();
+                !assumed_in_other.contains(&(fact.position, fact.activation))
+            })
+            .collect();
+        if unique.is_empty() {
+            text.push_str(&format!(
+                "The proof depends on no assumption unique to state {}.\n",
+                node
+            ));
+        } else {
+            text.push_str(&format!(
+                "The proof depends on these assumptions unique to state {}:\n",
+                node
+            ));
+            for handle in unique {
+                text.push_str(&format!("  {}: {}\n", handle, db.get(handle).unwrap()));
+            }
+        }
+        text
+    }
+
+    ///The assumptions of the current path which the given fact does not depend on, and which
+    ///could therefore be retracted without losing the fact, a line each.
+    pub fn independent(&self, fact_handle: FactHandle) -> String {
+        let db = &self.states[self.current].facts;
+        if db.get(fact_handle).is_none() {
+            return format!("Unknown Fact: {}\n", fact_handle);
+        }
+        let dependencies = db.assumptions_of(fact_handle);
+        let independent: Vec<_> = db
+            .assumptions()
+            .filter(|it| !dependencies.contains(it))
+            .collect();
+        if independent.is_empty() {
+            return format!(
+                "{} depends on every assumption of state {}.\n",
+                fact_handle, self.current
+            );
         }
+        independent
+            .into_iter()
+            .map(|handle| format!("{}: {}\n", handle, db.get(handle).unwrap()))
+            .collect()
     }
 
-    pub fn explain(&self, fact_handle: FactHandle, max_depth: usize) {
-        println!("Explaining Fact: {} in state {}", fact_handle, self.current);
+    ///Applies the rules of every stage up to `stage`, rebuilds the tree with them and reports
+    ///what the newly applied rules derived in the current state.
+    pub fn advance_stage(&mut self, stage: usize) -> Result<StageReport, StageError> {
+        if stage <= self.lock.stage {
+            return Err(StageError::AlreadyReached(stage));
+        }
+        let cells: Vec<_> = self.lock.size().cells().collect();
+        let kinds = |db: &FactDb| -> Vec<Option<FactKind>> {
+            cells
+                .iter()
+                .map(|(position, activation)| {
+                    db.handle_at(*position, *activation)
+                        .map(|it| db.get(it).unwrap().kind)
+                })
+                .collect()
+        };
+        let before = kinds(&self.states[self.current].facts);
+        let revealed: Vec<_> = (0..self.lock.rules.len())
+            .filter(|it| !self.lock.is_active(*it) && self.lock.stages[*it] <= stage)
+            .collect();
+        self.lock.stage = stage;
+        self.reroot();
+
         let db = &self.states[self.current].facts;
-        db.explain(fact_handle, self.lock, max_depth);
-        println!("============");
-        explain_fact(fact_handle, &db, &self.lock);
+        let new_facts: Vec<_> = kinds(db)
+            .into_iter()
+            .zip(before)
+            .enumerate()
+            .filter(|(_, (after, before))| after != before)
+            .filter_map(|(cell, _)| db.handle_at(cells[cell].0, cells[cell].1))
+            .collect();
+        let rules = revealed
+            .into_iter()
+            .map(|rule| {
+                let derived = new_facts
+                    .iter()
+                    .filter(|it| {
+                        db.get(**it)
+                            .unwrap()
+                            .reasons
+                            .contains(&FactReason::Rule(rule))
+                    })
+                    .count();
+                (rule, self.lock.rules[rule].clone(), derived)
+            })
+            .collect();
+        Ok(StageReport {
+            stage,
+            node: self.current,
+            rules,
+            new_facts: new_facts.len(),
+        })
+    }
+
+    ///Fills in a rune of the lock and rebuilds the tree with the new knowledge. Replacing a rune
+    ///that was known already takes back what the root deduced from it, like `editlock` does.
+    pub fn set_rune(&mut self, position: RunePosition, rune: Rune) {
+        if self.lock.runes[position].is_known() {
+            //Setting a rune can't fail, only removing a rule can.
+            let _ = self.apply_edit(LockEdit::SetRune { position, rune });
+            return;
+        }
+        self.lock.set_rune(position, rune);
+        self.reroot();
+    }
+
+    ///Runs pair analysis on the next open leaf which hasn't been deepened yet. Returns `None` once
+    ///there is nothing left to deepen.
+    pub fn deepen_idle_leaf(&mut self) -> Option<DeepeningReport> {
+        let states = &self.states;
+        let node = states.handles().find(|it| {
+            states.is_leaf(*it)
+                && !states[*it].deepened
+                && matches!(states[*it].state, SolverStateState::Unexplored)
+        })?;
+
+        let state = &mut self.states[node];
+        state.deepened = true;
+        let known = state.facts.fact_count();
+        let contradiction = match state.facts.deepen(&self.lock, &self.interrupt) {
+            //Deepen it again once idle time isn't interrupted anymore.
+            Ok(_) if self.interrupt.is_cancelled() => {
+                state.deepened = false;
+                None
+            }
+            Ok(_) => None,
+            Err(Contradiction(reason)) => {
+                state.state = SolverStateState::Contradicts(reason);
+                Some(reason)
+            }
+        };
+        let forced = state
+            .facts
+            .facts_since(known)
+            .filter(|(_, fact)| fact.kind == FactKind::ActivationMustBeOn)
+            .map(|(handle, fact)| (handle, fact.clone()))
+            .collect();
+        self.publish(node, known);
+        let lifted = self.lift_universal(node).len();
+        Some(DeepeningReport {
+            node,
+            forced,
+            contradiction,
+            lifted,
+        })
+    }
+
+    ///The size of the tree and of the current state's knowledge, and with `detailed` the counters
+    ///of the current state and the memory used by each state.
+    pub fn stats_report(&self, detailed: bool) -> String {
+        let facts = &self.states[self.current].facts;
+        let mut text = format!(
+            "{} states, {} facts in state {}\n",
+            self.states.handles().count(),
+            facts.fact_count(),
+            self.current
+        );
+        text.push_str(&format!(
+            "Memory: ~{} in state {}, ~{} in total\n",
+            format_bytes(facts.memory_usage()),
+            self.current,
+            format_bytes(self.memory_usage())
+        ));
+        if detailed {
+            match facts.stats() {
+                Some(stats) => text.push_str(&format!("{}\n", stats)),
+                None => text.push_str("Detailed statistics require the 'stats' feature.\n"),
+            }
+            if !self.kind_stats.is_empty() {
+                text.push_str(&format!("{}\n", self.kind_stats));
+            }
+            for node in self.states.handles() {
+                let facts = &self.states[node].facts;
+                text.push_str(&format!(
+                    "  {}: {} facts, ~{}\n",
+                    node,
+                    facts.fact_count(),
+                    format_bytes(facts.memory_usage())
+                ));
+            }
+        }
+        text
+    }
+
+    ///Approximately how many bytes the fact databases of all states take up.
+    pub fn memory_usage(&self) -> usize {
+        self.states
+            .handles()
+            .map(|it| self.states[it].facts.memory_usage())
+            .sum()
+    }
+
+    ///Cross-checks every fact of `node` against all completions of its assumptions, and reports
+    ///the unsound ones and the verdict. Gives up after `limit` partial assignments, in which case
+    ///only unsound facts can be reported. `progress` is called every million visits with the
+    ///number of visits and completions so far.
+    pub fn verify_node(
+        &self,
+        node: AssumptionTreeNodeHandle,
+        limit: usize,
+        progress: impl FnMut(usize, usize),
+    ) -> String {
+        let db = &self.states[node].facts;
+        let assumed: Vec<_> = db
+            .assumptions()
+            .map(|it| {
+                let fact = db.get(it).unwrap();
+                (fact.position, fact.activation)
+            })
+            .collect();
+        let completions = verify::enumerate(
+            &self.lock,
+            &assumed,
+            limit,
+            &self.interrupt,
+            1_000_000,
+            progress,
+        );
+        let unsound = verify::unsound_facts(db, &completions);
+        let mut text: String = unsound
+            .iter()
+            .map(|handle| format!("Unsound: {}: {}\n", handle, db.get(*handle).unwrap()))
+            .collect();
+        let verdict = match (completions.complete, unsound.is_empty()) {
+            (true, true) => format!(
+                "State {} is sound: {} facts hold in all {} completions.",
+                node,
+                db.fact_count(),
+                completions.count
+            ),
+            (true, false) => format!(
+                "State {} has {} unsound facts ({} completions).",
+                node,
+                unsound.len(),
+                completions.count
+            ),
+            (false, _) if completions.interrupted => format!(
+                "Interrupted after {} completions, so state {} could not be confirmed.",
+                completions.count, node
+            ),
+            (false, _) => format!(
+                "Gave up after {} partial assignments ({} completions), so state {} could not be confirmed.",
+                limit, completions.count, node
+            ),
+        };
+        text.push_str(&verdict);
+        text.push('\n');
+        text
+    }
+
+    ///Every fact of the current state and the grid of what is known, see `FactDb::info_dump`.
+    pub fn dump_knowledge(&self) -> String {
+        self.states[self.current].facts.info_dump()
+    }
+}
+
+///Bytes in the largest unit that keeps the number above 1, like `12.3 MiB`.
+pub fn format_bytes(bytes: usize) -> String {
+    let mut size = bytes as f64;
+    for unit in ["B", "KiB", "MiB"] {
+        if size < 1024.0 {
+            return format!("{:.1} {}", size, unit);
+        }
+        size /= 1024.0;
+    }
+    format!("{:.1} GiB", size)
+}
+
+///The first of the facts that is a contradiction.
+fn first_contradiction(
+    facts: &FactDb,
+    handles: impl IntoIterator<Item = FactHandle>,
+) -> Option<FactHandle> {
+    handles.into_iter().find(|it| {
+        facts
+            .get(*it)
+            .is_some_and(|it| matches!(it.kind, FactKind::Contradiction(_)))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        fact_solver::FactualSolver,
+        index::RunePosition,
+        outcome::Outcome,
+        rule::ActivationRuleKindHelpers,
+        test_support::{activation, sample_runes},
+        RuneLock,
+    };
+
+    use super::{Fact, FactKind, FactReason, Notice, SolverAction};
+
+    #[test]
+    fn test_states_report_their_outcome() {
+        let lock = RuneLock::with_rules(
+            sample_runes(),
+            vec![
+                (1, 2).alwanese(),
+                (2, 3).antakian_conjugate(),
+                (3, 4).alwanese(),
+                (4, 5).antakian_twins(),
+                (5, 6).increase_santor(),
+                (6, 7).alwanese(),
+                (7, 8).antakian_conjugate(),
+                (8, 9).alwanese(),
+                (9, 10).antakian_twins(),
+                (10, 11).increase_santor(),
+            ],
+        );
+        let mut solver = FactualSolver::new(lock);
+        let first = solver.assume(activation(1), RunePosition::new(0));
+        assert!(matches!(
+            solver.outcome(first),
+            Outcome::Progress { facts_added } if facts_added > 0
+        ));
+
+        //#2 has to be next to #1.
+        let node = solver.assume(activation(2), RunePosition::new(9));
+        assert!(matches!(
+            solver.outcome(node),
+            Outcome::Contradiction { fact: Some(_), ref blame, .. } if blame.contains(&0)
+        ));
+
+        solver.set_current(first);
+        let solution = solver.solve_all().assignments.remove(0);
+        let mut node = first;
+        for position in (0..12).map(RunePosition::new) {
+            let placed = solution[position].unwrap();
+            let facts = &solver.states[solver.current].facts;
+            if facts.handle_at(position, placed).is_none() {
+                node = solver.assume(placed, position);
+            }
+        }
+        assert_eq!(
+            solver.outcome(node),
+            Outcome::Solved {
+                assignment: solution
+                    .size()
+                    .all_positions()
+                    .map(|it| solution[it])
+                    .collect()
+            }
+        );
+
+        let lock = RuneLock::with_rules(
+            sample_runes(),
+            vec!["if 1 at 0 then 2 at 1".parse().unwrap()],
+        );
+        let mut solver = FactualSolver::new(lock);
+        let node = solver.assume(activation(1), RunePosition::new(0));
+        assert_eq!(
+            solver.outcome(node),
+            Outcome::Forced {
+                placements: vec![(RunePosition::new(1), activation(2))]
+            }
+        );
+    }
+
+    #[test]
+    fn test_candidates_covered_by_a_child_are_not_explored_again() {
+        let lock = RuneLock::with_rules(
+            sample_runes(),
+            vec![
+                "if 1 at 0 then 2 at 1".parse().unwrap(),
+                "if 2 at 1 then 1 at 0".parse().unwrap(),
+            ],
+        );
+        let mut solver = FactualSolver::new(lock);
+        let root = solver.root();
+        let child = solver.assume(activation(1), RunePosition::new(0));
+        solver.set_current(root);
+
+        assert_eq!(
+            solver.covering_child(root, RunePosition::new(1), activation(2)),
+            Some(child)
+        );
+        assert_eq!(
+            solver.covering_child(root, RunePosition::new(0), activation(1)),
+            None
+        );
+        assert_eq!(
+            solver.covering_child(root, RunePosition::new(2), activation(2)),
+            None
+        );
+
+        solver.take_notices();
+        solver.try_possibilities(RunePosition::new(1), false);
+        assert!(solver.take_notices().iter().any(|it| matches!(
+            it,
+            Notice::Covered { child: it, .. } if *it == child
+        )));
+        let covered = |solver: &FactualSolver| {
+            solver.states.children_of(root).any(|it| {
+                matches!(
+                    solver.states[it].action,
+                    SolverAction::Assume { position, activation: it }
+                        if position == RunePosition::new(1) && it == activation(2)
+                )
+            })
+        };
+        assert!(!covered(&solver));
+        solver.try_possibilities(RunePosition::new(1), true);
+        assert!(covered(&solver));
+    }
+
+    #[test]
+    fn test_sweeps_reach_the_states_assuming_each_candidate_would() {
+        let lock = RuneLock::with_rules(
+            sample_runes(),
+            vec![
+                "if 1 at 0 then 2 at 1".parse().unwrap(),
+                "if 1 at 0 then 3 at 1".parse().unwrap(),
+            ],
+        );
+        let mut swept = FactualSolver::new(lock.clone());
+        let root = swept.root();
+        let outcomes = swept.try_possibilities(activation(1), false);
+        assert!(outcomes
+            .iter()
+            .any(|(_, it)| matches!(it, Outcome::Contradiction { .. })));
+
+        let mut assumed = FactualSolver::new(lock);
+        for (node, outcome) in outcomes {
+            let SolverAction::Assume {
+                position,
+                activation,
+            } = swept.states[node].action
+            else {
+                panic!("A sweep only assumes");
+            };
+            assumed.set_current(root);
+            let expected = assumed.assume(activation, position);
+            assert_eq!(outcome, assumed.outcome(expected), "{}", position);
+            if let Outcome::Contradiction { .. } = outcome {
+                continue;
+            }
+            for position in (0..12).map(RunePosition::new) {
+                assert_eq!(
+                    swept.states[node]
+                        .facts
+                        .possibilities_for(position)
+                        .collect::<Vec<_>>(),
+                    assumed.states[expected]
+                        .facts
+                        .possibilities_for(position)
+                        .collect::<Vec<_>>()
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_replacing_a_rune_takes_back_what_followed_from_it() {
+        let lock = RuneLock::with_rules(
+            sample_runes(),
+            vec![
+                "1 2 different-runes".parse().unwrap(),
+                "1 at 0".parse().unwrap(),
+            ],
+        );
+        let mut solver = FactualSolver::new(lock);
+        //Position 9 shares the rune of position 0, where #1 is.
+        let nine = RunePosition::new(9);
+        assert!(!solver
+            .facts()
+            .possibilities_for(nine)
+            .any(|it| it == activation(2)));
+
+        solver.set_rune(nine, "S".parse().unwrap());
+        let fresh = FactualSolver::new(solver.lock().clone());
+        for position in (0..12).map(RunePosition::new) {
+            assert_eq!(
+                solver
+                    .facts()
+                    .possibilities_for(position)
+                    .collect::<Vec<_>>(),
+                fresh
+                    .facts()
+                    .possibilities_for(position)
+                    .collect::<Vec<_>>()
+            );
+        }
+        assert!(solver
+            .facts()
+            .possibilities_for(nine)
+            .any(|it| it == activation(2)));
     }
 
-    pub fn dump_knowledge(&self) {
-        self.states[self.current].facts.info_dump();
+    #[test]
+    fn test_lifting_keeps_the_handles_of_every_state() {
+        let lock = RuneLock::with_rules(
+            sample_runes(),
+            vec![(1, 2).alwanese(), (3, 4).antakian_twins()],
+        );
+        let mut solver = FactualSolver::new(lock);
+        let root = solver.root();
+        let first = solver.assume(activation(1), RunePosition::new(0));
+        let deeper = solver.assume(activation(5), RunePosition::new(6));
+        solver.set_current(root);
+        let second = solver.assume(activation(1), RunePosition::new(1));
+        let nodes = [root, first, deeper, second];
+        let known = |solver: &FactualSolver| {
+            nodes.map(|node| {
+                solver.states[node]
+                    .facts
+                    .iter_facts()
+                    .map(|(handle, it)| (handle, it.kind, it.position, it.activation))
+                    .collect::<Vec<_>>()
+            })
+        };
+        let before = known(&solver);
+
+        //An imported fact about a cell none of the states knows anything about.
+        let (position, activation) = (0..12)
+            .flat_map(|p| (1..=12).map(move |a| (RunePosition::new(p), activation(a))))
+            .find(|(p, a)| {
+                nodes
+                    .iter()
+                    .all(|it| solver.states[*it].facts.handle_at(*p, *a).is_none())
+            })
+            .unwrap();
+        let facts = &mut solver.states[deeper].facts;
+        let note = facts.add_note("imported".to_string());
+        assert!(facts
+            .integrate_and_consolidate(
+                Fact {
+                    kind: FactKind::ActivationCannotBeOn,
+                    activation,
+                    position,
+                    reasons: vec![FactReason::External(note)],
+                    range: Vec::new(),
+                },
+                &solver.lock,
+            )
+            .is_ok());
+        let lifted = solver.lift_universal(deeper);
+        assert_eq!(lifted.len(), 1);
+
+        assert_eq!(solver.states.handles().collect::<Vec<_>>(), nodes);
+        for ((node, before), after) in nodes.iter().zip(before).zip(known(&solver)) {
+            assert_eq!(after[..before.len()], before[..]);
+            assert!(solver.states[*node]
+                .facts
+                .handle_at(position, activation)
+                .is_some());
+        }
+        assert_eq!(
+            solver.states[root].facts.handle_at(position, activation),
+            Some(lifted[0])
+        );
     }
 }
@@ -0,0 +1,164 @@
+//! Finding facts by what they are about, like every fact about position 7, so what a saved session
+//! deduced can be looked up without exploring it again. A pattern is written like a fact:
+//! `<activation> <kind> <position>`, with `*` matching anything, e.g. `#3 cannot 7` or `* * 7`.
+
+use std::str::FromStr;
+
+use thiserror::Error;
+
+use crate::{
+    activation::{Activation, ActivationError, HumanActivation},
+    index::RunePosition,
+    size::MAX_POSITIONS,
+};
+
+use super::{
+    assumption_tree::AssumptionTreeNodeHandle, fact_db::FactHandle, Fact, FactKind, FactualSolver,
+};
+
+#[derive(Debug, Error)]
+pub enum FactPatternError {
+    #[error("A fact pattern is '<activation> <kind> <position>', like '#3 cannot 7' or '* * 7'")]
+    Malformed,
+    #[error("{0}")]
+    Activation(#[from] ActivationError),
+    #[error("'{0}' is not a fact kind, expected must, cannot, contradiction or *")]
+    UnknownKind(String),
+    #[error("'{0}' is not a position")]
+    PositionInvalid(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KindPattern {
+    Must,
+    Cannot,
+    Contradiction,
+}
+
+///Which facts to find, `None` matching anything.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FactPattern {
+    pub activation: Option<Activation>,
+    pub kind: Option<KindPattern>,
+    pub position: Option<RunePosition>,
+}
+
+impl FactPattern {
+    pub fn matches(&self, fact: &Fact) -> bool {
+        let kind = match fact.kind {
+            FactKind::ActivationMustBeOn => KindPattern::Must,
+            FactKind::ActivationCannotBeOn => KindPattern::Cannot,
+            FactKind::Contradiction(_) => KindPattern::Contradiction,
+        };
+        self.activation.is_none_or(|it| it == fact.activation)
+            && self.kind.is_none_or(|it| it == kind)
+            && self
+                .position
+                .is_none_or(|it| fact.positions().any(|position| position == it))
+    }
+}
+
+impl FromStr for FactPattern {
+    type Err = FactPatternError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let [activation, kind, position] = s.split_whitespace().collect::<Vec<_>>()[..] else {
+            return Err(FactPatternError::Malformed);
+        };
+        let any = |it: &str| it == "*";
+        Ok(Self {
+            activation: match activation {
+                it if any(it) => None,
+                it => Some(Activation::from(it.parse::<HumanActivation>()?)),
+            },
+            kind: match kind {
+                it if any(it) => None,
+                "must" => Some(KindPattern::Must),
+                "cannot" => Some(KindPattern::Cannot),
+                "contradiction" => Some(KindPattern::Contradiction),
+                it => return Err(FactPatternError::UnknownKind(it.into())),
+            },
+            position: match position {
+                it if any(it) => None,
+                it => Some(
+                    it.parse::<usize>()
+                        .ok()
+                        .filter(|it| *it < MAX_POSITIONS)
+                        .map(RunePosition::new)
+                        .ok_or_else(|| FactPatternError::PositionInvalid(it.into()))?,
+                ),
+            },
+        })
+    }
+}
+
+impl FactualSolver {
+    ///The facts matching the pattern, with the state they were deduced in. A fact the parent
+    ///state knows as well is only listed for the parent.
+    pub fn find_facts(
+        &self,
+        pattern: &FactPattern,
+    ) -> Vec<(AssumptionTreeNodeHandle, FactHandle, Fact)> {
+        let mut found = Vec::new();
+        for node in self.states.handles() {
+            let facts = &self.states[node].facts;
+            let parent = self.states.parent_of(node).map(|it| &self.states[it].facts);
+            for (handle, fact) in facts.iter_facts() {
+                let inherited = parent.is_some_and(|parent| {
+                    parent
+                        .handle_at(fact.position, fact.activation)
+                        .and_then(|it| parent.get(it))
+                        .is_some_and(|it| it.kind == fact.kind)
+                });
+                if !inherited && pattern.matches(fact) {
+                    found.push((node, handle, fact.clone()));
+                }
+            }
+        }
+        found
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        fact_solver::FactualSolver,
+        index::RunePosition,
+        rule::ActivationRuleKindHelpers,
+        test_support::{activation, sample_runes},
+        RuneLock,
+    };
+
+    use super::{FactPattern, KindPattern};
+
+    #[test]
+    fn test_facts_are_found_in_the_state_they_were_deduced_in() {
+        let pattern: FactPattern = "#2 cannot *".parse().unwrap();
+        assert_eq!(pattern.kind, Some(KindPattern::Cannot));
+        assert_eq!(pattern.position, None);
+        assert!("#2 cannot".parse::<FactPattern>().is_err());
+        assert!("#2 maybe 3".parse::<FactPattern>().is_err());
+        assert!("* * 64".parse::<FactPattern>().is_err());
+
+        let lock = RuneLock::with_rules(
+            sample_runes(),
+            vec![(1, 2).alwanese(), (2, 3).increase_santor()],
+        );
+        let mut solver = FactualSolver::new(lock);
+        let root = solver.current();
+        let first = solver.assume(activation(1), RunePosition::new(0));
+        solver.set_current(root);
+        solver.assume(activation(1), RunePosition::new(1));
+
+        let found = solver.find_facts(&pattern);
+        assert!(!found.is_empty());
+        assert!(found.iter().any(|(node, _, _)| *node == first));
+        assert!(found
+            .iter()
+            .all(|(_, _, fact)| fact.activation == activation(2)));
+
+        let assumed = solver.find_facts(&"#1 must 0".parse().unwrap());
+        assert_eq!(assumed.len(), 1);
+        assert_eq!(assumed[0].0, first);
+    }
+}
@@ -0,0 +1,324 @@
+use std::collections::HashSet;
+
+use crate::{
+    activation::Activation, assignment::Assignment, index::RunePosition, jobs::CancelToken,
+    RuneLock,
+};
+
+use super::{
+    fact_db::{FactDb, FactHandle},
+    grid::GridAxis,
+    view::View,
+    ContradictionKind, Fact, FactKind, FactReason,
+};
+
+type Cell = (RunePosition, Activation);
+
+///Checks whether `reasons` alone are sufficient to derive `fact`, using the same deduction steps
+///the consolidation uses. Eliminations by a given placement and the uniqueness of placements are
+///checked directly, everything else by consolidating the rules among `reasons` on the facts among
+///them, see `derived_by_rules`.
+pub fn entails(db: &FactDb, lock: &RuneLock, fact: &Fact, reasons: &[FactReason]) -> bool {
+    if fact.reasons.contains(&FactReason::Assumption) {
+        return reasons.contains(&FactReason::Assumption);
+    }
+    let givens = || {
+        fact.reasons
+            .iter()
+            .filter(|it| matches!(it, FactReason::Given(_) | FactReason::External(_)))
+    };
+    if givens().next().is_some() {
+        return givens().all(|it| reasons.contains(it));
+    }
+
+    let reason_facts: Vec<&Fact> = reasons
+        .iter()
+        .filter_map(|it| match it {
+            FactReason::Fact(handle, _) => db.get(*handle),
+            _ => None,
+        })
+        .collect();
+    let cell = (fact.position, fact.activation);
+
+    let direct = match fact.kind {
+        //A range fact holds if every one of its cells is excluded.
+        FactKind::ActivationCannotBeOn => fact.positions().all(|position| {
+            let cell = (position, fact.activation);
+            let excluded_by_given = reason_facts.iter().any(|it| {
+                it.kind == FactKind::ActivationMustBeOn
+                    && (it.position == position) != (it.activation == fact.activation)
+            });
+            excluded_by_given || excluded_by_rule(lock, cell, &reason_facts, reasons)
+        }),
+        FactKind::ActivationMustBeOn => {
+            let excluded = excluded_cells(db, &reason_facts);
+            let others_excluded = |axis: GridAxis| {
+                let (index, other_axis) = match axis {
+                    GridAxis::Position => (fact.position.index(), GridAxis::Activation),
+                    GridAxis::Activation => (fact.activation.index(), GridAxis::Position),
+                };
+                (0..db.len_of(other_axis))
+                    .map(|other| lane_cell(axis, index, other))
+                    .filter(|it| *it != cell)
+                    .all(|it| excluded.contains(&it))
+            };
+            others_excluded(RunePosition::axis()) || others_excluded(Activation::axis())
+        }
+        FactKind::Contradiction(ContradictionKind::ContradictingRequirements) => {
+            let on_cell = |kind: FactKind| {
+                reason_facts
+                    .iter()
+                    .any(|it| it.kind == kind && it.covers(fact.position, fact.activation))
+            };
+            on_cell(FactKind::ActivationMustBeOn) && on_cell(FactKind::ActivationCannotBeOn)
+        }
+        FactKind::Contradiction(ContradictionKind::NoOptionsLeft) => {
+            let excluded = excluded_cells(db, &reason_facts);
+            let all_excluded = |axis: GridAxis, index: usize, other_axis: GridAxis| {
+                (0..db.len_of(other_axis))
+                    .all(|other| excluded.contains(&lane_cell(axis, index, other)))
+            };
+            all_excluded(
+                GridAxis::Position,
+                fact.position.index(),
+                GridAxis::Activation,
+            ) || all_excluded(
+                GridAxis::Activation,
+                fact.activation.index(),
+                GridAxis::Position,
+            )
+        }
+    };
+    direct || derived_by_rules(db, lock, fact, &reason_facts, reasons)
+}
+
+///Whether consolidating, and deepening, with only the rules among `reasons` derives `fact` from
+///nothing but `reason_facts`. This covers what the direct checks of `entails` can't replay, like
+///pair analysis, santor chains, different runes and rune counts.
+fn derived_by_rules(
+    db: &FactDb,
+    lock: &RuneLock,
+    fact: &Fact,
+    reason_facts: &[&Fact],
+    reasons: &[FactReason],
+) -> bool {
+    let mut rules = lock.without_rules();
+    for reason in reasons {
+        if let FactReason::Rule(rule) = reason {
+            rules.add_rule(lock.rules()[*rule].clone(), 0);
+        }
+    }
+    if rules.rules().is_empty() {
+        return false;
+    }
+    let derived = db
+        .knowing_only(reason_facts.iter().copied())
+        .and_then(|mut it| {
+            it.consolidate(&rules)?;
+            it.deepen(&rules, &CancelToken::default())?;
+            Ok(it)
+        });
+    let derived = match (derived, fact.kind) {
+        (Ok(it), _) => it,
+        //The reasons contradict each other, which only entails a contradiction.
+        (Err(_), kind) => return matches!(kind, FactKind::Contradiction(_)),
+    };
+    let known_as = |position| {
+        derived
+            .handle_at(position, fact.activation)
+            .and_then(|it| derived.get(it))
+            .map(|it| it.kind)
+    };
+    match fact.kind {
+        FactKind::ActivationCannotBeOn => fact
+            .positions()
+            .all(|it| known_as(it) == Some(FactKind::ActivationCannotBeOn)),
+        FactKind::ActivationMustBeOn => known_as(fact.position) == Some(fact.kind),
+        FactKind::Contradiction(_) => false,
+    }
+}
+
+///Greedily drops reasons of the given fact as long as the remaining ones still entail it.
+///Reasons of facts that cannot be verified are returned unchanged.
+pub fn minimal_reasons(db: &FactDb, lock: &RuneLock, handle: FactHandle) -> Vec<FactReason> {
+    let fact = match db.get(handle) {
+        Some(it) => it,
+        None => return Vec::new(),
+    };
+    let mut reasons = fact.reasons.clone();
+    if !entails(db, lock, fact, &reasons) {
+        return reasons;
+    }
+
+    //Later facts tend to have the longer chains behind them, so try to get rid of them first.
+    let mut candidates = reasons.clone();
+    candidates.sort_by_key(|it| match it {
+        FactReason::Fact(handle, _) => std::cmp::Reverse(Some(*handle)),
+        _ => std::cmp::Reverse(None),
+    });
+    for candidate in candidates {
+        let without: Vec<_> = reasons
+            .iter()
+            .filter(|it| **it != candidate)
+            .copied()
+            .collect();
+        if entails(db, lock, fact, &without) {
+            reasons = without;
+        }
+    }
+    reasons
+}
+
+fn excluded_by_rule(
+    lock: &RuneLock,
+    cell: Cell,
+    reason_facts: &[&Fact],
+    reasons: &[FactReason],
+) -> bool {
+    let rules = reasons.iter().filter_map(|it| match it {
+        FactReason::Rule(rule) => Some(lock.rules[*rule].clone()),
+        _ => None,
+    });
+    for rule in rules {
+        for given in reason_facts
+            .iter()
+            .filter(|it| it.kind == FactKind::ActivationMustBeOn)
+        {
+            let violated = if (given.position, given.activation) == cell {
+                Assignment::from_tuple_iter(lock.size(), std::iter::once(cell))
+                    .map(|it| rule.validate(lock, &it).is_err())
+                    .unwrap_or(false)
+            } else {
+                rule.validate_tuple(lock, (given.position, given.activation), cell)
+                    .is_err()
+            };
+            if violated {
+                return true;
+            }
+        }
+    }
+    false
+}
+
+fn excluded_cells(db: &FactDb, reason_facts: &[&Fact]) -> HashSet<Cell> {
+    let mut excluded = HashSet::new();
+    for fact in reason_facts {
+        match fact.kind {
+            FactKind::ActivationCannotBeOn => {
+                excluded.extend(fact.positions().map(|it| (it, fact.activation)));
+            }
+            FactKind::ActivationMustBeOn => {
+                for activation in 0..db.len_of(GridAxis::Activation) {
+                    let activation = Activation::from_usize(activation);
+                    if activation != fact.activation {
+                        excluded.insert((fact.position, activation));
+                    }
+                }
+                for position in 0..db.len_of(GridAxis::Position) {
+                    let position = RunePosition::from_usize(position);
+                    if position != fact.position {
+                        excluded.insert((position, fact.activation));
+                    }
+                }
+            }
+            FactKind::Contradiction(_) => {}
+        }
+    }
+    excluded
+}
+
+fn lane_cell(axis: GridAxis, index: usize, other: usize) -> Cell {
+    match axis {
+        GridAxis::Position => (
+            RunePosition::from_usize(index),
+            Activation::from_usize(other),
+        ),
+        GridAxis::Activation => (
+            RunePosition::from_usize(other),
+            Activation::from_usize(index),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        fact_solver::{fact_db::FactDb, Fact, FactKind, FactReason},
+        index::RunePosition,
+        jobs::CancelToken,
+        rule::ActivationRuleKindHelpers,
+        size::LockSize,
+        test_support::{activation, sample_runes},
+        RuneLock,
+    };
+
+    use super::{entails, minimal_reasons};
+
+    #[test]
+    fn test_minimal_reasons_still_entail() {
+        let lock = RuneLock::with_rules(
+            sample_runes(),
+            vec![(1, 2).alwanese(), (2, 3).increase_santor()],
+        );
+        let mut db = FactDb::new(LockSize::STANDARD);
+        let _ = db.integrate_and_consolidate(
+            Fact {
+                kind: FactKind::ActivationMustBeOn,
+                activation: activation(1),
+                position: RunePosition::new(0),
+                reasons: vec![FactReason::Assumption],
+                range: Vec::new(),
+            },
+            &lock,
+        );
+
+        assert!(db.fact_count() > 1);
+        for (handle, fact) in db.iter_facts() {
+            assert!(entails(&db, &lock, fact, &fact.reasons), "{}", fact);
+            let minimal = minimal_reasons(&db, &lock, handle);
+            assert!(minimal.iter().all(|it| fact.reasons.contains(it)));
+            assert!(entails(&db, &lock, fact, &minimal), "{}", fact);
+        }
+    }
+
+    #[test]
+    fn test_pair_analysis_facts_are_minimized() {
+        let lock = RuneLock::with_rules(
+            sample_runes(),
+            vec![
+                (5, 4).antakian_conjugate(),
+                (8, 11).alwanese_conjugate(),
+                (8, 12).alwanese_conjugate(),
+            ],
+        );
+        let mut db = FactDb::new(LockSize::STANDARD);
+        assert!(db
+            .integrate_and_consolidate(
+                Fact {
+                    kind: FactKind::ActivationMustBeOn,
+                    activation: activation(11),
+                    position: RunePosition::new(7),
+                    reasons: vec![FactReason::Assumption],
+                    range: Vec::new(),
+                },
+                &lock,
+            )
+            .is_ok());
+        let known = db.fact_count();
+        assert!(matches!(
+            db.deepen(&lock, &CancelToken::default()),
+            Ok(true)
+        ));
+
+        //Pair analysis cites every fact about the related activation, not all of them are needed.
+        let minimized = db
+            .facts_since(known)
+            .filter(|(handle, fact)| {
+                let minimal = minimal_reasons(&db, &lock, *handle);
+                assert!(entails(&db, &lock, fact, &minimal), "{}", fact);
+                minimal.len() < fact.reasons.len()
+            })
+            .count();
+        assert!(minimized > 0);
+    }
+}
@@ -0,0 +1,267 @@
+//! Taking back a single given of the root state, e.g. a domain or an imported row that was
+//! transcribed wrongly, without rebuilding the session by hand.
+
+use std::fmt::{Display, Formatter};
+
+use thiserror::Error;
+
+use crate::{activation::Activation, domain::Region, index::RunePosition};
+
+use super::{
+    assumption_tree::AssumptionTreeNodeHandle,
+    fact_db::{FactDb, FactError::Contradiction, FactHandle},
+    Fact, FactReason, FactualSolver, SolverStateState,
+};
+
+#[derive(Debug, Error)]
+pub enum RetractError {
+    #[error("The root state knows nothing about {activation} on {position}")]
+    Unknown {
+        position: RunePosition,
+        activation: Activation,
+    },
+    #[error("{0} is not a given, but derived from other facts")]
+    Derived(FactHandle),
+}
+
+///What the root state gave up.
+pub enum Retracted {
+    ///The position was added to the domain with this index.
+    Domain(usize),
+    ///The imported row with this note was dropped.
+    External(String),
+}
+
+///What a state concluded, as far as comparing it before and after a retraction goes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Conclusion {
+    pub contradicts: bool,
+    ///Candidates neither placed nor eliminated.
+    pub open: usize,
+}
+
+pub struct BranchChange {
+    pub node: AssumptionTreeNodeHandle,
+    pub before: Conclusion,
+    pub after: Conclusion,
+}
+
+pub struct Retraction {
+    pub retracted: Retracted,
+    ///Whether the root state still knows the same about the cell, derived from what's left.
+    pub still_known: bool,
+    pub changes: Vec<BranchChange>,
+}
+
+impl Display for Conclusion {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self.contradicts {
+            true => write!(f, "contradicted"),
+            false => write!(f, "{} open candidates", self.open),
+        }
+    }
+}
+
+impl Display for BranchChange {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {} -> {}", self.node, self.before, self.after)
+    }
+}
+
+pub(super) fn conclusion(facts: &FactDb, state: &SolverStateState) -> Conclusion {
+    let open = facts
+        .size()
+        .cells()
+        .filter(|(position, activation)| facts.handle_at(*position, *activation).is_none())
+        .count();
+    Conclusion {
+        contradicts: matches!(state, SolverStateState::Contradicts(_)),
+        open,
+    }
+}
+
+///The imported facts of the root state with their notes, except the one with handle `except`.
+pub(super) fn externals(facts: &FactDb, except: Option<FactHandle>) -> Vec<(Fact, String)> {
+    facts
+        .iter_facts()
+        .filter(|(it, _)| Some(*it) != except)
+        .filter_map(|(_, fact)| {
+            fact.reasons.iter().find_map(|reason| match reason {
+                FactReason::External(note) => Some((fact.clone(), facts.note(*note).into())),
+                _ => None,
+            })
+        })
+        .collect()
+}
+
+impl FactualSolver {
+    ///Rebuilds the root state from the givens of the lock and the imported facts, and replays
+    ///every assumption on top of it.
+    pub(super) fn rebuild_root(&mut self, externals: Vec<(Fact, String)>) {
+        let mut facts = FactDb::new(self.lock.size());
+        let mut rebuilt = facts.integrate_givens(&self.lock);
+        for (fact, note) in externals {
+            if rebuilt.is_err() {
+                break;
+            }
+            let note = facts.add_note(note);
+            rebuilt = facts.integrate_and_consolidate(
+                Fact {
+                    reasons: vec![FactReason::External(note)],
+                    ..fact
+                },
+                &self.lock,
+            );
+        }
+        let root = self.states.root();
+        let state = &mut self.states[root];
+        state.state = match rebuilt {
+            Ok(()) => SolverStateState::Unexplored,
+            Err(Contradiction(reason)) => SolverStateState::Contradicts(reason),
+        };
+        state.facts = facts;
+        self.publish(root, 0);
+        self.reroot();
+    }
+
+    ///Takes back the given of the root state about the activation on the position: a domain
+    ///ruling it out is widened to include the position, an imported row is dropped. The root
+    ///state is rebuilt from the remaining givens and every assumption is replayed on top of it.
+    pub fn retract_given(
+        &mut self,
+        position: RunePosition,
+        activation: Activation,
+    ) -> Result<Retraction, RetractError> {
+        let root = self.states.root();
+        let facts = &self.states[root].facts;
+        let handle = facts
+            .handle_at(position, activation)
+            .ok_or(RetractError::Unknown {
+                position,
+                activation,
+            })?;
+        let fact = facts.get(handle).ok_or(RetractError::Unknown {
+            position,
+            activation,
+        })?;
+        let retracted = fact
+            .reasons
+            .iter()
+            .find_map(|reason| match reason {
+                FactReason::Given(domain) => Some(Retracted::Domain(*domain)),
+                FactReason::External(note) => Some(Retracted::External(facts.note(*note).into())),
+                _ => None,
+            })
+            .ok_or(RetractError::Derived(handle))?;
+        let kind = fact.kind;
+        let externals = externals(facts, Some(handle));
+
+        let before: Vec<_> = self
+            .states
+            .handles()
+            .map(|node| {
+                let state = &self.states[node];
+                (node, conclusion(&state.facts, &state.state))
+            })
+            .collect();
+
+        if let Retracted::Domain(index) = retracted {
+            let size = self.lock.size();
+            let domain = &mut self.lock.domains[index];
+            let positions = size
+                .all_positions()
+                .filter(|it| *it == position || domain.contains(*it, size))
+                .collect();
+            domain.region = Region::Positions(positions);
+        }
+        self.rebuild_root(externals);
+
+        let root_facts = &self.states[root].facts;
+        let still_known = root_facts
+            .handle_at(position, activation)
+            .and_then(|it| root_facts.get(it))
+            .is_some_and(|it| it.kind == kind);
+        let changes = before
+            .into_iter()
+            .filter_map(|(node, before)| {
+                let state = &self.states[node];
+                let after = conclusion(&state.facts, &state.state);
+                (after != before).then_some(BranchChange {
+                    node,
+                    before,
+                    after,
+                })
+            })
+            .collect();
+        Ok(Retraction {
+            retracted,
+            still_known,
+            changes,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        domain::{ActivationDomain, Region},
+        fact_solver::{import::parse_csv, FactualSolver},
+        index::RunePosition,
+        rule::ActivationRuleKindHelpers,
+        test_support::{activation, sample_runes},
+        RuneLock,
+    };
+
+    use super::{RetractError, Retracted};
+
+    #[test]
+    fn test_retracted_domains_are_widened() {
+        let mut lock = RuneLock::with_rules(sample_runes(), vec![(1, 2).alwanese()]);
+        lock.add_domain(ActivationDomain {
+            activation: activation(3),
+            region: Region::OuterRing,
+        });
+        let mut solver = FactualSolver::new(lock);
+        let assumed = solver.assume(activation(3), RunePosition::new(0));
+        let open_before = solver.open_candidates();
+
+        let retraction = solver
+            .retract_given(RunePosition::new(7), activation(3))
+            .unwrap();
+        assert!(matches!(retraction.retracted, Retracted::Domain(0)));
+        assert!(!retraction.still_known);
+        assert!(retraction.changes.iter().any(|it| it.node == solver.root()));
+        let size = solver.lock().size();
+        assert!(solver.lock().domains()[0].contains(RunePosition::new(7), size));
+        assert!(!solver.lock().domains()[0].contains(RunePosition::new(8), size));
+        assert_eq!(solver.current(), assumed);
+        assert_eq!(solver.open_candidates(), open_before);
+
+        assert!(matches!(
+            solver.retract_given(RunePosition::new(7), activation(3)),
+            Err(RetractError::Unknown { .. })
+        ));
+    }
+
+    #[test]
+    fn test_retracted_rows_are_dropped() {
+        let lock = RuneLock::with_rules(sample_runes(), vec![(1, 2).alwanese()]);
+        let mut solver = FactualSolver::new(lock);
+        solver.import(&parse_csv("0,1,assigned,typo\n", solver.lock().size()).unwrap());
+        let open_before = solver.open_candidates();
+
+        assert!(matches!(
+            solver.retract_given(RunePosition::new(0), activation(2)),
+            Err(RetractError::Derived(_))
+        ));
+        let retraction = solver
+            .retract_given(RunePosition::new(0), activation(1))
+            .unwrap();
+        assert!(
+            matches!(retraction.retracted, Retracted::External(ref note) if note.contains("typo"))
+        );
+        assert!(!retraction.still_known);
+        assert_eq!(retraction.changes.len(), 1);
+        assert!(solver.open_candidates() > open_before);
+    }
+}
@@ -0,0 +1,234 @@
+//! Estimating how likely each open candidate is from random completions of the current state, for
+//! when counting every solution is too expensive to prioritize assumptions by.
+
+use std::{
+    fmt::{Display, Formatter},
+    ops::ControlFlow,
+};
+
+use itertools::Itertools;
+
+use crate::{activation::Activation, index::RunePosition, size::LockSize, terminal};
+
+use super::{search::search_from, FactualSolver, SolverStateState};
+
+///How many states a run visits before it starts over, doubled with every restart in a row.
+const RESTART_NODES: usize = 32;
+
+pub struct Sampling {
+    pub size: LockSize,
+    ///How many solutions were asked for.
+    pub runs: usize,
+    ///How many were found, fewer than asked for if there are none or sampling was interrupted.
+    pub solutions: usize,
+    ///Indexed by position, then activation: how many sampled solutions placed it there.
+    pub uses: Vec<Vec<usize>>,
+    ///Whether the cell was undecided in the sampled state.
+    pub open: Vec<Vec<bool>>,
+    ///How often a run gave up on its order of candidates and started over.
+    pub restarts: usize,
+    ///Whether every run finished, instead of being interrupted.
+    pub complete: bool,
+}
+
+impl Sampling {
+    ///The share of the sampled solutions that place the activation on the position, if the cell
+    ///was undecided and there was a solution.
+    pub fn fraction(&self, position: RunePosition, activation: Activation) -> Option<f64> {
+        let (position, activation) = (position.index(), activation.index());
+        (self.open[position][activation] && self.solutions > 0)
+            .then(|| self.uses[position][activation] as f64 / self.solutions as f64)
+    }
+}
+
+impl FactualSolver {
+    ///Completes the current state `runs` times, each time trying the candidates in a different
+    ///random order and stopping at the first solution. The solutions are not drawn uniformly, but
+    ///are enough to tell likely candidates from unlikely ones. Sampling stops early if the state
+    ///has no solution.
+    pub fn sample(&self, runs: usize, seed: u64) -> Sampling {
+        let facts = &self.states[self.current].facts;
+        let size = self.lock.size();
+        let open = size
+            .all_positions()
+            .map(|position| {
+                size.all_activations()
+                    .map(|activation| facts.handle_at(position, activation).is_none())
+                    .collect()
+            })
+            .collect();
+        let mut sampling = Sampling {
+            size,
+            runs,
+            solutions: 0,
+            uses: vec![vec![0; size.activations()]; size.positions()],
+            open,
+            restarts: 0,
+            complete: true,
+        };
+        if matches!(
+            self.states[self.current].state,
+            SolverStateState::Contradicts(_)
+        ) {
+            return sampling;
+        }
+
+        let mut rng = fastrand::Rng::with_seed(seed);
+        let mut limit = RESTART_NODES;
+        while sampling.solutions < runs {
+            let mut solution = None;
+            let flow = search_from(
+                facts,
+                &self.lock,
+                &self.interrupt,
+                Some(&mut rng),
+                &mut 0,
+                limit,
+                &mut |assignment| {
+                    solution = Some(assignment);
+                    ControlFlow::Break(())
+                },
+            );
+            let Some(solution) = solution else {
+                if self.interrupt.is_cancelled() {
+                    sampling.complete = false;
+                    break;
+                }
+                if flow.is_continue() {
+                    //There is no solution for any other run to find.
+                    break;
+                }
+                //An unlucky order can lead into a large dead end, starting over with another
+                //order is usually faster than backtracking out of it.
+                sampling.restarts += 1;
+                limit *= 2;
+                continue;
+            };
+            limit = RESTART_NODES;
+            sampling.solutions += 1;
+            for position in size.all_positions() {
+                if let Some(activation) = solution[position] {
+                    sampling.uses[position.index()][activation.index()] += 1;
+                }
+            }
+        }
+        sampling
+    }
+}
+
+impl Display for Sampling {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if terminal::accessible() {
+            for position in self.size.all_positions() {
+                let fractions = self
+                    .size
+                    .all_activations()
+                    .filter_map(|it| Some((it, self.fraction(position, it)?)))
+                    .map(|(it, fraction)| format!("{} {:.0} percent", it, fraction * 100.0))
+                    .join(", ");
+                if !fractions.is_empty() {
+                    writeln!(f, "Position {}: {}", position, fractions)?;
+                }
+            }
+        } else {
+            self.fmt_grid(f)?;
+        }
+        match (self.complete, self.solutions) {
+            (true, 0) => write!(f, "The state has no solution."),
+            (true, _) => write!(
+                f,
+                "{} solutions sampled, starting over {} times.",
+                self.solutions, self.restarts
+            ),
+            (false, _) => write!(
+                f,
+                "Interrupted after sampling {} of {} solutions.",
+                self.solutions, self.runs
+            ),
+        }
+    }
+}
+
+impl Sampling {
+    fn fmt_grid(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "Rows: positions, columns: activations. Percent of the sampled solutions using an undecided candidate, . if it is decided."
+        )?;
+        write!(f, "   ")?;
+        for activation in self.size.all_activations() {
+            write!(f, "{:>5}", activation.to_string())?;
+        }
+        writeln!(f)?;
+        for position in self.size.all_positions() {
+            write!(f, "{:>3}", position)?;
+            for activation in self.size.all_activations() {
+                match self.fraction(position, activation) {
+                    Some(fraction) => write!(f, "{:>5.0}", fraction * 100.0)?,
+                    None => write!(f, "    {}", terminal::dimmed("."))?,
+                }
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        fact_solver::FactualSolver,
+        index::RunePosition,
+        rule::ActivationRuleKindHelpers,
+        test_support::{activation, sample_runes},
+        RuneLock,
+    };
+
+    #[test]
+    fn test_samples_are_solutions_of_the_state() {
+        let lock = RuneLock::with_rules(
+            sample_runes(),
+            vec![
+                (1, 2).alwanese(),
+                (2, 3).antakian_conjugate(),
+                (3, 4).alwanese(),
+                (4, 5).antakian_twins(),
+                (5, 6).increase_santor(),
+                (6, 7).alwanese(),
+                (7, 8).antakian_conjugate(),
+                (8, 9).alwanese(),
+                (9, 10).antakian_twins(),
+                (10, 11).increase_santor(),
+            ],
+        );
+        let mut solver = FactualSolver::new(lock);
+        solver.assume(activation(1), RunePosition::new(0));
+
+        let sampling = solver.sample(10, 7);
+        assert!(sampling.complete);
+        assert_eq!(sampling.solutions, 10);
+        assert_eq!(sampling.fraction(RunePosition::new(0), activation(1)), None);
+        for position in (1..12).map(RunePosition::new) {
+            let fractions: Vec<f64> = (1..=12)
+                .filter_map(|it| sampling.fraction(position, activation(it)))
+                .collect();
+            //A position with a candidate left has its activation undecided.
+            if !fractions.is_empty() {
+                assert!((fractions.iter().sum::<f64>() - 1.0).abs() < 1e-9);
+            }
+        }
+        let solutions = solver.solve_all().assignments;
+        for position in (1..12).map(RunePosition::new) {
+            for it in 1..=12 {
+                if sampling
+                    .fraction(position, activation(it))
+                    .is_some_and(|it| it > 0.0)
+                {
+                    assert!(solutions
+                        .iter()
+                        .any(|solution| solution[position] == Some(activation(it))));
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,223 @@
+//! Complete backtracking search over the fact database, for when driving `assume` and `try` by
+//! hand isn't worth it.
+
+use std::ops::ControlFlow;
+
+use crate::{
+    activation::Activation, assignment::Assignment, index::RunePosition, jobs::CancelToken,
+    RuneLock,
+};
+
+use super::{fact_db::FactDb, Fact, FactKind, FactReason, FactualSolver, SolverStateState};
+
+pub struct Solutions {
+    pub assignments: Vec<Assignment>,
+    ///Whether the search ran to the end, instead of being interrupted.
+    pub complete: bool,
+}
+
+pub struct SolutionCount {
+    pub count: usize,
+    ///Whether every solution was counted, instead of stopping at the limit or being interrupted.
+    pub complete: bool,
+    ///How many states the search visited.
+    pub nodes: usize,
+}
+
+impl FactualSolver {
+    ///Every assignment that extends the current state and satisfies all rules. Starting from the
+    ///root, these are all solutions of the lock.
+    pub fn solve_all(&self) -> Solutions {
+        let mut assignments = Vec::new();
+        let flow = self.search(&mut 0, |assignment| {
+            assignments.push(assignment);
+            ControlFlow::Continue(())
+        });
+        Solutions {
+            assignments,
+            complete: flow.is_continue(),
+        }
+    }
+
+    ///Counts the solutions that extend the current state, stopping once `limit` were found. A
+    ///limit of two is enough to tell whether the solution is unique.
+    pub fn count_solutions(&self, limit: usize) -> SolutionCount {
+        let (mut count, mut nodes) = (0, 0);
+        let flow = self.search(&mut nodes, |_| {
+            count += 1;
+            match count < limit {
+                true => ControlFlow::Continue(()),
+                false => ControlFlow::Break(()),
+            }
+        });
+        SolutionCount {
+            count,
+            complete: flow.is_continue(),
+            nodes,
+        }
+    }
+
+    ///Calls `found` with every solution that extends the current state, until it breaks or the
+    ///search is interrupted, counting the visited states in `nodes`.
+    fn search(
+        &self,
+        nodes: &mut usize,
+        mut found: impl FnMut(Assignment) -> ControlFlow<()>,
+    ) -> ControlFlow<()> {
+        let state = &self.states[self.current];
+        match state.state {
+            SolverStateState::Contradicts(_) => ControlFlow::Continue(()),
+            SolverStateState::Unexplored => search_from(
+                &state.facts,
+                &self.lock,
+                &self.interrupt,
+                None,
+                nodes,
+                usize::MAX,
+                &mut found,
+            ),
+        }
+    }
+}
+
+///The candidates of the position with the fewest activations left, which keeps the search tree
+///narrow, or `None` once every activation is placed. A lock of fewer activations than positions
+///may leave a position empty, so there the positions left for the activation with the fewest of
+///them are branched on instead.
+pub(super) fn narrowest_branch(
+    facts: &FactDb,
+    fixed: &Assignment,
+) -> Option<Vec<(RunePosition, Activation)>> {
+    let size = facts.size();
+    if !size.is_square() {
+        return size
+            .all_activations()
+            .filter(|it| fixed.position_of(*it).is_none())
+            .map(|activation| {
+                facts
+                    .possibilities_for(activation)
+                    .map(|position| (position, activation))
+                    .collect::<Vec<_>>()
+            })
+            .min_by_key(|candidates| candidates.len());
+    }
+    size.all_positions()
+        .filter(|it| fixed[*it].is_none())
+        .map(|position| {
+            facts
+                .possibilities_for(position)
+                .map(|activation| (position, activation))
+                .collect::<Vec<_>>()
+        })
+        .min_by_key(|candidates| candidates.len())
+}
+
+///Searches the completions of the facts depth first, giving up once `limit` states were visited.
+///With a random number generator, the candidates of a branch are tried in a random order.
+pub(super) fn search_from(
+    facts: &FactDb,
+    lock: &RuneLock,
+    interrupt: &CancelToken,
+    mut rng: Option<&mut fastrand::Rng>,
+    nodes: &mut usize,
+    limit: usize,
+    found: &mut impl FnMut(Assignment) -> ControlFlow<()>,
+) -> ControlFlow<()> {
+    if interrupt.is_cancelled() || *nodes >= limit {
+        return ControlFlow::Break(());
+    }
+    *nodes += 1;
+    let Ok(fixed) = facts.fixed_assignment() else {
+        return ControlFlow::Continue(());
+    };
+    let Some(mut candidates) = narrowest_branch(facts, &fixed) else {
+        return match lock.validate(&fixed) {
+            Ok(_) => found(fixed),
+            Err(_) => ControlFlow::Continue(()),
+        };
+    };
+    if let Some(rng) = rng.as_deref_mut() {
+        rng.shuffle(&mut candidates);
+    }
+    for (position, activation) in candidates {
+        let mut branch = facts.clone();
+        let assumed = branch.integrate_and_consolidate(
+            Fact {
+                kind: FactKind::ActivationMustBeOn,
+                reasons: vec![FactReason::Assumption],
+                position,
+                activation,
+                range: Vec::new(),
+            },
+            lock,
+        );
+        if assumed.is_ok() {
+            search_from(
+                &branch,
+                lock,
+                interrupt,
+                rng.as_deref_mut(),
+                nodes,
+                limit,
+                found,
+            )?;
+        }
+    }
+    ControlFlow::Continue(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        fact_solver::{verify::enumerate, FactualSolver},
+        index::RunePosition,
+        jobs::CancelToken,
+        rule::ActivationRuleKindHelpers,
+        test_support::{activation, sample_runes},
+        RuneLock,
+    };
+
+    #[test]
+    fn test_solve_all_finds_every_completion() {
+        let lock = RuneLock::with_rules(
+            sample_runes(),
+            vec![
+                (1, 2).alwanese(),
+                (2, 3).antakian_conjugate(),
+                (3, 4).alwanese(),
+                (4, 5).antakian_twins(),
+                (5, 6).increase_santor(),
+                (6, 7).alwanese(),
+                (7, 8).antakian_conjugate(),
+                (8, 9).alwanese(),
+                (9, 10).antakian_twins(),
+                (10, 11).increase_santor(),
+            ],
+        );
+        let first = (RunePosition::new(0), activation(1));
+        let mut solver = FactualSolver::new(lock.clone());
+        solver.assume(first.1, first.0);
+
+        let solutions = solver.solve_all();
+        let completions = enumerate(
+            &lock,
+            &[first],
+            usize::MAX,
+            &CancelToken::default(),
+            usize::MAX,
+            |_, _| {},
+        );
+        assert!(solutions.complete);
+        assert_eq!(solutions.assignments.len(), completions.count);
+        let counted = solver.count_solutions(usize::MAX);
+        assert!(counted.complete);
+        assert_eq!(counted.count, completions.count);
+        let limited = solver.count_solutions(2);
+        assert!(!limited.complete);
+        assert_eq!(limited.count, 2);
+        for assignment in solutions.assignments.iter() {
+            assert!(lock.validate(assignment).is_ok());
+            assert_eq!(assignment[first.0], Some(first.1));
+        }
+    }
+}
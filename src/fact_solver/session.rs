@@ -0,0 +1,219 @@
+//! Saving an investigation to disk and resuming it later: the lock, the whole assumption tree
+//! with the facts of every state, and the current state, as JSON.
+
+use std::{
+    fs::{self, File},
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+use serde_json::value::RawValue;
+use thiserror::Error;
+
+use super::FactualSolver;
+
+///How many earlier saves are kept next to a session file, as `<file>.1` (the latest) up to
+///`<file>.3`.
+pub const BACKUPS: usize = 3;
+
+#[derive(Debug, Error)]
+pub enum SessionError {
+    #[error("Could not access {path}: {source}")]
+    Io { path: String, source: io::Error },
+    #[error("{path} is not a saved session: {source}")]
+    Format {
+        path: String,
+        source: serde_json::Error,
+    },
+    #[error("{path} is damaged, its checksum doesn't match the session")]
+    Damaged { path: String },
+}
+
+///A session as written to disk, with the checksum of its JSON.
+#[derive(Serialize, Deserialize)]
+struct SessionFile {
+    checksum: String,
+    session: Box<RawValue>,
+}
+
+///FNV-1a, which is enough to notice a file that was cut off or changed by accident.
+pub(crate) fn checksum(text: &str) -> String {
+    let hash = text.bytes().fold(0xcbf29ce484222325u64, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    });
+    format!("{:016x}", hash)
+}
+
+///The path with the suffix appended to its file name.
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".");
+    name.push(suffix);
+    name.into()
+}
+
+///The `n`th latest backup of the session file, starting at 1.
+pub fn backup_path(path: &Path, n: usize) -> PathBuf {
+    with_suffix(path, &n.to_string())
+}
+
+///The backups of the session file that exist, the latest first.
+pub fn backups(path: &Path) -> Vec<PathBuf> {
+    (1..=BACKUPS)
+        .map(|it| backup_path(path, it))
+        .filter(|it| it.exists())
+        .collect()
+}
+
+///Moves every backup one place back, dropping the oldest, and copies the session file in as the
+///latest one.
+fn rotate_backups(path: &Path) -> io::Result<()> {
+    if !path.exists() {
+        return Ok(());
+    }
+    for n in (1..BACKUPS).rev() {
+        let backup = backup_path(path, n);
+        if backup.exists() {
+            fs::rename(&backup, backup_path(path, n + 1))?;
+        }
+    }
+    fs::copy(path, backup_path(path, 1)).map(|_| ())
+}
+
+impl FactualSolver {
+    ///Writes the session to a file next to the path first, and only replaces the last save with
+    ///it once it is complete, so a crash can't leave a half written session behind. The last
+    ///saves are kept as backups.
+    pub fn save(&self, path: &Path) -> Result<(), SessionError> {
+        let format_error = |source| SessionError::Format {
+            path: path.display().to_string(),
+            source,
+        };
+        let session = serde_json::to_string(self).map_err(format_error)?;
+        let text = serde_json::to_string(&SessionFile {
+            checksum: checksum(&session),
+            session: RawValue::from_string(session).map_err(format_error)?,
+        })
+        .map_err(format_error)?;
+
+        let temporary = with_suffix(path, "tmp");
+        let io_error = |source| SessionError::Io {
+            path: temporary.display().to_string(),
+            source,
+        };
+        let mut file = File::create(&temporary).map_err(io_error)?;
+        file.write_all(text.as_bytes())
+            .and_then(|()| file.sync_all())
+            .map_err(io_error)?;
+        let io_error = |source| SessionError::Io {
+            path: path.display().to_string(),
+            source,
+        };
+        rotate_backups(path).map_err(io_error)?;
+        fs::rename(&temporary, path).map_err(io_error)
+    }
+
+    ///Restores a session written by `save`, checking it against its checksum. Sessions saved
+    ///before they had one are restored unchecked. The interrupt isn't part of it, and has to be
+    ///set again.
+    pub fn load(path: &Path) -> Result<Self, SessionError> {
+        let text = fs::read_to_string(path).map_err(|source| SessionError::Io {
+            path: path.display().to_string(),
+            source,
+        })?;
+        let format_error = |source| SessionError::Format {
+            path: path.display().to_string(),
+            source,
+        };
+        match serde_json::from_str::<SessionFile>(&text) {
+            Ok(file) if checksum(file.session.get()) != file.checksum => {
+                Err(SessionError::Damaged {
+                    path: path.display().to_string(),
+                })
+            }
+            Ok(file) => serde_json::from_str(file.session.get()).map_err(format_error),
+            Err(_) => serde_json::from_str(&text).map_err(format_error),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs};
+
+    use crate::{
+        fact_solver::FactualSolver,
+        index::RunePosition,
+        rule::ActivationRuleKindHelpers,
+        test_support::{activation, sample_runes},
+        RuneLock,
+    };
+
+    use super::SessionError;
+
+    #[test]
+    fn test_saved_sessions_are_restored() {
+        let lock = RuneLock::with_rules(
+            sample_runes(),
+            vec![(1, 2).alwanese(), (2, 3).increase_santor()],
+        );
+        let mut solver = FactualSolver::new(lock);
+        let first = solver.assume(activation(1), RunePosition::new(0));
+        solver.assume(activation(2), RunePosition::new(1));
+        solver.set_current(first);
+
+        let path = env::temp_dir().join(format!("rune-lock-session-{}.json", std::process::id()));
+        solver.save(&path).unwrap();
+        let loaded = FactualSolver::load(&path);
+        let _ = std::fs::remove_file(&path);
+        let loaded = loaded.unwrap();
+
+        assert_eq!(loaded.current(), first);
+        assert_eq!(loaded.states.handles().count(), 3);
+        let (fixed, loaded_fixed) = (
+            solver.fixed_assignment().unwrap(),
+            loaded.fixed_assignment().unwrap(),
+        );
+        for position in (0..12).map(RunePosition::new) {
+            assert_eq!(loaded_fixed[position], fixed[position]);
+        }
+        for node in solver.states.handles() {
+            assert_eq!(
+                loaded.states[node].facts.fact_count(),
+                solver.states[node].facts.fact_count()
+            );
+        }
+    }
+
+    #[test]
+    fn test_saves_are_checked_and_backed_up() {
+        let lock = RuneLock::with_rules(sample_runes(), vec![(1, 2).alwanese()]);
+        let mut solver = FactualSolver::new(lock);
+        let directory = env::temp_dir().join(format!("rune-lock-backups-{}", std::process::id()));
+        fs::create_dir_all(&directory).unwrap();
+        let path = directory.join("session.json");
+
+        solver.save(&path).unwrap();
+        assert!(super::backups(&path).is_empty());
+        let first = solver.assume(activation(1), RunePosition::new(0));
+        for _ in 0..=super::BACKUPS {
+            solver.save(&path).unwrap();
+        }
+        assert_eq!(super::backups(&path).len(), super::BACKUPS);
+        assert!(!directory.join("session.json.tmp").exists());
+
+        let text = fs::read_to_string(&path).unwrap();
+        fs::write(&path, text.replacen("\"Assume\"", "\"Asume\"", 1)).unwrap();
+        let damaged = FactualSolver::load(&path);
+        let backup = FactualSolver::load(&super::backup_path(&path, 1));
+        //Sessions saved without a checksum.
+        fs::write(&path, serde_json::to_string(&solver).unwrap()).unwrap();
+        let unchecked = FactualSolver::load(&path);
+        let _ = fs::remove_dir_all(&directory);
+
+        assert!(matches!(damaged, Err(SessionError::Damaged { .. })));
+        assert_eq!(backup.unwrap().current(), first);
+        assert_eq!(unchecked.unwrap().current(), first);
+    }
+}
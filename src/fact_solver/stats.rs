@@ -0,0 +1,41 @@
+use std::fmt::Display;
+
+///Counters about the work a `FactDb` did. They are only collected with the `stats` feature.
+#[derive(Clone, Debug, Default)]
+pub struct FactDbStats {
+    pub must_be_created: usize,
+    pub cannot_be_created: usize,
+    pub contradictions_created: usize,
+    ///Integrations which didn't change anything, as the fact was already known.
+    pub unchanged_integrations: usize,
+    pub consolidation_iterations: usize,
+    ///Candidates checked against a rule while consolidating rules.
+    pub rule_prune_attempts: usize,
+    ///Checks that eliminated the candidate.
+    pub rule_prune_successes: usize,
+}
+
+impl Display for FactDbStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "Facts created: {} must be, {} cannot be, {} contradictions",
+            self.must_be_created, self.cannot_be_created, self.contradictions_created
+        )?;
+        writeln!(
+            f,
+            "Integrations without changes: {}",
+            self.unchanged_integrations
+        )?;
+        writeln!(
+            f,
+            "Consolidation iterations: {}",
+            self.consolidation_iterations
+        )?;
+        write!(
+            f,
+            "Rule prunes: {} of {} checks",
+            self.rule_prune_successes, self.rule_prune_attempts
+        )
+    }
+}
@@ -0,0 +1,132 @@
+//! Notifications about facts as they are integrated, in any state, for consumers that want to
+//! follow the solver live, like an overlay of the physical lock.
+
+use std::sync::mpsc::{self, Receiver, Sender};
+
+use crate::{activation::Activation, index::RunePosition};
+
+use super::{assumption_tree::AssumptionTreeNodeHandle, fact_db::FactHandle, Fact, FactKind};
+
+///Which facts a subscriber is interested in. Every criterion that isn't empty has to match, so the
+///default filter matches every fact.
+#[derive(Clone, Debug, Default)]
+pub struct FactFilter {
+    ///Matches facts about any of the cells.
+    pub cells: Vec<(RunePosition, Activation)>,
+    pub activations: Vec<Activation>,
+    pub kinds: Vec<FactKind>,
+}
+
+#[derive(Clone, Debug)]
+pub struct FactEvent {
+    ///The state the fact was integrated in.
+    pub node: AssumptionTreeNodeHandle,
+    pub handle: FactHandle,
+    pub kind: FactKind,
+    pub activation: Activation,
+    ///The positions the fact is about, several for range facts.
+    pub positions: Vec<RunePosition>,
+}
+
+impl FactFilter {
+    pub fn matches(&self, fact: &Fact) -> bool {
+        (self.cells.is_empty()
+            || self
+                .cells
+                .iter()
+                .any(|(position, activation)| fact.covers(*position, *activation)))
+            && (self.activations.is_empty() || self.activations.contains(&fact.activation))
+            && (self.kinds.is_empty() || self.kinds.contains(&fact.kind))
+    }
+}
+
+#[derive(Clone, Default)]
+pub(super) struct Subscribers(Vec<(FactFilter, Sender<FactEvent>)>);
+
+impl super::FactualSolver {
+    ///Sends every fact matching the filter that is integrated from now on, in any state, to the
+    ///returned receiver. Dropping the receiver ends the subscription.
+    pub fn subscribe(&mut self, filter: FactFilter) -> Receiver<FactEvent> {
+        let (sender, receiver) = mpsc::channel();
+        self.subscribers.0.push((filter, sender));
+        receiver
+    }
+
+    ///Notifies the subscribers of the facts of `node` after the first `known` ones.
+    pub(super) fn publish(&mut self, node: AssumptionTreeNodeHandle, known: usize) {
+        //Every state is published right after it was consolidated, before it is derived from.
+        self.learn(node);
+        if self.subscribers.0.is_empty() {
+            return;
+        }
+        let facts = &self.states[node].facts;
+        self.subscribers.0.retain(|(filter, sender)| {
+            facts
+                .facts_since(known)
+                .filter(|(_, fact)| filter.matches(fact))
+                .all(|(handle, fact)| {
+                    sender
+                        .send(FactEvent {
+                            node,
+                            handle,
+                            kind: fact.kind,
+                            activation: fact.activation,
+                            positions: fact.positions().collect(),
+                        })
+                        .is_ok()
+                })
+        });
+    }
+
+    ///Notifies the subscribers of the facts `node` derived on top of its parent.
+    pub(super) fn publish_derived(&mut self, node: AssumptionTreeNodeHandle) {
+        self.publish(node, self.inherited(node));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        fact_solver::{FactKind, FactualSolver},
+        index::RunePosition,
+        rule::ActivationRuleKindHelpers,
+        test_support::{activation, sample_runes},
+        RuneLock,
+    };
+
+    use super::FactFilter;
+
+    #[test]
+    fn test_subscribers_only_get_matching_facts() {
+        let lock = RuneLock::with_rules(
+            sample_runes(),
+            vec![(1, 2).alwanese(), (2, 3).increase_santor()],
+        );
+        let mut solver = FactualSolver::new(lock);
+        let placements = solver.subscribe(FactFilter {
+            kinds: vec![FactKind::ActivationMustBeOn],
+            ..Default::default()
+        });
+        let cell = solver.subscribe(FactFilter {
+            cells: vec![(RunePosition::new(0), activation(1))],
+            ..Default::default()
+        });
+
+        let node = solver.assume(activation(1), RunePosition::new(0));
+        let placed: Vec<_> = placements.try_iter().collect();
+        assert!(placed
+            .iter()
+            .all(|it| it.kind == FactKind::ActivationMustBeOn && it.node == node));
+        assert!(placed
+            .iter()
+            .any(|it| it.activation == activation(1) && it.positions == [RunePosition::new(0)]));
+        let about_cell: Vec<_> = cell.try_iter().collect();
+        assert_eq!(about_cell.len(), 1);
+        assert_eq!(about_cell[0].node, node);
+
+        drop(placements);
+        solver.assume(activation(2), RunePosition::new(2));
+        assert_eq!(cell.try_iter().count(), 0);
+        assert_eq!(solver.subscribers.0.len(), 1);
+    }
+}
@@ -0,0 +1,326 @@
+//! Proving that the current state has exactly one solution by searching all of its completions,
+//! for locks where that takes longer than one sitting. The search can stop at any time and keeps
+//! its progress in a checkpoint file: the branch it was in at every level of the search tree, as
+//! every branch before it is done. Resuming replays that path and continues right there.
+
+use std::{
+    fs, io,
+    ops::ControlFlow,
+    path::Path,
+    time::{Duration, Instant},
+};
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::{activation::Activation, jobs::CancelToken, RuneLock};
+
+use super::{
+    fact_db::FactDb, search::narrowest_branch, session::checksum, Fact, FactKind, FactReason,
+    FactualSolver, SolverStateState,
+};
+
+#[derive(Debug, Error)]
+pub enum CheckpointError {
+    #[error("Could not access {path}: {source}")]
+    Io { path: String, source: io::Error },
+    #[error("{path} is not a checkpoint: {source}")]
+    Format {
+        path: String,
+        source: serde_json::Error,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Uniqueness {
+    NoSolution,
+    ///The activation at every position, by its index, like `Outcome::Solved`.
+    Unique(Vec<Option<Activation>>),
+    Multiple(Vec<Option<Activation>>, Vec<Option<Activation>>),
+    ///The search stopped before it was done, with about this share of it explored.
+    Paused {
+        explored: f64,
+    },
+}
+
+#[derive(Debug, Clone)]
+pub struct UniquenessReport {
+    pub verdict: Uniqueness,
+    ///The states visited, in this run and the ones before it.
+    pub nodes: usize,
+    ///Whether the search continued from a checkpoint.
+    pub resumed: bool,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct Checkpoint {
+    ///What the search starts from, the lock and the candidates of the state, so a checkpoint of
+    ///another lock or state isn't resumed.
+    fingerprint: String,
+    ///The branch the search was in at every level, with how many branches the level has.
+    path: Vec<(usize, usize)>,
+    solutions: Vec<Vec<Option<Activation>>>,
+    nodes: usize,
+}
+
+impl Checkpoint {
+    ///The share of the search tree before the path, if the branches of a level were all about
+    ///the same size.
+    fn explored(&self) -> f64 {
+        let mut share = 1.0;
+        let mut explored = 0.0;
+        for (branch, branches) in self.path.iter() {
+            share /= *branches as f64;
+            explored += *branch as f64 * share;
+        }
+        explored
+    }
+}
+
+enum Stop {
+    Paused,
+    Decided,
+}
+
+struct Search<'a> {
+    lock: &'a RuneLock,
+    interrupt: &'a CancelToken,
+    deadline: Option<Instant>,
+    ///The most states to visit in this run.
+    max_nodes: usize,
+    visited: usize,
+    checkpoint: Checkpoint,
+}
+
+impl Search<'_> {
+    ///Searches the completions of the facts depth first, skipping the branches of each level
+    ///before the one `resume` names.
+    fn run(&mut self, facts: &FactDb, resume: &[usize]) -> ControlFlow<Stop> {
+        //The states on the way back to where the search stopped were visited before, and every
+        //run visits at least one new state, so it makes progress however short it is.
+        if resume.is_empty() {
+            if self.visited > 0
+                && (self.interrupt.is_cancelled()
+                    || self.deadline.is_some_and(|it| Instant::now() >= it)
+                    || self.visited >= self.max_nodes)
+            {
+                return ControlFlow::Break(Stop::Paused);
+            }
+            self.visited += 1;
+            self.checkpoint.nodes += 1;
+        }
+        let Ok(fixed) = facts.fixed_assignment() else {
+            return ControlFlow::Continue(());
+        };
+        let Some(candidates) = narrowest_branch(facts, &fixed) else {
+            if self.lock.validate(&fixed).is_ok() {
+                let solutions = &mut self.checkpoint.solutions;
+                solutions.push(
+                    self.lock
+                        .size()
+                        .all_positions()
+                        .map(|it| fixed[it])
+                        .collect(),
+                );
+                if solutions.len() > 1 {
+                    return ControlFlow::Break(Stop::Decided);
+                }
+            }
+            return ControlFlow::Continue(());
+        };
+        let start = resume.first().copied().unwrap_or(0);
+        for (branch, (position, activation)) in candidates.iter().enumerate().skip(start) {
+            let mut facts = facts.clone();
+            let assumed = facts.integrate_and_consolidate(
+                Fact {
+                    kind: FactKind::ActivationMustBeOn,
+                    reasons: vec![FactReason::Assumption],
+                    position: *position,
+                    activation: *activation,
+                    range: Vec::new(),
+                },
+                self.lock,
+            );
+            if assumed.is_err() {
+                continue;
+            }
+            let resume = match branch == start {
+                true => resume.get(1..).unwrap_or_default(),
+                false => &[],
+            };
+            self.checkpoint.path.push((branch, candidates.len()));
+            self.run(&facts, resume)?;
+            self.checkpoint.path.pop();
+        }
+        ControlFlow::Continue(())
+    }
+}
+
+impl FactualSolver {
+    ///Searches for a second solution of the current state, for at most `time`, continuing from
+    ///the checkpoint if it is one of this state. If the search stops before it is done, its
+    ///progress is written to the checkpoint, and once it is done, the checkpoint is removed.
+    pub fn prove_unique(
+        &self,
+        checkpoint: &Path,
+        time: Option<Duration>,
+    ) -> Result<UniquenessReport, CheckpointError> {
+        self.prove_unique_within(checkpoint, time.map(|it| Instant::now() + it), usize::MAX)
+    }
+
+    fn prove_unique_within(
+        &self,
+        path: &Path,
+        deadline: Option<Instant>,
+        max_nodes: usize,
+    ) -> Result<UniquenessReport, CheckpointError> {
+        let io_error = |source| CheckpointError::Io {
+            path: path.display().to_string(),
+            source,
+        };
+        let state = &self.states[self.current];
+        if let SolverStateState::Contradicts(_) = state.state {
+            return Ok(UniquenessReport {
+                verdict: Uniqueness::NoSolution,
+                nodes: 0,
+                resumed: false,
+            });
+        }
+        let fingerprint = self.fingerprint();
+        let saved = match fs::read_to_string(path) {
+            Ok(text) => Some(serde_json::from_str::<Checkpoint>(&text).map_err(|source| {
+                CheckpointError::Format {
+                    path: path.display().to_string(),
+                    source,
+                }
+            })?),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => None,
+            Err(err) => return Err(io_error(err)),
+        };
+        let (checkpoint, resumed) = match saved {
+            Some(saved) if saved.fingerprint == fingerprint => (saved, true),
+            _ => (
+                Checkpoint {
+                    fingerprint,
+                    ..Checkpoint::default()
+                },
+                false,
+            ),
+        };
+
+        let resume: Vec<_> = checkpoint.path.iter().map(|(branch, _)| *branch).collect();
+        let mut search = Search {
+            lock: &self.lock,
+            interrupt: &self.interrupt,
+            deadline,
+            max_nodes,
+            visited: 0,
+            checkpoint: Checkpoint {
+                path: Vec::new(),
+                ..checkpoint
+            },
+        };
+        let flow = search.run(&state.facts, &resume);
+        let checkpoint = search.checkpoint;
+        let verdict = match (flow, &checkpoint.solutions[..]) {
+            (ControlFlow::Break(Stop::Paused), _) => {
+                let text = serde_json::to_string(&checkpoint).map_err(|source| {
+                    CheckpointError::Format {
+                        path: path.display().to_string(),
+                        source,
+                    }
+                })?;
+                let temporary = path.with_extension("tmp");
+                fs::write(&temporary, text)
+                    .and_then(|()| fs::rename(&temporary, path))
+                    .map_err(io_error)?;
+                Uniqueness::Paused {
+                    explored: checkpoint.explored(),
+                }
+            }
+            (_, []) => Uniqueness::NoSolution,
+            (_, [solution]) => Uniqueness::Unique(solution.clone()),
+            (_, [first, second, ..]) => Uniqueness::Multiple(first.clone(), second.clone()),
+        };
+        if !matches!(verdict, Uniqueness::Paused { .. }) && path.exists() {
+            fs::remove_file(path).map_err(io_error)?;
+        }
+        Ok(UniquenessReport {
+            verdict,
+            nodes: checkpoint.nodes,
+            resumed,
+        })
+    }
+
+    ///Identifies the lock and the candidates of the current state.
+    fn fingerprint(&self) -> String {
+        let facts = &self.states[self.current].facts;
+        let candidates: Vec<Vec<Activation>> = self
+            .lock
+            .size()
+            .all_positions()
+            .map(|it| facts.possibilities_for(it).collect())
+            .collect();
+        let lock = serde_json::to_string(&self.lock).unwrap_or_default();
+        let candidates = serde_json::to_string(&candidates).unwrap_or_default();
+        checksum(&(lock + &candidates))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs};
+
+    use crate::{
+        fact_solver::FactualSolver,
+        index::RunePosition,
+        rule::ActivationRuleKindHelpers,
+        test_support::{activation, sample_runes},
+        RuneLock,
+    };
+
+    use super::Uniqueness;
+
+    #[test]
+    fn test_paused_proofs_resume_where_they_stopped() {
+        let lock = RuneLock::with_rules(
+            sample_runes(),
+            vec![
+                (1, 2).alwanese(),
+                (2, 3).antakian_conjugate(),
+                (3, 4).alwanese(),
+                (4, 5).antakian_twins(),
+                (5, 6).increase_santor(),
+                (6, 7).alwanese(),
+                (7, 8).antakian_conjugate(),
+                (8, 9).alwanese(),
+                (9, 10).antakian_twins(),
+                (10, 11).increase_santor(),
+            ],
+        );
+        let mut solver = FactualSolver::new(lock);
+        solver.assume(activation(1), RunePosition::new(0));
+        let path = env::temp_dir().join(format!("rune-lock-proof-{}.json", std::process::id()));
+
+        let whole = solver.prove_unique(&path, None).unwrap();
+        assert!(!path.exists());
+
+        let mut runs = 0;
+        let verdict = loop {
+            let report = solver.prove_unique_within(&path, None, 3).unwrap();
+            assert_eq!(report.resumed, runs > 0);
+            runs += 1;
+            match report.verdict {
+                Uniqueness::Paused { explored } => assert!((0.0..1.0).contains(&explored)),
+                verdict => break verdict,
+            }
+        };
+        let _ = fs::remove_file(&path);
+        assert!(runs > 1);
+        assert_eq!(verdict, whole.verdict);
+        assert_eq!(
+            matches!(verdict, Uniqueness::Multiple(..)),
+            solver.count_solutions(2).count > 1
+        );
+    }
+}
@@ -0,0 +1,230 @@
+//! Exhaustive cross-check of the facts of a state against every completion of its assumptions.
+
+use crate::{
+    activation::Activation, assignment::Assignment, index::RunePosition, jobs::CancelToken,
+    RuneLock,
+};
+
+use super::{
+    fact_db::{FactDb, FactHandle},
+    FactKind, FactReason,
+};
+
+///Which cells are used by at least one completion.
+pub struct Completions {
+    pub count: usize,
+    ///Whether every completion was visited before the limit was reached.
+    pub complete: bool,
+    ///Whether the enumeration stopped early because it was cancelled.
+    pub interrupted: bool,
+    ///The first few completions that were found.
+    pub examples: Vec<Assignment>,
+    seen: Vec<Vec<bool>>,
+}
+
+///How many completions are kept as examples.
+const EXAMPLES: usize = 10;
+
+///Enumerates all assignments that extend `assumed` and satisfy every rule and domain of the lock,
+///visiting at most `limit` partial assignments and stopping early once `cancel` is set.
+///`progress` is called every `report_every` visits with the number of visits and completions so
+///far.
+pub fn enumerate(
+    lock: &RuneLock,
+    assumed: &[(RunePosition, Activation)],
+    limit: usize,
+    cancel: &CancelToken,
+    report_every: usize,
+    mut progress: impl FnMut(usize, usize),
+) -> Completions {
+    struct Search<'a, F> {
+        lock: &'a RuneLock,
+        assumed: &'a [(RunePosition, Activation)],
+        limit: usize,
+        cancel: &'a CancelToken,
+        report_every: usize,
+        progress: F,
+        visited: usize,
+        completions: Completions,
+    }
+
+    impl<F: FnMut(usize, usize)> Search<'_, F> {
+        fn place(&mut self, assignment: &Assignment, index: usize) {
+            let size = self.lock.size();
+            if index == size.activations() {
+                self.completions.count += 1;
+                if self.completions.examples.len() < EXAMPLES {
+                    self.completions.examples.push(assignment.clone());
+                }
+                for position in size.all_positions() {
+                    if let Some(activation) = assignment[position] {
+                        self.completions.seen[position.index()][activation.index()] = true;
+                    }
+                }
+                return;
+            }
+            let activation = Activation::new(index as u8).unwrap();
+            //Every assumption has to hold, so assuming an activation on two positions, or two
+            //activations on a position, leaves no completion.
+            let assumed = |position| {
+                self.assumed
+                    .iter()
+                    .all(|(it, assumed)| (*assumed == activation) == (*it == position))
+            };
+            for position in size.all_positions() {
+                if !assumed(position)
+                    || assignment[position].is_some()
+                    || self
+                        .lock
+                        .domains
+                        .iter()
+                        .any(|it| it.activation == activation && !it.contains(position, size))
+                {
+                    continue;
+                }
+                if self.visited >= self.limit {
+                    self.completions.complete = false;
+                    return;
+                }
+                if self.cancel.is_cancelled() {
+                    self.completions.complete = false;
+                    self.completions.interrupted = true;
+                    return;
+                }
+                self.visited += 1;
+                if self.visited.is_multiple_of(self.report_every) {
+                    (self.progress)(self.visited, self.completions.count);
+                }
+                let mut next = assignment.clone();
+                next.assign(position, activation);
+                if self.lock.validate(&next).is_ok() {
+                    self.place(&next, index + 1);
+                }
+            }
+        }
+    }
+
+    let mut search = Search {
+        lock,
+        assumed,
+        limit,
+        cancel,
+        report_every,
+        progress: &mut progress,
+        visited: 0,
+        completions: Completions {
+            count: 0,
+            complete: true,
+            interrupted: false,
+            examples: Vec::new(),
+            seen: vec![vec![false; lock.size().activations()]; lock.size().positions()],
+        },
+    };
+    search.place(&Assignment::empty(lock.size()), 0);
+    search.completions
+}
+
+///The facts of `db` that contradict at least one of the completions.
+pub fn unsound_facts(db: &FactDb, completions: &Completions) -> Vec<FactHandle> {
+    db.iter_facts()
+        .filter(|(_, fact)| !fact.reasons.contains(&FactReason::Assumption))
+        .filter(|(_, fact)| {
+            let (position, activation) = (fact.position.index(), fact.activation.index());
+            match fact.kind {
+                FactKind::ActivationMustBeOn => completions
+                    .seen
+                    .iter()
+                    .enumerate()
+                    .any(|(other, seen)| other != position && seen[activation]),
+                FactKind::ActivationCannotBeOn => fact
+                    .positions()
+                    .any(|it| completions.seen[it.index()][activation]),
+                FactKind::Contradiction(_) => completions.count > 0,
+            }
+        })
+        .map(|(handle, _)| handle)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        activation::Activation,
+        fact_solver::{fact_db::FactDb, Fact, FactKind, FactReason},
+        index::RunePosition,
+        jobs::CancelToken,
+        rule::ActivationRuleKindHelpers,
+        size::LockSize,
+        test_support::{activation, sample_runes},
+        RuneLock,
+    };
+
+    use super::{enumerate, unsound_facts};
+
+    #[test]
+    fn test_consolidated_facts_hold_in_all_completions() {
+        let lock = RuneLock::with_rules(
+            sample_runes(),
+            vec![
+                (1, 2).alwanese(),
+                (2, 3).antakian_conjugate(),
+                (3, 4).alwanese(),
+                (4, 5).antakian_twins(),
+                (5, 6).increase_santor(),
+            ],
+        );
+        let first = (RunePosition::new(0), activation(1));
+        let mut db = FactDb::new(LockSize::STANDARD);
+        let consolidated = db.integrate_and_consolidate(
+            Fact {
+                kind: FactKind::ActivationMustBeOn,
+                position: first.0,
+                activation: first.1,
+                reasons: vec![FactReason::Assumption],
+                range: Vec::new(),
+            },
+            &lock,
+        );
+        assert!(consolidated.is_ok());
+
+        let completions = enumerate(
+            &lock,
+            &[first],
+            usize::MAX,
+            &CancelToken::default(),
+            usize::MAX,
+            |_, _| {},
+        );
+        assert!(completions.complete);
+        assert!(completions.count > 0);
+        assert!(unsound_facts(&db, &completions).is_empty());
+    }
+
+    #[test]
+    fn test_conflicting_assumptions_have_no_completions() {
+        let lock = RuneLock::with_rules(sample_runes(), vec![]);
+        let enumerate_assumed = |assumed: &[(RunePosition, Activation)]| {
+            enumerate(
+                &lock,
+                assumed,
+                usize::MAX,
+                &CancelToken::default(),
+                usize::MAX,
+                |_, _| {},
+            )
+        };
+
+        let twice = enumerate_assumed(&[
+            (RunePosition::new(5), activation(12)),
+            (RunePosition::new(11), activation(1)),
+            (RunePosition::new(6), activation(1)),
+        ]);
+        assert!(twice.complete);
+        assert_eq!(twice.count, 0);
+        let shared = enumerate_assumed(&[
+            (RunePosition::new(5), activation(12)),
+            (RunePosition::new(5), activation(1)),
+        ]);
+        assert_eq!(shared.count, 0);
+    }
+}
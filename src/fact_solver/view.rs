@@ -1,14 +1,12 @@
-use std::any::TypeId;
-
-use ndarray::Axis;
-
 use crate::{activation::Activation, index::RunePosition};
 
+use super::grid::GridAxis;
+
 pub trait View {
     type Complement: View;
 
     fn from_usize(it: usize) -> Self;
-    fn axis() -> Axis;
+    fn axis() -> GridAxis;
     fn index(&self) -> usize;
 }
 
@@ -24,8 +22,8 @@ impl View for RunePosition {
         Self::new(it)
     }
 
-    fn axis() -> Axis {
-        Axis(0) //TODO CHeck if this is the correct assignment
+    fn axis() -> GridAxis {
+        GridAxis::Position
     }
 
     fn index(&self) -> usize {
@@ -34,11 +32,11 @@ impl View for RunePosition {
 }
 
 impl ChooseView for RunePosition {
-    fn choose_position(s: Self, c: Self::Complement) -> RunePosition {
+    fn choose_position(s: Self, _c: Self::Complement) -> RunePosition {
         s
     }
 
-    fn choose_activation(s: Self, c: Self::Complement) -> Activation {
+    fn choose_activation(_s: Self, c: Self::Complement) -> Activation {
         c
     }
 }
@@ -50,8 +48,8 @@ impl View for Activation {
         Self::new(it as u8).unwrap()
     }
 
-    fn axis() -> Axis {
-        Axis(1)
+    fn axis() -> GridAxis {
+        GridAxis::Activation
     }
 
     fn index(&self) -> usize {
@@ -59,11 +57,11 @@ impl View for Activation {
     }
 }
 impl ChooseView for Activation {
-    fn choose_position(s: Self, c: Self::Complement) -> RunePosition {
+    fn choose_position(_s: Self, c: Self::Complement) -> RunePosition {
         c
     }
 
-    fn choose_activation(s: Self, c: Self::Complement) -> Activation {
+    fn choose_activation(s: Self, _c: Self::Complement) -> Activation {
         s
     }
 }
@@ -0,0 +1,306 @@
+//! A C ABI for driving the solver from other languages, enabled by the `ffi` feature. Locks and
+//! solvers are handed out as opaque pointers that have to be freed with their `_free` function,
+//! and strings returned by the library with `rune_lock_string_free`. Activations are 1 based,
+//! positions 0 based, like on the command line. Structured results, like explanations, are JSON.
+//! A panic never unwinds into the host: the function returns null or -1 instead, and the solver
+//! it panicked in should be freed, as it may have been left halfway through a change.
+
+use std::{
+    ffi::{c_char, CStr, CString},
+    panic::{self, AssertUnwindSafe},
+    ptr,
+};
+
+use serde_json::json;
+
+use crate::{
+    activation::{Activation, HumanActivation},
+    fact_solver::FactualSolver,
+    index::RunePosition,
+    rune::Rune,
+    size::LockSize,
+    RuleKind, RuneLock,
+};
+
+///How many lines an explanation may take up before sub-proofs are summarized.
+const EXPLAIN_LINES: usize = 40;
+
+///The rune id that stands for a rune that can't be seen on the lock yet.
+pub const RUNE_UNKNOWN: u8 = u8::MAX;
+
+fn parse_activation(size: LockSize, one_based: u8) -> Option<Activation> {
+    HumanActivation::new(one_based)
+        .ok()
+        .map(Activation::from)
+        .filter(|it| size.contains(*it))
+}
+
+fn parse_position(size: LockSize, zero_based: u8) -> Option<RunePosition> {
+    size.position(zero_based as usize)
+}
+
+fn into_c_string(text: String) -> *mut c_char {
+    CString::new(text).map_or(ptr::null_mut(), CString::into_raw)
+}
+
+///Runs the body of an entry point, and returns `failed` if it panics instead of unwinding across
+///the C ABI, which would abort the host.
+fn guarded<T>(failed: T, body: impl FnOnce() -> T) -> T {
+    panic::catch_unwind(AssertUnwindSafe(body)).unwrap_or(failed)
+}
+
+///Creates a lock of the game's size without rules from the ids of its 12 runes, `RUNE_UNKNOWN`
+///for runes that aren't known yet.
+///
+/// # Safety
+///
+///`runes` has to point to 12 readable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn rune_lock_new(runes: *const u8) -> *mut RuneLock {
+    rune_lock_new_sized(
+        runes,
+        LockSize::STANDARD.positions() as u8,
+        LockSize::STANDARD.activations() as u8,
+    )
+}
+
+///Like `rune_lock_new`, for a lock of `positions` runes, the outer circle first, and
+///`activations` activations. Returns null if there is no lock of that size.
+///
+/// # Safety
+///
+///`runes` has to point to `positions` readable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn rune_lock_new_sized(
+    runes: *const u8,
+    positions: u8,
+    activations: u8,
+) -> *mut RuneLock {
+    let (false, Ok(size)) = (
+        runes.is_null(),
+        LockSize::new(positions as usize, activations as usize),
+    ) else {
+        return ptr::null_mut();
+    };
+    let runes = std::slice::from_raw_parts(runes, size.positions());
+    guarded(ptr::null_mut(), || {
+        let runes = runes
+            .iter()
+            .map(|it| match *it {
+                RUNE_UNKNOWN => Rune::Unknown,
+                id => Rune::new(id),
+            })
+            .collect();
+        RuneLock::sized(size, runes).map_or(ptr::null_mut(), |it| Box::into_raw(Box::new(it)))
+    })
+}
+
+///Adds a rule written like on the command line, e.g. `1 2 alwanese`. Returns 0 if it was added,
+///-1 if it couldn't be parsed or is about activations or positions the lock doesn't have.
+///
+/// # Safety
+///
+///`lock` has to come from `rune_lock_new`, and `rule` has to be a null terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn rune_lock_add_rule(lock: *mut RuneLock, rule: *const c_char) -> i32 {
+    let (Some(lock), false) = (lock.as_mut(), rule.is_null()) else {
+        return -1;
+    };
+    let rule = CStr::from_ptr(rule);
+    guarded(-1, || match rule.to_str().map(str::parse::<RuleKind>) {
+        Ok(Ok(rule)) if rule.check_size(lock.size()).is_ok() => {
+            lock.add_rule(rule, 0);
+            0
+        }
+        _ => -1,
+    })
+}
+
+///# Safety
+///
+///`lock` has to come from `rune_lock_new` and not be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn rune_lock_free(lock: *mut RuneLock) {
+    if !lock.is_null() {
+        let lock = Box::from_raw(lock);
+        guarded((), || drop(lock));
+    }
+}
+
+///Starts solving a copy of the lock. Later changes to the lock don't affect the solver.
+///
+/// # Safety
+///
+///`lock` has to come from `rune_lock_new`.
+#[no_mangle]
+pub unsafe extern "C" fn rune_solver_new(lock: *const RuneLock) -> *mut FactualSolver {
+    let Some(lock) = lock.as_ref() else {
+        return ptr::null_mut();
+    };
+    guarded(ptr::null_mut(), || {
+        Box::into_raw(Box::new(FactualSolver::new(lock.clone())))
+    })
+}
+
+///Assumes the activation on the position in the current state, and continues in the state that
+///follows. Returns what that state came to as JSON, or null for an invalid activation or
+///position.
+///
+/// # Safety
+///
+///`solver` has to come from `rune_solver_new`.
+#[no_mangle]
+pub unsafe extern "C" fn rune_solver_assume(
+    solver: *mut FactualSolver,
+    activation: u8,
+    position: u8,
+) -> *mut c_char {
+    let Some(solver) = solver.as_mut() else {
+        return ptr::null_mut();
+    };
+    let size = solver.lock().size();
+    let (Some(activation), Some(position)) = (
+        parse_activation(size, activation),
+        parse_position(size, position),
+    ) else {
+        return ptr::null_mut();
+    };
+    guarded(ptr::null_mut(), || {
+        let node = solver.assume(activation, position);
+        //Notices are for interactive frontends, the outcome tells the host what it needs to know.
+        solver.take_notices();
+        serde_json::to_string(&solver.outcome(node)).map_or(ptr::null_mut(), into_c_string)
+    })
+}
+
+///The activations the current state still allows on the position, as a mask in which bit `n`
+///stands for activation `n + 1`. Returns -1 for an invalid position, or on a lock of more
+///activations than the mask has bits for.
+///
+/// # Safety
+///
+///`solver` has to come from `rune_solver_new`.
+#[no_mangle]
+pub unsafe extern "C" fn rune_solver_candidates(solver: *const FactualSolver, position: u8) -> i32 {
+    let Some(solver) = solver.as_ref() else {
+        return -1;
+    };
+    let size = solver.lock().size();
+    let (Some(position), true) = (
+        parse_position(size, position),
+        size.activations() < i32::BITS as usize,
+    ) else {
+        return -1;
+    };
+    guarded(-1, || {
+        solver
+            .facts()
+            .possibilities_for(position)
+            .fold(0, |mask, it| mask | 1 << it.index())
+    })
+}
+
+///Why the current state knows what it knows about the activation on the position, as JSON with
+///the fact, the rules its proof rests on and the lines of its explanation. Returns null for an
+///invalid activation or position, or if nothing is known about it.
+///
+/// # Safety
+///
+///`solver` has to come from `rune_solver_new`.
+#[no_mangle]
+pub unsafe extern "C" fn rune_solver_explain(
+    solver: *const FactualSolver,
+    activation: u8,
+    position: u8,
+) -> *mut c_char {
+    let Some(solver) = solver.as_ref() else {
+        return ptr::null_mut();
+    };
+    let size = solver.lock().size();
+    let (Some(activation), Some(position)) = (
+        parse_activation(size, activation),
+        parse_position(size, position),
+    ) else {
+        return ptr::null_mut();
+    };
+    guarded(ptr::null_mut(), || {
+        let facts = solver.facts();
+        let Some((handle, fact)) = facts
+            .handle_at(position, activation)
+            .and_then(|it| Some((it, facts.get(it)?)))
+        else {
+            return ptr::null_mut();
+        };
+        let explanation = json!({
+            "fact": handle.to_string(),
+            "statement": fact.to_string(),
+            "rules": facts.rules_of(handle),
+            "lines": facts.explanation(handle, solver.lock(), EXPLAIN_LINES, usize::MAX, true),
+        });
+        into_c_string(explanation.to_string())
+    })
+}
+
+///# Safety
+///
+///`solver` has to come from `rune_solver_new` and not be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn rune_solver_free(solver: *mut FactualSolver) {
+    if !solver.is_null() {
+        let solver = Box::from_raw(solver);
+        guarded((), || drop(solver));
+    }
+}
+
+///# Safety
+///
+///`string` has to be returned by this library and not be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn rune_lock_string_free(string: *mut c_char) {
+    if !string.is_null() {
+        drop(CString::from_raw(string));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ffi::{CStr, CString};
+
+    use super::*;
+
+    #[test]
+    fn test_solver_is_driven_through_the_c_api() {
+        unsafe {
+            let lock = rune_lock_new([0, 2, 1, 3, 2, 1, 3, 2, 1, 0, 2, 1].as_ptr());
+            let rule = CString::new("1 2 alwanese").unwrap();
+            assert_eq!(rune_lock_add_rule(lock, rule.as_ptr()), 0);
+            let rule = CString::new("1 2 friends").unwrap();
+            assert_eq!(rune_lock_add_rule(lock, rule.as_ptr()), -1);
+
+            let solver = rune_solver_new(lock);
+            rune_lock_free(lock);
+            assert_eq!(rune_solver_candidates(solver, 0), 0xfff);
+            assert!(rune_solver_assume(solver, 13, 0).is_null());
+
+            let outcome = rune_solver_assume(solver, 1, 0);
+            let text = CStr::from_ptr(outcome).to_str().unwrap();
+            assert!(text.contains("Progress") || text.contains("Forced"));
+            rune_lock_string_free(outcome);
+            assert_eq!(rune_solver_candidates(solver, 0), 1);
+
+            let explanation = rune_solver_explain(solver, 2, 6);
+            let json: serde_json::Value =
+                serde_json::from_str(CStr::from_ptr(explanation).to_str().unwrap()).unwrap();
+            assert_eq!(json["rules"], serde_json::json!([0]));
+            rune_lock_string_free(explanation);
+            rune_solver_free(solver);
+        }
+    }
+
+    #[test]
+    fn test_panics_dont_unwind_into_the_host() {
+        assert!(guarded(ptr::null_mut::<c_char>(), || panic!("solver bug")).is_null());
+        assert_eq!(guarded(-1, || panic!("solver bug")), -1);
+        assert_eq!(guarded(-1, || 3), 3);
+    }
+}
@@ -0,0 +1,149 @@
+//! Explanations of the rule kinds, with the position pairs that satisfy them on a given lock.
+
+use itertools::Itertools;
+
+use crate::{
+    activation::{Activation, HumanActivation},
+    rule::{self, RuleKind, KIND_ALIASES},
+    RuneLock,
+};
+
+///Rule kinds by the name used when entering rules, with their predicate over the positions `p`
+///and `q` of the first and the second activation. `s` is the number of sectors of each circle.
+const ENTRIES: [(&str, &str); 19] = [
+    (
+        "alwanese",
+        "(q - p) mod s is 1 or 2: q is one or two sectors clockwise of p, in either circle",
+    ),
+    (
+        "antakian-conjugates",
+        "p and q are on the same circle, in opposite sectors: q = p + s / 2 (mod s)",
+    ),
+    (
+        "alwanese-conjugates",
+        "p and q are in opposite sectors, in either circle: q mod s = (p + s / 2) mod s",
+    ),
+    ("different-runes", "rune(p) != rune(q)"),
+    ("antakian-twins", "p and q are on the same circle"),
+    ("increase-santor", "santor(p) < santor(q)"),
+    ("decrease-santor", "santor(p) > santor(q)"),
+    (
+        "max-0-conductive",
+        "p and q are neighbours on the same circle, or the same sector of both circles",
+    ),
+    (
+        "not-alwanese",
+        "not alwanese: (q - p) mod s is 0 or 3 to s - 1",
+    ),
+    ("not-antakian-twins", "p and q are on different circles"),
+    (
+        "follows",
+        "'Y follows X': if activation n is on an X rune, activation n + 1 is on a Y rune",
+    ),
+    (
+        "follows-within",
+        "'Y follows X within k': if activation n is on an X rune, one of the activations n + 1 to n + k is on a Y rune",
+    ),
+    (
+        "before",
+        "'X before Y': every activation on an X rune is lower than every activation on a Y rune",
+    ),
+    ("at", "'n at x': activation n is on position x"),
+    (
+        "not-at",
+        "'n not-at x': activation n is anywhere but position x",
+    ),
+    (
+        "all-different-runes",
+        "'n m k all-different-runes': the runes of the positions of all the activations differ",
+    ),
+    (
+        "not",
+        "'not <rule>': once the activations of the rule are placed, the rule does not hold",
+    ),
+    ("or", "'<rule> or <rule>': at least one of the rules holds"),
+    (
+        "if",
+        "'if <rule> then <rule>': once the condition's activations are placed and it holds, so does the consequence",
+    ),
+];
+
+///The predicate of the rule kind named `kind`, like "santor(p) < santor(q)".
+pub fn predicate(kind: &str) -> Option<&'static str> {
+    ENTRIES
+        .iter()
+        .find(|(name, _)| *name == kind)
+        .map(|(_, predicate)| *predicate)
+}
+
+///The glossary entry of `kind`, or of every kind.
+pub fn glossary(lock: &RuneLock, kind: Option<&str>) -> String {
+    let kind = kind.map(rule::resolve_alias);
+    let entries: Vec<_> = ENTRIES
+        .iter()
+        .filter(|(name, _)| kind.is_none() || kind == Some(*name))
+        .collect();
+    if entries.is_empty() {
+        return format!(
+            "Unknown rule kind. Known kinds: {}\n",
+            ENTRIES.iter().map(|(name, _)| name).join(", ")
+        );
+    }
+
+    let mut text = String::new();
+    for (name, predicate) in entries {
+        match KIND_ALIASES.iter().find(|(_, it)| it == name) {
+            Some((alias, _)) => text.push_str(&format!("{} (or {}): {}\n", name, alias, predicate)),
+            None => text.push_str(&format!("{}: {}\n", name, predicate)),
+        }
+        if name.ends_with("-santor") {
+            text.push_str(&format!(
+                "    santor: {}\n",
+                lock.santors().iter().join(" ")
+            ));
+        }
+        if predicate.contains("mod s") {
+            text.push_str(&format!("    s = {}\n", lock.size().sectors()));
+        }
+        match format!("1 2 {}", name).parse::<RuleKind>() {
+            Ok(rule) => text.push_str(&pairs(lock, rule)),
+            Err(_) if ["follows", "follows-within", "before"].contains(name) => {
+                text.push_str(&runes(lock))
+            }
+            Err(_) => {}
+        }
+    }
+    text
+}
+
+fn pairs(lock: &RuneLock, rule: RuleKind) -> String {
+    let first = Activation::from(HumanActivation::new(1).unwrap());
+    let second = Activation::from(HumanActivation::new(2).unwrap());
+    let mut text = String::new();
+    for p in lock.size().all_positions() {
+        let satisfying = lock
+            .size()
+            .all_positions()
+            .filter(|q| *q != p && rule.validate_tuple(lock, (p, first), (*q, second)).is_ok())
+            .join(" ");
+        if satisfying.is_empty() {
+            text.push_str(&format!("    p = {:2}: no q\n", p));
+        } else {
+            text.push_str(&format!("    p = {:2}: q in {}\n", p, satisfying));
+        }
+    }
+    text
+}
+
+fn runes(lock: &RuneLock) -> String {
+    let mut text = String::new();
+    for (rune, mut positions) in &lock
+        .size()
+        .all_positions()
+        .sorted_by_key(|it| lock.runes[*it].to_string())
+        .group_by(|it| lock.runes[*it])
+    {
+        text.push_str(&format!("    {} runes at {}\n", rune, positions.join(" ")));
+    }
+    text
+}
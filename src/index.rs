@@ -3,7 +3,11 @@ use std::{
     ops::{Index, IndexMut},
 };
 
-#[derive(Clone, Copy, PartialEq, Eq, Debug, PartialOrd, Ord, Hash)]
+use serde::{Deserialize, Serialize};
+
+use crate::size::{LockSize, MAX_POSITIONS};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 pub struct RunePosition(usize);
 
 impl Display for RunePosition {
@@ -12,76 +16,79 @@ impl Display for RunePosition {
     }
 }
 
-//This expects both elements of the "vertical" sector to have a higher santor than
-//the elements in the side sectors - which works if the rings are equally spaced.
-pub const SANTOR: [u32; 12] = [
-    //Outer Circle
-    7, 5, 2, 0, 2, 5, //Inner Circle
-    6, 4, 3, 1, 3, 4,
-];
-pub const MAX_SANTOR: u32 = 7;
-pub const MIN_SANTOR: u32 = 7;
-
 impl RunePosition {
     pub fn new(index: usize) -> Self {
-        assert!(index < 12);
+        assert!(index < MAX_POSITIONS);
         Self(index)
     }
 
-    pub fn antiakian_conjugate(&self) -> RunePosition {
-        if self.0 < 6 {
-            return RunePosition::new((self.0 + 3) % 6);
-        } else {
-            return RunePosition::new((self.0 + 3) % 6 + 6);
-        }
+    ///The sector of the position, the same on both circles.
+    fn sector(&self, size: LockSize) -> usize {
+        self.0 % size.sectors()
+    }
+
+    pub fn antiakian_conjugate(&self, size: LockSize) -> RunePosition {
+        let sectors = size.sectors();
+        RunePosition::new(self.0 - self.sector(size) + (self.sector(size) + sectors / 2) % sectors)
     }
 
-    pub fn antakian_conjugate_of(&self, other: RunePosition) -> bool {
-        self.antakian_twins(other) && self.alwanese_conjugate_of(other)
+    pub fn antakian_conjugate_of(&self, other: RunePosition, size: LockSize) -> bool {
+        self.antakian_twins(other, size) && self.alwanese_conjugate_of(other, size)
     }
 
-    pub fn alwanese_of(&self, other: RunePosition) -> bool {
-        let distance = (12 + self.0 - other.0).rem_euclid(6);
+    pub fn alwanese_of(&self, other: RunePosition, size: LockSize) -> bool {
+        let sectors = size.sectors();
+        let distance = (sectors + self.sector(size) - other.sector(size)) % sectors;
         distance <= 2 && distance > 0
     }
 
-    pub fn alwanese_conjugate_of(&self, other: RunePosition) -> bool {
-        self.0 % 6 == (other.0 + 3) % 6
+    pub fn alwanese_conjugate_of(&self, other: RunePosition, size: LockSize) -> bool {
+        self.sector(size) == (other.sector(size) + size.sectors() / 2) % size.sectors()
     }
 
-    pub fn antakian_twins(&self, other: RunePosition) -> bool {
-        (self.0 < 6) == (other.0 < 6)
+    pub fn antakian_twins(&self, other: RunePosition, size: LockSize) -> bool {
+        (self.0 < size.sectors()) == (other.0 < size.sectors())
     }
 
-    pub fn increases_santor(&self, other: RunePosition) -> bool {
-        self.santor() < other.santor()
+    pub fn max_0_conductive(&self, two: RunePosition, size: LockSize) -> bool {
+        let sectors = size.sectors();
+        match self.antakian_twins(two, size) {
+            true => {
+                (self.sector(size) + 1) % sectors == two.sector(size)
+                    || (two.sector(size) + 1) % sectors == self.sector(size)
+            }
+            false => (self.0 + sectors) % size.positions() == two.0,
+        }
     }
 
-    pub fn santor(&self) -> u32 {
-        SANTOR[self.0]
+    pub fn index(&self) -> usize {
+        self.0
     }
+}
 
-    pub fn max_0_conductive(&self, two: RunePosition) -> bool {
-        return match self.antakian_twins(two) {
-            true => (self.0 + 1) % 6 == two.0 % 6 || (two.0 + 1) % 6 == self.0 % 6,
-            false => (self.0 + 6) % 12 == two.0,
-        };
+impl<T> Index<RunePosition> for [T] {
+    type Output = T;
+
+    fn index(&self, index: RunePosition) -> &Self::Output {
+        self.index(index.0)
     }
+}
 
-    pub fn index(&self) -> usize {
-        self.0
+impl<T> IndexMut<RunePosition> for [T] {
+    fn index_mut(&mut self, index: RunePosition) -> &mut Self::Output {
+        self.index_mut(index.0)
     }
 }
 
-impl<T> Index<RunePosition> for [T; 12] {
-    type Output = <Self as Index<usize>>::Output;
+impl<T> Index<RunePosition> for Vec<T> {
+    type Output = T;
 
     fn index(&self, index: RunePosition) -> &Self::Output {
         self.index(index.0)
     }
 }
 
-impl<T> IndexMut<RunePosition> for [T; 12] {
+impl<T> IndexMut<RunePosition> for Vec<T> {
     fn index_mut(&mut self, index: RunePosition) -> &mut Self::Output {
         self.index_mut(index.0)
     }
@@ -93,16 +100,22 @@ mod tests {
 
     use itertools::Itertools;
 
+    use crate::size::LockSize;
+
     use super::RunePosition;
 
     fn test_pairs(
         pass: HashSet<(usize, usize)>,
-        test: impl Fn(RunePosition, RunePosition) -> bool,
+        test: impl Fn(RunePosition, RunePosition, LockSize) -> bool,
     ) {
         for a in 0..12 {
             for b in 0..12 {
-                let expected = pass.get(&(a, b)).is_some();
-                let actual = test(RunePosition::new(a), RunePosition::new(b));
+                let expected = pass.contains(&(a, b));
+                let actual = test(
+                    RunePosition::new(a),
+                    RunePosition::new(b),
+                    LockSize::STANDARD,
+                );
                 assert_eq!(
                     actual,
                     expected,
@@ -131,7 +144,7 @@ mod tests {
                 (10, 7),
                 (11, 8),
             ]),
-            |a, b| a.antakian_conjugate_of(b),
+            |a, b, size| a.antakian_conjugate_of(b, size),
         );
     }
 
@@ -188,7 +201,7 @@ mod tests {
                 (11, 6),
                 (11, 7),
             ]),
-            |a, b| b.alwanese_of(a),
+            |a, b, size| b.alwanese_of(a, size),
         );
     }
 
@@ -221,7 +234,7 @@ mod tests {
                 (11, 8),
                 (11, 2),
             ]),
-            |a, b| a.alwanese_conjugate_of(b),
+            |a, b, size| a.alwanese_conjugate_of(b, size),
         );
     }
 
@@ -230,13 +243,12 @@ mod tests {
         test_pairs(
             HashSet::from_iter(
                 (0..6)
-                    .into_iter()
                     .permutations(2)
                     .map(|it| (it[0], it[1]))
                     .chain((6..12).permutations(2).map(|it| (it[0], it[1])))
                     .chain((0..12).map(|it| (it, it))),
             ),
-            |a, b| a.antakian_twins(b),
+            |a, b, size| a.antakian_twins(b, size),
         );
     }
 
@@ -245,19 +257,15 @@ mod tests {
         test_pairs(
             HashSet::from_iter(
                 (0..6)
-                    .into_iter()
                     .flat_map(|num| [(num, (num + 1) % 6), ((num + 1) % 6, num)])
-                    .chain((0..6).into_iter().flat_map(|num| {
+                    .chain((0..6).flat_map(|num| {
                         [(num + 6, (num + 1) % 6 + 6), ((num + 1) % 6 + 6, num + 6)]
                     }))
-                    .chain((0..12).into_iter().map(|num| (num, (num + 6) % 12))),
+                    .chain((0..12).map(|num| (num, (num + 6) % 12))),
             ),
-            |a, b| a.max_0_conductive(b),
+            |a, b, size| a.max_0_conductive(b, size),
         );
 
-        assert_eq!(
-            RunePosition::new(9).max_0_conductive(RunePosition::new(5)),
-            false
-        )
+        assert!(!RunePosition::new(9).max_0_conductive(RunePosition::new(5), LockSize::STANDARD))
     }
 }
@@ -0,0 +1,90 @@
+//! Hypotheses about rules that weren't found in the game yet, from known solutions of a lock. A
+//! rule that holds in every known solution might just hold by chance, so whatever is inferred
+//! only enters a lock as an `unverified` rule.
+
+use itertools::Itertools;
+
+use crate::{
+    assignment::Assignment,
+    rule::{RuleKind, PAIR_KINDS},
+    RuneLock,
+};
+
+///The rules in their canonical form that hold in every one of the solutions, but aren't part of the
+///lock yet.
+pub fn consistent_rules(lock: &RuneLock, solutions: &[Assignment]) -> Vec<RuleKind> {
+    let known: Vec<_> = lock.rules.iter().map(|it| it.canonical()).collect();
+    let activations = || lock.size().all_activations();
+    let pair_rules = activations()
+        .cartesian_product(activations())
+        .filter(|(first, second)| first != second)
+        .flat_map(|(first, second)| {
+            PAIR_KINDS
+                .iter()
+                .filter_map(move |kind| RuleKind::between(kind, first, second))
+        });
+    let runes: Vec<_> = lock
+        .runes
+        .iter()
+        .filter(|it| it.is_known())
+        .unique()
+        .copied()
+        .collect();
+    let follows = runes
+        .iter()
+        .cartesian_product(runes.iter())
+        .map(|(first, second)| RuleKind::RuneFollowsImmediately {
+            first: *first,
+            second: *second,
+        });
+    pair_rules
+        .chain(follows)
+        .map(|it| it.canonical())
+        .unique()
+        .filter(|it| !known.contains(it))
+        .filter(|rule| {
+            solutions
+                .iter()
+                .all(|solution| rule.validate(lock, solution).is_ok())
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        assignment::Assignment,
+        rule::ActivationRuleKindHelpers,
+        test_support::{activation, sample_runes},
+        RuneLock,
+    };
+
+    use super::consistent_rules;
+
+    #[test]
+    fn test_inferred_rules_hold_in_every_solution() {
+        let lock = RuneLock::with_rules(sample_runes(), vec![(1, 2).alwanese()]);
+        let solution = |activations: [u8; 12]| {
+            Assignment::from_iter(
+                lock.size(),
+                activations.into_iter().map(|it| Some(activation(it))),
+            )
+            .unwrap()
+        };
+        let solutions = [
+            solution([1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12]),
+            solution([1, 3, 2, 4, 5, 6, 7, 8, 9, 10, 11, 12]),
+        ];
+
+        let inferred = consistent_rules(&lock, &solutions);
+        assert!(!inferred.contains(&(1, 2).alwanese()));
+        assert!(inferred.contains(&(4, 5).alwanese()));
+        //Only holds in the first solution, #2 and #3 swap places in the second one.
+        assert!(!inferred.contains(&(2, 3).alwanese()));
+        for rule in inferred.iter() {
+            for solution in solutions.iter() {
+                assert!(rule.validate(&lock, solution).is_ok(), "{}", rule);
+            }
+        }
+    }
+}
@@ -0,0 +1,158 @@
+//! Heavy commands run as jobs on their own thread. If a job doesn't finish within the latency
+//! budget, the prompt returns and the job keeps running in the background.
+
+use std::{
+    fmt::{Display, Formatter},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+use thiserror::Error;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct JobId(usize);
+
+impl JobId {
+    pub fn from_raw(id: usize) -> Self {
+        Self(id)
+    }
+}
+
+impl Display for JobId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "J{}", self.0)
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum JobError {
+    #[error("Job {0} does not exist")]
+    UnknownJob(JobId),
+    #[error("Job {0} was cancelled")]
+    Cancelled(JobId),
+}
+
+///Set by `cancel` or by Ctrl-C. Long-running work should check it at points where stopping leaves
+///everything consistent, and give up once it is set.
+#[derive(Clone, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed)
+    }
+
+    ///Makes the token usable for the next piece of work.
+    pub fn reset(&self) {
+        self.0.store(false, Ordering::Relaxed)
+    }
+}
+
+struct Job<T> {
+    id: JobId,
+    description: String,
+    cancel: CancelToken,
+    handle: JoinHandle<Option<T>>,
+}
+
+pub struct Jobs<T> {
+    next_id: usize,
+    jobs: Vec<Job<T>>,
+}
+
+impl<T> Default for Jobs<T> {
+    fn default() -> Self {
+        Self {
+            next_id: 1,
+            jobs: Vec::new(),
+        }
+    }
+}
+
+impl<T: Send + 'static> Jobs<T> {
+    ///Starts `work` on its own thread. It returns `None` if it noticed being cancelled.
+    pub fn spawn(
+        &mut self,
+        description: impl Into<String>,
+        work: impl FnOnce(&CancelToken) -> Option<T> + Send + 'static,
+    ) -> JobId {
+        let id = JobId(self.next_id);
+        self.next_id += 1;
+        let cancel = CancelToken::default();
+        let token = cancel.clone();
+        self.jobs.push(Job {
+            id,
+            description: description.into(),
+            cancel,
+            handle: thread::spawn(move || work(&token)),
+        });
+        id
+    }
+
+    ///Waits at most `budget` (forever if `None`) for the job to finish and returns its result.
+    ///Returns `Ok(None)` if the job is still running afterwards. Once `interrupt` is set, the job
+    ///is asked to stop and waited for.
+    pub fn wait(
+        &mut self,
+        id: JobId,
+        budget: Option<Duration>,
+        interrupt: &CancelToken,
+    ) -> Result<Option<T>, JobError> {
+        let index = self
+            .jobs
+            .iter()
+            .position(|it| it.id == id)
+            .ok_or(JobError::UnknownJob(id))?;
+        let started = Instant::now();
+        while !self.jobs[index].handle.is_finished() {
+            if interrupt.is_cancelled() {
+                self.jobs[index].cancel.cancel();
+            } else if budget.is_some_and(|budget| started.elapsed() >= budget) {
+                return Ok(None);
+            }
+            thread::sleep(Duration::from_millis(5));
+        }
+        let job = self.jobs.remove(index);
+        match job.handle.join() {
+            Ok(Some(result)) => Ok(Some(result)),
+            Ok(None) => Err(JobError::Cancelled(id)),
+            Err(panic) => std::panic::resume_unwind(panic),
+        }
+    }
+
+    pub fn cancel(&mut self, id: JobId) -> Result<(), JobError> {
+        let index = self
+            .jobs
+            .iter()
+            .position(|it| it.id == id)
+            .ok_or(JobError::UnknownJob(id))?;
+        let job = self.jobs.remove(index);
+        job.cancel.cancel();
+        Ok(())
+    }
+}
+
+///A line per job, with whether it is still running.
+impl<T> Display for Jobs<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if self.jobs.is_empty() {
+            writeln!(f, "No jobs.")?;
+        }
+        for job in self.jobs.iter() {
+            let state = match job.handle.is_finished() {
+                true => "done",
+                false => "running",
+            };
+            writeln!(f, "{}: {} ({})", job.id, job.description, state)?;
+        }
+        Ok(())
+    }
+}
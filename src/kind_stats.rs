@@ -0,0 +1,199 @@
+//! What the rules of each kind did while consolidating, kept across sessions in a local store.
+//! Kinds that paid off before are consolidated first, so a contradiction they find spares the
+//! checks of the other kinds.
+
+use std::{
+    collections::BTreeMap,
+    fmt::{Display, Formatter},
+    fs, io,
+    path::Path,
+};
+
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+///How many eliminations a contradiction is worth when ordering the kinds, as it ends the
+///consolidation of the state right away.
+const CONTRADICTION_WEIGHT: f64 = 12.0;
+
+#[derive(Debug, Error)]
+pub enum KindStatsError {
+    #[error("Could not access {path}: {source}")]
+    Io { path: String, source: io::Error },
+    #[error("{path} is not a rule kind statistics file: {source}")]
+    Format {
+        path: String,
+        source: serde_json::Error,
+    },
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KindRecord {
+    ///Candidates checked against a rule of the kind.
+    pub checks: u64,
+    ///Facts the eliminations of the checks added.
+    pub eliminations: u64,
+    ///Consolidations the kind found a contradiction in.
+    pub contradictions: u64,
+}
+
+impl KindRecord {
+    ///What a check of the kind is worth, on average.
+    pub fn payoff(&self) -> f64 {
+        (self.eliminations as f64 + CONTRADICTION_WEIGHT * self.contradictions as f64)
+            / (self.checks + 1) as f64
+    }
+}
+
+///Records by the name of the kind, as used when entering rules.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct KindStats {
+    kinds: BTreeMap<String, KindRecord>,
+}
+
+impl KindStats {
+    ///Loads the store, or starts an empty one if there is none yet.
+    pub fn load(path: &Path) -> Result<Self, KindStatsError> {
+        let text = match fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(source) => {
+                return Err(KindStatsError::Io {
+                    path: path.display().to_string(),
+                    source,
+                })
+            }
+        };
+        serde_json::from_str(&text).map_err(|source| KindStatsError::Format {
+            path: path.display().to_string(),
+            source,
+        })
+    }
+
+    ///Saves the store, creating the directory it goes in.
+    pub fn save(&self, path: &Path) -> Result<(), KindStatsError> {
+        let text = serde_json::to_string_pretty(self).map_err(|source| KindStatsError::Format {
+            path: path.display().to_string(),
+            source,
+        })?;
+        let parent = path.parent().filter(|it| !it.as_os_str().is_empty());
+        parent
+            .map_or(Ok(()), fs::create_dir_all)
+            .and_then(|()| fs::write(path, text))
+            .map_err(|source| KindStatsError::Io {
+                path: path.display().to_string(),
+                source,
+            })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.kinds.is_empty()
+    }
+
+    pub fn get(&self, kind: &str) -> Option<&KindRecord> {
+        self.kinds.get(kind)
+    }
+
+    pub fn record(&mut self, kind: &str, update: impl FnOnce(&mut KindRecord)) {
+        update(self.kinds.entry(kind.to_string()).or_default())
+    }
+
+    pub fn merge(&mut self, other: &KindStats) {
+        for (kind, other) in other.kinds.iter() {
+            self.record(kind, |it| {
+                it.checks += other.checks;
+                it.eliminations += other.eliminations;
+                it.contradictions += other.contradictions;
+            });
+        }
+    }
+
+    ///The kinds with records, the ones that paid off most first.
+    pub fn order(&self) -> Vec<String> {
+        self.kinds
+            .iter()
+            .sorted_by(|(_, a), (_, b)| b.payoff().total_cmp(&a.payoff()))
+            .map(|(kind, _)| kind.clone())
+            .collect()
+    }
+}
+
+impl Display for KindStats {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{:<20} {:>10} {:>12} {:>14}",
+            "Rule kind", "checks", "eliminations", "contradictions"
+        )?;
+        for kind in self.order() {
+            let record = &self.kinds[&kind];
+            write!(
+                f,
+                "\n{:<20} {:>10} {:>12} {:>14}",
+                kind, record.checks, record.eliminations, record.contradictions
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs};
+
+    use crate::{
+        fact_solver::FactualSolver,
+        index::RunePosition,
+        rule::ActivationRuleKindHelpers,
+        test_support::{activation, sample_runes},
+        RuneLock,
+    };
+
+    use super::KindStats;
+
+    #[test]
+    fn test_kinds_that_paid_off_go_first() {
+        let mut earlier = KindStats::default();
+        earlier.record("alwanese", |it| {
+            it.checks = 100;
+            it.eliminations = 10;
+        });
+        earlier.record("antakian-twins", |it| {
+            it.checks = 100;
+            it.eliminations = 5;
+            it.contradictions = 2;
+        });
+        assert_eq!(earlier.order(), vec!["antakian-twins", "alwanese"]);
+
+        let lock = RuneLock::with_rules(
+            sample_runes(),
+            vec![(1, 2).alwanese(), (3, 4).antakian_twins()],
+        );
+        let mut solver = FactualSolver::new(lock);
+        solver.tune(earlier.clone());
+        let kinds: Vec<_> = solver
+            .lock()
+            .rules_by_kind()
+            .into_iter()
+            .map(|(kind, _)| kind)
+            .collect();
+        assert_eq!(kinds, vec!["antakian-twins", "alwanese"]);
+
+        solver.assume(activation(1), RunePosition::new(0));
+        let learned = solver.kind_stats().get("alwanese").unwrap();
+        assert!(learned.checks > 100);
+        assert!(learned.eliminations > 10);
+    }
+
+    #[test]
+    fn test_saving_creates_the_directory() {
+        let directory = env::temp_dir().join(format!("rune-lock-stats-{}", std::process::id()));
+        let path = directory.join("rune-lock").join("kind-stats.json");
+        let mut stats = KindStats::default();
+        stats.record("alwanese", |it| it.checks = 3);
+        stats.save(&path).unwrap();
+        assert_eq!(KindStats::load(&path).unwrap(), stats);
+        fs::remove_dir_all(directory).unwrap();
+    }
+}
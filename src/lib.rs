@@ -0,0 +1,400 @@
+//! Deduction of Rune Circle Lock solutions: a lock is described by a `RuneLock`, and explored by a
+//! `FactualSolver`, which keeps a tree of assumptions with everything that follows from them.
+
+pub mod activation;
+pub mod archive;
+pub mod assignment;
+pub mod consistency;
+pub mod coordinate;
+pub mod diagnosis;
+pub mod domain;
+pub mod fact_solver;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+pub mod glossary;
+pub mod index;
+pub mod inference;
+pub mod jobs;
+pub mod kind_stats;
+pub mod lock_builder;
+pub mod lock_file;
+pub mod logging;
+pub mod macros;
+pub mod messages;
+pub mod outcome;
+pub mod rule;
+pub mod rune;
+pub mod size;
+pub mod solver;
+pub mod solver_nodes;
+pub mod terminal;
+#[cfg(test)]
+mod test_support;
+
+use std::{path::Path, sync::OnceLock};
+
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::activation::Activation;
+
+pub use crate::{
+    assignment::Assignment,
+    domain::ActivationDomain,
+    fact_solver::FactualSolver,
+    index::RunePosition,
+    lock_file::LockFileError,
+    rule::{Normalization, RuleKind, RuleTemplate},
+    rune::Rune,
+    size::LockSize,
+};
+
+///A lock: the runes on its positions, the outer circle first, and the rules about the order they
+///are activated in.
+///
+///```
+///use rune_lock_solver::{rule::ActivationRuleKindHelpers, Rune, RuneLock};
+///
+/////Z = 0, V = 1, S = 2, C = 3
+///let mut lock = RuneLock::new([0, 2, 1, 3, 2, 1, 3, 2, 1, 0, 2, 1].map(Rune::new));
+///lock.add_rule((1, 2).alwanese(), 0);
+///lock.add_rule("V follows Z".parse().unwrap(), 0);
+///assert_eq!(lock.rules().len(), 2);
+///assert_eq!(lock.rules()[1].syntax(), "V follows Z");
+///```
+#[derive(Clone, Serialize, Deserialize)]
+pub struct RuneLock {
+    //Sessions saved before locks had a size are of the game's size
+    #[serde(default)]
+    size: LockSize,
+    //Address: Outer Circle, then Inner Circle
+    runes: Vec<Rune>,
+    rules: Vec<RuleKind>,
+    //The stage of the game each rule is revealed at, rules of later stages than `stage` don't apply
+    stages: Vec<usize>,
+    stage: usize,
+    //The templates rules were expanded from, with the indices of their rules
+    templates: Vec<(RuleTemplate, Vec<usize>)>,
+    //Rules that were inferred instead of found in the game, and might not hold
+    hypotheses: Vec<usize>,
+    domains: Vec<ActivationDomain>,
+    //Rule kinds in the order their rules are consolidated in, kinds not listed go last
+    #[serde(skip)]
+    kind_order: Vec<String>,
+    //For every rule relating two activations, indexed by the position of the first one: the
+    //positions of the second one the rule allows alongside it, as bits. Built on first use and
+    //dropped whenever the rules or runes change.
+    #[serde(skip)]
+    pairs: OnceLock<Vec<Option<Vec<u64>>>>,
+    //The santor of every position, built on first use.
+    #[serde(skip)]
+    santors: OnceLock<Vec<u32>>,
+}
+
+#[derive(Debug, Error)]
+pub enum RuneLockError {
+    #[error("Rule {0} was violated: {1}")]
+    RuleViolated(usize, RuleKind),
+    #[error("Rule {0} is not fulfillable: {1}")]
+    RuleUnfulfillable(usize, RuleKind),
+    #[error("{runes} runes don't fill the positions of a lock of {size}")]
+    RuneCount { runes: usize, size: LockSize },
+}
+
+impl RuneLock {
+    ///A lock of the game's size, 12 by 12.
+    pub fn new(runes: [Rune; 12]) -> Self {
+        Self::sized(LockSize::STANDARD, runes.to_vec()).expect("fills the standard size")
+    }
+
+    ///A lock of any size, with a rune on each of its positions.
+    pub fn sized(size: LockSize, runes: Vec<Rune>) -> Result<Self, RuneLockError> {
+        if runes.len() != size.positions() {
+            return Err(RuneLockError::RuneCount {
+                runes: runes.len(),
+                size,
+            });
+        }
+        Ok(Self {
+            size,
+            runes,
+            rules: Vec::new(),
+            stages: Vec::new(),
+            stage: 0,
+            templates: Vec::new(),
+            hypotheses: Vec::new(),
+            domains: Vec::new(),
+            kind_order: Vec::new(),
+            pairs: OnceLock::new(),
+            santors: OnceLock::new(),
+        })
+    }
+
+    ///Loads a lock file, see `lock_file` for the formats.
+    pub fn from_path(path: &Path) -> Result<Self, LockFileError> {
+        lock_file::load_lock(path)
+    }
+
+    pub fn runes(&self) -> &[Rune] {
+        &self.runes
+    }
+
+    ///The runes of the outer and of the inner circle.
+    pub fn circles(&self) -> (&[Rune], &[Rune]) {
+        self.runes.split_at(self.size.sectors())
+    }
+
+    pub fn size(&self) -> LockSize {
+        self.size
+    }
+
+    ///The same runes and size, without any rules.
+    pub fn without_rules(&self) -> Self {
+        Self::sized(self.size, self.runes.clone()).expect("the runes fill the size")
+    }
+
+    ///How high up every position is on the lock, see `LockSize::santors`.
+    pub fn santors(&self) -> &[u32] {
+        self.santors.get_or_init(|| self.size.santors())
+    }
+
+    pub fn santor(&self, position: RunePosition) -> u32 {
+        self.santors()[position]
+    }
+
+    ///The santor of the top sector, the lowest one is 0.
+    pub fn max_santor(&self) -> u32 {
+        self.santors().iter().copied().max().unwrap_or_default()
+    }
+
+    pub fn increases_santor(&self, from: RunePosition, to: RunePosition) -> bool {
+        self.santor(from) < self.santor(to)
+    }
+
+    ///The number of distinct santor values strictly between the bounds.
+    pub fn santors_between(&self, low: Option<u32>, high: Option<u32>) -> usize {
+        self.santors()
+            .iter()
+            .filter(|it| low.is_none_or(|low| **it > low) && high.is_none_or(|high| **it < high))
+            .unique()
+            .count()
+    }
+
+    pub fn rules(&self) -> &[RuleKind] {
+        &self.rules
+    }
+
+    ///The stage of the game the rule is revealed at.
+    pub fn stage_of(&self, rule: usize) -> usize {
+        self.stages[rule]
+    }
+
+    pub fn domains(&self) -> &[ActivationDomain] {
+        &self.domains
+    }
+
+    ///Adds a restriction of an activation known before solving starts, and returns its index.
+    pub fn add_domain(&mut self, domain: ActivationDomain) -> usize {
+        self.domains.push(domain);
+        self.domains.len() - 1
+    }
+
+    ///A lock whose rules all apply from the start.
+    pub fn with_rules(runes: [Rune; 12], rules: Vec<RuleKind>) -> Self {
+        let mut lock = Self::new(runes);
+        for rule in rules {
+            lock.add_rule(rule, 0);
+        }
+        lock
+    }
+
+    pub fn add_rule(&mut self, rule: RuleKind, stage: usize) {
+        self.rules.push(rule);
+        self.stages.push(stage);
+        self.pairs = OnceLock::new();
+    }
+
+    ///Fills in a rune, like one that was unknown when the lock was entered.
+    pub fn set_rune(&mut self, position: RunePosition, rune: Rune) {
+        self.runes[position] = rune;
+        self.pairs = OnceLock::new();
+    }
+
+    ///Whether a rule relating two activations allows the first one on `first` and the second one
+    ///on `second`, as `validate_tuple` would tell, looked up in a table built once for the lock.
+    ///Rules about something else allow every pair.
+    pub fn allows_pair(&self, rule: usize, first: RunePosition, second: RunePosition) -> bool {
+        let pairs = self.pairs.get_or_init(|| {
+            self.rules
+                .iter()
+                .map(|rule| {
+                    let (a, b) = rule.activation_pair()?;
+                    let mut allowed = vec![0u64; self.size.positions()];
+                    for (first, row) in allowed.iter_mut().enumerate() {
+                        for second in 0..self.size.positions() {
+                            let placements = [
+                                (RunePosition::new(first), a),
+                                (RunePosition::new(second), b),
+                            ];
+                            if rule.validate_subset(self, placements).is_ok() {
+                                *row |= 1 << second;
+                            }
+                        }
+                    }
+                    Some(allowed)
+                })
+                .collect()
+        });
+        pairs[rule]
+            .as_ref()
+            .is_none_or(|it| it[first.index()] & (1 << second.index()) != 0)
+    }
+
+    ///Removes a rule, the rules after it move up by one index. Returns the rule, if there was one
+    ///with that index. A template the rule was expanded from no longer holds as a whole, so it is
+    ///dropped, and its other rules stay as rules of their own.
+    pub fn remove_rule(&mut self, rule: usize) -> Option<RuleKind> {
+        if rule >= self.rules.len() {
+            return None;
+        }
+        self.templates.retain(|(_, rules)| !rules.contains(&rule));
+        let shift = |rules: &mut Vec<usize>| {
+            rules.retain(|it| *it != rule);
+            for it in rules.iter_mut().filter(|it| **it > rule) {
+                *it -= 1;
+            }
+        };
+        for (_, rules) in self.templates.iter_mut() {
+            shift(rules);
+        }
+        shift(&mut self.hypotheses);
+        self.stages.remove(rule);
+        self.pairs = OnceLock::new();
+        Some(self.rules.remove(rule))
+    }
+
+    ///Appends the rules of `template`, remembering where they came from.
+    pub fn add_template(&mut self, template: RuleTemplate, stage: usize) {
+        let start = self.rules.len();
+        for rule in template.expand() {
+            self.add_rule(rule, stage);
+        }
+        self.templates
+            .push((template, (start..self.rules.len()).collect()));
+    }
+
+    ///Appends a rule that wasn't found in the game, like one inferred from example solutions.
+    pub fn add_hypothesis(&mut self, rule: RuleKind, stage: usize) {
+        self.hypotheses.push(self.rules.len());
+        self.add_rule(rule, stage);
+    }
+
+    pub fn is_hypothesis(&self, rule: usize) -> bool {
+        self.hypotheses.contains(&rule)
+    }
+
+    pub fn is_active(&self, rule: usize) -> bool {
+        self.stages[rule] <= self.stage
+    }
+
+    ///The rules of the stages reached so far, with their indices.
+    pub fn active_rules(&self) -> impl Iterator<Item = (usize, &RuleKind)> {
+        self.rules
+            .iter()
+            .enumerate()
+            .filter(|(index, _)| self.is_active(*index))
+    }
+
+    ///Every rule with its index, or only those about the activation.
+    pub fn rules_about(
+        &self,
+        activation: Option<Activation>,
+    ) -> impl Iterator<Item = (usize, &RuleKind)> {
+        self.rules
+            .iter()
+            .enumerate()
+            .filter(move |(_, rule)| activation.is_none_or(|it| rule.activations().contains(&it)))
+    }
+
+    ///Consolidates the rules of these kinds first, in this order.
+    pub fn set_kind_order(&mut self, order: Vec<String>) {
+        self.kind_order = order;
+    }
+
+    ///The active rules grouped by kind, in the order the kinds are consolidated in.
+    pub fn rules_by_kind(&self) -> Vec<(&'static str, Vec<(usize, &RuleKind)>)> {
+        let rank = |kind: &str| {
+            self.kind_order
+                .iter()
+                .position(|it| it == kind)
+                .unwrap_or(self.kind_order.len())
+        };
+        self.active_rules()
+            .into_group_map_by(|(_, rule)| rule.name())
+            .into_iter()
+            .sorted_by_key(|(kind, rules)| (rank(kind), rules[0].0))
+            .collect()
+    }
+
+    ///Brings the rules into their canonical form and merges duplicates, so every rule is only
+    ///checked and cited once. Returns what was changed.
+    pub fn canonicalize(&mut self) -> Vec<Normalization> {
+        let (rules, canonical_index, normalizations) = rule::canonicalize(&self.rules);
+        //A merged rule applies from the earliest stage any of its duplicates was revealed at.
+        let mut stages = vec![usize::MAX; rules.len()];
+        for (original, canonical) in canonical_index.iter().enumerate() {
+            stages[*canonical] = stages[*canonical].min(self.stages[original]);
+        }
+        //A merged rule is only a hypothesis if none of its duplicates was found in the game.
+        let found: Vec<_> = (0..canonical_index.len())
+            .filter(|it| !self.is_hypothesis(*it))
+            .map(|it| canonical_index[it])
+            .collect();
+        self.hypotheses = (0..rules.len()).filter(|it| !found.contains(it)).collect();
+        self.rules = rules;
+        self.stages = stages;
+        self.pairs = OnceLock::new();
+        for (_, rules) in self.templates.iter_mut() {
+            for rule in rules.iter_mut() {
+                *rule = canonical_index[*rule];
+            }
+        }
+        normalizations
+    }
+
+    ///The template the rule was expanded from, if any.
+    pub fn template_of(&self, rule: usize) -> Option<&RuleTemplate> {
+        self.templates
+            .iter()
+            .find(|(_, rules)| rules.contains(&rule))
+            .map(|(template, _)| template)
+    }
+
+    ///Where a rule came from, like ` (from '<template>')`, to be appended to the rule when it is
+    ///cited.
+    fn provenance(&self, rule: usize) -> String {
+        let mut provenance = String::new();
+        if let Some(template) = self.template_of(rule) {
+            provenance.push_str(&format!(" (from '{}')", template));
+        }
+        if self.is_hypothesis(rule) {
+            provenance.push_str(" (unverified)");
+        }
+        provenance
+    }
+
+    pub fn validate(&self, assignment: &Assignment) -> Result<(), RuneLockError> {
+        //Build IndexOf Array
+        for (rule_no, rule) in self.active_rules() {
+            rule.validate(self, assignment).map_err(|err| match err {
+                rule::RuleError::Violated => RuneLockError::RuleViolated(rule_no, rule.clone()),
+                rule::RuleError::Unfulfillable => {
+                    RuneLockError::RuleUnfulfillable(rule_no, rule.clone())
+                }
+            })?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,299 @@
+//! Building a `RuneLock` in code, with the checks a lock file gets from its syntax: the runes fill
+//! both circles, and every rule and domain is about something that can be on the lock.
+
+use itertools::Itertools;
+use thiserror::Error;
+
+use crate::{
+    activation::Activation,
+    domain::{ActivationDomain, DomainParseError},
+    index::RunePosition,
+    rule::RuleTemplate,
+    rune::Rune,
+    size::{LockSize, LockSizeError, MAX_POSITIONS},
+    RuleKind, RuneLock,
+};
+
+#[derive(Debug, Error)]
+pub enum RuneLockBuildError {
+    #[error("{0} runes can't be split into two circles of an even number of sectors, of at most {MAX_POSITIONS} runes in all")]
+    RuneCount(usize),
+    #[error("{0}")]
+    Size(LockSizeError),
+    #[error("Rule {rule} '{kind}' is about {activation}, which is out of range")]
+    ActivationOutOfRange {
+        rule: usize,
+        kind: RuleKind,
+        activation: Activation,
+    },
+    #[error("Rule {rule} '{kind}' is about position {position}, which is out of range")]
+    PositionOutOfRange {
+        rule: usize,
+        kind: RuleKind,
+        position: RunePosition,
+    },
+    #[error("Domain {domain} is out of range: {error}")]
+    DomainOutOfRange {
+        domain: usize,
+        error: DomainParseError,
+    },
+    #[error("Rule {rule} '{kind}' relates {activation} to itself")]
+    SameActivation {
+        rule: usize,
+        kind: RuleKind,
+        activation: Activation,
+    },
+    #[error("Rule {rule} '{kind}' negates a rule about runes, which can't be negated")]
+    NotNegatable { rule: usize, kind: RuleKind },
+    #[error("Rule {rule} '{kind}' is about {rune}, which is on no position of the lock")]
+    MissingRune {
+        rule: usize,
+        kind: RuleKind,
+        rune: Rune,
+    },
+}
+
+#[derive(Default)]
+pub struct RuneLockBuilder {
+    runes: Vec<Rune>,
+    activations: Option<usize>,
+    rules: Vec<(RuleKind, usize)>,
+    templates: Vec<(RuleTemplate, usize)>,
+    domains: Vec<ActivationDomain>,
+    stage: usize,
+}
+
+impl RuneLock {
+    pub fn builder() -> RuneLockBuilder {
+        RuneLockBuilder::default()
+    }
+}
+
+impl RuneLockBuilder {
+    ///The runes of the outer circle, then the inner circle.
+    pub fn runes(mut self, runes: impl IntoIterator<Item = Rune>) -> Self {
+        self.runes = runes.into_iter().collect();
+        self
+    }
+
+    ///How many activations a solution places, by default one for every rune.
+    pub fn activations(mut self, activations: usize) -> Self {
+        self.activations = Some(activations);
+        self
+    }
+
+    ///Rules and templates added after this are revealed at `stage`.
+    pub fn stage(mut self, stage: usize) -> Self {
+        self.stage = stage;
+        self
+    }
+
+    pub fn rule(mut self, rule: RuleKind) -> Self {
+        self.rules.push((rule, self.stage));
+        self
+    }
+
+    pub fn template(mut self, template: RuleTemplate) -> Self {
+        self.templates.push((template, self.stage));
+        self
+    }
+
+    pub fn domain(mut self, domain: ActivationDomain) -> Self {
+        self.domains.push(domain);
+        self
+    }
+
+    ///Checks the lock and builds it. Rules come before the rules of templates, in the order they
+    ///were added.
+    pub fn build(self) -> Result<RuneLock, RuneLockBuildError> {
+        let count = self.runes.len();
+        let size =
+            LockSize::new(count, self.activations.unwrap_or(count)).map_err(|it| match it {
+                LockSizeError::Positions(_) | LockSizeError::TooLarge(_) => {
+                    RuneLockBuildError::RuneCount(count)
+                }
+                it => RuneLockBuildError::Size(it),
+            })?;
+        let mut lock = RuneLock::sized(size, self.runes).expect("the size is the rune count");
+        for (rule, stage) in self.rules {
+            lock.add_rule(rule, stage);
+        }
+        for (template, stage) in self.templates {
+            lock.add_template(template, stage);
+        }
+        for (index, domain) in self.domains.into_iter().enumerate() {
+            domain
+                .check_size(size)
+                .map_err(|error| RuneLockBuildError::DomainOutOfRange {
+                    domain: index,
+                    error,
+                })?;
+            lock.add_domain(domain);
+        }
+        for (index, kind) in lock.rules().iter().enumerate() {
+            check_rule(lock.runes(), size, index, kind.clone())?;
+        }
+        Ok(lock)
+    }
+}
+
+fn check_rule(
+    runes: &[Rune],
+    size: LockSize,
+    rule: usize,
+    kind: RuleKind,
+) -> Result<(), RuneLockBuildError> {
+    match kind {
+        RuleKind::RuneFollowsImmediately { first, second }
+        | RuleKind::RuneFollowsWithin { first, second, .. }
+        | RuleKind::RuneClassBefore {
+            earlier: first,
+            later: second,
+        } => {
+            //An unknown rune might turn out to be any of them.
+            if runes.contains(&Rune::Unknown) {
+                return Ok(());
+            }
+            match [first, second].into_iter().find(|it| !runes.contains(it)) {
+                Some(rune) => Err(RuneLockBuildError::MissingRune { rule, kind, rune }),
+                None => Ok(()),
+            }
+        }
+        //The alternatives are checked as if they were rules of their own.
+        RuleKind::AnyOf(alternatives) => alternatives
+            .into_iter()
+            .try_for_each(|it| check_rule(runes, size, rule, it)),
+        RuleKind::Implies {
+            condition,
+            consequence,
+        } => {
+            check_rule(runes, size, rule, *condition)?;
+            check_rule(runes, size, rule, *consequence)
+        }
+        RuleKind::Not(negated)
+            if matches!(
+                *negated,
+                RuleKind::RuneFollowsImmediately { .. }
+                    | RuleKind::RuneFollowsWithin { .. }
+                    | RuleKind::RuneClassBefore { .. }
+            ) =>
+        {
+            Err(RuneLockBuildError::NotNegatable {
+                rule,
+                kind: RuleKind::Not(negated),
+            })
+        }
+        kind => {
+            let activations = kind.activations();
+            if let Some(activation) = activations.iter().find(|it| !size.contains(**it)) {
+                return Err(RuneLockBuildError::ActivationOutOfRange {
+                    activation: *activation,
+                    rule,
+                    kind,
+                });
+            }
+            if let Some(position) = kind
+                .positions()
+                .into_iter()
+                .find(|it| it.index() >= size.positions())
+            {
+                return Err(RuneLockBuildError::PositionOutOfRange {
+                    position,
+                    rule,
+                    kind,
+                });
+            }
+            match activations.into_iter().duplicates().next() {
+                Some(activation) => Err(RuneLockBuildError::SameActivation {
+                    rule,
+                    kind,
+                    activation,
+                }),
+                None => Ok(()),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        rule::{ActivationRuleKindHelpers, RuleTemplate},
+        rune::Rune,
+        size::LockSize,
+        test_support::sample_runes,
+        RuleKind, RuneLock,
+    };
+
+    use super::RuneLockBuildError;
+
+    #[test]
+    fn test_invalid_locks_are_rejected() {
+        let runes = sample_runes();
+        let lock = RuneLock::builder()
+            .runes(runes)
+            .rule((1, 2).alwanese())
+            .stage(1)
+            .template(RuleTemplate::santor_chain(10, 12))
+            .build()
+            .unwrap();
+        assert_eq!(lock.rules().len(), 3);
+        assert_eq!(lock.stage_of(2), 1);
+
+        assert!(matches!(
+            RuneLock::builder().runes(runes[..6].to_vec()).build(),
+            Err(RuneLockBuildError::RuneCount(6))
+        ));
+        assert!(matches!(
+            RuneLock::builder()
+                .runes(runes)
+                .rule((3, 3).different_runes())
+                .build(),
+            Err(RuneLockBuildError::SameActivation { rule: 0, .. })
+        ));
+        let follows = RuleKind::RuneFollowsImmediately {
+            first: Rune::new(0),
+            second: Rune::new(7),
+        };
+        assert!(matches!(
+            RuneLock::builder().runes(runes).rule(follows.clone()).build(),
+            Err(RuneLockBuildError::MissingRune { rune, .. }) if rune == Rune::new(7)
+        ));
+        let mut unknown = runes;
+        unknown[4] = Rune::Unknown;
+        assert!(RuneLock::builder()
+            .runes(unknown)
+            .rule(follows)
+            .build()
+            .is_ok());
+
+        assert!(matches!(
+            RuneLock::builder().runes(runes).rule((1, 13).alwanese()).build(),
+            Err(RuneLockBuildError::ActivationOutOfRange { activation, .. })
+                if activation.index() == 12
+        ));
+
+        let small = RuneLock::builder()
+            .runes(runes[..8].to_vec())
+            .activations(6)
+            .rule((1, 6).alwanese())
+            .build()
+            .unwrap();
+        assert_eq!(small.size(), LockSize::new(8, 6).unwrap());
+        assert!(matches!(
+            RuneLock::builder()
+                .runes(runes[..8].to_vec())
+                .activations(6)
+                .rule("1 at 8".parse().unwrap())
+                .build(),
+            Err(RuneLockBuildError::PositionOutOfRange { .. })
+        ));
+        assert!(matches!(
+            RuneLock::builder()
+                .runes(runes[..8].to_vec())
+                .activations(9)
+                .build(),
+            Err(RuneLockBuildError::Size(_))
+        ));
+    }
+}
@@ -0,0 +1,567 @@
+//! Lock definitions in plain text files, in the format the `new-lock` wizard asks for: the runes of
+//! the outer and the inner circle on the first two lines, then one rule per line. Both circles have
+//! the same, even number of runes, six on the game's locks. A lock takes an activation for every
+//! position unless a line like `activations 6` right after the circles says otherwise. Empty lines and
+//! lines starting with `#` are skipped. Lines like `domain 1 outer` restrict an activation to a
+//! part of the lock. Rules after a `stage 2` line only apply once the session reached that stage of
+//! the game. Rules prefixed with `unverified` are hypotheses that weren't found in the game. Errors
+//! cite the line and column they were found at.
+//!
+//! Files ending in `.toml` are read as TOML instead, with the circles, rules and domains written
+//! the same way:
+//!
+//! ```toml
+//! outer = "Z S V C S V"
+//! inner = "C S V Z ? 7"
+//! activations = 12
+//! domains = ["1 outer"]
+//! rules = ["1 2 alwanese", "V follows Z", "10..12 santor-chain"]
+//!
+//! [stages]
+//! 2 = ["3 4 different-runes"]
+//! ```
+
+use std::{collections::BTreeMap, fmt::Display, fs, io, path::Path};
+
+use itertools::Itertools;
+use serde::Deserialize;
+use thiserror::Error;
+use toml::Spanned;
+
+use crate::{
+    activation::HumanActivation,
+    domain::{ActivationDomain, DomainParseError},
+    rule::{RuleKind, RuleParseError, RuleTemplate},
+    rune::{Rune, RuneError},
+    size::{LockSize, LockSizeError},
+    RuneLock,
+};
+
+///A 1 based line and column in a lock file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Span {
+    pub line: usize,
+    pub column: usize,
+}
+
+impl Display for Span {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.line, self.column)
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum LockSyntaxError {
+    #[error("{0}")]
+    Rune(#[from] RuneError),
+    #[error("Expected {expected} runes like the outer circle, got {got}")]
+    CircleLength { expected: usize, got: usize },
+    #[error("{0}")]
+    Size(#[from] LockSizeError),
+    #[error("Expected a number of activations, got '{0}'")]
+    Activations(String),
+    #[error("The number of activations goes right after the circles")]
+    LateActivations,
+    #[error("Missing the runes of the {0} circle")]
+    MissingCircle(&'static str),
+    #[error("{0}")]
+    Rule(#[from] RuleParseError),
+    #[error("{0}")]
+    Domain(#[from] DomainParseError),
+    #[error("Expected a stage number, got '{0}'")]
+    Stage(String),
+    #[error("{0}")]
+    Toml(String),
+}
+
+#[derive(Debug, Error)]
+pub enum LockFileError {
+    #[error("Could not read {path}: {source}")]
+    Io { path: String, source: io::Error },
+    #[error("{path}:{span}: {error}")]
+    Invalid {
+        path: String,
+        span: Span,
+        error: LockSyntaxError,
+    },
+}
+
+///Loads a lock file in the text format, or in TOML if its extension is `.toml`.
+pub fn load_lock(path: &Path) -> Result<RuneLock, LockFileError> {
+    let source = fs::read_to_string(path).map_err(|source| LockFileError::Io {
+        path: path.display().to_string(),
+        source,
+    })?;
+    let lock = match is_toml(path) {
+        true => parse_toml_lock(&source),
+        false => parse_lock(&source),
+    };
+    lock.map_err(|(span, error)| LockFileError::Invalid {
+        path: path.display().to_string(),
+        span,
+        error,
+    })
+}
+
+pub fn parse_lock(source: &str) -> Result<RuneLock, (Span, LockSyntaxError)> {
+    let mut lines = source
+        .lines()
+        .enumerate()
+        .map(|(index, line)| (index + 1, line))
+        .filter(|(_, line)| !line.trim().is_empty() && !line.trim_start().starts_with('#'));
+
+    let mut circle = |name: &'static str, sectors| {
+        let (line, text) = lines.next().ok_or((
+            Span {
+                line: source.lines().count().max(1),
+                column: 1,
+            },
+            LockSyntaxError::MissingCircle(name),
+        ))?;
+        parse_circle(text, sectors).map_err(|(column, error)| (Span { line, column }, error))
+    };
+    let mut runes = circle("outer", None)?;
+    runes.extend(circle("inner", Some(runes.len()))?);
+
+    let mut lines = lines.peekable();
+    let mut size = LockSize::new(runes.len(), runes.len()).expect("the outer circle was checked");
+    if let Some((line, text)) = lines.next_if(|(_, text)| is_activations(text)) {
+        let column = words(text).get(1).map_or(1, |(column, _)| *column);
+        size = parse_activations(text)
+            .and_then(|it| Ok(LockSize::new(runes.len(), it)?))
+            .map_err(|error| (Span { line, column }, error))?;
+    }
+    let mut lock = RuneLock::sized(size, runes).expect("the circles fill the size");
+    let mut stage = 0;
+    for (line, text) in lines {
+        if is_activations(text) {
+            return Err((Span { line, column: 1 }, LockSyntaxError::LateActivations));
+        }
+        if let Some(number) = text.trim_start().strip_prefix("stage ") {
+            stage = number.trim().parse().map_err(|_| {
+                let column = words(text).get(1).map_or(1, |(column, _)| *column);
+                (
+                    Span { line, column },
+                    LockSyntaxError::Stage(number.trim().into()),
+                )
+            })?;
+            continue;
+        }
+        if let Some(domain) = text.trim_start().strip_prefix("domain ") {
+            match parse_domain(domain, size) {
+                Ok(domain) => lock.domains.push(domain),
+                Err(error) => {
+                    let column = blame_domain(text, &error);
+                    return Err((Span { line, column }, error.into()));
+                }
+            }
+            continue;
+        }
+        if let Err(error) = add_rule_line(&mut lock, text, stage) {
+            let column = blame(text, &error);
+            return Err((Span { line, column }, error.into()));
+        }
+    }
+    Ok(lock)
+}
+
+pub fn is_toml(path: &Path) -> bool {
+    path.extension().is_some_and(|it| it == "toml")
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TomlLock {
+    outer: Spanned<String>,
+    inner: Spanned<String>,
+    ///One for every position if it isn't given.
+    activations: Option<Spanned<usize>>,
+    #[serde(default)]
+    domains: Vec<Spanned<String>>,
+    #[serde(default)]
+    rules: Vec<Spanned<String>>,
+    ///The rules revealed at each later stage of the game.
+    #[serde(default)]
+    stages: BTreeMap<Spanned<String>, Vec<Spanned<String>>>,
+}
+
+pub fn parse_toml_lock(source: &str) -> Result<RuneLock, (Span, LockSyntaxError)> {
+    let document: TomlLock = toml::from_str(source).map_err(|error| {
+        let offset = error.span().map_or(0, |it| it.start);
+        (
+            span_at(source, offset),
+            LockSyntaxError::Toml(error.message().to_string()),
+        )
+    })?;
+    //Errors in a string are reported at the column of the offending word inside of it, after the
+    //opening quote.
+    let at =
+        |string: &Spanned<String>, column: usize| span_at(source, string.span().start + column);
+
+    let circle = |string: &Spanned<String>, sectors| {
+        parse_circle(string.get_ref(), sectors)
+            .map_err(|(column, error)| (at(string, column), error))
+    };
+    let mut runes = circle(&document.outer, None)?;
+    runes.extend(circle(&document.inner, Some(runes.len()))?);
+
+    let size = match &document.activations {
+        Some(activations) => LockSize::new(runes.len(), *activations.get_ref())
+            .map_err(|error| (span_at(source, activations.span().start), error.into()))?,
+        None => LockSize::new(runes.len(), runes.len()).expect("the outer circle was checked"),
+    };
+    let mut lock = RuneLock::sized(size, runes).expect("the circles fill the size");
+    for domain in document.domains.iter() {
+        let text = format!("domain {}", domain.get_ref());
+        match parse_domain(domain.get_ref(), size) {
+            Ok(it) => lock.domains.push(it),
+            Err(error) => {
+                //`blame_domain` counts the `domain ` prefix of the text format as well.
+                let column = blame_domain(&text, &error).saturating_sub(7).max(1);
+                return Err((at(domain, column), error.into()));
+            }
+        }
+    }
+    let stages = document.stages.iter().map(|(stage, rules)| {
+        stage
+            .get_ref()
+            .parse::<usize>()
+            .map(|it| (it, rules))
+            .map_err(|_| {
+                (
+                    span_at(source, stage.span().start),
+                    LockSyntaxError::Stage(stage.get_ref().clone()),
+                )
+            })
+    });
+    for staged in [Ok((0, &document.rules))].into_iter().chain(stages) {
+        let (stage, rules) = staged?;
+        for rule in rules.iter() {
+            let text = rule.get_ref();
+            if let Err(error) = add_rule_line(&mut lock, text, stage) {
+                return Err((at(rule, blame(text, &error)), error.into()));
+            }
+        }
+    }
+    Ok(lock)
+}
+
+///Adds a rule, a template or an `unverified` rule, written as in a lock file. It has to fit the
+///size of the lock.
+fn add_rule_line(lock: &mut RuneLock, text: &str, stage: usize) -> Result<(), RuleParseError> {
+    let size = lock.size();
+    if let Some(rule) = text.trim_start().strip_prefix("unverified ") {
+        let rule = rule.parse::<RuleKind>()?;
+        rule.check_size(size)?;
+        lock.add_hypothesis(rule, stage);
+    } else if text.contains("..") {
+        let template = text.parse::<RuleTemplate>()?;
+        template.check_size(size)?;
+        lock.add_template(template, stage);
+    } else {
+        let rule = text.parse::<RuleKind>()?;
+        rule.check_size(size)?;
+        lock.add_rule(rule, stage);
+    }
+    Ok(())
+}
+
+fn parse_domain(text: &str, size: LockSize) -> Result<ActivationDomain, DomainParseError> {
+    let domain = text.parse::<ActivationDomain>()?;
+    domain.check_size(size)?;
+    Ok(domain)
+}
+
+fn is_activations(line: &str) -> bool {
+    line.trim_start().starts_with("activations ")
+}
+
+fn parse_activations(line: &str) -> Result<usize, LockSyntaxError> {
+    let number = line.trim_start()["activations ".len()..].trim();
+    number
+        .parse()
+        .map_err(|_| LockSyntaxError::Activations(number.into()))
+}
+
+///The line and column of a byte offset into `source`.
+fn span_at(source: &str, offset: usize) -> Span {
+    let before = &source[..offset.min(source.len())];
+    let line_start = before.rfind('\n').map_or(0, |it| it + 1);
+    Span {
+        line: before.matches('\n').count() + 1,
+        column: before[line_start..].chars().count() + 1,
+    }
+}
+
+///Writes the lock in the format `parse_lock` reads.
+pub fn write_lock(lock: &RuneLock) -> String {
+    let mut text = String::new();
+    let (outer, inner) = lock.circles();
+    text.push_str(&outer.iter().join(" "));
+    text.push('\n');
+    text.push_str(&inner.iter().join(" "));
+    text.push('\n');
+    if !lock.size().is_square() {
+        text.push_str(&format!("activations {}\n", lock.size().activations()));
+    }
+    for domain in lock.domains.iter() {
+        text.push_str("domain ");
+        text.push_str(&domain.syntax());
+        text.push('\n');
+    }
+    for stage in lock.stages.iter().copied().sorted().dedup() {
+        if stage > 0 {
+            text.push_str(&format!("stage {}\n", stage));
+        }
+        for (index, rule) in lock.rules.iter().enumerate() {
+            if lock.stages[index] == stage && lock.template_of(index).is_none() {
+                if lock.is_hypothesis(index) {
+                    text.push_str("unverified ");
+                }
+                text.push_str(&rule.syntax());
+                text.push('\n');
+            }
+        }
+        for (template, rules) in lock.templates.iter() {
+            if rules.first().is_some_and(|it| lock.stages[*it] == stage) {
+                text.push_str(&template.syntax());
+                text.push('\n');
+            }
+        }
+    }
+    text
+}
+
+///Parses the runes of a circle, `sectors` of them if the other circle is known already, or as many
+///as make up a lock otherwise. Errors come with the 1 based column of the offending word.
+pub fn parse_circle(
+    line: &str,
+    sectors: Option<usize>,
+) -> Result<Vec<Rune>, (usize, LockSyntaxError)> {
+    let words = words(line);
+    let mut runes = Vec::new();
+    for (column, word) in words.iter() {
+        runes.push(
+            word.parse()
+                .map_err(|it| (*column, LockSyntaxError::Rune(it)))?,
+        );
+    }
+    let end = || line.trim_end().len() + 1;
+    match sectors {
+        Some(expected) if runes.len() != expected => {
+            let column = words.get(expected).map_or_else(end, |(column, _)| *column);
+            Err((
+                column,
+                LockSyntaxError::CircleLength {
+                    expected,
+                    got: runes.len(),
+                },
+            ))
+        }
+        Some(_) => Ok(runes),
+        None => match LockSize::new(runes.len() * 2, runes.len() * 2) {
+            Ok(_) => Ok(runes),
+            Err(error) => Err((end(), error.into())),
+        },
+    }
+}
+
+///The words of a line with their 1 based columns.
+fn words(line: &str) -> Vec<(usize, &str)> {
+    let mut words = Vec::new();
+    let mut start = None;
+    for (index, char) in line.char_indices().chain([(line.len(), ' ')]) {
+        match (start, char.is_whitespace()) {
+            (None, false) => start = Some(index),
+            (Some(begin), true) => {
+                words.push((line[..begin].chars().count() + 1, &line[begin..index]));
+                start = None;
+            }
+            _ => {}
+        }
+    }
+    words
+}
+
+///Finds the column of the word a rule couldn't be parsed because of.
+fn blame(line: &str, error: &RuleParseError) -> usize {
+    let words = words(line);
+    let culprit = words.iter().find(|(_, word)| match error {
+        RuleParseError::UnknownKind(kind) => word == kind,
+        RuleParseError::ActivationInvalid(_) => word.split("..").any(|it| {
+            it.chars().all(|c| c.is_ascii_digit() || c == '#')
+                && it.parse::<HumanActivation>().is_err()
+        }),
+        RuleParseError::RuneInvalid(_) => {
+            !["follows", "within", "before", "unverified"].contains(word)
+                && word.parse::<Rune>().is_err()
+        }
+        RuleParseError::PositionInvalid(position) => word == position,
+        RuleParseError::GapInvalid(gap) => word == gap,
+        RuleParseError::NotNegatable(kind) => word == kind,
+        //Templates name the last activation of their range, which is outside if any one is.
+        RuleParseError::ActivationOutside(number, _) => word.split("..").any(|it| {
+            it.trim_start_matches('#')
+                .parse::<u8>()
+                .is_ok_and(|it| it >= *number)
+        }),
+        RuleParseError::PositionOutside(position, _) => *word == position.to_string(),
+        RuleParseError::Malformed => false,
+    });
+    culprit.or(words.first()).map_or(1, |(column, _)| *column)
+}
+
+///Finds the column of the word a domain couldn't be parsed because of.
+fn blame_domain(line: &str, error: &DomainParseError) -> usize {
+    let words = words(line);
+    let culprit = match error {
+        DomainParseError::Malformed => words.get(2),
+        DomainParseError::ActivationInvalid(_) => words.get(1),
+        DomainParseError::PositionInvalid(position) => {
+            words.iter().skip(3).find(|(_, word)| word == position)
+        }
+    };
+    culprit.or(words.first()).map_or(1, |(column, _)| *column)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::size::LockSize;
+
+    use super::{parse_lock, parse_toml_lock, write_lock, LockSyntaxError, Span};
+
+    fn error_of(source: &str) -> (Span, LockSyntaxError) {
+        match parse_lock(source) {
+            Ok(_) => panic!("{:?} should not parse", source),
+            Err(it) => it,
+        }
+    }
+
+    #[test]
+    fn test_errors_cite_line_and_column() {
+        let lock = "# A lock\nZ S V C S V\nC S V Z S V\n1 2 alwanese\n10..12 santor-chain\n";
+        let lock = parse_lock(lock).unwrap();
+        assert_eq!(lock.rules.len(), 3);
+
+        let error = error_of("Z S V C S V\nC S V Z S V\ndomain 1 in 0 12\n");
+        assert_eq!(
+            error.0,
+            Span {
+                line: 3,
+                column: 15
+            }
+        );
+
+        let error = error_of("Z S V C S V\nC S X Z S V\n");
+        assert_eq!(error.0, Span { line: 2, column: 5 });
+        assert!(matches!(error.1, LockSyntaxError::Rune(_)));
+
+        let error = error_of("Z S V C S V\nC S V Z S V\n\n1 13 alwanese\n");
+        assert_eq!(error.0, Span { line: 4, column: 3 });
+
+        let error = error_of("Z S V C S V\nC S V Z S V\n1 2 alwanse\n");
+        assert_eq!(error.0, Span { line: 3, column: 5 });
+
+        let error = error_of("Z S V C S\n");
+        assert_eq!(
+            error.0,
+            Span {
+                line: 1,
+                column: 10
+            }
+        );
+        assert!(matches!(error.1, LockSyntaxError::Size(_)));
+
+        let error = error_of("Z S V C S V\nC S V Z S\n");
+        assert_eq!(
+            error.0,
+            Span {
+                line: 2,
+                column: 10
+            }
+        );
+        assert!(matches!(
+            error.1,
+            LockSyntaxError::CircleLength {
+                expected: 6,
+                got: 5
+            }
+        ));
+    }
+
+    #[test]
+    fn test_smaller_locks() {
+        let source =
+            "Z S V C\nC S ? Z\nactivations 6\ndomain 1 in 0 7\n2 1 alwanese\n5..6 santor-chain\n";
+        let lock = parse_lock(source).unwrap_or_else(|(span, error)| panic!("{}: {}", span, error));
+        assert_eq!(lock.size(), LockSize::new(8, 6).unwrap());
+        assert_eq!(write_lock(&lock), source);
+
+        let error = error_of("Z S V C\nC S ? Z\nactivations 6\n1 7 alwanese\n");
+        assert_eq!(error.0, Span { line: 4, column: 3 });
+        let error = error_of("Z S V C\nC S ? Z\n1 2 alwanese\nactivations 6\n");
+        assert!(matches!(error.1, LockSyntaxError::LateActivations));
+        let error = error_of("Z S V C\nC S ? Z\nactivations 9\n");
+        assert_eq!(
+            error.0,
+            Span {
+                line: 3,
+                column: 13
+            }
+        );
+        assert!(matches!(error.1, LockSyntaxError::Size(_)));
+        let error = error_of("Z S V C\nC S ? Z\ndomain 1 in 0 8\n");
+        assert_eq!(
+            error.0,
+            Span {
+                line: 3,
+                column: 15
+            }
+        );
+
+        let toml = "outer = \"Z S V C\"\ninner = \"C S ? Z\"\nactivations = 6\n";
+        let lock =
+            parse_toml_lock(toml).unwrap_or_else(|(span, error)| panic!("{}: {}", span, error));
+        assert_eq!(lock.size(), LockSize::new(8, 6).unwrap());
+    }
+
+    #[test]
+    fn test_written_lock_reads_back() {
+        let source = "Z S V C S V\nC S V Z ? 7\ndomain 1 in 0 6\n2 1 alwanese\nV follows Z\nunverified 5 6 alwanese\nstage 1\n3 4 different-runes\n10..12 santor-chain\n";
+        let lock = parse_lock(source).unwrap_or_else(|(span, error)| panic!("{}: {}", span, error));
+        assert_eq!(write_lock(&lock), source);
+    }
+
+    #[test]
+    fn test_toml_locks_match_text_locks() {
+        let toml = r#"
+outer = "Z S V C S V"
+inner = "C S V Z ? 7"
+domains = ["1 in 0 6"]
+rules = ["2 1 alwanese", "V follows Z"]
+
+[stages]
+1 = ["3 4 different-runes", "10..12 santor-chain"]
+"#;
+        let lock =
+            parse_toml_lock(toml).unwrap_or_else(|(span, error)| panic!("{}: {}", span, error));
+        assert_eq!(
+            write_lock(&lock),
+            "Z S V C S V\nC S V Z ? 7\ndomain 1 in 0 6\n2 1 alwanese\nV follows Z\nstage 1\n3 4 different-runes\n10..12 santor-chain\n"
+        );
+
+        let error = match parse_toml_lock(
+            "outer = \"Z S V C S V\"\ninner = \"C S V Z S V\"\nrules = [\"1 2 alwanse\"]\n",
+        ) {
+            Ok(_) => panic!("the rule kind is misspelled"),
+            Err(it) => it,
+        };
+        assert_eq!(
+            error.0,
+            Span {
+                line: 3,
+                column: 15
+            }
+        );
+    }
+}
@@ -0,0 +1,42 @@
+//! Thin facade over `log`, so the deduction core can be built without any logging dependency.
+
+#[cfg(feature = "logging")]
+pub use log::debug;
+
+#[cfg(not(feature = "logging"))]
+macro_rules! debug {
+    ($($arg:tt)*) => {{
+        if false {
+            let _ = format_args!($($arg)*);
+        }
+    }};
+}
+#[cfg(not(feature = "logging"))]
+pub(crate) use debug;
+
+///Whether expensive debug output (like knowledge dumps) should be produced.
+pub fn debug_enabled() -> bool {
+    #[cfg(feature = "logging")]
+    {
+        log::log_enabled!(log::Level::Debug)
+    }
+    #[cfg(not(feature = "logging"))]
+    {
+        false
+    }
+}
+
+///Logs what `RUST_LOG` asks for, or what `filters` asks for if given, in the same syntax, like
+///`debug` or `rune_lock_solver::fact_solver=trace`.
+pub fn init(filters: Option<&str>) {
+    #[cfg(feature = "logging")]
+    {
+        let mut builder = env_logger::Builder::from_default_env();
+        if let Some(filters) = filters {
+            builder.parse_filters(filters);
+        }
+        builder.init();
+    }
+    #[cfg(not(feature = "logging"))]
+    let _ = filters;
+}
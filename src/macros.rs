@@ -0,0 +1,177 @@
+//! Macros of session commands, kept across sessions in a local TOML file: a macro is a name for a
+//! few commands separated by `;`, like `assume $1 $2; dump`, which run one after the other when
+//! the name is typed. `$1` to `$9` stand for the words typed after the name. A macro of a single
+//! command is an alias.
+
+use std::{
+    collections::BTreeMap,
+    fmt::{Display, Formatter},
+    fs, io,
+    path::Path,
+};
+
+use thiserror::Error;
+
+///How deep macros may run other macros, so a macro that runs itself is caught.
+const MAX_NESTING: usize = 8;
+
+#[derive(Debug, Error)]
+pub enum MacroError {
+    #[error("Could not access {path}: {source}")]
+    Io { path: String, source: io::Error },
+    #[error("{path} is not a macro file: {source}")]
+    Format {
+        path: String,
+        source: toml::de::Error,
+    },
+    #[error("Could not write the macros: {0}")]
+    Serialize(#[from] toml::ser::Error),
+    #[error("'{0}' is not a valid macro name, it has to be a single word")]
+    InvalidName(String),
+    #[error("The macro '{0}' has no commands")]
+    Empty(String),
+    #[error("The macro '{0}' runs macros more than {MAX_NESTING} levels deep")]
+    TooDeep(String),
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Macros {
+    macros: BTreeMap<String, String>,
+}
+
+impl Macros {
+    ///Loads the macros, or starts without any if there is no file yet.
+    pub fn load(path: &Path) -> Result<Self, MacroError> {
+        let text = match fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(source) => {
+                return Err(MacroError::Io {
+                    path: path.display().to_string(),
+                    source,
+                })
+            }
+        };
+        let macros = toml::from_str(&text).map_err(|source| MacroError::Format {
+            path: path.display().to_string(),
+            source,
+        })?;
+        Ok(Self { macros })
+    }
+
+    ///Saves the macros, creating the directory they go in.
+    pub fn save(&self, path: &Path) -> Result<(), MacroError> {
+        let text = toml::to_string(&self.macros)?;
+        let parent = path.parent().filter(|it| !it.as_os_str().is_empty());
+        parent
+            .map_or(Ok(()), fs::create_dir_all)
+            .and_then(|()| fs::write(path, text))
+            .map_err(|source| MacroError::Io {
+                path: path.display().to_string(),
+                source,
+            })
+    }
+
+    ///Defines the macro, replacing an earlier one of the same name.
+    pub fn define(&mut self, name: &str, commands: &str) -> Result<(), MacroError> {
+        if name.is_empty() || name.contains(char::is_whitespace) {
+            return Err(MacroError::InvalidName(name.into()));
+        }
+        if commands.split(';').all(|it| it.trim().is_empty()) {
+            return Err(MacroError::Empty(name.into()));
+        }
+        self.macros.insert(name.into(), commands.trim().into());
+        Ok(())
+    }
+
+    ///Removes the macro, returning whether there was one.
+    pub fn remove(&mut self, name: &str) -> bool {
+        self.macros.remove(name).is_some()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.macros.is_empty()
+    }
+
+    ///The commands the line runs, if it starts with the name of a macro. Macros among them are
+    ///expanded as well.
+    pub fn expand(&self, line: &str) -> Option<Result<Vec<String>, MacroError>> {
+        let name = line.split_whitespace().next()?;
+        self.macros.contains_key(name).then(|| {
+            let mut commands = Vec::new();
+            self.expand_into(line, 0, &mut commands)
+                .map_err(|()| MacroError::TooDeep(name.into()))?;
+            Ok(commands)
+        })
+    }
+
+    fn expand_into(&self, line: &str, depth: usize, commands: &mut Vec<String>) -> Result<(), ()> {
+        let mut words = line.split_whitespace();
+        let Some(body) = words.next().and_then(|it| self.macros.get(it)) else {
+            commands.push(line.trim().into());
+            return Ok(());
+        };
+        if depth == MAX_NESTING {
+            return Err(());
+        }
+        let arguments: Vec<_> = words.collect();
+        for command in body.split(';').map(str::trim).filter(|it| !it.is_empty()) {
+            let mut command = command.to_string();
+            //From the back, so `$1` doesn't replace the start of `$10`.
+            for (index, argument) in arguments.iter().enumerate().rev() {
+                command = command.replace(&format!("${}", index + 1), argument);
+            }
+            self.expand_into(&command, depth + 1, commands)?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for Macros {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for (index, (name, commands)) in self.macros.iter().enumerate() {
+            if index > 0 {
+                writeln!(f)?;
+            }
+            write!(f, "{}: {}", name, commands)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{MacroError, Macros};
+
+    #[test]
+    fn test_macros_expand_to_their_commands() {
+        let mut macros = Macros::default();
+        macros.define("ad", "assume $1 $2; dump").unwrap();
+        macros.define("look", "ad $1 #3; explain F$2").unwrap();
+        macros.define("loop", "loop").unwrap();
+        assert!(matches!(
+            macros.define("two words", "dump"),
+            Err(MacroError::InvalidName(_))
+        ));
+        assert!(matches!(
+            macros.define("none", " ; "),
+            Err(MacroError::Empty(_))
+        ));
+
+        assert!(macros.expand("dump").is_none());
+        assert_eq!(
+            macros.expand("look 4 12").unwrap().unwrap(),
+            vec!["assume 4 #3", "dump", "explain F12"]
+        );
+        assert!(matches!(
+            macros.expand("loop"),
+            Some(Err(MacroError::TooDeep(_)))
+        ));
+
+        let text = toml::to_string(&macros.macros).unwrap();
+        let parsed = Macros {
+            macros: toml::from_str(&text).unwrap(),
+        };
+        assert_eq!(parsed, macros);
+    }
+}
@@ -1,69 +1,74 @@
-pub mod activation;
-pub mod assignment;
-pub mod command;
-pub mod fact_solver;
-pub mod index;
-pub mod rule;
-pub mod rune;
-pub mod solver;
-pub mod solver_nodes;
+mod cli;
+mod command;
+mod wizard;
 
-use std::io;
-use std::io::BufRead;
+use std::collections::VecDeque;
+use std::fs;
+use std::io::{self, IsTerminal};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::thread;
+use std::time::Duration;
 
-use assignment::Assignment;
-use crossterm::style::Stylize;
-use rule::ActivationRuleKindHelpers;
-use rule::RuleKind;
-use rune::Rune;
-use thiserror::Error;
+use itertools::Itertools;
+use rune_lock_solver::activation::{Activation, HumanActivation};
+use rune_lock_solver::fact_solver::advisor::Advice;
+use rune_lock_solver::fact_solver::assumption_tree::AssumptionTreeNodeHandle;
+use rune_lock_solver::fact_solver::auto::AutoOutcome;
+use rune_lock_solver::fact_solver::edit::EditImpact;
+use rune_lock_solver::fact_solver::fsck::Finding;
+use rune_lock_solver::fact_solver::import::{self, ImportOutcome};
+use rune_lock_solver::fact_solver::merge::{Merge, Side};
+use rune_lock_solver::fact_solver::retract::Retracted;
+use rune_lock_solver::fact_solver::sampling::Sampling;
+use rune_lock_solver::fact_solver::search::{SolutionCount, Solutions};
+use rune_lock_solver::fact_solver::session;
+use rune_lock_solver::fact_solver::uniqueness::{CheckpointError, Uniqueness, UniquenessReport};
+use rune_lock_solver::fact_solver::{self, Displayed, FactualSolver};
+use rune_lock_solver::index::RunePosition;
+use rune_lock_solver::jobs::{CancelToken, JobId, Jobs};
+use rune_lock_solver::kind_stats::KindStats;
+use rune_lock_solver::macros::Macros;
+use rune_lock_solver::outcome::Outcome;
+use rune_lock_solver::rule::ActivationRuleKindHelpers;
+use rune_lock_solver::{diagnosis, glossary, lock_file, logging, messages, terminal};
+use rune_lock_solver::{Assignment, RuleKind, RuleTemplate, Rune, RuneLock};
 
-use crate::command::SolverCommand;
-use crate::fact_solver::FactualSolver;
+use crate::cli::{Cli, CliCommand, InputLines, NodeNames};
+use crate::command::{SolverCommand, SolverCommandError};
 
-pub struct RuneLock {
-    //Address: Outer Circle, then Inner Circle
-    runes: [Rune; 12],
-    rules: Vec<RuleKind>,
-}
+///How many partial assignments `verify-node` visits before it gives up.
+const VERIFY_LIMIT: usize = 50_000_000;
 
-#[derive(Debug, Error)]
-enum RuneLockError {
-    #[error("Rule {0} was violated: {1}")]
-    RuleViolated(usize, RuleKind),
-    #[error("Rule {0} is not fulfillable: {1}")]
-    RuleUnfulfillable(usize, RuleKind),
-}
+///How many contradictions a sweep has to run into before they are grouped by what they rest on.
+const CLUSTERED_CONTRADICTIONS: usize = 3;
 
-impl RuneLock {
-    fn validate(&self, assignment: &Assignment) -> Result<(), RuneLockError> {
-        //Build IndexOf Array
-        for (rule_no, rule) in self.rules.iter().enumerate() {
-            rule.validate(self, assignment).map_err(|err| match err {
-                rule::RuleError::Violated => RuneLockError::RuleViolated(rule_no, *rule),
-                rule::RuleError::Unfulfillable => RuneLockError::RuleUnfulfillable(rule_no, *rule),
-            })?;
-        }
+///How many solutions `solve` lists, the rest are only counted.
+const SOLUTIONS_SHOWN: usize = 20;
 
-        Ok(())
-    }
-}
+///How many lines the reason tree of `explain` takes up, larger sub-proofs are summarized.
+const EXPLAIN_LINES: usize = 40;
 
-fn main() {
-    env_logger::init();
+///How long input may be missing before an idle step of deepening runs.
+const IDLE_TICK: Duration = Duration::from_millis(200);
+///How many MiB the states may take up before the session warns about it, unless changed with
+///`memory-budget`.
+const MEMORY_BUDGET: usize = 1024;
+
+fn hardcoded_lock() -> RuneLock {
     //Z = 0
     //V = 1
     //S = 2
     //C = 3
-
-    let lock = RuneLock {
-        runes: [
+    let mut lock = RuneLock::with_rules(
+        [
             //Outer circle
             0, 2, 1, 3, 2, 1, //Inner Circle
             3, 2, 1, 0, 2, 1,
         ]
-        .map(|it| Rune::new(it)),
-        rules: vec![
+        .map(Rune::new),
+        vec![
             (1, 2).alwanese(),
             (2, 3).antakian_conjugate(),
             (3, 4).alwanese(),
@@ -72,8 +77,6 @@ fn main() {
             (7, 8).different_runes(),
             (9, 10).alwanese(),
             (9, 10).antakian_twins(),
-            (10, 11).increase_santor(),
-            (11, 12).increase_santor(),
             (8, 10).antakian_twins(),
             (1, 12).alwanese(),
             (1, 12).max_0_conductive(),
@@ -82,50 +85,1313 @@ fn main() {
                 second: Rune::new(1),
             },
         ],
+    );
+    lock.add_template(RuleTemplate::santor_chain(10, 12), 0);
+    lock
+}
+
+///Prints advice computed for `node`, and returns the assumptions reachable through the rank
+///hotkeys. Those are only offered if the advice is about the current state.
+fn print_advice(
+    solver: &FactualSolver,
+    node: AssumptionTreeNodeHandle,
+    advice: Vec<Advice>,
+) -> Vec<(RunePosition, Activation)> {
+    if node != solver.current() {
+        println!("Advice for state {}:", node);
+    }
+    if advice.is_empty() {
+        println!("Nothing to advise.");
+    }
+    for (rank, advice) in advice.iter().enumerate().take(10) {
+        println!("{:2}. {}", rank + 1, advice);
+    }
+    if node != solver.current() {
+        return Vec::new();
+    }
+    let quick_assume: Vec<_> = advice
+        .iter()
+        .take(9)
+        .map(|it| (it.position, it.activation))
+        .collect();
+    if !quick_assume.is_empty() {
+        println!(
+            "Type 1-{} to assume the suggestion with that rank.",
+            quick_assume.len()
+        );
+    }
+    quick_assume
+}
+
+///The outcome of every state of a sweep, and which earlier assumptions its contradictions rest
+///on, if there are a few of them.
+fn print_outcomes(solver: &FactualSolver, outcomes: &[(AssumptionTreeNodeHandle, Outcome)]) {
+    for (node, outcome) in outcomes {
+        println!("State {}: {}", node, outcome);
+    }
+    let states: Vec<_> = outcomes.iter().map(|(node, _)| *node).collect();
+    let clusters = solver.cluster_contradictions(&states);
+    if clusters.contradictions >= CLUSTERED_CONTRADICTIONS {
+        print!("{}", clusters);
+    }
+}
+
+fn print_uniqueness(report: &UniquenessReport, node: AssumptionTreeNodeHandle, path: &Path) {
+    let numbers = |solution: &[Option<Activation>]| {
+        solution
+            .iter()
+            .map(|it| match it {
+                Some(it) => HumanActivation::from(*it).number().to_string(),
+                None => "-".to_string(),
+            })
+            .join(" ")
     };
+    if report.resumed {
+        println!("Continued from {}.", path.display());
+    }
+    match &report.verdict {
+        Uniqueness::NoSolution => println!("State {} has no solution.", node),
+        Uniqueness::Unique(solution) => println!(
+            "State {} has exactly one solution: {}",
+            node,
+            numbers(solution)
+        ),
+        Uniqueness::Multiple(first, second) => {
+            println!("State {} has multiple solutions, like:", node);
+            println!("  {}", numbers(first));
+            println!("  {}", numbers(second));
+        }
+        Uniqueness::Paused { explored } => println!(
+            "Stopped with about {:.1}% explored, `prove-unique {}` continues.",
+            explored * 100.0,
+            path.display()
+        ),
+    }
+    println!("{} states visited so far.", report.nodes);
+}
 
-    let mut solver = FactualSolver::new(&lock);
-    // let mut assignment = Assignment::new([None; 12]).unwrap();
-    let stdin = io::stdin();
+fn print_solutions(solutions: &Solutions, node: AssumptionTreeNodeHandle) {
+    for solution in solutions.assignments.iter().take(SOLUTIONS_SHOWN) {
+        println!("  {}", cli::activations_of(solution).join(" "));
+    }
+    let count = solutions.assignments.len();
+    if count > SOLUTIONS_SHOWN {
+        println!("  ... and {} more", count - SOLUTIONS_SHOWN);
+    }
+    match solutions.complete {
+        true => println!("{} solutions in state {}.", count, node),
+        false => println!(
+            "Interrupted after finding {} solutions in state {}.",
+            count, node
+        ),
+    }
+}
+
+fn print_auto(outcome: &AutoOutcome, depth: usize) {
+    match outcome.solution {
+        Some(node) => println!("Found a solution in state {}.", node),
+        None if outcome.interrupted => println!("Interrupted."),
+        None => println!("No solution within {} assumptions.", depth),
+    }
+    println!(
+        "Added {} states, {} of the visited ones are contradicted.",
+        outcome.added, outcome.contradictions
+    );
+}
+
+///What a heavy command hands back once its job is done.
+enum JobResult {
+    Advice(AssumptionTreeNodeHandle, Vec<Advice>),
+    ///The copy of the solver a sweep explored, which replaces the session's.
+    Tried(Box<FactualSolver>, Vec<(AssumptionTreeNodeHandle, Outcome)>),
+    ///The copy of the solver `auto` explored, with the depth it was given.
+    Auto(Box<FactualSolver>, AutoOutcome, usize),
+    Solutions(AssumptionTreeNodeHandle, Solutions),
+    Sampling(Box<Sampling>),
+    Uniqueness(
+        AssumptionTreeNodeHandle,
+        PathBuf,
+        Result<UniquenessReport, CheckpointError>,
+    ),
+    ///The verdict of `verify-node`, which reports its progress as it goes.
+    Verified(String),
+}
+
+///Runs `work` as a job on a copy of the solver, which the job's cancellation interrupts.
+fn spawn_on_copy(
+    jobs: &mut Jobs<JobResult>,
+    description: String,
+    solver: &FactualSolver,
+    work: impl FnOnce(FactualSolver) -> JobResult + Send + 'static,
+) -> JobId {
+    let mut copy = solver.clone();
+    jobs.spawn(description, move |cancel| {
+        copy.set_interrupt(cancel.clone());
+        Some(work(copy))
+    })
+}
+
+///Shows what the job came to, taking over the states it explored. Returns whether it failed.
+fn finish_job(
+    result: JobResult,
+    solver: &mut FactualSolver,
+    interrupt: &CancelToken,
+    quick_assume: &mut Vec<(RunePosition, Activation)>,
+) -> bool {
+    let mut adopt = |explored: Box<FactualSolver>| {
+        *solver = *explored;
+        solver.set_interrupt(interrupt.clone());
+        print_notices(solver);
+    };
+    match result {
+        JobResult::Advice(node, advice) => *quick_assume = print_advice(solver, node, advice),
+        JobResult::Tried(explored, outcomes) => {
+            adopt(explored);
+            print_outcomes(solver, &outcomes);
+        }
+        JobResult::Auto(explored, outcome, depth) => {
+            adopt(explored);
+            print_auto(&outcome, depth);
+        }
+        JobResult::Solutions(node, solutions) => print_solutions(&solutions, node),
+        JobResult::Sampling(sampling) => println!("{}", sampling),
+        JobResult::Uniqueness(node, path, report) => match report {
+            Ok(report) => print_uniqueness(&report, node, &path),
+            Err(err) => {
+                println!("{}", err);
+                return true;
+            }
+        },
+        JobResult::Verified(report) => print!("{}", report),
+    }
+    false
+}
+
+///What the solver noticed while carrying out the last command, a line each.
+fn print_notices(solver: &mut FactualSolver) {
+    for notice in solver.take_notices() {
+        println!("{}", notice);
+    }
+}
+
+///Prints the whole tree and the current assignment.
+fn display_ui(solver: &FactualSolver) -> Displayed {
+    let (text, displayed) = solver.render_ui();
+    print!("{}", text);
+    displayed
+}
+
+///Prints only what changed since `shown`.
+fn display_changes(solver: &FactualSolver, shown: &Displayed) -> Displayed {
+    let (text, displayed) = solver.render_changes(shown);
+    print!("{}", text);
+    displayed
+}
+
+///Assumes the activation on the position in the current state, unless another child of it
+///already covers the assumption (see `FactualSolver::covering_child`), or, with
+///`guaranteed_progress`, it isn't a bifurcation (see `FactualSolver::options`). `force` assumes it
+///anyway. Returns whether it was assumed.
+fn assume(
+    solver: &mut FactualSolver,
+    position: RunePosition,
+    activation: Activation,
+    guaranteed_progress: bool,
+    force: bool,
+) -> bool {
+    if force {
+        solver.assume(activation, position);
+        return true;
+    }
+    if let Some(child) = solver.covering_child(solver.current(), position, activation) {
+        println!(
+            "State {} already covers {} = {}: it places {} there, and {} = {} forces its assumption. Add --force to assume it anyway.",
+            child, position, activation, activation, position, activation
+        );
+        return false;
+    }
+    let (activations, positions) = solver.options(position, activation);
+    if guaranteed_progress && activations.min(positions) > 2 {
+        println!(
+            "Refusing to assume {} = {}: {} has {} activations left and {} has {} positions left. Pick a candidate with at most two options, or add --force.",
+            position, activation, position, activations, activation, positions
+        );
+        return false;
+    }
+    solver.assume(activation, position);
+    true
+}
+
+///A line per rule, with the activations and runes it is about.
+fn print_rules(lock: &RuneLock, activation: Option<Activation>) {
+    let mut listed = 0;
+    for (index, rule) in lock.rules_about(activation) {
+        let mut line = format!("Rule {}: '{}'", index, rule);
+        let activations = rule.activations();
+        if !activations.is_empty() {
+            line += &format!(", activations {}", activations.iter().join(" "));
+        }
+        let runes = rule.runes();
+        if !runes.is_empty() {
+            line += &format!(", runes {}", runes.iter().join(" "));
+        }
+        if lock.is_hypothesis(index) {
+            line += ", hypothesis";
+        }
+        if !lock.is_active(index) {
+            line += &format!(", from stage {}", lock.stage_of(index));
+        }
+        println!("{}", line);
+        listed += 1;
+    }
+    if listed == 0 {
+        println!(
+            "No rule is about {}.",
+            activation.map_or("anything".into(), |it| it.to_string())
+        );
+    }
+}
 
-    println!("{}", "Rune Lock".red());
+///Lists the rules only one of the sessions applies and the conflicts of a merge, numbered for
+///`resolve`.
+fn print_merge(merge: &Merge) {
+    for rule in &merge.only_ours {
+        println!("Only this session applies '{}'.", rule);
+    }
+    for rule in &merge.only_theirs {
+        println!("Only {} applies '{}'.", merge.source, rule);
+    }
+    if merge.conflicts.is_empty() {
+        println!("No conflicts with {}.", merge.source);
+        return;
+    }
+    for (index, conflict) in merge.conflicts.iter().enumerate() {
+        print!("{}. {}", index + 1, conflict);
+    }
+    println!("Resolve them with 'resolve <conflict> ours|theirs'.");
+}
+
+///Lists what `fsck` found, if anything.
+fn print_findings(findings: &[Finding]) {
+    if findings.is_empty() {
+        return;
+    }
+    for finding in findings {
+        match finding.repaired {
+            true => println!("Repaired: {}", finding.inconsistency),
+            false => println!("Inconsistent: {}", finding.inconsistency),
+        }
+    }
+    if findings.iter().any(|it| !it.repaired) {
+        println!("`fsck --repair` repairs what can be repaired.");
+    }
+}
+
+///Walks through the rules and givens the assignment violates, one after the other.
+fn why_invalid(lock: &RuneLock, assignment: &Assignment) {
+    let violations = diagnosis::violations(lock, assignment);
+    for (index, violation) in violations.iter().enumerate() {
+        println!("Violation {} of {}:", index + 1, violations.len());
+        print!("{}", diagnosis::render_violation(lock, violation));
+    }
+    let givens = diagnosis::violated_givens(lock, assignment);
+    for index in givens.iter() {
+        println!("Given {} is violated: {}", index, lock.domains()[*index]);
+    }
+    if violations.is_empty() && givens.is_empty() {
+        println!("The assignment violates no rule and no given.");
+    }
+}
+
+///Prints what a change of the lock does to the session.
+fn print_edit_impact(impact: &EditImpact) {
+    for fact in impact.gained.iter() {
+        println!("  + {}", fact);
+    }
+    for fact in impact.lost.iter() {
+        println!("  - {}", fact);
+    }
+    println!(
+        "The root state gains {} facts and loses {}.",
+        impact.gained.len(),
+        impact.lost.len()
+    );
+    for change in impact.changes.iter() {
+        println!("{}", change);
+    }
+    println!("{} states change their conclusions.", impact.changes.len());
+    for node in impact.broken_solutions.iter() {
+        println!("The solution in state {} violates the changed lock.", node);
+    }
+}
+
+fn main() {
+    let cli = match Cli::parse_with_config() {
+        Ok(it) => it,
+        Err(err) => {
+            println!("Could not read the config: {}", err);
+            process::exit(2);
+        }
+    };
+    logging::init(cli.log_level.as_deref());
+    //The first Ctrl-C stops the running command at the next safe point, a second one quits.
+    let interrupt = CancelToken::default();
+    let handler_interrupt = interrupt.clone();
+    let handler = ctrlc::set_handler(move || {
+        if handler_interrupt.is_cancelled() {
+            process::exit(130);
+        }
+        handler_interrupt.cancel();
+        println!("Interrupting, press Ctrl-C again to quit.");
+    });
+    if let Err(err) = handler {
+        println!("Could not handle Ctrl-C: {}", err);
+    }
+    terminal::set_accessible(cli.accessible);
+    if let Some(path) = cli.messages.as_deref() {
+        match messages::Catalog::from_path(path) {
+            Ok(catalog) => messages::install(catalog),
+            Err(err) => {
+                println!("Could not load the messages: {}", err);
+                process::exit(2);
+            }
+        }
+    }
+    let history = cli.history();
+    let nodes = NodeNames::default();
+    let mut lines = match cli::input_lines(cli.script.as_deref(), history.as_deref(), nodes.clone())
+    {
+        Ok(it) => it,
+        Err(err) => {
+            println!("Could not read the script: {}", err);
+            process::exit(2);
+        }
+    };
+    //Scripts and piped input only get the output of the commands, and an exit status.
+    let batch = cli.script.is_some() || !io::stdin().is_terminal();
+    let kind_stats = cli.kind_stats(batch);
+    let macros = cli.macros();
+    let json = cli.json();
+    match cli.command.unwrap_or(CliCommand::Repl { lockfile: None }) {
+        CliCommand::Repl { lockfile } => {
+            let lock = match lockfile {
+                Some(path) => load_lock(&path),
+                None => hardcoded_lock(),
+            };
+            let end = repl(
+                canonical(lock),
+                lines,
+                interrupt,
+                kind_stats.as_deref(),
+                macros.as_deref(),
+                batch,
+                nodes,
+            );
+            if batch {
+                process::exit(end.exit_code());
+            }
+        }
+        CliCommand::NewLock => {
+            if let Some(lock) = wizard::new_lock(&mut lines) {
+                let end = repl(
+                    canonical(lock),
+                    lines,
+                    interrupt,
+                    kind_stats.as_deref(),
+                    macros.as_deref(),
+                    batch,
+                    nodes,
+                );
+                if batch {
+                    process::exit(end.exit_code());
+                }
+            }
+        }
+        CliCommand::Solve { lockfile } => {
+            cli::solve(canonical(load_lock(&lockfile)), json, &interrupt)
+        }
+        CliCommand::Check { lockfiles } => {
+            process::exit(cli::check(&lockfiles, json, &interrupt).exit_code())
+        }
+        CliCommand::Verify { lockfile, solution } => {
+            if !cli::verify(&canonical(load_lock(&lockfile)), &solution, json) {
+                process::exit(1);
+            }
+        }
+        CliCommand::Generate => print!("{}", lock_file::write_lock(&hardcoded_lock())),
+        CliCommand::Infer {
+            lockfile,
+            solutions,
+            kinds,
+            into,
+        } => {
+            let lock = canonical(load_lock(&lockfile));
+            let into = into.then_some(lockfile.as_path());
+            if let Err(err) = cli::infer(&lock, &solutions, &kinds, into) {
+                println!("{}", err);
+                process::exit(2);
+            }
+        }
+        CliCommand::GrepFacts { pattern, paths } => {
+            if let Err(err) = cli::grep_facts(&pattern, &paths) {
+                println!("{}", err);
+                process::exit(2);
+            }
+        }
+        CliCommand::Locks { archive, command } => {
+            if let Err(err) = cli::locks(&archive, command) {
+                println!("{}", err);
+                process::exit(2);
+            }
+        }
+    }
+}
+
+///Loads a lock file, or exits after reporting why it couldn't be loaded.
+fn load_lock(path: &Path) -> RuneLock {
+    match RuneLock::from_path(path) {
+        Ok(lock) => lock,
+        Err(err) => {
+            println!("{}", err);
+            process::exit(2);
+        }
+    }
+}
+
+fn canonical(mut lock: RuneLock) -> RuneLock {
+    for normalization in lock.canonicalize() {
+        println!("{}", normalization);
+    }
+    lock
+}
+
+///How a session ended, for the exit status of scripts.
+enum SessionEnd {
+    AssertionFailed,
+    Solved,
+    Contradiction,
+    Undecided,
+}
+
+impl SessionEnd {
+    ///0 for a solved lock, 3 for one that is still open and 4 for a contradiction, like `check`
+    ///does for locks with one, several or no solutions. 1 if an assertion failed.
+    fn exit_code(self) -> i32 {
+        match self {
+            SessionEnd::Solved => 0,
+            SessionEnd::AssertionFailed => 1,
+            SessionEnd::Undecided => 3,
+            SessionEnd::Contradiction => 4,
+        }
+    }
+}
+
+///The interactive session, learning from and adding to the rule kind statistics at `kind_stats`,
+///with the macros kept at `macro_path`. In `batch` mode, the tree and grid aren't shown after
+///every command. The ids of the states are kept in `nodes` for completion. Returns what the last
+///current state came to.
+fn repl(
+    lock: RuneLock,
+    mut lines: InputLines,
+    interrupt: CancelToken,
+    kind_stats: Option<&Path>,
+    macro_path: Option<&Path>,
+    batch: bool,
+    nodes: NodeNames,
+) -> SessionEnd {
+    let mut solver = FactualSolver::new(lock);
+    solver.set_interrupt(interrupt.clone());
+    if let Some(path) = kind_stats {
+        match KindStats::load(path) {
+            Ok(mut stats) => {
+                //The root state was consolidated before there was anything to learn from.
+                stats.merge(solver.kind_stats());
+                solver.tune(stats);
+            }
+            Err(err) => println!("Could not load the rule kind statistics: {}", err),
+        }
+    }
+    let mut macros = match macro_path.map(Macros::load) {
+        Some(Ok(macros)) => macros,
+        Some(Err(err)) => {
+            println!("Could not load the macros: {}", err);
+            Macros::default()
+        }
+        None => Macros::default(),
+    };
+    //The commands of a macro that are still to run.
+    let mut queued = VecDeque::new();
+    // let mut assignment = Assignment::new([None; 12]).unwrap();
 
     // solver_ui(&solver, &lock);
-    solver.display_ui();
-    for line in stdin.lock().lines() {
+    let mut displayed = match batch {
+        true => solver.displayed(),
+        false => {
+            println!("{}", terminal::title("Rune Lock"));
+            display_ui(&solver)
+        }
+    };
+    //Assumptions reachable through the rank hotkeys, only valid right after an `advise`.
+    let mut quick_assume = Vec::new();
+    //The change of the lock previewed by the last command, if it was an `editlock`.
+    let mut edit_preview = None;
+    //The conflicts of the last `merge`, resolved one by one.
+    let mut merging: Option<Merge> = None;
+
+    //Input is read on its own thread, so the solver can use the time spent waiting for it. The
+    //next line is only read once the last one was handled, so line editing doesn't run while the
+    //output of a command is printed.
+    let (sender, receiver) = mpsc::channel();
+    let (request_line, line_requests) = mpsc::channel();
+    thread::spawn(move || {
+        while line_requests.recv().is_ok() {
+            let Some(line) = lines.next() else { break };
+            if sender.send(line).is_err() {
+                break;
+            }
+        }
+    });
+    let mut line_requested = false;
+    let mut idle_deepening = false;
+    //Only accept assumptions that are bifurcations, unless forced.
+    let mut guaranteed_progress = false;
+    //Teaching mode: only deduction, queries and explanations, no guessing.
+    let mut deduce_only = false;
+    let mut notifications = Vec::new();
+    //Heavy commands give the prompt back after this long and continue as a job.
+    let mut budget = Duration::from_millis(500);
+    let mut memory_budget = MEMORY_BUDGET;
+    //Only warn once each time the budget is exceeded.
+    let mut over_memory_budget = false;
+    let mut jobs = Jobs::default();
+    //The job exploring a copy of the states, commands that need them wait for it.
+    let mut exploring: Option<JobId> = None;
+    //The session as it was before `begin`, restored on `abort` or when a command fails.
+    let mut transaction: Option<FactualSolver> = None;
+    let mut assertions_held = true;
+    loop {
+        let line = match queued.pop_front() {
+            Some(command) => {
+                println!("> {}", command);
+                Ok(command)
+            }
+            None => {
+                if !line_requested {
+                    *nodes.lock().unwrap() = solver.nodes().map(|it| it.to_string()).collect();
+                    line_requested = request_line.send(()).is_ok();
+                }
+                //A job exploring a copy of the states replaces them once it is done, so deepening
+                //them meanwhile would be lost.
+                let line = if idle_deepening && exploring.is_none() {
+                    match receiver.recv_timeout(IDLE_TICK) {
+                        Ok(line) => line,
+                        Err(RecvTimeoutError::Timeout) => {
+                            if let Some(report) = solver.deepen_idle_leaf() {
+                                if !report.is_empty() {
+                                    notifications.push(report);
+                                }
+                            }
+                            continue;
+                        }
+                        Err(RecvTimeoutError::Disconnected) => break,
+                    }
+                } else {
+                    match receiver.recv() {
+                        Ok(line) => line,
+                        Err(_) => break,
+                    }
+                };
+                line_requested = false;
+                line
+            }
+        };
+        for notification in notifications.drain(..) {
+            println!("While idle: {}", notification);
+        }
+        let mut refresh = false;
+        //An interrupt only applies to the command that was running, or to idle deepening until
+        //the next command.
+        interrupt.reset();
         if let Ok(line) = line {
+            if let Some(commands) = macros.expand(&line) {
+                match commands {
+                    Ok(commands) => queued.extend(commands),
+                    Err(err) => println!("{}", err),
+                }
+                continue;
+            }
             //Parse Line
-            let command = SolverCommand::parse(line.as_str());
+            let command = SolverCommand::parse(line.as_str(), solver.lock().size());
+            let last_advice = std::mem::take(&mut quick_assume);
+            let last_edit = edit_preview.take();
+            let mut failed = false;
+            //The job a heavy command started, and whether it explores the states.
+            let mut spawned: Option<(JobId, bool)> = None;
+            if let (Ok(command), Some(job)) = (&command, exploring) {
+                if !command.runs_beside_jobs() {
+                    exploring = None;
+                    println!("Waiting for job {} to finish exploring.", job);
+                    match jobs.wait(job, None, &interrupt) {
+                        Ok(Some(result)) => {
+                            finish_job(result, &mut solver, &interrupt, &mut quick_assume);
+                        }
+                        Ok(None) => {}
+                        Err(err) => println!("{}", err),
+                    }
+                }
+            }
             match command {
-                Err(err) => println!("Didn't understand command: {}", err),
+                Err(err) => {
+                    println!("Didn't understand command: {}", err);
+                    failed = true;
+                }
+                Ok(command) if deduce_only && command.guesses() => {
+                    println!("Guessing is disabled in deduce-only mode: find out what the rules force first. 'set mode explore' at the root state ends it.");
+                    failed = true;
+                }
                 Ok(command) => match command {
                     SolverCommand::View { node } => match solver.get_tree_handle(node) {
                         Ok(handle) => solver.set_current(handle),
-                        Err(err) => println!("{}", err),
+                        Err(err) => {
+                            println!("{}", err);
+                            failed = true;
+                        }
                     },
                     SolverCommand::Assume {
                         position,
                         activation,
+                        force,
                     } => {
-                        solver.assume(activation, position);
+                        failed = !assume(
+                            &mut solver,
+                            position,
+                            activation.into(),
+                            guaranteed_progress,
+                            force,
+                        );
+                    }
+                    SolverCommand::Up => match solver.parent_of(solver.current()) {
+                        Some(parent) => solver.set_current(parent),
+                        None => {
+                            println!("Already at the root state.");
+                            failed = true;
+                        }
+                    },
+                    SolverCommand::Root => solver.set_current(solver.root()),
+                    SolverCommand::Prune { node } => {
+                        match solver.get_tree_handle(node).and_then(|it| solver.prune(it)) {
+                            Ok(removed) => println!("Removed {} states.", removed.len()),
+                            Err(err) => {
+                                println!("{}", err);
+                                failed = true;
+                            }
+                        }
                     }
-                    SolverCommand::TryInPosition { position } => {
-                        solver.try_possibilities(position);
+                    SolverCommand::TryInPosition { position, force } => {
+                        let description = format!("try {} in state {}", position, solver.current());
+                        let job = spawn_on_copy(&mut jobs, description, &solver, move |mut it| {
+                            let outcomes = it.try_possibilities(position, force);
+                            JobResult::Tried(Box::new(it), outcomes)
+                        });
+                        spawned = Some((job, true));
                     }
-                    SolverCommand::TryActivation { activation } => {
-                        solver.try_possibilities(activation);
+                    SolverCommand::TryActivation { activation, force } => {
+                        let description =
+                            format!("try {} in state {}", activation, solver.current());
+                        let job = spawn_on_copy(&mut jobs, description, &solver, move |mut it| {
+                            let outcomes =
+                                it.try_possibilities(Activation::from(activation), force);
+                            JobResult::Tried(Box::new(it), outcomes)
+                        });
+                        spawned = Some((job, true));
+                    }
+                    SolverCommand::Explain {
+                        fact_handle,
+                        dot: Some(path),
+                        ..
+                    } => match solver.explanation_dot(fact_handle) {
+                        Some(dot) => match fs::write(&path, dot) {
+                            Ok(()) => println!(
+                                "Wrote the proof of {} as a graph to {}.",
+                                fact_handle,
+                                path.display()
+                            ),
+                            Err(err) => {
+                                println!("Could not write {}: {}", path.display(), err);
+                                failed = true;
+                            }
+                        },
+                        None => {
+                            println!("State {} has no fact {}.", solver.current(), fact_handle);
+                            failed = true;
+                        }
+                    },
+                    SolverCommand::Explain {
+                        fact_handle,
+                        max_depth,
+                        minimal,
+                        dot: None,
+                    } => match max_depth {
+                        Some(depth) => {
+                            print!(
+                                "{}",
+                                solver.explain(fact_handle, usize::MAX, depth, minimal)
+                            )
+                        }
+                        None => print!(
+                            "{}",
+                            solver.explain(fact_handle, EXPLAIN_LINES, usize::MAX, minimal)
+                        ),
+                    },
+                    SolverCommand::Advise { activation } => {
+                        let node = solver.current();
+                        let work = solver.advise_job(activation.map(Activation::from));
+                        let job = jobs.spawn(format!("advise in state {}", node), move |cancel| {
+                            work(cancel).map(|advice| JobResult::Advice(node, advice))
+                        });
+                        spawned = Some((job, false));
+                    }
+                    SolverCommand::Stats { detailed } => {
+                        print!("{}", solver.stats_report(detailed))
+                    }
+                    SolverCommand::Jobs => print!("{}", jobs),
+                    SolverCommand::Wait { job } => {
+                        if exploring == Some(job) {
+                            exploring = None;
+                        }
+                        match jobs.wait(job, None, &interrupt) {
+                            Ok(Some(result)) => {
+                                failed =
+                                    finish_job(result, &mut solver, &interrupt, &mut quick_assume);
+                            }
+                            Ok(None) => {}
+                            Err(err) => {
+                                println!("{}", err);
+                                failed = true;
+                            }
+                        }
+                    }
+                    SolverCommand::Cancel { job } => match jobs.cancel(job) {
+                        Ok(_) if exploring == Some(job) => {
+                            exploring = None;
+                            println!("Cancelled job {}, the states are left as they were.", job)
+                        }
+                        Ok(_) => println!("Cancelled job {}.", job),
+                        Err(err) => {
+                            println!("{}", err);
+                            failed = true;
+                        }
+                    },
+                    SolverCommand::Budget { millis } => budget = Duration::from_millis(millis),
+                    SolverCommand::MemoryBudget { mebibytes } => {
+                        memory_budget = mebibytes;
+                        over_memory_budget = false;
+                    }
+                    SolverCommand::QuickAssume { rank } => match last_advice.get(rank - 1) {
+                        Some((position, activation)) => {
+                            failed = !assume(
+                                &mut solver,
+                                *position,
+                                *activation,
+                                guaranteed_progress,
+                                false,
+                            );
+                        }
+                        None => {
+                            println!("No advice with rank {} to assume.", rank);
+                            failed = true;
+                        }
+                    },
+                    SolverCommand::DefineMacro { name, commands } => {
+                        if !matches!(
+                            SolverCommand::parse(&name, solver.lock().size()),
+                            Err(SolverCommandError::UnknownCommand(_))
+                        ) {
+                            println!("'{}' is a command already.", name);
+                            failed = true;
+                        } else if let Err(err) = macros
+                            .define(&name, &commands)
+                            .and_then(|()| macro_path.map_or(Ok(()), |path| macros.save(path)))
+                        {
+                            println!("{}", err);
+                            failed = true;
+                        }
                     }
-                    SolverCommand::Explain { fact_handle } => {
-                        solver.explain(fact_handle, 10);
+                    SolverCommand::RemoveMacro { name } => {
+                        if !macros.remove(&name) {
+                            println!("There is no macro '{}'.", name);
+                            failed = true;
+                        } else if let Some(Err(err)) = macro_path.map(|it| macros.save(it)) {
+                            println!("{}", err);
+                            failed = true;
+                        }
                     }
-                    SolverCommand::Dump => solver.dump_knowledge(),
+                    SolverCommand::ListMacros if macros.is_empty() => {
+                        println!("No macros defined, see `macro define <name> <commands>`.")
+                    }
+                    SolverCommand::ListMacros => println!("{}", macros),
+                    SolverCommand::Begin => match transaction {
+                        Some(_) => println!("A transaction is already running."),
+                        None => transaction = Some(solver.clone()),
+                    },
+                    SolverCommand::Commit => match transaction.take() {
+                        Some(_) => println!("Transaction committed."),
+                        None => println!("No transaction to commit."),
+                    },
+                    SolverCommand::Abort => match transaction.take() {
+                        Some(before) => {
+                            solver = before;
+                            println!("Transaction aborted.");
+                        }
+                        None => println!("No transaction to abort."),
+                    },
+                    SolverCommand::ExplainDiff {
+                        node_a,
+                        node_b,
+                        position,
+                        activation,
+                    } => match (solver.target(node_a), solver.target(node_b)) {
+                        (Ok(a), Ok(b)) => {
+                            print!("{}", solver.explain_diff(a, b, position, activation.into()))
+                        }
+                        (Err(err), _) | (_, Err(err)) => {
+                            println!("{}", err);
+                            failed = true;
+                        }
+                    },
+                    SolverCommand::Rules { activation } => {
+                        print_rules(solver.lock(), activation.map(Activation::from))
+                    }
+                    SolverCommand::RuleMatrix { rule } => match solver.rule_matrix(rule) {
+                        Ok(matrix) => println!("{}", matrix),
+                        Err(err) => {
+                            println!("{}", err);
+                            failed = true;
+                        }
+                    },
+                    SolverCommand::Fsck { repair } => {
+                        let findings = solver.fsck(repair);
+                        if findings.is_empty() {
+                            println!("No inconsistencies found.");
+                        }
+                        print_findings(&findings);
+                        refresh = repair && !findings.is_empty();
+                        failed = findings.iter().any(|it| !it.repaired);
+                    }
+                    SolverCommand::Save { path } => {
+                        print_findings(&solver.fsck(false));
+                        match solver.save(&path) {
+                            Ok(()) => println!("Saved the session to {}.", path.display()),
+                            Err(err) => {
+                                println!("{}", err);
+                                failed = true;
+                            }
+                        }
+                    }
+                    SolverCommand::Load { path } => match FactualSolver::load(&path) {
+                        Ok(loaded) => {
+                            let stats = solver.kind_stats().clone();
+                            solver = loaded;
+                            solver.set_interrupt(interrupt.clone());
+                            solver.tune(stats);
+                            print_findings(&solver.fsck(false));
+                            refresh = true;
+                        }
+                        Err(err) => {
+                            println!("{}", err);
+                            if let Some(backup) = session::backups(&path).first() {
+                                println!(
+                                    "The latest backup is {0}, `load {0}` restores it.",
+                                    backup.display()
+                                );
+                            }
+                            failed = true;
+                        }
+                    },
+                    SolverCommand::ExportProof { fact_handle, path } => {
+                        match solver.certificate(solver.current(), fact_handle) {
+                            Some(certificate) => match certificate.save(&path) {
+                                Ok(()) => println!(
+                                    "Wrote a certificate of {} steps to {}.",
+                                    certificate.steps.len(),
+                                    path.display()
+                                ),
+                                Err(err) => {
+                                    println!("{}", err);
+                                    failed = true;
+                                }
+                            },
+                            None => {
+                                println!("State {} has no fact {}.", solver.current(), fact_handle);
+                                failed = true;
+                            }
+                        }
+                    }
+                    SolverCommand::RetractGiven {
+                        position,
+                        activation,
+                    } => match solver.retract_given(position, Activation::from(activation)) {
+                        Ok(retraction) => {
+                            match retraction.retracted {
+                                Retracted::Domain(index) => println!(
+                                    "Widened domain {} to '{}'.",
+                                    index,
+                                    solver.lock().domains()[index]
+                                ),
+                                Retracted::External(note) => println!("Dropped {}.", note),
+                            }
+                            if retraction.still_known {
+                                println!("The remaining givens still lead to the same fact.");
+                            }
+                            for change in retraction.changes.iter() {
+                                println!("{}", change);
+                            }
+                            println!(
+                                "{} states changed their conclusions.",
+                                retraction.changes.len()
+                            );
+                        }
+                        Err(err) => {
+                            println!("{}", err);
+                            failed = true;
+                        }
+                    },
+                    SolverCommand::EditLock { edit } => match solver.preview_edit(edit) {
+                        Ok(impact) => {
+                            println!("Previewing '{}':", edit);
+                            print_edit_impact(&impact);
+                            println!("Apply it with 'editlock apply'.");
+                            edit_preview = Some(edit);
+                        }
+                        Err(err) => {
+                            println!("{}", err);
+                            failed = true;
+                        }
+                    },
+                    SolverCommand::ApplyEdit => match last_edit {
+                        Some(edit) => match solver.apply_edit(edit) {
+                            Ok(impact) => {
+                                println!("Applied '{}'.", edit);
+                                print_edit_impact(&impact);
+                                refresh = true;
+                            }
+                            Err(err) => {
+                                println!("{}", err);
+                                failed = true;
+                            }
+                        },
+                        None => {
+                            println!("No edit to apply, preview one with 'editlock' first.");
+                            failed = true;
+                        }
+                    },
+                    SolverCommand::Merge { path: Some(path) } => {
+                        let merged = FactualSolver::load(&path)
+                            .map_err(|err| err.to_string())
+                            .and_then(|other| {
+                                solver
+                                    .merge(&other, &path.display().to_string())
+                                    .map_err(|err| err.to_string())
+                            });
+                        match merged {
+                            Ok(merge) => {
+                                print_merge(&merge);
+                                merging = Some(merge);
+                            }
+                            Err(err) => {
+                                println!("{}", err);
+                                failed = true;
+                            }
+                        }
+                    }
+                    SolverCommand::Merge { path: None } => {
+                        if solver.merge_decisions().is_empty() {
+                            println!("No conflicts of a merge were resolved yet.");
+                        }
+                        for decision in solver.merge_decisions() {
+                            println!("{}", decision);
+                        }
+                    }
+                    SolverCommand::Resolve { conflict, trusted } => match &merging {
+                        Some(merge) => match solver.resolve(merge, conflict, trusted) {
+                            Ok(decision) => {
+                                println!("{}", decision);
+                                if decision.ruled_out_by == Side::Ours && trusted == Side::Theirs {
+                                    println!(
+                                        "Rules {} of this session rule it out, edit them with 'editlock' to follow the decision.",
+                                        merge.conflicts[conflict - 1].rules.iter().join(", ")
+                                    );
+                                }
+                                refresh = true;
+                            }
+                            Err(err) => {
+                                println!("{}", err);
+                                failed = true;
+                            }
+                        },
+                        None => {
+                            println!(
+                                "No conflicts to resolve, list them with 'merge <session>' first."
+                            );
+                            failed = true;
+                        }
+                    },
+                    SolverCommand::ImportCsv { path } => {
+                        let rows = std::fs::read_to_string(&path)
+                            .map_err(|err| err.to_string())
+                            .and_then(|it| {
+                                import::parse_csv(&it, solver.lock().size())
+                                    .map_err(|err| err.to_string())
+                            });
+                        match rows {
+                            Ok(rows) => {
+                                let outcomes = solver.import(&rows);
+                                let mut imported = 0;
+                                for (row, outcome) in rows.iter().zip(outcomes) {
+                                    match outcome {
+                                        ImportOutcome::Imported => imported += 1,
+                                        ImportOutcome::AlreadyKnown => {
+                                            println!("Row {}: {} is already known.", row.row, row)
+                                        }
+                                        ImportOutcome::Conflict(reason) => {
+                                            println!(
+                                                "Row {}: left out {}, {}.",
+                                                row.row, row, reason
+                                            );
+                                            failed = true;
+                                        }
+                                    }
+                                }
+                                println!("Imported {} of {} rows.", imported, rows.len());
+                            }
+                            Err(err) => {
+                                println!("Could not import {}: {}", path.display(), err);
+                                failed = true;
+                            }
+                        }
+                    }
+                    SolverCommand::WhyInvalid { solution } => {
+                        match cli::parse_solution(&solution, solver.lock().size()) {
+                            Ok(assignment) => why_invalid(solver.lock(), &assignment),
+                            Err(err) => {
+                                println!("{}", err);
+                                failed = true;
+                            }
+                        }
+                    }
+                    SolverCommand::Auto { depth } => {
+                        let description = format!("auto {} in state {}", depth, solver.current());
+                        let job = spawn_on_copy(&mut jobs, description, &solver, move |mut it| {
+                            let outcome = it.auto_explore(depth);
+                            JobResult::Auto(Box::new(it), outcome, depth)
+                        });
+                        spawned = Some((job, true));
+                    }
+                    SolverCommand::Audit => println!("{}", solver.audit_rules()),
+                    SolverCommand::CheckRules => {
+                        let conflicts = solver.lock().check_rules();
+                        if conflicts.is_empty() {
+                            println!("No conflicting rules found.");
+                        }
+                        for conflict in conflicts.iter() {
+                            println!("{}", conflict);
+                        }
+                        failed = !conflicts.is_empty();
+                    }
+                    SolverCommand::Unique => {
+                        let node = solver.current();
+                        match solver.count_solutions(2) {
+                            SolutionCount {
+                                count: 0,
+                                complete: true,
+                                ..
+                            } => {
+                                println!("State {} has no solution.", node)
+                            }
+                            SolutionCount {
+                                count: 1,
+                                complete: true,
+                                ..
+                            } => {
+                                println!("State {} has exactly one solution.", node)
+                            }
+                            SolutionCount { count: 2.., .. } => {
+                                println!("State {} has multiple solutions.", node)
+                            }
+                            SolutionCount { count, .. } => println!(
+                                "Interrupted after finding {} solutions in state {}.",
+                                count, node
+                            ),
+                        }
+                    }
+                    SolverCommand::ProveUnique { path, seconds } => {
+                        let node = solver.current();
+                        let description = format!("prove-unique in state {}", node);
+                        let job = spawn_on_copy(&mut jobs, description, &solver, move |it| {
+                            let report = it.prove_unique(&path, seconds.map(Duration::from_secs));
+                            JobResult::Uniqueness(node, path, report)
+                        });
+                        spawned = Some((job, false));
+                    }
+                    SolverCommand::Solve => {
+                        let node = solver.current();
+                        let description = format!("solve state {}", node);
+                        let job = spawn_on_copy(&mut jobs, description, &solver, move |it| {
+                            JobResult::Solutions(node, it.solve_all())
+                        });
+                        spawned = Some((job, false));
+                    }
+                    SolverCommand::Sample { runs } => {
+                        let seed = fastrand::u64(..);
+                        let description = format!("sample state {}", solver.current());
+                        let job = spawn_on_copy(&mut jobs, description, &solver, move |it| {
+                            JobResult::Sampling(Box::new(it.sample(runs, seed)))
+                        });
+                        spawned = Some((job, false));
+                    }
+                    SolverCommand::VerifyNode { node } => match solver.target(node) {
+                        Ok(node) => {
+                            let description = format!("verify-node {}", node);
+                            let job = spawn_on_copy(&mut jobs, description, &solver, move |it| {
+                                let report =
+                                    it.verify_node(node, VERIFY_LIMIT, |visited, found| {
+                                        println!(
+                                            "... visited {} partial assignments, {} completions",
+                                            visited, found
+                                        )
+                                    });
+                                JobResult::Verified(report)
+                            });
+                            spawned = Some((job, false));
+                        }
+                        Err(err) => {
+                            println!("{}", err);
+                            failed = true;
+                        }
+                    },
+                    SolverCommand::Independent { fact_handle } => {
+                        print!("{}", solver.independent(fact_handle))
+                    }
+                    SolverCommand::SetRune { position, rune } => solver.set_rune(position, rune),
+                    SolverCommand::Glossary { kind } => {
+                        print!("{}", glossary::glossary(solver.lock(), kind.as_deref()))
+                    }
+                    SolverCommand::DeduceOnly { enabled } if solver.current() != solver.root() => {
+                        println!(
+                            "The mode can only be {} at the root state, see 'root'.",
+                            match enabled {
+                                true => "entered",
+                                false => "left",
+                            }
+                        );
+                        failed = true;
+                    }
+                    SolverCommand::DeduceOnly { enabled } => deduce_only = enabled,
+                    SolverCommand::Idle { enabled } => idle_deepening = enabled,
+                    SolverCommand::Accessible { enabled } => {
+                        terminal::set_accessible(enabled);
+                        refresh = true;
+                    }
+                    SolverCommand::Progress { enabled } => guaranteed_progress = enabled,
+                    SolverCommand::Dump { why: false } => print!("{}", solver.dump_knowledge()),
+                    SolverCommand::Dump { why: true } => print!("{}", solver.facts().reason_dump()),
+                    SolverCommand::Refresh => refresh = true,
+                    SolverCommand::Assert { assertion } => match solver.check(&assertion) {
+                        Ok(()) => println!("Assertion holds."),
+                        Err(err) => {
+                            println!("Assertion failed: {}", err);
+                            assertions_held = false;
+                            failed = true;
+                        }
+                    },
+                    SolverCommand::Stage { stage } => match solver.advance_stage(stage) {
+                        Ok(report) => println!("{}", report),
+                        Err(err) => {
+                            println!("{}", err);
+                            failed = true;
+                        }
+                    },
                 },
             }
+            if let Some((job, explores)) = spawned {
+                //Scripts have no prompt to give back, so they wait for every job.
+                match jobs.wait(job, (!batch).then_some(budget), &interrupt) {
+                    Ok(Some(result)) => {
+                        failed = finish_job(result, &mut solver, &interrupt, &mut quick_assume);
+                    }
+                    Ok(None) if explores => {
+                        println!(
+                            "Still exploring, continuing in the background as job {}. Commands that need the states wait for it, 'cancel {}' leaves them as they were.",
+                            job, job
+                        );
+                        exploring = Some(job);
+                    }
+                    Ok(None) => println!(
+                        "Still working, continuing in the background as job {}.",
+                        job
+                    ),
+                    Err(err) => {
+                        println!("{}", err);
+                        failed = true;
+                    }
+                }
+            }
+            print_notices(&mut solver);
+            if failed && !queued.is_empty() {
+                queued.clear();
+                println!("The rest of the macro is skipped, as the command failed.");
+            }
+            if failed {
+                if let Some(before) = transaction.take() {
+                    solver = before;
+                    println!("Transaction aborted, as the command failed.");
+                }
+            }
         } else {
             break;
         }
 
-        solver.display_ui();
-        println!("==============================");
+        if !batch {
+            displayed = match refresh {
+                true => display_ui(&solver),
+                false => display_changes(&solver, &displayed),
+            };
+        }
+        let memory = solver.memory_usage();
+        let exceeded = memory > memory_budget.saturating_mul(1024 * 1024);
+        if exceeded && !over_memory_budget {
+            println!(
+                "The states take up ~{}, more than the budget of {} MiB. Consider starting a new session from the assumptions of the most promising state, or raise the budget with 'memory-budget <MiB>'.",
+                fact_solver::format_bytes(memory),
+                memory_budget
+            );
+        }
+        over_memory_budget = exceeded;
+        if batch {
+            continue;
+        }
+        if transaction.is_some() {
+            println!("(Preview: changes are applied on 'commit' and discarded on 'abort'.)");
+        }
+        if deduce_only {
+            println!("(Deduce-only mode: assuming and searching are disabled.)");
+        }
+        println!("{}", terminal::separator());
+    }
+    if let Some(job) = exploring {
+        if let Ok(Some(result)) = jobs.wait(job, None, &interrupt) {
+            finish_job(result, &mut solver, &interrupt, &mut Vec::new());
+        }
+    }
+    if let Some(path) = kind_stats {
+        if let Err(err) = solver.kind_stats().save(path) {
+            println!("Could not save the rule kind statistics: {}", err);
+        }
+    }
+    match solver.outcome(solver.current()) {
+        _ if !assertions_held => SessionEnd::AssertionFailed,
+        Outcome::Solved { .. } => SessionEnd::Solved,
+        Outcome::Contradiction { .. } => SessionEnd::Contradiction,
+        Outcome::Progress { .. } | Outcome::Forced { .. } => SessionEnd::Undecided,
     }
 }
@@ -0,0 +1,198 @@
+//! The phrasing of rules, facts and contradictions, looked up in a catalog so other phrasings or
+//! languages can be plugged in without touching the solver. A catalog is a TOML table of message
+//! keys to templates, in which `{name}` stands for the argument of that name. Keys a catalog
+//! leaves out keep their English phrasing.
+
+use std::{collections::HashMap, fmt::Display, fs, io, path::Path, sync::RwLock};
+
+use thiserror::Error;
+
+const ENGLISH: [(&str, &str); 24] = [
+    ("rule.alwanese", "{first} & {second} are Alwanese"),
+    (
+        "rule.antakian-conjugates",
+        "{first} & {second} are Antakian Conjugates",
+    ),
+    (
+        "rule.alwanese-conjugates",
+        "{first} & {second} are Alwanese Conjugates",
+    ),
+    (
+        "rule.different-runes",
+        "{first} & {second} are Different Runes",
+    ),
+    (
+        "rule.antakian-twins",
+        "{first} & {second} are Antakian Twins",
+    ),
+    ("rule.increase-santor", "{first} & {second} increase Santor"),
+    ("rule.decrease-santor", "{first} & {second} decrease Santor"),
+    ("rule.follows", "{second} immediately follows {first}"),
+    (
+        "rule.follows-within",
+        "{second} follows {first} within {within} activations",
+    ),
+    (
+        "rule.before",
+        "activations on {earlier} come before those on {later}",
+    ),
+    (
+        "rule.max-0-conductive",
+        "{first} & {second} are max 0 Conductive",
+    ),
+    ("rule.not-alwanese", "{first} & {second} are not Alwanese"),
+    (
+        "rule.not-antakian-twins",
+        "{first} & {second} are not Antakian Twins",
+    ),
+    ("rule.at-position", "{activation} is on {position}"),
+    ("rule.not-at-position", "{activation} is not on {position}"),
+    (
+        "rule.all-different-runes",
+        "{activations} are all Different Runes",
+    ),
+    ("rule.not", "not: {rule}"),
+    ("rule.any-of", "either {alternatives}"),
+    ("rule.implies", "if {condition}, then {consequence}"),
+    (
+        "template.santor-chain",
+        "Santor increases along {first}..{last}",
+    ),
+    ("fact.cannot-be-on", "{activation} cannot be on {positions}"),
+    ("fact.must-be-on", "{activation} must be on {position}"),
+    (
+        "contradiction.contradicting-requirements",
+        "{activation} has contradicting facts regarding position {position}",
+    ),
+    (
+        "contradiction.no-options-left",
+        "{activation} or {position} has no options left to go",
+    ),
+];
+
+///The catalog `message` looks phrases up in, English if none was installed.
+static INSTALLED: RwLock<Option<Catalog>> = RwLock::new(None);
+
+#[derive(Debug, Error)]
+pub enum CatalogError {
+    #[error("Could not read {path}: {source}")]
+    Io { path: String, source: io::Error },
+    #[error("{0}")]
+    Toml(#[from] toml::de::Error),
+    #[error("'{0}' is not a message key")]
+    UnknownKey(String),
+    #[error("The message '{key}' has no argument '{argument}'")]
+    UnknownArgument { key: String, argument: String },
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Catalog {
+    messages: HashMap<String, String>,
+}
+
+///The names of the `{name}` arguments in the template.
+fn arguments(template: &str) -> impl Iterator<Item = &str> {
+    template
+        .split('{')
+        .skip(1)
+        .filter_map(|it| it.split_once('}').map(|(name, _)| name))
+}
+
+fn english(key: &str) -> Option<&'static str> {
+    ENGLISH
+        .iter()
+        .find(|(it, _)| *it == key)
+        .map(|(_, template)| *template)
+}
+
+impl Catalog {
+    ///Parses a catalog, checking that it only has known keys, and that its templates only use
+    ///arguments the English ones use as well.
+    pub fn parse(source: &str) -> Result<Self, CatalogError> {
+        let messages: HashMap<String, String> = toml::from_str(source)?;
+        for (key, template) in messages.iter() {
+            let english = english(key).ok_or_else(|| CatalogError::UnknownKey(key.clone()))?;
+            if let Some(argument) =
+                arguments(template).find(|it| !arguments(english).any(|known| known == *it))
+            {
+                return Err(CatalogError::UnknownArgument {
+                    key: key.clone(),
+                    argument: argument.into(),
+                });
+            }
+        }
+        Ok(Self { messages })
+    }
+
+    pub fn from_path(path: &Path) -> Result<Self, CatalogError> {
+        let source = fs::read_to_string(path).map_err(|source| CatalogError::Io {
+            path: path.display().to_string(),
+            source,
+        })?;
+        Self::parse(&source)
+    }
+
+    ///The message with the arguments filled in.
+    pub fn render(&self, key: &str, arguments: &[(&str, &dyn Display)]) -> String {
+        let template = self
+            .messages
+            .get(key)
+            .map(String::as_str)
+            .or_else(|| english(key))
+            .unwrap_or(key);
+        let mut message = template.to_string();
+        for (name, value) in arguments {
+            message = message.replace(&format!("{{{}}}", name), &value.to_string());
+        }
+        message
+    }
+}
+
+///Makes every message from now on use the catalog.
+pub fn install(catalog: Catalog) {
+    *INSTALLED.write().unwrap() = Some(catalog);
+}
+
+///The message in the installed catalog, with the arguments filled in.
+pub fn message(key: &str, arguments: &[(&str, &dyn Display)]) -> String {
+    match INSTALLED.read().unwrap().as_ref() {
+        Some(catalog) => catalog.render(key, arguments),
+        None => Catalog::default().render(key, arguments),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Catalog, CatalogError};
+
+    #[test]
+    fn test_catalogs_override_english() {
+        let catalog = Catalog::parse(
+            r#"
+"rule.alwanese" = "{first} und {second} sind Alwanesisch"
+"fact.must-be-on" = "{activation} muss auf {position} liegen"
+"#,
+        )
+        .unwrap();
+        assert_eq!(
+            catalog.render("rule.alwanese", &[("first", &"#1"), ("second", &"#2")]),
+            "#1 und #2 sind Alwanesisch"
+        );
+        assert_eq!(
+            catalog.render(
+                "rule.antakian-twins",
+                &[("first", &"#1"), ("second", &"#2")]
+            ),
+            "#1 & #2 are Antakian Twins"
+        );
+
+        assert!(matches!(
+            Catalog::parse(r#""rule.alwanesse" = "{first}""#),
+            Err(CatalogError::UnknownKey(_))
+        ));
+        assert!(matches!(
+            Catalog::parse(r#""fact.must-be-on" = "{activation} on {rune}""#),
+            Err(CatalogError::UnknownArgument { argument, .. }) if argument == "rune"
+        ));
+    }
+}
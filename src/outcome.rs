@@ -0,0 +1,63 @@
+//! What a step of solving came to, in the same shape for every solver, so frontends don't have to
+//! tell the results of the solvers apart or read them from printed text.
+
+use std::fmt::{Display, Formatter};
+
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+
+use crate::{activation::Activation, fact_solver::fact_db::FactHandle, index::RunePosition};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Outcome {
+    ///The state can't be part of a solution.
+    Contradiction {
+        ///The contradicting fact, for solvers that keep facts.
+        fact: Option<FactHandle>,
+        ///The rules the contradiction rests on, as far as the solver tracks them.
+        blame: Vec<usize>,
+        ///The contradiction in words.
+        reason: String,
+    },
+    ///Candidates were ruled out, but nothing new was placed. The candidate solver counts its
+    ///deduction steps instead of facts.
+    Progress { facts_added: usize },
+    ///Activations were placed without being assumed, but the lock isn't solved yet.
+    Forced {
+        placements: Vec<(RunePosition, Activation)>,
+    },
+    ///The activation at every position, by its index. A lock of fewer activations than positions
+    ///leaves some of them empty.
+    Solved { assignment: Vec<Option<Activation>> },
+}
+
+impl Display for Outcome {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Outcome::Contradiction { reason, .. } => write!(f, "Contradiction: {}", reason),
+            Outcome::Progress { facts_added } => write!(
+                f,
+                "{} new facts, more assumptions are required.",
+                facts_added
+            ),
+            Outcome::Forced { placements } => write!(
+                f,
+                "Forced {}.",
+                placements
+                    .iter()
+                    .map(|(position, activation)| format!("{} = {}", position, activation))
+                    .join(", ")
+            ),
+            Outcome::Solved { assignment } => {
+                write!(
+                    f,
+                    "Solved: {}",
+                    assignment
+                        .iter()
+                        .map(|it| it.map_or_else(|| "-".to_string(), |it| it.to_string()))
+                        .join(" ")
+                )
+            }
+        }
+    }
+}
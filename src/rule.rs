@@ -1,16 +1,20 @@
-use std::fmt::Display;
+use std::{fmt::Display, str::FromStr};
 
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
 use crate::{
-    activation::Activation,
+    activation::{Activation, ActivationError, HumanActivation},
     assignment::{Assignment, AssignmentError},
-    index::{RunePosition, MAX_SANTOR, MIN_SANTOR},
-    rune::Rune,
+    index::RunePosition,
+    messages,
+    rune::{Rune, RuneError},
+    size::{LockSize, MAX_POSITIONS},
     RuneLock,
 };
 
-#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+#[derive(Debug, Clone, Hash, PartialEq, Eq, Serialize, Deserialize)]
 pub enum RuleKind {
     Alwanese {
         first: Activation,
@@ -36,47 +40,170 @@ pub enum RuleKind {
         first: Activation,
         second: Activation,
     },
+    DecreaseSantor {
+        first: Activation,
+        second: Activation,
+    },
     RuneFollowsImmediately {
         first: Rune,
         second: Rune,
     },
+    ///If activation n is on a `first` rune, one of the activations n + 1 to n + `within` is on a
+    ///`second` rune.
+    RuneFollowsWithin {
+        first: Rune,
+        second: Rune,
+        within: u8,
+    },
+    ///Every activation on an `earlier` rune is lower than every activation on a `later` rune.
+    RuneClassBefore {
+        earlier: Rune,
+        later: Rune,
+    },
     Max0Conductive {
         first: Activation,
         second: Activation,
     },
+    NotAlwanese {
+        first: Activation,
+        second: Activation,
+    },
+    NotAntakianTwins {
+        first: Activation,
+        second: Activation,
+    },
+    ActivationAtPosition {
+        activation: Activation,
+        position: RunePosition,
+    },
+    ActivationNotAtPosition {
+        activation: Activation,
+        position: RunePosition,
+    },
+    ///No two of the activations are on the same known rune.
+    AllDifferentRunes {
+        activations: Vec<Activation>,
+    },
+    ///Holds unless every activation of the rule is placed and the rule holds.
+    Not(Box<RuleKind>),
+    ///Holds if at least one of the rules does.
+    AnyOf(Vec<RuleKind>),
+    ///The consequence has to hold once every activation of the condition is placed and the
+    ///condition holds.
+    Implies {
+        condition: Box<RuleKind>,
+        consequence: Box<RuleKind>,
+    },
 }
 
 impl Display for RuleKind {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            RuleKind::Alwanese { first, second } => {
-                write!(f, "{} & {} are Alwanese", first, second)
+        let (key, first, second) = match self {
+            RuleKind::RuneFollowsImmediately { first, second } => {
+                ("rule.follows", first.to_string(), second.to_string())
             }
-            RuleKind::AntakianConjugates { first, second } => {
-                write!(f, "{} & {} are Antakian Conjugates", first, second)
+            RuleKind::RuneFollowsWithin {
+                first,
+                second,
+                within,
+            } => {
+                return f.write_str(&messages::message(
+                    "rule.follows-within",
+                    &[("first", first), ("second", second), ("within", within)],
+                ))
             }
-            RuleKind::AlwaneseConjugates { first, second } => {
-                write!(f, "{} & {} are Alwanese Conjugates", first, second)
+            RuleKind::RuneClassBefore { earlier, later } => {
+                return f.write_str(&messages::message(
+                    "rule.before",
+                    &[("earlier", earlier), ("later", later)],
+                ))
             }
-            RuleKind::DifferentRunes { first, second } => {
-                write!(f, "{} & {} are Different Runes", first, second)
+            RuleKind::ActivationAtPosition {
+                activation,
+                position,
+            } => {
+                return f.write_str(&messages::message(
+                    "rule.at-position",
+                    &[("activation", activation), ("position", position)],
+                ))
             }
-            RuleKind::AntakianTwins { first, second } => {
-                write!(f, "{} & {} are Antakian Twins", first, second)
+            RuleKind::ActivationNotAtPosition {
+                activation,
+                position,
+            } => {
+                return f.write_str(&messages::message(
+                    "rule.not-at-position",
+                    &[("activation", activation), ("position", position)],
+                ))
             }
-            RuleKind::IncreaseSantor { first, second } => {
-                write!(f, "{} & {} increase Santor", first, second)
+            RuleKind::AllDifferentRunes { activations } => {
+                return f.write_str(&messages::message(
+                    "rule.all-different-runes",
+                    &[("activations", &activations.iter().join(" & "))],
+                ))
             }
-            RuleKind::RuneFollowsImmediately { first, second } => {
-                write!(f, "{} immediately follows {}", second, first)
+            RuleKind::Not(rule) => {
+                return f.write_str(&messages::message("rule.not", &[("rule", rule)]))
             }
-            RuleKind::Max0Conductive { first, second } => {
-                write!(f, "{} & {} are max 0 Conductive", first, second)
+            RuleKind::AnyOf(alternatives) => {
+                return f.write_str(&messages::message(
+                    "rule.any-of",
+                    &[("alternatives", &alternatives.iter().join(" or "))],
+                ))
             }
-        }
+            RuleKind::Implies {
+                condition,
+                consequence,
+            } => {
+                return f.write_str(&messages::message(
+                    "rule.implies",
+                    &[("condition", condition), ("consequence", consequence)],
+                ))
+            }
+            kind => {
+                let (first, second) = kind.activation_pair().unwrap();
+                let key = match kind {
+                    RuleKind::Alwanese { .. } => "rule.alwanese",
+                    RuleKind::AntakianConjugates { .. } => "rule.antakian-conjugates",
+                    RuleKind::AlwaneseConjugates { .. } => "rule.alwanese-conjugates",
+                    RuleKind::DifferentRunes { .. } => "rule.different-runes",
+                    RuleKind::AntakianTwins { .. } => "rule.antakian-twins",
+                    RuleKind::IncreaseSantor { .. } => "rule.increase-santor",
+                    RuleKind::DecreaseSantor { .. } => "rule.decrease-santor",
+                    RuleKind::Max0Conductive { .. } => "rule.max-0-conductive",
+                    RuleKind::NotAlwanese { .. } => "rule.not-alwanese",
+                    RuleKind::NotAntakianTwins { .. } => "rule.not-antakian-twins",
+                    RuleKind::RuneFollowsImmediately { .. }
+                    | RuleKind::RuneFollowsWithin { .. }
+                    | RuleKind::RuneClassBefore { .. }
+                    | RuleKind::ActivationAtPosition { .. }
+                    | RuleKind::ActivationNotAtPosition { .. }
+                    | RuleKind::AllDifferentRunes { .. }
+                    | RuleKind::Not(_)
+                    | RuleKind::AnyOf(_)
+                    | RuleKind::Implies { .. } => unreachable!(),
+                };
+                (key, first.to_string(), second.to_string())
+            }
+        };
+        f.write_str(&messages::message(
+            key,
+            &[("first", &first), ("second", &second)],
+        ))
     }
 }
 
+///Rules over two activations, written as the pair of their 1 based numbers.
+///
+///```
+///use rune_lock_solver::{activation::{Activation, HumanActivation}, rule::ActivationRuleKindHelpers, RuleKind};
+///
+///let first = Activation::from(HumanActivation::new(1).unwrap());
+///let second = Activation::from(HumanActivation::new(2).unwrap());
+///assert_eq!((1, 2).alwanese(), RuleKind::Alwanese { first, second });
+///assert_eq!(RuleKind::between("alwanese", first, second), Some((1, 2).alwanese()));
+///assert_eq!("1 2 alwanese".parse::<RuleKind>().unwrap(), (1, 2).alwanese());
+///```
 pub trait ActivationRuleKindHelpers {
     fn alwanese(self) -> RuleKind;
     fn antakian_conjugate(self) -> RuleKind;
@@ -84,56 +211,474 @@ pub trait ActivationRuleKindHelpers {
     fn different_runes(self) -> RuleKind;
     fn antakian_twins(self) -> RuleKind;
     fn increase_santor(self) -> RuleKind;
+    fn decrease_santor(self) -> RuleKind;
     fn max_0_conductive(self) -> RuleKind;
+    fn not_alwanese(self) -> RuleKind;
+    fn not_antakian_twins(self) -> RuleKind;
+}
+
+fn human(one_based: u8) -> Activation {
+    HumanActivation::new(one_based)
+        .expect("rule activations should be valid")
+        .into()
 }
 
 impl ActivationRuleKindHelpers for (u8, u8) {
     fn alwanese(self) -> RuleKind {
         RuleKind::Alwanese {
-            first: Activation::from_human(self.0).expect("rule activations should be valid"),
-            second: Activation::from_human(self.1).expect("rule activations should be valid"),
+            first: human(self.0),
+            second: human(self.1),
         }
     }
 
     fn antakian_conjugate(self) -> RuleKind {
         RuleKind::AntakianConjugates {
-            first: Activation::from_human(self.0).expect("rule activations should be valid"),
-            second: Activation::from_human(self.1).expect("rule activations should be valid"),
+            first: human(self.0),
+            second: human(self.1),
         }
     }
 
     fn alwanese_conjugate(self) -> RuleKind {
         RuleKind::AlwaneseConjugates {
-            first: Activation::from_human(self.0).unwrap(),
-            second: Activation::from_human(self.1).unwrap(),
+            first: human(self.0),
+            second: human(self.1),
         }
     }
 
     fn different_runes(self) -> RuleKind {
         RuleKind::DifferentRunes {
-            first: Activation::from_human(self.0).unwrap(),
-            second: Activation::from_human(self.1).unwrap(),
+            first: human(self.0),
+            second: human(self.1),
         }
     }
 
     fn antakian_twins(self) -> RuleKind {
         RuleKind::AntakianTwins {
-            first: Activation::from_human(self.0).unwrap(),
-            second: Activation::from_human(self.1).unwrap(),
+            first: human(self.0),
+            second: human(self.1),
         }
     }
 
     fn increase_santor(self) -> RuleKind {
         RuleKind::IncreaseSantor {
-            first: Activation::from_human(self.0).unwrap(),
-            second: Activation::from_human(self.1).unwrap(),
+            first: human(self.0),
+            second: human(self.1),
+        }
+    }
+
+    fn decrease_santor(self) -> RuleKind {
+        RuleKind::DecreaseSantor {
+            first: human(self.0),
+            second: human(self.1),
         }
     }
 
     fn max_0_conductive(self) -> RuleKind {
         RuleKind::Max0Conductive {
-            first: Activation::from_human(self.0).unwrap(),
-            second: Activation::from_human(self.1).unwrap(),
+            first: human(self.0),
+            second: human(self.1),
+        }
+    }
+
+    fn not_alwanese(self) -> RuleKind {
+        RuleKind::NotAlwanese {
+            first: human(self.0),
+            second: human(self.1),
+        }
+    }
+
+    fn not_antakian_twins(self) -> RuleKind {
+        RuleKind::NotAntakianTwins {
+            first: human(self.0),
+            second: human(self.1),
+        }
+    }
+}
+
+///A quantified rule that expands to a number of plain rules.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RuleTemplate {
+    ///Santor strictly increases from `first` along every activation up to `last`.
+    SantorChain { first: Activation, last: Activation },
+}
+
+impl Display for RuleTemplate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RuleTemplate::SantorChain { first, last } => f.write_str(&messages::message(
+                "template.santor-chain",
+                &[("first", first), ("last", last)],
+            )),
+        }
+    }
+}
+
+impl RuleTemplate {
+    pub fn santor_chain(first: u8, last: u8) -> Self {
+        RuleTemplate::SantorChain {
+            first: human(first),
+            last: human(last),
+        }
+    }
+
+    ///The template in the syntax `FromStr` accepts.
+    pub fn syntax(&self) -> String {
+        match self {
+            RuleTemplate::SantorChain { first, last } => format!(
+                "{}..{} santor-chain",
+                HumanActivation::from(*first).number(),
+                HumanActivation::from(*last).number()
+            ),
+        }
+    }
+
+    ///The activations the template quantifies over, in order.
+    pub fn activations(&self) -> Vec<Activation> {
+        match self {
+            RuleTemplate::SantorChain { first, last } => (first.index()..=last.index())
+                .map(|it| Activation::new(it as u8).expect("lies between two activations"))
+                .collect(),
+        }
+    }
+
+    ///Whether every rule of the template fits a lock of `size`, see `RuleKind::check_size`.
+    pub fn check_size(&self, size: LockSize) -> Result<(), RuleParseError> {
+        self.expand().iter().try_for_each(|it| it.check_size(size))
+    }
+
+    pub fn expand(&self) -> Vec<RuleKind> {
+        match self {
+            RuleTemplate::SantorChain { .. } => self
+                .activations()
+                .into_iter()
+                .tuple_windows()
+                .map(|(first, second)| RuleKind::IncreaseSantor { first, second })
+                .collect(),
+        }
+    }
+}
+
+///Parses templates written as `10..12 santor-chain` (activations are 1 based).
+impl FromStr for RuleTemplate {
+    type Err = RuleParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let words: Vec<_> = s.split_whitespace().collect();
+        let [range, kind] = words[..] else {
+            return Err(RuleParseError::Malformed);
+        };
+        let Some((first, last)) = range.split_once("..") else {
+            return Err(RuleParseError::Malformed);
+        };
+        let first = Activation::from(first.parse::<HumanActivation>()?);
+        let last = Activation::from(last.parse::<HumanActivation>()?);
+        if first >= last {
+            return Err(RuleParseError::Malformed);
+        }
+        match kind {
+            "santor-chain" => Ok(RuleTemplate::SantorChain { first, last }),
+            _ => Err(RuleParseError::UnknownKind(kind.into())),
+        }
+    }
+}
+
+///A change `canonicalize` made to a rule.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Normalization {
+    Reordered { rule: usize, original: RuleKind },
+    Merged { original: RuleKind, into: usize },
+}
+
+impl Display for Normalization {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Normalization::Reordered { rule, original } => {
+                write!(f, "Rule {}: '{}' was reordered", rule, original)
+            }
+            Normalization::Merged { original, into } => {
+                write!(
+                    f,
+                    "'{}' is a duplicate of rule {} and was merged",
+                    original, into
+                )
+            }
+        }
+    }
+}
+
+///Canonicalizes the rules and drops duplicates. Returns the remaining rules, the new index of
+///every original rule, and what was changed.
+pub fn canonicalize(rules: &[RuleKind]) -> (Vec<RuleKind>, Vec<usize>, Vec<Normalization>) {
+    let mut canonical_rules: Vec<RuleKind> = Vec::new();
+    let mut canonical_index = Vec::new();
+    let mut normalizations = Vec::new();
+    for original in rules {
+        let canonical = original.canonical();
+        if let Some(into) = canonical_rules.iter().position(|it| *it == canonical) {
+            canonical_index.push(into);
+            normalizations.push(Normalization::Merged {
+                original: original.clone(),
+                into,
+            });
+            continue;
+        }
+        if canonical != *original {
+            normalizations.push(Normalization::Reordered {
+                rule: canonical_rules.len(),
+                original: original.clone(),
+            });
+        }
+        canonical_index.push(canonical_rules.len());
+        canonical_rules.push(canonical);
+    }
+    (canonical_rules, canonical_index, normalizations)
+}
+
+#[derive(Debug, Error)]
+pub enum RuleParseError {
+    #[error("Expected '<activation> <activation> <kind>', '<rune> follows <rune> [within <gap>]', '<rune> before <rune>', '<activation> at <position>', '<activation> not-at <position>', '<activation>... all-different-runes' or '<activation>..<activation> santor-chain'")]
+    Malformed,
+    #[error("Unknown rule kind: {0}")]
+    UnknownKind(String),
+    #[error("Activation is invalid: {0}")]
+    ActivationInvalid(#[from] ActivationError),
+    #[error("{0}")]
+    RuneInvalid(#[from] RuneError),
+    #[error("Position is invalid: {0}")]
+    PositionInvalid(String),
+    #[error("'{0}' rules can't be negated")]
+    NotNegatable(String),
+    #[error("Gap is invalid, expected at least 1: {0}")]
+    GapInvalid(String),
+    #[error("A lock of {1} has no activation {0}")]
+    ActivationOutside(u8, LockSize),
+    #[error("A lock of {1} has no position {0}")]
+    PositionOutside(usize, LockSize),
+}
+
+///Parses rules written as `1 2 alwanese` (activations are 1 based), `V follows Z`,
+///`V follows Z within 3`, `Z before V`, `1 at 0` or `3 6 9 all-different-runes`, negated by a
+///leading `not`. Rules joined by `or` hold if one of them does, and
+///`if <rule> then <rule>` makes one rule conditional on another.
+impl FromStr for RuleKind {
+    type Err = RuleParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(rule) = s.trim_start().strip_prefix("if ") {
+            let words: Vec<_> = rule.split_whitespace().collect();
+            let Some(then) = words.iter().position(|it| *it == "then") else {
+                return Err(RuleParseError::Malformed);
+            };
+            return Ok(RuleKind::Implies {
+                condition: Box::new(words[..then].join(" ").parse()?),
+                consequence: Box::new(words[then + 1..].join(" ").parse()?),
+            });
+        }
+        if s.split_whitespace().any(|it| it == "or") {
+            let words: Vec<_> = s.split_whitespace().collect();
+            return words
+                .split(|it| *it == "or")
+                .map(|it| it.join(" ").parse())
+                .collect::<Result<_, _>>()
+                .map(RuleKind::AnyOf);
+        }
+        if let Some(rule) = s.trim_start().strip_prefix("not ") {
+            return rule.parse::<RuleKind>()?.negated();
+        }
+        let words: Vec<_> = s.split_whitespace().collect();
+        if let [second, "follows", first, "within", within] = words[..] {
+            return Ok(RuleKind::RuneFollowsWithin {
+                first: first.parse()?,
+                second: second.parse()?,
+                within: within
+                    .parse()
+                    .ok()
+                    .filter(|it| *it > 0)
+                    .ok_or_else(|| RuleParseError::GapInvalid(within.into()))?,
+            });
+        }
+        if let Some((kind, activations)) = words
+            .split_last()
+            .filter(|(kind, _)| NARY_KINDS.contains(kind))
+        {
+            let activations = activations
+                .iter()
+                .map(|it| it.parse::<HumanActivation>().map(Activation::from))
+                .collect::<Result<Vec<_>, _>>()?;
+            if activations.len() < 2 {
+                return Err(RuleParseError::Malformed);
+            }
+            return RuleKind::among(kind, activations)
+                .ok_or_else(|| RuleParseError::UnknownKind(kind.to_string()));
+        }
+        let [first, second, third] = words[..] else {
+            return Err(RuleParseError::Malformed);
+        };
+        if second == "follows" {
+            return Ok(RuleKind::RuneFollowsImmediately {
+                first: third.parse()?,
+                second: first.parse()?,
+            });
+        }
+        if second == "before" {
+            return Ok(RuleKind::RuneClassBefore {
+                earlier: first.parse()?,
+                later: third.parse()?,
+            });
+        }
+        if let "at" | "not-at" = second {
+            let activation = Activation::from(first.parse::<HumanActivation>()?);
+            let position = third
+                .parse::<usize>()
+                .ok()
+                .filter(|it| *it < MAX_POSITIONS)
+                .map(RunePosition::new)
+                .ok_or_else(|| RuleParseError::PositionInvalid(third.into()))?;
+            return Ok(match second {
+                "at" => RuleKind::ActivationAtPosition {
+                    activation,
+                    position,
+                },
+                _ => RuleKind::ActivationNotAtPosition {
+                    activation,
+                    position,
+                },
+            });
+        }
+
+        let first = Activation::from(first.parse::<HumanActivation>()?);
+        let second = Activation::from(second.parse::<HumanActivation>()?);
+        RuleKind::between(third, first, second)
+            .ok_or_else(|| RuleParseError::UnknownKind(third.into()))
+    }
+}
+
+///The names of the rule kinds over two activations, as used when entering rules.
+pub const PAIR_KINDS: [&str; 10] = [
+    "alwanese",
+    "antakian-conjugates",
+    "alwanese-conjugates",
+    "different-runes",
+    "antakian-twins",
+    "increase-santor",
+    "decrease-santor",
+    "max-0-conductive",
+    "not-alwanese",
+    "not-antakian-twins",
+];
+
+///The names of the rule kinds over any number of activations, entered after them.
+pub const NARY_KINDS: [&str; 1] = ["all-different-runes"];
+
+///Other names of rule kinds over two activations, and the kind they stand for. Antakian twins
+///are on the same circle, so rules about the circles are entered as those.
+pub const KIND_ALIASES: [(&str, &str); 2] = [
+    ("same-ring", "antakian-twins"),
+    ("different-ring", "not-antakian-twins"),
+];
+
+///The kind named `kind`, following aliases.
+pub fn resolve_alias(kind: &str) -> &str {
+    KIND_ALIASES
+        .iter()
+        .find(|(alias, _)| *alias == kind)
+        .map_or(kind, |(_, it)| it)
+}
+
+impl RuleKind {
+    ///The rule that holds when this one doesn't. Rules about runes aren't decided by placing
+    ///activations, so they can't be negated.
+    pub fn negated(self) -> Result<Self, RuleParseError> {
+        match self {
+            RuleKind::RuneFollowsImmediately { .. }
+            | RuleKind::RuneFollowsWithin { .. }
+            | RuleKind::RuneClassBefore { .. } => {
+                Err(RuleParseError::NotNegatable(self.name().into()))
+            }
+            rule => Ok(RuleKind::Not(Box::new(rule))),
+        }
+    }
+
+    ///Whether the rule holds on the placements. It is only known to hold once every activation it
+    ///is about is placed, and, for rules about runes, placed on runes that are known.
+    pub fn truth(&self, lock: &RuneLock, assignment: &Assignment) -> Truth {
+        match self {
+            RuleKind::Not(rule) => match rule.truth(lock, assignment) {
+                Truth::Holds => Truth::Fails,
+                Truth::Fails => Truth::Holds,
+                Truth::Unknown => Truth::Unknown,
+            },
+            RuleKind::AnyOf(alternatives) => {
+                let truths: Vec<_> = alternatives
+                    .iter()
+                    .map(|it| it.truth(lock, assignment))
+                    .collect();
+                if truths.contains(&Truth::Holds) {
+                    Truth::Holds
+                } else if truths.iter().all(|it| *it == Truth::Fails) {
+                    Truth::Fails
+                } else {
+                    Truth::Unknown
+                }
+            }
+            RuleKind::Implies {
+                condition,
+                consequence,
+            } => match condition.truth(lock, assignment) {
+                Truth::Fails => Truth::Holds,
+                Truth::Holds => consequence.truth(lock, assignment),
+                Truth::Unknown => match consequence.truth(lock, assignment) {
+                    Truth::Holds => Truth::Holds,
+                    _ => Truth::Unknown,
+                },
+            },
+            rule => match rule.validate(lock, assignment) {
+                Err(_) => Truth::Fails,
+                Ok(()) if rule.decided(lock, assignment) => Truth::Holds,
+                Ok(()) => Truth::Unknown,
+            },
+        }
+    }
+
+    ///Whether every activation the rule is about is placed, and on a known rune if the rule is
+    ///about runes, so it either holds or is violated.
+    fn decided(&self, lock: &RuneLock, assignment: &Assignment) -> bool {
+        let reads_runes = matches!(
+            self,
+            RuleKind::DifferentRunes { .. } | RuleKind::AllDifferentRunes { .. }
+        );
+        let activations = self.activations();
+        !activations.is_empty()
+            && activations.into_iter().all(|it| {
+                assignment
+                    .position_of(it)
+                    .is_some_and(|position| !reads_runes || lock.runes[position].is_known())
+            })
+    }
+
+    ///The rule of the kind named `kind` (see `PAIR_KINDS` and `KIND_ALIASES`) over the two
+    ///activations.
+    pub fn between(kind: &str, first: Activation, second: Activation) -> Option<Self> {
+        match resolve_alias(kind) {
+            "alwanese" => Some(RuleKind::Alwanese { first, second }),
+            "antakian-conjugates" => Some(RuleKind::AntakianConjugates { first, second }),
+            "alwanese-conjugates" => Some(RuleKind::AlwaneseConjugates { first, second }),
+            "different-runes" => Some(RuleKind::DifferentRunes { first, second }),
+            "antakian-twins" => Some(RuleKind::AntakianTwins { first, second }),
+            "increase-santor" => Some(RuleKind::IncreaseSantor { first, second }),
+            "decrease-santor" => Some(RuleKind::DecreaseSantor { first, second }),
+            "max-0-conductive" => Some(RuleKind::Max0Conductive { first, second }),
+            "not-alwanese" => Some(RuleKind::NotAlwanese { first, second }),
+            "not-antakian-twins" => Some(RuleKind::NotAntakianTwins { first, second }),
+            _ => None,
+        }
+    }
+
+    ///The rule of the kind named `kind` (see `NARY_KINDS`) over the activations.
+    pub fn among(kind: &str, activations: Vec<Activation>) -> Option<Self> {
+        match kind {
+            "all-different-runes" => Some(RuleKind::AllDifferentRunes { activations }),
+            _ => None,
         }
     }
 }
@@ -146,6 +691,15 @@ pub enum RuleError {
     Unfulfillable,
 }
 
+///Whether a rule holds on some placements, see `RuleKind::truth`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Truth {
+    Holds,
+    Fails,
+    ///Activations of the rule aren't placed yet, or are placed on runes that aren't known.
+    Unknown,
+}
+
 #[derive(Debug, Error)]
 pub enum ValidateTupleError {
     #[error("{0}")]
@@ -156,25 +710,26 @@ pub enum ValidateTupleError {
 
 impl RuleKind {
     pub fn validate(&self, lock: &RuneLock, assignment: &Assignment) -> Result<(), RuleError> {
+        let size = lock.size();
         match self {
             RuleKind::Alwanese { first, second } => match (
                 assignment.position_of(*first),
                 assignment.position_of(*second),
             ) {
-                (Some(one), Some(two)) if !two.alwanese_of(one) => Err(RuleError::Violated),
+                (Some(one), Some(two)) if !two.alwanese_of(one, size) => Err(RuleError::Violated),
                 _ => Ok(()),
             },
             RuleKind::AntakianConjugates { first, second } => match (
                 assignment.position_of(*first),
                 assignment.position_of(*second),
             ) {
-                (None, Some(three)) if assignment[three.antiakian_conjugate()].is_some() => {
+                (None, Some(three)) if assignment[three.antiakian_conjugate(size)].is_some() => {
                     Err(RuleError::Unfulfillable)
                 }
-                (Some(two), None) if assignment[two.antiakian_conjugate()].is_some() => {
+                (Some(two), None) if assignment[two.antiakian_conjugate(size)].is_some() => {
                     Err(RuleError::Unfulfillable)
                 }
-                (Some(one), Some(two)) if !one.antakian_conjugate_of(two) => {
+                (Some(one), Some(two)) if !one.antakian_conjugate_of(two, size) => {
                     Err(RuleError::Violated)
                 }
                 _ => Ok(()),
@@ -183,7 +738,7 @@ impl RuleKind {
                 assignment.position_of(*first),
                 assignment.position_of(*second),
             ) {
-                (Some(one), Some(two)) if !one.alwanese_conjugate_of(two) => {
+                (Some(one), Some(two)) if !one.alwanese_conjugate_of(two, size) => {
                     Err(RuleError::Violated)
                 }
                 _ => Ok(()),
@@ -192,8 +747,10 @@ impl RuleKind {
                 assignment.position_of(*first),
                 assignment.position_of(*second),
             ) {
-                (Some(one), Some(two)) if lock.runes[one] == lock.runes[two] => {
-                    return Err(RuleError::Violated)
+                (Some(one), Some(two))
+                    if lock.runes[one].is_known() && lock.runes[one] == lock.runes[two] =>
+                {
+                    Err(RuleError::Violated)
                 }
                 _ => Ok(()),
             },
@@ -201,8 +758,8 @@ impl RuleKind {
                 assignment.position_of(*first),
                 assignment.position_of(*second),
             ) {
-                (Some(one), Some(two)) if !one.antakian_twins(two) => {
-                    return Err(RuleError::Violated)
+                (Some(one), Some(two)) if !one.antakian_twins(two, size) => {
+                    Err(RuleError::Violated)
                 }
                 _ => Ok(()),
             },
@@ -211,23 +768,41 @@ impl RuleKind {
                     assignment.position_of(*first),
                     assignment.position_of(*second),
                 ) {
-                    (Some(f), Some(s)) if !f.increases_santor(s) => Err(RuleError::Violated),
-                    (Some(f), None) if f.santor() == MAX_SANTOR => Err(RuleError::Unfulfillable),
-                    (None, Some(s)) if s.santor() == MIN_SANTOR => Err(RuleError::Unfulfillable),
+                    (Some(f), Some(s)) if !lock.increases_santor(f, s) => Err(RuleError::Violated),
+                    (Some(f), None) if lock.santor(f) == lock.max_santor() => {
+                        Err(RuleError::Unfulfillable)
+                    }
+                    (None, Some(s)) if lock.santor(s) == 0 => Err(RuleError::Unfulfillable),
+                    _ => Ok(()),
+                }
+            }
+            RuleKind::DecreaseSantor { first, second } => {
+                match (
+                    assignment.position_of(*first),
+                    assignment.position_of(*second),
+                ) {
+                    (Some(f), Some(s)) if !lock.increases_santor(s, f) => Err(RuleError::Violated),
+                    (Some(f), None) if lock.santor(f) == 0 => Err(RuleError::Unfulfillable),
+                    (None, Some(s)) if lock.santor(s) == lock.max_santor() => {
+                        Err(RuleError::Unfulfillable)
+                    }
                     _ => Ok(()),
                 }
             }
             RuleKind::RuneFollowsImmediately { first, second } => {
                 for (position, rune) in lock.runes.iter().enumerate() {
                     let position = RunePosition::new(position);
-                    if rune == first {
+                    if rune.is_known() && rune == first {
                         if let Some(first_assignment) = assignment[position] {
                             let next = first_assignment
-                                .next()
+                                .next(size)
                                 .map_err(|_| RuleError::Unfulfillable)?; //TODO Check in bounds
                             let second_position = assignment.position_of(next);
                             match second_position {
-                                Some(second_position) if lock.runes[second_position] != *second => {
+                                Some(second_position)
+                                    if lock.runes[second_position].is_known()
+                                        && lock.runes[second_position] != *second =>
+                                {
                                     return Err(RuleError::Violated)
                                 }
                                 _ => {}
@@ -235,29 +810,858 @@ impl RuleKind {
                         }
                     }
                 }
-                return Ok(());
+                Ok(())
+            }
+            //Only violated once the whole window after an activation on a `first` rune is placed
+            //elsewhere, and unfulfillable once there is no window or no `second` rune left for it.
+            RuleKind::RuneFollowsWithin {
+                first,
+                second,
+                within,
+            } => {
+                let is = |position: RunePosition, rune: &Rune| {
+                    lock.runes[position].is_known() && lock.runes[position] == *rune
+                };
+                let free = size.all_positions().any(|it| {
+                    assignment[it].is_none() && (is(it, second) || !lock.runes[it].is_known())
+                });
+                let mut result = Ok(());
+                for position in size.all_positions().filter(|it| is(*it, first)) {
+                    let Some(activation) = assignment[position] else {
+                        continue;
+                    };
+                    let window: Vec<_> = activation.following(*within, size).collect();
+                    let placed: Vec<_> = window
+                        .iter()
+                        .filter_map(|it| assignment.position_of(*it))
+                        .collect();
+                    if placed
+                        .iter()
+                        .any(|it| is(*it, second) || !lock.runes[*it].is_known())
+                    {
+                        continue;
+                    }
+                    if window.is_empty() {
+                        result = Err(RuleError::Unfulfillable);
+                    } else if placed.len() == window.len() {
+                        return Err(RuleError::Violated);
+                    } else if !free {
+                        result = Err(RuleError::Unfulfillable);
+                    }
+                }
+                result
+            }
+            RuleKind::RuneClassBefore { earlier, later } => {
+                let on = |rune: &Rune| {
+                    size.all_positions()
+                        .filter(|it| lock.runes[*it].is_known() && lock.runes[*it] == *rune)
+                        .filter_map(|it| Some((it, assignment[it]?)))
+                        .collect::<Vec<_>>()
+                };
+                let violated = on(earlier).into_iter().any(|(position, activation)| {
+                    on(later)
+                        .into_iter()
+                        .any(|(other, it)| other != position && it <= activation)
+                });
+                match violated {
+                    true => Err(RuleError::Violated),
+                    false => Ok(()),
+                }
             }
             RuleKind::Max0Conductive { first, second } => match (
                 assignment.position_of(*first),
                 assignment.position_of(*second),
             ) {
-                (Some(one), Some(two)) if !one.max_0_conductive(two) => {
-                    return Err(RuleError::Violated)
+                (Some(one), Some(two)) if !one.max_0_conductive(two, size) => {
+                    Err(RuleError::Violated)
+                }
+                _ => Ok(()),
+            },
+            RuleKind::NotAlwanese { first, second } => match (
+                assignment.position_of(*first),
+                assignment.position_of(*second),
+            ) {
+                (Some(one), Some(two)) if two.alwanese_of(one, size) => Err(RuleError::Violated),
+                _ => Ok(()),
+            },
+            RuleKind::NotAntakianTwins { first, second } => match (
+                assignment.position_of(*first),
+                assignment.position_of(*second),
+            ) {
+                (Some(one), Some(two)) if one.antakian_twins(two, size) => Err(RuleError::Violated),
+                _ => Ok(()),
+            },
+            RuleKind::ActivationAtPosition {
+                activation,
+                position,
+            } => match (assignment.position_of(*activation), assignment[*position]) {
+                (Some(it), _) if it != *position => Err(RuleError::Violated),
+                (_, Some(it)) if it != *activation => Err(RuleError::Unfulfillable),
+                _ => Ok(()),
+            },
+            RuleKind::ActivationNotAtPosition {
+                activation,
+                position,
+            } => match assignment.position_of(*activation) {
+                Some(it) if it == *position => Err(RuleError::Violated),
+                _ => Ok(()),
+            },
+            RuleKind::AllDifferentRunes { activations } => {
+                let mut runes = activations
+                    .iter()
+                    .filter_map(|it| assignment.position_of(*it))
+                    .map(|it| lock.runes[it])
+                    .filter(Rune::is_known);
+                match runes.all_unique() {
+                    true => Ok(()),
+                    false => Err(RuleError::Violated),
                 }
+            }
+            //Only violated once the rule is known to hold, a rule that is violated, can't be
+            //fulfilled anymore or is about runes nobody has seen leaves its negation fulfilled.
+            RuleKind::Not(rule) => match rule.truth(lock, assignment) {
+                Truth::Holds => Err(RuleError::Violated),
+                _ => Ok(()),
+            },
+            //Only violated once no alternative can hold anymore, and only if one of them is.
+            RuleKind::AnyOf(alternatives) => {
+                let mut violated = false;
+                for alternative in alternatives {
+                    match alternative.validate(lock, assignment) {
+                        Ok(()) => return Ok(()),
+                        Err(RuleError::Violated) => violated = true,
+                        Err(RuleError::Unfulfillable) => {}
+                    }
+                }
+                match violated {
+                    true => Err(RuleError::Violated),
+                    false => Err(RuleError::Unfulfillable),
+                }
+            }
+            //Undecided and failed conditions leave the consequence open.
+            RuleKind::Implies {
+                condition,
+                consequence,
+            } => match condition.truth(lock, assignment) {
+                Truth::Holds => consequence.validate(lock, assignment),
                 _ => Ok(()),
             },
         }
     }
 
+    ///The name of the kind, as used when entering rules.
+    pub fn name(&self) -> &'static str {
+        match self {
+            RuleKind::Alwanese { .. } => "alwanese",
+            RuleKind::AntakianConjugates { .. } => "antakian-conjugates",
+            RuleKind::AlwaneseConjugates { .. } => "alwanese-conjugates",
+            RuleKind::DifferentRunes { .. } => "different-runes",
+            RuleKind::AntakianTwins { .. } => "antakian-twins",
+            RuleKind::IncreaseSantor { .. } => "increase-santor",
+            RuleKind::DecreaseSantor { .. } => "decrease-santor",
+            RuleKind::RuneFollowsImmediately { .. } => "follows",
+            RuleKind::RuneFollowsWithin { .. } => "follows-within",
+            RuleKind::RuneClassBefore { .. } => "before",
+            RuleKind::Max0Conductive { .. } => "max-0-conductive",
+            RuleKind::NotAlwanese { .. } => "not-alwanese",
+            RuleKind::NotAntakianTwins { .. } => "not-antakian-twins",
+            RuleKind::ActivationAtPosition { .. } => "at",
+            RuleKind::ActivationNotAtPosition { .. } => "not-at",
+            RuleKind::AllDifferentRunes { .. } => "all-different-runes",
+            RuleKind::Not(_) => "not",
+            RuleKind::AnyOf(_) => "or",
+            RuleKind::Implies { .. } => "if",
+        }
+    }
+
+    ///The rule in the syntax `FromStr` accepts.
+    pub fn syntax(&self) -> String {
+        let (first, second, kind) = match self {
+            RuleKind::RuneFollowsImmediately { first, second } => {
+                return format!("{} follows {}", second, first)
+            }
+            RuleKind::RuneFollowsWithin {
+                first,
+                second,
+                within,
+            } => return format!("{} follows {} within {}", second, first, within),
+            RuleKind::RuneClassBefore { earlier, later } => {
+                return format!("{} before {}", earlier, later)
+            }
+            RuleKind::ActivationAtPosition {
+                activation,
+                position,
+            } => {
+                return format!(
+                    "{} at {}",
+                    HumanActivation::from(*activation).number(),
+                    position
+                )
+            }
+            RuleKind::ActivationNotAtPosition {
+                activation,
+                position,
+            } => {
+                return format!(
+                    "{} not-at {}",
+                    HumanActivation::from(*activation).number(),
+                    position
+                )
+            }
+            RuleKind::AllDifferentRunes { activations } => {
+                return format!(
+                    "{} all-different-runes",
+                    activations
+                        .iter()
+                        .map(|it| HumanActivation::from(*it).number())
+                        .join(" ")
+                )
+            }
+            RuleKind::Not(rule) => return format!("not {}", rule.syntax()),
+            RuleKind::AnyOf(alternatives) => {
+                return alternatives.iter().map(RuleKind::syntax).join(" or ")
+            }
+            RuleKind::Implies {
+                condition,
+                consequence,
+            } => return format!("if {} then {}", condition.syntax(), consequence.syntax()),
+            RuleKind::Alwanese { first, second } => (first, second, "alwanese"),
+            RuleKind::AntakianConjugates { first, second } => {
+                (first, second, "antakian-conjugates")
+            }
+            RuleKind::AlwaneseConjugates { first, second } => {
+                (first, second, "alwanese-conjugates")
+            }
+            RuleKind::DifferentRunes { first, second } => (first, second, "different-runes"),
+            RuleKind::AntakianTwins { first, second } => (first, second, "antakian-twins"),
+            RuleKind::IncreaseSantor { first, second } => (first, second, "increase-santor"),
+            RuleKind::DecreaseSantor { first, second } => (first, second, "decrease-santor"),
+            RuleKind::Max0Conductive { first, second } => (first, second, "max-0-conductive"),
+            RuleKind::NotAlwanese { first, second } => (first, second, "not-alwanese"),
+            RuleKind::NotAntakianTwins { first, second } => (first, second, "not-antakian-twins"),
+        };
+        format!(
+            "{} {} {}",
+            HumanActivation::from(*first).number(),
+            HumanActivation::from(*second).number(),
+            kind
+        )
+    }
+
+    ///Symmetric rules with their activations in ascending order, every other rule unchanged.
+    pub fn canonical(&self) -> RuleKind {
+        let ordered = |first: &Activation, second: &Activation| match first <= second {
+            true => (*first, *second),
+            false => (*second, *first),
+        };
+        match self {
+            RuleKind::AntakianConjugates { first, second } => {
+                let (first, second) = ordered(first, second);
+                RuleKind::AntakianConjugates { first, second }
+            }
+            RuleKind::AlwaneseConjugates { first, second } => {
+                let (first, second) = ordered(first, second);
+                RuleKind::AlwaneseConjugates { first, second }
+            }
+            RuleKind::DifferentRunes { first, second } => {
+                let (first, second) = ordered(first, second);
+                RuleKind::DifferentRunes { first, second }
+            }
+            RuleKind::AntakianTwins { first, second } => {
+                let (first, second) = ordered(first, second);
+                RuleKind::AntakianTwins { first, second }
+            }
+            RuleKind::Max0Conductive { first, second } => {
+                let (first, second) = ordered(first, second);
+                RuleKind::Max0Conductive { first, second }
+            }
+            RuleKind::NotAntakianTwins { first, second } => {
+                let (first, second) = ordered(first, second);
+                RuleKind::NotAntakianTwins { first, second }
+            }
+            RuleKind::AllDifferentRunes { activations } => RuleKind::AllDifferentRunes {
+                activations: activations.iter().copied().sorted().collect(),
+            },
+            RuleKind::Not(rule) => RuleKind::Not(Box::new(rule.canonical())),
+            RuleKind::AnyOf(alternatives) => {
+                RuleKind::AnyOf(alternatives.iter().map(RuleKind::canonical).collect())
+            }
+            RuleKind::Implies {
+                condition,
+                consequence,
+            } => RuleKind::Implies {
+                condition: Box::new(condition.canonical()),
+                consequence: Box::new(consequence.canonical()),
+            },
+            RuleKind::Alwanese { .. }
+            | RuleKind::IncreaseSantor { .. }
+            | RuleKind::DecreaseSantor { .. }
+            | RuleKind::RuneFollowsImmediately { .. }
+            | RuleKind::RuneFollowsWithin { .. }
+            | RuleKind::RuneClassBefore { .. }
+            | RuleKind::NotAlwanese { .. }
+            | RuleKind::ActivationAtPosition { .. }
+            | RuleKind::ActivationNotAtPosition { .. } => self.clone(),
+        }
+    }
+
+    ///The two activations of rules that relate a pair of activations.
+    pub fn activation_pair(&self) -> Option<(Activation, Activation)> {
+        match self {
+            RuleKind::Alwanese { first, second }
+            | RuleKind::AntakianConjugates { first, second }
+            | RuleKind::AlwaneseConjugates { first, second }
+            | RuleKind::DifferentRunes { first, second }
+            | RuleKind::AntakianTwins { first, second }
+            | RuleKind::IncreaseSantor { first, second }
+            | RuleKind::DecreaseSantor { first, second }
+            | RuleKind::Max0Conductive { first, second }
+            | RuleKind::NotAlwanese { first, second }
+            | RuleKind::NotAntakianTwins { first, second } => Some((*first, *second)),
+            RuleKind::Not(rule) => rule.activation_pair(),
+            //Alternatives about the same two activations, in any order.
+            RuleKind::AnyOf(alternatives) => {
+                let (first, second) = alternatives.first()?.activation_pair()?;
+                alternatives
+                    .iter()
+                    .all(|it| {
+                        it.activation_pair()
+                            .is_some_and(|it| it == (first, second) || it == (second, first))
+                    })
+                    .then_some((first, second))
+            }
+            RuleKind::Implies { .. } => match self.activations()[..] {
+                [first, second] => Some((first, second)),
+                _ => None,
+            },
+            RuleKind::RuneFollowsImmediately { .. }
+            | RuleKind::RuneFollowsWithin { .. }
+            | RuleKind::RuneClassBefore { .. }
+            | RuleKind::ActivationAtPosition { .. }
+            | RuleKind::ActivationNotAtPosition { .. }
+            | RuleKind::AllDifferentRunes { .. } => None,
+        }
+    }
+
+    ///The activation of rules about where a single activation is.
+    pub fn single_activation(&self) -> Option<Activation> {
+        match self {
+            RuleKind::ActivationAtPosition { activation, .. }
+            | RuleKind::ActivationNotAtPosition { activation, .. } => Some(*activation),
+            RuleKind::Not(rule) => rule.single_activation(),
+            RuleKind::AnyOf(alternatives) => {
+                let activation = alternatives.first()?.single_activation()?;
+                alternatives
+                    .iter()
+                    .all(|it| it.single_activation() == Some(activation))
+                    .then_some(activation)
+            }
+            RuleKind::Implies { .. } => match self.activations()[..] {
+                [activation] => Some(activation),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    ///Every activation the rule is about, none for rules about runes.
+    pub fn activations(&self) -> Vec<Activation> {
+        match self {
+            RuleKind::AllDifferentRunes { activations } => activations.clone(),
+            RuleKind::AnyOf(alternatives) => alternatives
+                .iter()
+                .flat_map(RuleKind::activations)
+                .unique()
+                .collect(),
+            RuleKind::Not(rule) => rule.activations(),
+            RuleKind::Implies {
+                condition,
+                consequence,
+            } => condition
+                .activations()
+                .into_iter()
+                .chain(consequence.activations())
+                .unique()
+                .collect(),
+            rule => match (rule.activation_pair(), rule.single_activation()) {
+                (Some((first, second)), _) => vec![first, second],
+                (None, Some(activation)) => vec![activation],
+                (None, None) => Vec::new(),
+            },
+        }
+    }
+
+    ///Every position the rule is about, only rules placing an activation are.
+    pub fn positions(&self) -> Vec<RunePosition> {
+        match self {
+            RuleKind::ActivationAtPosition { position, .. }
+            | RuleKind::ActivationNotAtPosition { position, .. } => vec![*position],
+            RuleKind::AnyOf(alternatives) => alternatives
+                .iter()
+                .flat_map(RuleKind::positions)
+                .unique()
+                .collect(),
+            RuleKind::Not(rule) => rule.positions(),
+            RuleKind::Implies {
+                condition,
+                consequence,
+            } => condition
+                .positions()
+                .into_iter()
+                .chain(consequence.positions())
+                .unique()
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    ///Whether the rule is only about activations and positions a lock of `size` has. Parsing
+    ///only knows the largest lock.
+    pub fn check_size(&self, size: LockSize) -> Result<(), RuleParseError> {
+        if let Some(activation) = self
+            .activations()
+            .into_iter()
+            .find(|it| !size.contains(*it))
+        {
+            return Err(RuleParseError::ActivationOutside(
+                HumanActivation::from(activation).number(),
+                size,
+            ));
+        }
+        match self
+            .positions()
+            .into_iter()
+            .find(|it| it.index() >= size.positions())
+        {
+            Some(position) => Err(RuleParseError::PositionOutside(position.index(), size)),
+            None => Ok(()),
+        }
+    }
+
+    ///Every rune the rule is about, none for rules about activations.
+    pub fn runes(&self) -> Vec<Rune> {
+        match self {
+            RuleKind::RuneFollowsImmediately { first, second }
+            | RuleKind::RuneFollowsWithin { first, second, .. }
+            | RuleKind::RuneClassBefore {
+                earlier: first,
+                later: second,
+            } => vec![*first, *second].into_iter().unique().collect(),
+            RuleKind::AnyOf(alternatives) => alternatives
+                .iter()
+                .flat_map(RuleKind::runes)
+                .unique()
+                .collect(),
+            RuleKind::Not(rule) => rule.runes(),
+            RuleKind::Implies {
+                condition,
+                consequence,
+            } => condition
+                .runes()
+                .into_iter()
+                .chain(consequence.runes())
+                .unique()
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    ///Counting for `Y follows X` rules: the activations on the X runes are followed by distinct
+    ///activations on Y runes, so there can't be more X runes than Y runes, the last activation
+    ///can't be on an X rune, and one that can't be followed on a Y rune can't be on an X rune
+    ///either. With as many X runes as Y runes, every activation on a Y rune needs its predecessor
+    ///on an X rune as well. For `X before Y` rules, an activation on an X rune needs an activation
+    ///above it for every Y rune, and one on a Y rune an activation below it for every X rune.
+    ///Whether the activation on the position is ruled out by that, given the cells `excluded`
+    ///rules out. Only decided for locks without unknown runes that activate every position.
+    pub fn excluded_by_rune_counts(
+        &self,
+        lock: &RuneLock,
+        (position, activation): (RunePosition, Activation),
+        excluded: impl Fn(RunePosition, Activation) -> bool,
+    ) -> bool {
+        let (RuleKind::RuneFollowsImmediately { first, second }
+        | RuleKind::RuneClassBefore {
+            earlier: first,
+            later: second,
+        }) = *self
+        else {
+            return false;
+        };
+        let size = lock.size();
+        if first == second || lock.runes.contains(&Rune::Unknown) || !size.is_square() {
+            return false;
+        }
+        let positions_of = |rune: Rune| {
+            size.all_positions()
+                .filter(move |it| lock.runes[*it] == rune)
+        };
+        if let RuleKind::RuneClassBefore { .. } = self {
+            return match lock.runes[position] {
+                rune if rune == first => {
+                    size.activations() - 1 - activation.index() < positions_of(second).count()
+                }
+                rune if rune == second => activation.index() < positions_of(first).count(),
+                _ => false,
+            };
+        }
+        let (firsts, seconds) = (positions_of(first).count(), positions_of(second).count());
+        if firsts > seconds {
+            return true;
+        }
+        let nowhere = |rune: Rune, activation: Result<Activation, _>| match activation {
+            Ok(activation) => positions_of(rune).all(|it| excluded(it, activation)),
+            Err(_) => true,
+        };
+        match lock.runes[position] {
+            rune if rune == first => nowhere(second, activation.next(size)),
+            rune if rune == second && firsts == seconds => nowhere(first, activation.prev()),
+            _ => false,
+        }
+    }
+
     pub fn validate_tuple(
         &self,
         lock: &RuneLock,
         a: (RunePosition, Activation),
         b: (RunePosition, Activation),
+    ) -> Result<(), ValidateTupleError> {
+        self.validate_subset(lock, [a, b])
+    }
+
+    ///Validates the rule against just the given placements, as if the activations not among
+    ///them were still open. Rules over more than two activations are checked this way, with the
+    ///placements known so far.
+    pub fn validate_subset(
+        &self,
+        lock: &RuneLock,
+        placements: impl IntoIterator<Item = (RunePosition, Activation)>,
     ) -> Result<(), ValidateTupleError> {
         //TODO Speed this up
-        let fake_assignment = Assignment::from_tuple_iter([a, b].into_iter())?;
+        let fake_assignment = Assignment::from_tuple_iter(lock.size(), placements.into_iter())?;
         self.validate(lock, &fake_assignment)?;
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        assignment::Assignment,
+        index::RunePosition,
+        rune::Rune,
+        size::LockSize,
+        test_support::{activation, sample_runes},
+        RuleKind, RuneLock,
+    };
+
+    use super::{ActivationRuleKindHelpers, RuleError, RuleTemplate, Truth, ValidateTupleError};
+
+    #[test]
+    fn test_negated_rules() {
+        let lock = RuneLock::with_rules([0; 12].map(Rune::new), vec![]);
+        let first = (RunePosition::new(0), super::human(1));
+        let twin = (RunePosition::new(1), super::human(2));
+        let other_circle = (RunePosition::new(7), super::human(2));
+
+        let rule = (1, 2).not_antakian_twins();
+        assert!(rule.validate_tuple(&lock, first, twin).is_err());
+        assert!(rule.validate_tuple(&lock, first, other_circle).is_ok());
+
+        let rule = (1, 2).not_alwanese();
+        assert!(rule.validate_tuple(&lock, first, twin).is_err());
+        assert!(rule.validate_tuple(&lock, first, other_circle).is_err());
+        let far = (RunePosition::new(3), super::human(2));
+        assert!(rule.validate_tuple(&lock, first, far).is_ok());
+    }
+
+    #[test]
+    fn test_negation_wrapper() {
+        let lock = RuneLock::with_rules([0; 12].map(Rune::new), vec![]);
+        let first = (RunePosition::new(0), super::human(1));
+        let twin = (RunePosition::new(1), super::human(2));
+        let other_circle = (RunePosition::new(7), super::human(2));
+
+        let rule: super::RuleKind = "not 1 2 antakian-twins".parse().unwrap();
+        assert_eq!(rule.syntax(), "not 1 2 antakian-twins");
+        assert!(rule.validate_tuple(&lock, first, twin).is_err());
+        assert!(rule.validate_tuple(&lock, first, other_circle).is_ok());
+        //Undecided until both are placed.
+        let alone = Assignment::from_tuple_iter(lock.size(), std::iter::once(first)).unwrap();
+        assert!(rule.validate(&lock, &alone).is_ok());
+
+        let rule: super::RuleKind = "not 3 at 0".parse().unwrap();
+        let on = Assignment::from_tuple_iter(
+            lock.size(),
+            std::iter::once((RunePosition::new(0), super::human(3))),
+        )
+        .unwrap();
+        assert!(rule.validate(&lock, &on).is_err());
+        assert!(matches!(
+            "not V follows Z".parse::<super::RuleKind>(),
+            Err(super::RuleParseError::NotNegatable(_))
+        ));
+    }
+
+    #[test]
+    fn test_rules_about_unknown_runes_are_undecided() {
+        let mut runes = sample_runes();
+        runes[0] = Rune::Unknown;
+        let lock = RuneLock::new(runes);
+        let assignment = |placements: &[(usize, u8)]| {
+            Assignment::from_tuple_iter(
+                lock.size(),
+                placements.iter().map(|(position, activation)| {
+                    (RunePosition::new(*position), super::human(*activation))
+                }),
+            )
+            .unwrap()
+        };
+        let unseen = assignment(&[(0, 1), (1, 2), (3, 3)]);
+        let seen = assignment(&[(9, 1), (1, 2), (3, 3)]);
+
+        let rule: RuleKind = "1 2 different-runes".parse().unwrap();
+        assert_eq!(rule.truth(&lock, &unseen), Truth::Unknown);
+        assert_eq!(rule.truth(&lock, &seen), Truth::Holds);
+        let rule: RuleKind = "not 1 2 different-runes".parse().unwrap();
+        assert!(rule.validate(&lock, &unseen).is_ok());
+        assert!(rule.validate(&lock, &seen).is_err());
+        let rule: RuleKind = "if 1 2 different-runes then 3 at 0".parse().unwrap();
+        assert!(rule.validate(&lock, &unseen).is_ok());
+        assert!(rule.validate(&lock, &seen).is_err());
+    }
+
+    #[test]
+    fn test_any_of_holds_while_an_alternative_can() {
+        let lock = RuneLock::with_rules([0; 12].map(Rune::new), vec![]);
+        let rule: super::RuleKind = "1 2 alwanese or 1 at 6".parse().unwrap();
+        assert_eq!(rule.syntax(), "1 2 alwanese or 1 at 6");
+        assert_eq!(rule.activations(), vec![super::human(1), super::human(2)]);
+        assert_eq!(rule.activation_pair(), None);
+
+        let assignment = |placements: &[(usize, u8)]| {
+            Assignment::from_tuple_iter(
+                lock.size(),
+                placements.iter().map(|(position, activation)| {
+                    (RunePosition::new(*position), super::human(*activation))
+                }),
+            )
+            .unwrap()
+        };
+        assert!(rule.validate(&lock, &assignment(&[(6, 1)])).is_ok());
+        assert!(rule.validate(&lock, &assignment(&[(0, 1), (1, 2)])).is_ok());
+        assert!(matches!(
+            rule.validate(&lock, &assignment(&[(0, 1), (3, 2)])),
+            Err(super::RuleError::Violated)
+        ));
+
+        let either_order: super::RuleKind = "1 2 alwanese or 2 1 alwanese".parse().unwrap();
+        assert_eq!(
+            either_order.activation_pair(),
+            Some((super::human(1), super::human(2)))
+        );
+    }
+
+    #[test]
+    fn test_implications_only_bind_once_the_condition_holds() {
+        let lock = RuneLock::with_rules([0; 12].map(Rune::new), vec![]);
+        let rule: super::RuleKind = "if 1 at 0 then 2 3 alwanese".parse().unwrap();
+        assert_eq!(rule.syntax(), "if 1 at 0 then 2 3 alwanese");
+        assert_eq!(rule.activation_pair(), None);
+
+        let assignment = |placements: &[(usize, u8)]| {
+            Assignment::from_tuple_iter(
+                lock.size(),
+                placements.iter().map(|(position, activation)| {
+                    (RunePosition::new(*position), super::human(*activation))
+                }),
+            )
+            .unwrap()
+        };
+        //The condition is undecided, fails or holds.
+        assert!(rule.validate(&lock, &assignment(&[(1, 2), (4, 3)])).is_ok());
+        assert!(rule
+            .validate(&lock, &assignment(&[(5, 1), (1, 2), (4, 3)]))
+            .is_ok());
+        assert!(rule
+            .validate(&lock, &assignment(&[(0, 1), (1, 2), (4, 3)]))
+            .is_err());
+        assert!(rule
+            .validate(&lock, &assignment(&[(0, 1), (1, 2), (2, 3)]))
+            .is_ok());
+        assert!("if 1 at 0 2 at 3".parse::<super::RuleKind>().is_err());
+    }
+
+    #[test]
+    fn test_all_different_runes() {
+        let lock = RuneLock::new(sample_runes());
+        let rule: RuleKind = "3 6 9 all-different-runes".parse().unwrap();
+        assert_eq!(rule.activations().len(), 3);
+        assert_eq!(rule.activation_pair(), None);
+        assert_eq!(rule.syntax().parse::<RuleKind>().unwrap(), rule);
+        assert!("3 all-different-runes".parse::<RuleKind>().is_err());
+
+        let cell = |position, it| (RunePosition::new(position), activation(it));
+        assert!(rule
+            .validate_subset(&lock, [cell(0, 3), cell(1, 6), cell(2, 9)])
+            .is_ok());
+        assert!(rule
+            .validate_subset(&lock, [cell(0, 3), cell(1, 6), cell(4, 9)])
+            .is_err());
+        assert!(rule
+            .validate_subset(&lock, [cell(0, 3), cell(9, 1)])
+            .is_ok());
+    }
+
+    #[test]
+    fn test_follows_within_a_gap() {
+        let lock = RuneLock::new(sample_runes());
+        let rule: RuleKind = "V follows Z within 2".parse().unwrap();
+        assert_eq!(rule.syntax().parse::<RuleKind>().unwrap(), rule);
+        assert!("V follows Z within 0".parse::<RuleKind>().is_err());
+        assert!("not V follows Z within 2".parse::<RuleKind>().is_err());
+
+        let cell = |position, it| (RunePosition::new(position), activation(it));
+        assert!(rule
+            .validate_subset(&lock, [cell(0, 1), cell(1, 2)])
+            .is_ok());
+        assert!(rule
+            .validate_subset(&lock, [cell(0, 1), cell(1, 2), cell(2, 3)])
+            .is_ok());
+        assert!(matches!(
+            rule.validate_subset(&lock, [cell(0, 1), cell(1, 2), cell(3, 3)]),
+            Err(ValidateTupleError::RuleError(RuleError::Violated))
+        ));
+        assert!(matches!(
+            rule.validate_subset(&lock, [cell(0, 12)]),
+            Err(ValidateTupleError::RuleError(RuleError::Unfulfillable))
+        ));
+        //Every V rune is taken by activations outside the gap.
+        assert!(matches!(
+            rule.validate_subset(
+                &lock,
+                [cell(0, 1), cell(2, 5), cell(5, 6), cell(8, 7), cell(11, 8)]
+            ),
+            Err(ValidateTupleError::RuleError(RuleError::Unfulfillable))
+        ));
+    }
+
+    #[test]
+    fn test_rune_orderings() {
+        let lock = RuneLock::new(sample_runes());
+        let rule: RuleKind = "Z before C".parse().unwrap();
+        assert_eq!(
+            rule,
+            RuleKind::RuneClassBefore {
+                earlier: Rune::new(0),
+                later: Rune::new(3),
+            }
+        );
+        assert_eq!(rule.syntax().parse::<RuleKind>().unwrap(), rule);
+        assert!("not Z before C".parse::<RuleKind>().is_err());
+
+        let cell = |position, it| (RunePosition::new(position), activation(it));
+        assert!(rule.validate_tuple(&lock, cell(0, 5), cell(3, 7)).is_ok());
+        assert!(rule.validate_tuple(&lock, cell(9, 5), cell(6, 3)).is_err());
+        assert!(rule.validate_tuple(&lock, cell(1, 5), cell(6, 3)).is_ok());
+        assert!(rule.excluded_by_rune_counts(&lock, cell(3, 2), |_, _| false));
+        assert!(!rule.excluded_by_rune_counts(&lock, cell(3, 3), |_, _| false));
+        assert!(rule.excluded_by_rune_counts(&lock, cell(9, 11), |_, _| false));
+    }
+
+    #[test]
+    fn test_ring_aliases() {
+        let lock = RuneLock::with_rules([0; 12].map(Rune::new), vec![]);
+        let outer = (RunePosition::new(0), super::human(1));
+        let also_outer = (RunePosition::new(4), super::human(2));
+        let inner = (RunePosition::new(6), super::human(2));
+
+        let rule: super::RuleKind = "1 2 same-ring".parse().unwrap();
+        assert_eq!(rule, (1, 2).antakian_twins());
+        assert!(rule.validate_tuple(&lock, outer, also_outer).is_ok());
+        assert!(rule.validate_tuple(&lock, outer, inner).is_err());
+
+        let rule: super::RuleKind = "1 2 different-ring".parse().unwrap();
+        assert_eq!(rule, (1, 2).not_antakian_twins());
+        assert!(rule.validate_tuple(&lock, outer, inner).is_ok());
+    }
+
+    #[test]
+    fn test_decrease_santor() {
+        let lock = RuneLock::with_rules([0; 12].map(Rune::new), vec![]);
+        let top = (RunePosition::new(0), super::human(1));
+        let bottom = (RunePosition::new(3), super::human(2));
+
+        let rule = (1, 2).decrease_santor();
+        assert!(rule.validate_tuple(&lock, top, bottom).is_ok());
+        let rule = (2, 1).decrease_santor();
+        assert!(rule.validate_tuple(&lock, bottom, top).is_err());
+    }
+
+    #[test]
+    fn test_single_activation_rules_round_trip() {
+        for syntax in ["3 at 0", "12 not-at 11"] {
+            let rule: super::RuleKind = syntax.parse().unwrap();
+            assert_eq!(rule.syntax(), syntax);
+        }
+        let outside: super::RuleKind = "3 at 12".parse().unwrap();
+        assert!(matches!(
+            outside.check_size(LockSize::STANDARD),
+            Err(super::RuleParseError::PositionOutside(12, _))
+        ));
+        assert!("3 at 64".parse::<super::RuleKind>().is_err());
+    }
+
+    #[test]
+    fn test_santor_chain_expands_to_pairs() {
+        let template: RuleTemplate = "10..12 santor-chain".parse().unwrap();
+        assert_eq!(template, RuleTemplate::santor_chain(10, 12));
+        assert_eq!(
+            template.expand(),
+            vec![(10, 11).increase_santor(), (11, 12).increase_santor()]
+        );
+        assert!("12..10 santor-chain".parse::<RuleTemplate>().is_err());
+    }
+
+    #[test]
+    fn test_canonicalize_merges_swapped_duplicates() {
+        let rules = [
+            (2, 1).antakian_twins(),
+            (1, 2).alwanese(),
+            (1, 2).antakian_twins(),
+            (2, 1).alwanese(),
+        ];
+        let (canonical, index, normalizations) = super::canonicalize(&rules);
+        assert_eq!(
+            canonical,
+            vec![
+                (1, 2).antakian_twins(),
+                (1, 2).alwanese(),
+                (2, 1).alwanese()
+            ]
+        );
+        assert_eq!(index, vec![0, 1, 0, 2]);
+        assert_eq!(normalizations.len(), 2);
+    }
+
+    #[test]
+    fn test_rules_are_listed_by_what_they_are_about() {
+        let follows: RuleKind = "V follows Z".parse().unwrap();
+        assert_eq!(follows.runes(), vec![Rune::new(0), Rune::new(1)]);
+        assert!(follows.activations().is_empty());
+        assert!((1, 2).alwanese().runes().is_empty());
+
+        let lock = RuneLock::with_rules(
+            [0; 12].map(Rune::new),
+            vec![(1, 2).alwanese(), follows, (2, 3).antakian_twins()],
+        );
+        let about = |it| {
+            lock.rules_about(Some(super::human(it)))
+                .map(|(index, _)| index)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(about(2), vec![0, 2]);
+        assert_eq!(about(1), vec![0]);
+        assert_eq!(lock.rules_about(None).count(), 3);
+    }
+}
@@ -1,22 +1,60 @@
-use std::fmt::Display;
+use std::{fmt::Display, str::FromStr};
 
-#[derive(Debug, PartialEq, Copy, Clone, Hash, Eq)]
-pub struct Rune(u8);
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+#[derive(Debug, PartialEq, Copy, Clone, Hash, Eq, Serialize, Deserialize)]
+pub enum Rune {
+    Known(u8),
+    ///A rune that can't be seen on the lock yet. Rules never draw conclusions from it.
+    Unknown,
+}
+
+#[derive(Debug, Error)]
+pub enum RuneError {
+    #[error("Unknown rune '{0}', expected one of Z, V, S, C, ? or a number")]
+    Unknown(String),
+}
 
 impl Rune {
     pub fn new(id: u8) -> Self {
-        Self(id)
+        Self::Known(id)
+    }
+
+    pub fn is_known(&self) -> bool {
+        matches!(self, Rune::Known(_))
     }
 }
 
 impl Display for Rune {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self.0 {
-            0 => write!(f, "Z"),
-            1 => write!(f, "V"),
-            2 => write!(f, "S"),
-            3 => write!(f, "C"),
-            _ => write!(f, "{}", self.0),
+        match self {
+            Rune::Known(0) => write!(f, "Z"),
+            Rune::Known(1) => write!(f, "V"),
+            Rune::Known(2) => write!(f, "S"),
+            Rune::Known(3) => write!(f, "C"),
+            Rune::Known(id) => write!(f, "{}", id),
+            Rune::Unknown => write!(f, "?"),
+        }
+    }
+}
+
+///Accepts the letters used by `Display` (case insensitive), `?` for an unknown rune, as well as
+///raw rune ids.
+impl FromStr for Rune {
+    type Err = RuneError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_uppercase().as_str() {
+            "Z" => Ok(Rune::Known(0)),
+            "V" => Ok(Rune::Known(1)),
+            "S" => Ok(Rune::Known(2)),
+            "C" => Ok(Rune::Known(3)),
+            "?" => Ok(Rune::Unknown),
+            other => other
+                .parse::<u8>()
+                .map(Rune::Known)
+                .map_err(|_| RuneError::Unknown(s.into())),
         }
     }
 }
@@ -0,0 +1,215 @@
+//! The size of a lock: how many positions it has, split evenly over its outer and inner circle,
+//! and how many activations a solution places on them. The locks of the game are 12 by 12, smaller
+//! practice locks and larger hypothetical ones have other sizes.
+
+use std::fmt::Display;
+
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::{
+    activation::{Activation, ActivationError},
+    index::RunePosition,
+};
+
+///The most positions a lock can have, sets of positions or activations are kept as the bits of a
+///`u64`.
+pub const MAX_POSITIONS: usize = 64;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum LockSizeError {
+    #[error("A lock has two circles of an even number of sectors, {0} positions can't be split into them")]
+    Positions(usize),
+    #[error("A lock has at most {MAX_POSITIONS} positions, not {0}")]
+    TooLarge(usize),
+    #[error(
+        "A lock of {positions} positions takes 1 to {positions} activations, not {activations}"
+    )]
+    Activations {
+        positions: usize,
+        activations: usize,
+    },
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "StoredSize")]
+pub struct LockSize {
+    positions: usize,
+    activations: usize,
+}
+
+///A size as it is stored, checked by `LockSize::new` when it is read.
+#[derive(Deserialize)]
+struct StoredSize {
+    positions: usize,
+    activations: usize,
+}
+
+impl TryFrom<StoredSize> for LockSize {
+    type Error = LockSizeError;
+
+    fn try_from(stored: StoredSize) -> Result<Self, Self::Error> {
+        LockSize::new(stored.positions, stored.activations)
+    }
+}
+
+impl Default for LockSize {
+    fn default() -> Self {
+        Self::STANDARD
+    }
+}
+
+impl Display for LockSize {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} positions and {} activations",
+            self.positions, self.activations
+        )
+    }
+}
+
+impl LockSize {
+    ///The size of the locks in the game.
+    pub const STANDARD: LockSize = LockSize {
+        positions: 12,
+        activations: 12,
+    };
+
+    ///Opposite sectors only exist on circles of an even number of them, so `positions` is a
+    ///multiple of four. Every activation needs a position of its own.
+    pub fn new(positions: usize, activations: usize) -> Result<Self, LockSizeError> {
+        if positions == 0 || !positions.is_multiple_of(4) {
+            return Err(LockSizeError::Positions(positions));
+        }
+        if positions > MAX_POSITIONS {
+            return Err(LockSizeError::TooLarge(positions));
+        }
+        if activations == 0 || activations > positions {
+            return Err(LockSizeError::Activations {
+                positions,
+                activations,
+            });
+        }
+        Ok(Self {
+            positions,
+            activations,
+        })
+    }
+
+    pub fn positions(self) -> usize {
+        self.positions
+    }
+
+    pub fn activations(self) -> usize {
+        self.activations
+    }
+
+    ///The positions on each circle.
+    pub fn sectors(self) -> usize {
+        self.positions / 2
+    }
+
+    ///Whether every position takes an activation, not only every activation a position.
+    pub fn is_square(self) -> bool {
+        self.positions == self.activations
+    }
+
+    ///Every position, the outer circle first.
+    pub fn all_positions(self) -> impl Iterator<Item = RunePosition> + Clone {
+        (0..self.positions).map(RunePosition::new)
+    }
+
+    pub fn all_activations(self) -> impl Iterator<Item = Activation> + Clone {
+        (0..self.activations as u8).map(|it| Activation::new(it).expect("the size is bounded"))
+    }
+
+    ///Every cell, position by position.
+    pub fn cells(self) -> impl Iterator<Item = (RunePosition, Activation)> {
+        self.all_positions()
+            .cartesian_product(self.all_activations())
+    }
+
+    pub fn position(self, index: usize) -> Option<RunePosition> {
+        (index < self.positions).then(|| RunePosition::new(index))
+    }
+
+    pub fn activation(self, zero_based: usize) -> Result<Activation, ActivationError> {
+        match zero_based < self.activations {
+            true => Activation::new(zero_based as u8),
+            false => Err(ActivationError::OutOfBounds),
+        }
+    }
+
+    pub fn contains(self, activation: Activation) -> bool {
+        activation.index() < self.activations
+    }
+
+    ///The santor of every position: how high up it is on the lock, when the first sector of both
+    ///circles is at the top. Positions at the same height share their santor, the lowest is 0.
+    ///
+    ///The circles are equally spaced, the inner one at three quarters of the radius of the outer
+    ///one, which puts both positions of the top sector above the rest on the game's lock.
+    pub fn santors(self) -> Vec<u32> {
+        let sectors = self.sectors();
+        //Rounded, so heights that are the same on paper compare as the same.
+        let heights: Vec<i64> = self
+            .all_positions()
+            .map(|position| {
+                let radius = match position.index() < sectors {
+                    true => 4.0,
+                    false => 3.0,
+                };
+                let angle =
+                    std::f64::consts::TAU * (position.index() % sectors) as f64 / sectors as f64;
+                (radius * angle.cos() * 1e6).round() as i64
+            })
+            .collect();
+        let levels: Vec<i64> = heights.iter().copied().sorted().dedup().collect();
+        heights
+            .iter()
+            .map(|height| levels.binary_search(height).expect("is one of the levels") as u32)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{LockSize, LockSizeError};
+
+    #[test]
+    fn test_sizes() {
+        assert_eq!(LockSize::new(12, 12), Ok(LockSize::STANDARD));
+        assert_eq!(LockSize::new(10, 10), Err(LockSizeError::Positions(10)));
+        assert_eq!(LockSize::new(68, 12), Err(LockSizeError::TooLarge(68)));
+        assert!(matches!(
+            LockSize::new(8, 9),
+            Err(LockSizeError::Activations { .. })
+        ));
+
+        let small = LockSize::new(8, 6).unwrap();
+        assert_eq!(small.sectors(), 4);
+        assert!(!small.is_square());
+        assert_eq!(small.cells().count(), 48);
+        assert!(small.activation(6).is_err());
+        assert!(small.position(8).is_none());
+
+        assert!(serde_json::from_str::<LockSize>(r#"{"positions":6,"activations":6}"#).is_err());
+        let text = serde_json::to_string(&small).unwrap();
+        assert_eq!(serde_json::from_str::<LockSize>(&text).unwrap(), small);
+    }
+
+    #[test]
+    fn test_santors() {
+        assert_eq!(
+            LockSize::STANDARD.santors(),
+            [7, 5, 2, 0, 2, 5, 6, 4, 3, 1, 3, 4]
+        );
+        //The sides of a square lock are level with its center.
+        assert_eq!(
+            LockSize::new(8, 8).unwrap().santors(),
+            [4, 2, 0, 2, 3, 2, 1, 2]
+        );
+    }
+}
@@ -0,0 +1,116 @@
+use std::fmt::Debug;
+
+use crate::{activation::Activation, size::LockSize};
+
+///A set of activations as a bitmask, bit `n` standing for the activation with index `n`.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct CandidateSet(u64);
+
+impl CandidateSet {
+    pub fn empty() -> Self {
+        Self(0)
+    }
+
+    ///Every activation of a lock of `size`.
+    pub fn full(size: LockSize) -> Self {
+        Self(u64::MAX >> (u64::BITS as usize - size.activations()))
+    }
+
+    pub fn contains(&self, activation: Activation) -> bool {
+        self.0 & (1 << activation.index()) != 0
+    }
+
+    ///Returns whether the activation wasn't in the set before.
+    pub fn insert(&mut self, activation: Activation) -> bool {
+        let added = !self.contains(activation);
+        self.0 |= 1 << activation.index();
+        added
+    }
+
+    ///Returns whether the activation was in the set.
+    pub fn remove(&mut self, activation: Activation) -> bool {
+        let removed = self.contains(activation);
+        self.0 &= !(1 << activation.index());
+        removed
+    }
+
+    pub fn retain(&mut self, mut keep: impl FnMut(Activation) -> bool) {
+        for activation in self.iter() {
+            if !keep(activation) {
+                self.remove(activation);
+            }
+        }
+    }
+
+    pub fn union(self, other: Self) -> Self {
+        Self(self.0 | other.0)
+    }
+
+    pub fn intersection(self, other: Self) -> Self {
+        Self(self.0 & other.0)
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.count_ones() as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+
+    ///The activations in ascending order.
+    pub fn iter(&self) -> impl Iterator<Item = Activation> {
+        let bits = self.0;
+        (0..u64::BITS as u8)
+            .filter(move |it| bits & (1 << it) != 0)
+            .map(|it| Activation::new(it).unwrap())
+    }
+}
+
+impl FromIterator<Activation> for CandidateSet {
+    fn from_iter<T: IntoIterator<Item = Activation>>(iter: T) -> Self {
+        let mut set = Self::empty();
+        for activation in iter {
+            set.insert(activation);
+        }
+        set
+    }
+}
+
+impl Debug for CandidateSet {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_set().entries(self.iter()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{activation::Activation, size::LockSize};
+
+    use super::CandidateSet;
+
+    #[test]
+    fn test_set_operations() {
+        let activation = |it| Activation::new(it).unwrap();
+        let low: CandidateSet = (0..6).map(activation).collect();
+        let even: CandidateSet = (0..12).step_by(2).map(activation).collect();
+        assert_eq!(CandidateSet::full(LockSize::STANDARD).len(), 12);
+        assert_eq!(CandidateSet::full(LockSize::new(64, 64).unwrap()).len(), 64);
+        assert_eq!(low.union(even).len(), 9);
+        assert_eq!(
+            low.intersection(even).iter().collect::<Vec<_>>(),
+            vec![activation(0), activation(2), activation(4)]
+        );
+
+        let mut set = low;
+        assert!(set.remove(activation(3)));
+        assert!(!set.remove(activation(3)));
+        assert!(set.insert(activation(11)));
+        set.retain(|it| it.index() % 2 == 1);
+        assert_eq!(
+            set.iter().collect::<Vec<_>>(),
+            vec![activation(1), activation(5), activation(11)]
+        );
+        assert!(CandidateSet::empty().is_empty());
+    }
+}
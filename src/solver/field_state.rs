@@ -1,36 +1,16 @@
-use std::{
-    collections::HashSet,
-    fmt::{Display, Formatter},
-};
+use std::fmt::Display;
 
-use thiserror::Error;
+use crate::activation::Activation;
 
-use crate::{
-    activation::Activation,
-    assignment::Assignment,
-    index::RunePosition,
-    solver_nodes::{
-        SolverNodeAction, SolverNodeData, SolverNodeHandle, SolverNodeState, SolverNodes,
-        SolverNodesError,
-    },
-    RuneLock,
-};
+use super::candidate_set::CandidateSet;
 
 #[derive(Clone)]
 pub enum FieldState {
     Assumed(Activation),
-    Unsure(HashSet<Activation>),
+    Unsure(CandidateSet),
     Deduced(Activation),
 }
 
-impl Default for FieldState {
-    fn default() -> Self {
-        Self::Unsure(HashSet::from_iter(
-            (0..12).map(|it| Activation::new(it).unwrap()),
-        ))
-    }
-}
-
 impl Display for FieldState {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -38,9 +18,7 @@ impl Display for FieldState {
             FieldState::Assumed(a) => write!(f, "{}?", a),
             FieldState::Unsure(p) => {
                 write!(f, "[")?;
-                let mut act: Vec<_> = p.iter().collect();
-                act.sort();
-                for i in act {
+                for i in p.iter() {
                     write!(f, "{} ", i)?;
                 }
                 write!(f, "]")?;
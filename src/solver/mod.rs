@@ -1,17 +1,15 @@
+pub mod candidate_set;
 pub mod field_state;
 pub mod solver_state;
 
-use std::{
-    collections::HashSet,
-    fmt::{Display, Formatter},
-};
-
 use thiserror::Error;
 
 use crate::{
     activation::Activation,
-    assignment::Assignment,
     index::RunePosition,
+    logging::debug,
+    outcome::Outcome,
+    size::LockSize,
     solver_nodes::{
         SolverNodeAction, SolverNodeData, SolverNodeHandle, SolverNodeState, SolverNodes,
         SolverNodesError,
@@ -30,6 +28,7 @@ enum ActivationPossibility {
     AlreadyAssumed,
 }
 
+#[allow(clippy::large_enum_variant)]
 enum DeductionIterationResult {
     Unsolvable { reason: String },
     MadeDeductions(SolverState),
@@ -41,7 +40,7 @@ pub enum DeductionResult {
     Indecisive,
 }
 
-pub enum DeduceWithAssumptionResult {
+pub(crate) enum DeduceWithAssumptionResult {
     Unsolvable { reason: String },
     Done(Vec<SolverState>),
     Solved(Vec<SolverState>),
@@ -77,39 +76,20 @@ pub enum SolverError {
     PopInitialState,
 }
 
-pub enum ExploreResult {
-    Unsolvable { reason: String },
-    Indecisive,
-    Solved { solution: SolverNodeHandle },
-}
-
-impl Display for ExploreResult {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            ExploreResult::Unsolvable { reason } => write!(
-                f,
-                "This assignment turns out to be impossible. ({})",
-                reason
-            ),
-            ExploreResult::Indecisive => write!(
-                f,
-                "This assignment leads to another unclear situation. More assumptions required."
-            ),
-            ExploreResult::Solved { solution } => {
-                write!(f, "The assignment {} solves the lock.", solution)
-            }
-        }
-    }
-}
-
 pub struct Solver {
     nodes: SolverNodes,
     current: SolverNodeHandle,
 }
 
+impl Default for Solver {
+    fn default() -> Self {
+        Self::new(LockSize::default())
+    }
+}
+
 impl Solver {
-    pub fn new() -> Self {
-        let (nodes, root) = SolverNodes::new(SolverState::default());
+    pub fn new(size: LockSize) -> Self {
+        let (nodes, root) = SolverNodes::new(SolverState::new(size));
         Self {
             nodes,
             current: root,
@@ -121,7 +101,7 @@ impl Solver {
         lock: &RuneLock,
         position: RunePosition,
         assume_to_be: Activation,
-    ) -> Result<ExploreResult, SolverError> {
+    ) -> Result<Outcome, SolverError> {
         let result = self
             .peek()
             .deduce_with_assumption(lock, position, assume_to_be);
@@ -130,9 +110,28 @@ impl Solver {
             Ok(DeduceWithAssumptionResult::Unsolvable { reason }) => {
                 self.nodes[self.current].rule_out(position, assume_to_be);
 
-                Ok(ExploreResult::Unsolvable { reason })
+                Ok(Outcome::Contradiction {
+                    fact: None,
+                    blame: Vec::new(),
+                    reason,
+                })
             }
             Ok(DeduceWithAssumptionResult::Done(steps)) => {
+                let before = self.peek().fixed_assignments();
+                let after = steps.last().unwrap().fixed_assignments();
+                let placements: Vec<_> = lock
+                    .size()
+                    .all_positions()
+                    .filter(|it| *it != position && before[*it].is_none())
+                    .filter_map(|it| Some((it, after[it]?)))
+                    .collect();
+                let outcome = if placements.is_empty() {
+                    Outcome::Progress {
+                        facts_added: steps.len() - 1,
+                    }
+                } else {
+                    Outcome::Forced { placements }
+                };
                 self.current = self.nodes.insert_child(
                     self.current,
                     SolverNodeData {
@@ -144,10 +143,11 @@ impl Solver {
                         },
                     },
                 );
-                Ok(ExploreResult::Indecisive)
+                Ok(outcome)
             }
             Ok(DeduceWithAssumptionResult::Solved(steps)) => {
-                println!("AAAHHHH SOLVED");
+                let solved = steps.last().unwrap().fixed_assignments();
+                let assignment = lock.size().all_positions().map(|it| solved[it]).collect();
                 self.nodes[self.current].rule_out(position, assume_to_be);
                 let solution = self.nodes.insert_child(
                     self.current,
@@ -161,19 +161,19 @@ impl Solver {
                     },
                 );
                 self.current = solution;
-                Ok(ExploreResult::Solved { solution })
+                Ok(Outcome::Solved { assignment })
             }
             Err(err) => Err(err),
         }
     }
 
     pub fn peek(&self) -> &SolverState {
-        &self.nodes[self.current].deduction_chain.last().unwrap()
+        self.nodes[self.current].deduction_chain.last().unwrap()
     }
 
-    pub fn print_nodes(&self) {
-        println!("{}", self.nodes);
-        println!("{}", self.current);
+    ///The tree of states and the current one, for debugging.
+    pub fn dump_nodes(&self) -> String {
+        format!("{}\n{}", self.nodes, self.current)
     }
 
     pub fn view(&mut self, node: usize) -> Result<(), SolverNodesError> {
@@ -185,25 +185,27 @@ impl Solver {
         &mut self,
         lock: &RuneLock,
         activation: Activation,
-    ) -> Result<ExploreResult, SolverError> {
+    ) -> Result<Outcome, SolverError> {
         let state = self.peek();
         let to_try = state.possible_positions_of(activation);
-        println!("to try: {:?}", to_try);
-        let mut solved = false;
+        debug!("to try: {:?}", to_try);
+        let mut solved = None;
         for i in to_try {
             match self.explore(lock, i, activation)? {
-                ExploreResult::Unsolvable { reason } => {
-                    println!("Assumption {} in {} is false: {}", activation, i, reason);
+                Outcome::Contradiction { reason, .. } => {
+                    debug!("Assumption {} in {} is false: {}", activation, i, reason);
                 }
-                ExploreResult::Indecisive => return Ok(ExploreResult::Indecisive),
-                ExploreResult::Solved { .. } => {
-                    solved = true;
+                outcome @ (Outcome::Progress { .. } | Outcome::Forced { .. }) => {
+                    return Ok(outcome)
+                }
+                outcome @ Outcome::Solved { .. } => {
+                    solved = Some(outcome);
                 }
             }
         }
 
-        if solved {
-            Ok(ExploreResult::Indecisive)
+        if let Some(solved) = solved {
+            Ok(solved)
         } else {
             self.nodes[self.current].state = SolverNodeState::Unsolvable;
             let assumption = self.nodes[self.current].action;
@@ -216,7 +218,9 @@ impl Solver {
                 self.nodes[self.current].rule_out(position, activation)
             }
 
-            Ok(ExploreResult::Unsolvable {
+            Ok(Outcome::Contradiction {
+                fact: None,
+                blame: Vec::new(),
                 reason: format!(
                     "Activation {} has no position it can be assigned to.",
                     activation
@@ -229,24 +233,26 @@ impl Solver {
         &mut self,
         lock: &RuneLock,
         position: RunePosition,
-    ) -> Result<ExploreResult, SolverError> {
+    ) -> Result<Outcome, SolverError> {
         let state = self.peek();
         let to_try = state.possible_activations_of(position);
-        let mut solved = false;
+        let mut solved = None;
         for i in to_try {
             match self.explore(lock, position, i)? {
-                ExploreResult::Unsolvable { reason } => {
-                    println!("Assumption {} in {} is false: {}", i, position, reason);
+                Outcome::Contradiction { reason, .. } => {
+                    debug!("Assumption {} in {} is false: {}", i, position, reason);
+                }
+                outcome @ (Outcome::Progress { .. } | Outcome::Forced { .. }) => {
+                    return Ok(outcome)
                 }
-                ExploreResult::Indecisive => return Ok(ExploreResult::Indecisive),
-                ExploreResult::Solved { .. } => {
-                    solved = true;
+                outcome @ Outcome::Solved { .. } => {
+                    solved = Some(outcome);
                 }
             }
         }
 
-        if solved {
-            Ok(ExploreResult::Indecisive)
+        if let Some(solved) = solved {
+            Ok(solved)
         } else {
             self.nodes[self.current].state = SolverNodeState::Unsolvable;
             let assumption = self.nodes[self.current].action;
@@ -259,7 +265,9 @@ impl Solver {
                 self.nodes[self.current].rule_out(position, activation)
             }
 
-            Ok(ExploreResult::Unsolvable {
+            Ok(Outcome::Contradiction {
+                fact: None,
+                blame: Vec::new(),
                 reason: format!(
                     "Positon {} has no activation that can be assigned to it.",
                     position
@@ -1,24 +1,13 @@
-use std::{
-    collections::HashSet,
-    fmt::{Display, Formatter},
-};
-
-use thiserror::Error;
+use std::fmt::Display;
 
 use crate::{
-    activation::Activation,
-    assignment::Assignment,
-    index::RunePosition,
-    solver_nodes::{
-        SolverNodeAction, SolverNodeData, SolverNodeHandle, SolverNodeState, SolverNodes,
-        SolverNodesError,
-    },
-    RuneLock,
+    activation::Activation, assignment::Assignment, index::RunePosition, logging::debug,
+    size::LockSize, RuneLock,
 };
 
 use super::{
-    field_state::FieldState, ActivationPossibility, DeduceWithAssumptionResult,
-    DeductionIterationResult, SolverError,
+    candidate_set::CandidateSet, field_state::FieldState, ActivationPossibility,
+    DeduceWithAssumptionResult, DeductionIterationResult, SolverError,
 };
 
 #[derive(Clone)]
@@ -36,7 +25,8 @@ pub enum SolverStateAction {
 
 #[derive(Clone)]
 pub struct SolverState {
-    state: [FieldState; 12],
+    size: LockSize,
+    state: Vec<FieldState>,
     action: SolverStateAction,
 }
 
@@ -44,35 +34,22 @@ impl Display for SolverState {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         for (position, state) in self.state.iter().enumerate() {
             let position = RunePosition::new(position);
-            write!(f, "{:2}: {}\n", position, state)?;
+            writeln!(f, "{:2}: {}", position, state)?;
         }
         Ok(())
     }
 }
 
-impl Default for SolverState {
-    fn default() -> Self {
+impl SolverState {
+    ///Every activation of a lock of `size` still possible everywhere.
+    pub fn new(size: LockSize) -> Self {
         SolverState {
             action: SolverStateAction::Default,
-            state: [
-                FieldState::default(),
-                FieldState::default(),
-                FieldState::default(),
-                FieldState::default(),
-                FieldState::default(),
-                FieldState::default(),
-                FieldState::default(),
-                FieldState::default(),
-                FieldState::default(),
-                FieldState::default(),
-                FieldState::default(),
-                FieldState::default(),
-            ],
+            size,
+            state: vec![FieldState::Unsure(CandidateSet::full(size)); size.positions()],
         }
     }
-}
 
-impl SolverState {
     pub fn with_assumed(
         &self,
         lock: &RuneLock,
@@ -89,7 +66,7 @@ impl SolverState {
         Ok(new_state)
     }
 
-    pub fn deduce_with_assumption(
+    pub(crate) fn deduce_with_assumption(
         &self,
         lock: &RuneLock,
         position: RunePosition,
@@ -106,18 +83,10 @@ impl SolverState {
                     substates.push(deduced);
                 }
                 DeductionIterationResult::Indecisive => {
-                    println!("Indecisive, check for solve");
-                    let last = substates.last().unwrap();
-                    let mut solved = true;
-                    for i in last.state.iter() {
-                        if let FieldState::Unsure(_) = i {
-                            solved = false;
-                        }
-                    }
-                    if solved {
-                        if let Ok(_) = lock.validate(&last.fixed_assignments()) {
-                            return Ok(DeduceWithAssumptionResult::Solved(substates));
-                        }
+                    debug!("Indecisive, check for solve");
+                    let last = substates.last().unwrap().fixed_assignments();
+                    if last.is_complete() && lock.validate(&last).is_ok() {
+                        return Ok(DeduceWithAssumptionResult::Solved(substates));
                     }
                     return Ok(DeduceWithAssumptionResult::Done(substates));
                 }
@@ -127,28 +96,23 @@ impl SolverState {
 
     pub fn possible_activations_of(&self, pos: RunePosition) -> Vec<Activation> {
         match &self.state[pos] {
-            FieldState::Unsure(possibilities) => {
-                let mut poss: Vec<_> = possibilities.iter().cloned().collect();
-                poss.sort();
-                poss
-            }
+            FieldState::Unsure(possibilities) => possibilities.iter().collect(),
             FieldState::Assumed(_) | FieldState::Deduced(_) => Vec::new(),
         }
     }
 
     pub fn possible_positions_of(&self, activation: Activation) -> Vec<RunePosition> {
-        let mut positions = Vec::with_capacity(12);
+        let mut positions = Vec::new();
         for (position, state) in self.state.iter().enumerate() {
             let position = RunePosition::new(position);
 
             match state {
-                FieldState::Unsure(possibilities) if possibilities.contains(&activation) => {
+                FieldState::Unsure(possibilities) if possibilities.contains(activation) => {
                     positions.push(position)
                 }
                 _ => {}
             }
         }
-        positions.sort();
         positions
     }
 
@@ -158,7 +122,8 @@ impl SolverState {
             FieldState::Assumed(activation) => Some(*activation),
             FieldState::Unsure(_) => None,
         });
-        Assignment::from_iter(i).expect("solver should only contain valid assignment states")
+        Assignment::from_iter(self.size, i)
+            .expect("solver should only contain valid assignment states")
     }
 
     fn assume(&mut self, position: RunePosition, to_be: Activation) -> Result<(), SolverError> {
@@ -212,15 +177,12 @@ impl SolverState {
                 FieldState::Deduced(_) => {}
                 FieldState::Assumed(_) => {}
                 FieldState::Unsure(possibilities) => possibilities.retain(|possibility| {
-                    if assignment.contains(*possibility) {
+                    if assignment.contains(possibility) {
                         false
                     } else {
                         let mut assignment = assignment.clone();
-                        assignment.assign(position, *possibility);
-                        match lock.validate(&assignment) {
-                            Ok(_) => true,
-                            Err(_) => false,
-                        }
+                        assignment.assign(position, possibility);
+                        lock.validate(&assignment).is_ok()
                     }
                 }),
             }
@@ -228,13 +190,19 @@ impl SolverState {
     }
 
     fn deduce(&self, lock: &RuneLock) -> DeductionIterationResult {
-        let mut activation_possibility = [0; 12].map(|_| ActivationPossibility::None);
+        let mut activation_possibility: Vec<_> = self
+            .size
+            .all_activations()
+            .map(|_| ActivationPossibility::None)
+            .collect();
         for (position, state) in self.state.iter().enumerate() {
             let position = RunePosition::new(position);
             match state {
                 FieldState::Unsure(possibilities) => {
-                    if possibilities.len() == 1 {
-                        let activation = *possibilities.iter().next().unwrap();
+                    //Only where every position takes an activation does the last one left have to
+                    //be there.
+                    if possibilities.len() == 1 && self.size.is_square() {
+                        let activation = possibilities.iter().next().unwrap();
                         activation_possibility[activation.index()] =
                             ActivationPossibility::ForcedAt(position);
                     } else {
@@ -311,9 +279,9 @@ impl SolverState {
         }
         if changed {
             deduced_state.prune_state(lock);
-            return DeductionIterationResult::MadeDeductions(deduced_state);
+            DeductionIterationResult::MadeDeductions(deduced_state)
         } else {
-            return DeductionIterationResult::Indecisive;
+            DeductionIterationResult::Indecisive
         }
     }
 
@@ -324,10 +292,11 @@ impl SolverState {
                 panic!("Cannot rule something out that is fixed.")
             }
             FieldState::Unsure(probs) => {
-                probs.remove(&assume_to_be);
+                probs.remove(assume_to_be);
             }
         }
         SolverState {
+            size: self.size,
             state,
             action: SolverStateAction::RuleOut {
                 activation: assume_to_be,
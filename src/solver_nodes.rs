@@ -91,14 +91,14 @@ impl SolverNodes {
 
         self.nodes[parent.0].children.push(child_handle);
 
-        return child_handle;
+        child_handle
     }
 
     pub fn get_handle(&self, node: usize) -> Result<SolverNodeHandle, SolverNodesError> {
         if node >= self.nodes.len() {
             return Err(SolverNodesError::UnknownNode(node));
         }
-        return Ok(SolverNodeHandle(node));
+        Ok(SolverNodeHandle(node))
     }
 
     pub fn parent_of(&self, node: SolverNodeHandle) -> Option<SolverNodeHandle> {
@@ -157,7 +157,7 @@ impl Display for SolverNodes {
             indent: usize,
             f: &mut Formatter<'_>,
         ) -> std::fmt::Result {
-            write!(f, "{0:1$} - ({3}) {2}\n", "", indent, nodes[handle], handle)?;
+            writeln!(f, "{0:1$} - ({3}) {2}", "", indent, nodes[handle], handle)?;
             for child in nodes.nodes[handle.0].children.iter() {
                 do_fmt(*child, nodes, indent + 2, f)?;
             }
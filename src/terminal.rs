@@ -0,0 +1,52 @@
+//! Terminal styling. Without the `terminal` feature all text is printed unstyled. In accessible mode
+//! it is unstyled as well, and the drawings of the hexagon, the tree and the grids are replaced by
+//! labelled lines a screen reader can read out.
+
+use std::{
+    fmt::Display,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+#[cfg(feature = "terminal")]
+use crossterm::style::{Color, ResetColor, SetForegroundColor, Stylize};
+
+static ACCESSIBLE: AtomicBool = AtomicBool::new(false);
+
+pub fn set_accessible(enabled: bool) {
+    ACCESSIBLE.store(enabled, Ordering::Relaxed);
+}
+
+pub fn accessible() -> bool {
+    ACCESSIBLE.load(Ordering::Relaxed)
+}
+
+///The line between the output of two commands, empty in accessible mode.
+pub fn separator() -> &'static str {
+    match accessible() {
+        true => "",
+        false => "==============================",
+    }
+}
+
+///Text of lesser importance, like placeholders for unknown values.
+pub fn dimmed(text: impl Display) -> String {
+    #[cfg(feature = "terminal")]
+    if !accessible() {
+        return format!(
+            "{}{}{}",
+            SetForegroundColor(Color::DarkGrey),
+            text,
+            ResetColor
+        );
+    }
+    text.to_string()
+}
+
+///Headlines.
+pub fn title(text: impl Display) -> String {
+    #[cfg(feature = "terminal")]
+    if !accessible() {
+        return format!("{}", text.to_string().red());
+    }
+    text.to_string()
+}
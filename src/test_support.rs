@@ -0,0 +1,16 @@
+//! Helpers shared by the tests of every module.
+
+use crate::{
+    activation::{Activation, HumanActivation},
+    rune::Rune,
+};
+
+///The activation with the 1 based number `number`, as rules and the command line write it.
+pub(crate) fn activation(number: u8) -> Activation {
+    Activation::from(HumanActivation::new(number).unwrap())
+}
+
+///The runes of the lock most tests are about, Z = 0, V = 1, S = 2, C = 3.
+pub(crate) fn sample_runes() -> [Rune; 12] {
+    [0, 2, 1, 3, 2, 1, 3, 2, 1, 0, 2, 1].map(Rune::new)
+}
@@ -0,0 +1,132 @@
+//! Interactive entry of a new lock, for when the lock isn't hardcoded in `main.rs`.
+
+use std::io;
+
+use rune_lock_solver::{
+    domain::ActivationDomain,
+    lock_file,
+    rule::{RuleKind, RuleTemplate},
+    rune::Rune,
+    size::LockSize,
+    terminal, RuneLock,
+};
+
+///Asks for the runes of both circles and then the rules of the lock. Returns `None` if the input
+///ends before the lock is complete.
+pub fn new_lock(lines: &mut impl Iterator<Item = io::Result<String>>) -> Option<RuneLock> {
+    println!("{}", terminal::title("New Lock"));
+    let mut runes = read_circle(lines, "outer", None)?;
+    runes.extend(read_circle(lines, "inner", Some(runes.len()))?);
+    let size = read_size(lines, runes.len())?;
+    let mut lock = RuneLock::sized(size, runes).expect("the circles fill the size");
+
+    println!("Enter the rules, one per line, e.g. '1 2 alwanese' or 'V follows Z'.");
+    println!("Kinds: alwanese, antakian-conjugates, alwanese-conjugates, different-runes,");
+    println!("       antakian-twins, increase-santor, decrease-santor, max-0-conductive,");
+    println!("       not-alwanese, not-antakian-twins, same-ring, different-ring");
+    println!("Chains of santor hints can be entered at once, e.g. '10..12 santor-chain'.");
+    println!("Positions known for sure are rules as well, e.g. '3 at 0' or '3 not-at 6'.");
+    println!(
+        "Runes that must all differ take any number of activations: '3 6 9 all-different-runes'."
+    );
+    println!("Gaps of more than one activation read 'V follows Z within 3'.");
+    println!("Rune orderings read 'Z before V': every activation on a Z is lower than on a V.");
+    println!("Any of these rules but 'follows' and 'before' can be negated, e.g. 'not 1 2 max-0-conductive'.");
+    println!("Clues with alternatives are joined by 'or', e.g. '1 2 alwanese or 1 at 0'.");
+    println!("Conditional clues read 'if <rule> then <rule>', e.g. 'if 1 at 0 then 2 at 6'.");
+    println!("Known places of activations are entered like 'domain 1 outer' or 'domain 1 in 0 2'.");
+    println!("Finish with an empty line.");
+    loop {
+        let line = lines.next()?.ok()?;
+        let line = line.trim();
+        if line.is_empty() {
+            break;
+        }
+        if let Some(domain) = line.strip_prefix("domain ") {
+            let domain = domain
+                .parse::<ActivationDomain>()
+                .and_then(|it| it.check_size(size).map(|_| it));
+            match domain {
+                Ok(domain) => {
+                    println!("Given {}: {}", lock.domains().len(), domain);
+                    lock.add_domain(domain);
+                }
+                Err(err) => println!("Didn't understand domain: {}", err),
+            }
+            continue;
+        }
+        if line.contains("..") {
+            let template = line
+                .parse::<RuleTemplate>()
+                .and_then(|it| it.check_size(size).map(|_| it));
+            match template {
+                Ok(template) => {
+                    let start = lock.rules().len();
+                    lock.add_template(template, 0);
+                    for (index, rule) in lock.rules().iter().enumerate().skip(start) {
+                        println!("Rule {}: {}", index, rule);
+                    }
+                }
+                Err(err) => println!("Didn't understand template: {}", err),
+            }
+            continue;
+        }
+        let rule = line
+            .parse::<RuleKind>()
+            .and_then(|it| it.check_size(size).map(|_| it));
+        match rule {
+            Ok(rule) => {
+                println!("Rule {}: {}", lock.rules().len(), rule);
+                lock.add_rule(rule, 0);
+            }
+            Err(err) => println!("Didn't understand rule: {}", err),
+        }
+    }
+
+    Some(lock)
+}
+
+fn read_circle(
+    lines: &mut impl Iterator<Item = io::Result<String>>,
+    circle: &str,
+    sectors: Option<usize>,
+) -> Option<Vec<Rune>> {
+    loop {
+        println!(
+            "Runes of the {} circle, clockwise from the top (e.g. 'Z S V C ? V'):",
+            circle
+        );
+        let line = lines.next()?.ok()?;
+        match lock_file::parse_circle(&line, sectors) {
+            Ok(runes) => return Some(runes),
+            Err((column, err)) => println!("Column {}: {}", column, err),
+        }
+    }
+}
+
+fn read_size(
+    lines: &mut impl Iterator<Item = io::Result<String>>,
+    positions: usize,
+) -> Option<LockSize> {
+    loop {
+        println!(
+            "Number of activations (empty for {}, one on every position):",
+            positions
+        );
+        let line = lines.next()?.ok()?;
+        let activations = match line.trim() {
+            "" => positions,
+            number => match number.parse() {
+                Ok(it) => it,
+                Err(_) => {
+                    println!("Expected a number of activations, got '{}'", number);
+                    continue;
+                }
+            },
+        };
+        match LockSize::new(positions, activations) {
+            Ok(size) => return Some(size),
+            Err(err) => println!("{}", err),
+        }
+    }
+}